@@ -0,0 +1,55 @@
+//! Timing of splitting a template with many fragments
+//!
+//! Run with `cargo bench --bench split`.
+use std::time::{Duration, Instant};
+
+use template_fragments::{filter_template, split_templates};
+
+/// A template with `count` sibling fragments, each with nested blocks
+fn many_fragments(count: usize) -> String {
+    let mut res = String::from("<body>\n");
+    for idx in 0..count {
+        res.push_str(&format!(
+            "  {{% fragment item-{idx} group-{} %}}\n",
+            idx % 10
+        ));
+        res.push_str(&format!("  <div id=\"item-{idx}\">\n"));
+        res.push_str(&format!("    {{% fragment-block content-{idx} %}}\n"));
+        res.push_str("    <p>{{ item.content }}</p>\n");
+        res.push_str("    {% endfragment-block %}\n");
+        res.push_str("  </div>\n");
+        res.push_str("  {% endfragment %}\n");
+    }
+    res.push_str("</body>\n");
+    res
+}
+
+fn bench(name: &str, mut f: impl FnMut()) {
+    // warm up and estimate the number of iterations for ~1s
+    let start = Instant::now();
+    let mut iterations = 0;
+    while start.elapsed() < Duration::from_millis(200) {
+        f();
+        iterations += 1;
+    }
+    let iterations = iterations * 5;
+
+    let start = Instant::now();
+    for _ in 0..iterations {
+        f();
+    }
+    let per_iteration = start.elapsed() / iterations;
+    println!("{name:<32} {per_iteration:>12.2?} / iteration");
+}
+
+fn main() {
+    for count in [10, 100, 500] {
+        let src = many_fragments(count);
+        bench(&format!("split_templates({count})"), || {
+            std::hint::black_box(split_templates(&src).unwrap());
+        });
+        bench(&format!("filter_template({count})"), || {
+            std::hint::black_box(filter_template(&src, "group-3").unwrap());
+        });
+    }
+}
@@ -3,16 +3,14 @@ use template_fragments::{join_path, split_templates};
 
 fn main() -> Result<(), PanicOnErrors> {
     let env = {
-        let mut source = minijinja::Source::new();
+        let mut env = minijinja::Environment::new();
 
         for (path, template) in [("index.html", include_str!("templates/index.html"))] {
             for (fragment_name, template_fragment) in split_templates(template)? {
-                source.add_template(join_path(path, &fragment_name), &template_fragment)?;
+                env.add_template_owned(join_path(path, &fragment_name), template_fragment)?;
             }
         }
 
-        let mut env = minijinja::Environment::new();
-        env.set_source(source);
         env
     };
 
@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::{DeriveInput, Ident, LitStr};
+
+use crate::{resolve_path, split_template};
+
+pub fn expand(input: DeriveInput) -> syn::Result<TokenStream> {
+    let path = parse_path(&input)?;
+    let full_path = resolve_path(&path)?;
+    let templates = split_template(&path, &full_path)?;
+
+    // the names of the generated constants, together with their fragments
+    let mut consts: HashMap<String, &str> = HashMap::new();
+    let mut items = Vec::new();
+    let mut arms = Vec::new();
+    for (fragment, content) in &templates {
+        let name = const_name(fragment);
+        if name == "FRAGMENTS" {
+            return Err(syn::Error::new(
+                path.span(),
+                format!("fragment {fragment:?} clashes with the FRAGMENTS constant"),
+            ));
+        }
+        if let Some(other) = consts.insert(name.clone(), fragment) {
+            return Err(syn::Error::new(
+                path.span(),
+                format!("fragments {other:?} and {fragment:?} both map to the constant {name}"),
+            ));
+        }
+
+        let ident = Ident::new(&name, Span::call_site());
+        let doc = match fragment.as_str() {
+            "" => format!("The base template of `{}`", path.value()),
+            _ => format!("The fragment `{fragment}` of `{}`", path.value()),
+        };
+        items.push(quote! {
+            #[doc = #doc]
+            pub const #ident: &'static str = #content;
+        });
+        arms.push(quote! { #fragment => ::core::option::Option::Some(Self::#ident), });
+    }
+
+    let fragments = templates.iter().map(|(fragment, _)| fragment);
+    let full_path = full_path.to_string_lossy();
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics #name #ty_generics #where_clause {
+            #(#items)*
+
+            /// The sorted names of all fragments, the base template is `""`
+            pub const FRAGMENTS: &'static [&'static str] = &[#(#fragments),*];
+
+            /// Look up a fragment by its name, the base template is `""`
+            pub fn fragment(name: &str) -> ::core::option::Option<&'static str> {
+                match name {
+                    #(#arms)*
+                    _ => ::core::option::Option::None,
+                }
+            }
+        }
+
+        // recompile when the template changes
+        const _: &[u8] = include_bytes!(#full_path);
+    })
+}
+
+/// Parse the `#[template(path = "...")]` attribute
+fn parse_path(input: &DeriveInput) -> syn::Result<LitStr> {
+    let mut path = None;
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("template"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("path") {
+                path = Some(meta.value()?.parse::<LitStr>()?);
+                Ok(())
+            } else {
+                Err(meta.error("unknown template option, expected `path`"))
+            }
+        })?;
+    }
+    path.ok_or_else(|| {
+        syn::Error::new_spanned(
+            &input.ident,
+            "missing `#[template(path = \"...\")]` attribute",
+        )
+    })
+}
+
+/// The name of the constant of a fragment, e.g., `FOOTER_LINKS` for
+/// `footer-links`
+fn const_name(fragment: &str) -> String {
+    if fragment.is_empty() {
+        return String::from("BASE");
+    }
+
+    let mut name: String = fragment
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' => c.to_ascii_uppercase(),
+            _ => '_',
+        })
+        .collect();
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        name.insert(0, '_');
+    }
+    name
+}
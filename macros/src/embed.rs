@@ -0,0 +1,50 @@
+use std::path::{Path, PathBuf};
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::LitStr;
+
+use crate::resolve_path;
+
+pub fn expand(root: LitStr) -> syn::Result<TokenStream> {
+    let full_root = resolve_path(&root)?;
+    let templates = template_fragments::split_directory(&full_root)
+        .map_err(|err| syn::Error::new(root.span(), format!("{}: {err}", root.value())))?;
+    let files = list_files(&full_root)
+        .map_err(|err| syn::Error::new(root.span(), format!("{}: {err}", root.value())))?;
+
+    let mut templates: Vec<_> = templates.into_iter().collect();
+    templates.sort();
+    let entries = templates
+        .iter()
+        .map(|(name, content)| quote! { (#name, #content) });
+    let files = files.iter().map(|path| path.to_string_lossy());
+
+    Ok(quote! {
+        {
+            // recompile when the templates change
+            #(const _: &[u8] = include_bytes!(#files);)*
+            ::template_fragments::EmbeddedTemplates::from_sorted(&[#(#entries),*])
+        }
+    })
+}
+
+/// List all files below `root`, as read by `split_directory`
+fn list_files(root: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut res = Vec::new();
+    let mut dirs = vec![root.to_path_buf()];
+
+    while let Some(dir) = dirs.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else {
+                res.push(path);
+            }
+        }
+    }
+    res.sort();
+
+    Ok(res)
+}
@@ -0,0 +1,98 @@
+//! Compile-time macros for [template-fragments](https://docs.rs/template-fragments)
+//!
+//! Templates are read and split while compiling, paths are relative to the
+//! directory of the `Cargo.toml` of the crate using the macros. Invalid
+//! templates are reported as compile errors. The syntax of each template is
+//! selected by its extension, see `template_fragments::Config::for_path`.
+//!
+//! `#[derive(TemplateFragments)]` adds one associated constant per fragment
+//! of a template:
+//!
+//! ```rust
+//! use template_fragments_macros::TemplateFragments;
+//!
+//! #[derive(TemplateFragments)]
+//! #[template(path = "tests/templates/index.html")]
+//! struct Index;
+//!
+//! assert_eq!(Index::ITEM, "<li>{{ item }}</li>\n");
+//! assert_eq!(Index::fragment("item"), Some(Index::ITEM));
+//! assert_eq!(Index::FRAGMENTS, ["", "footer-links", "item"]);
+//! ```
+//!
+//! `embed_template_dir!` embeds all templates of a directory tree:
+//!
+//! ```rust
+//! use template_fragments::EmbeddedTemplates;
+//! use template_fragments_macros::embed_template_dir;
+//!
+//! static TEMPLATES: EmbeddedTemplates = embed_template_dir!("tests/templates");
+//!
+//! assert_eq!(TEMPLATES.get("index.html#item"), Some("<li>{{ item }}</li>\n"));
+//! ```
+mod derive;
+mod embed;
+
+use std::path::PathBuf;
+
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, DeriveInput, LitStr};
+
+/// Embed the fragments of a template as associated constants
+///
+/// The template is given by the `#[template(path = "...")]` attribute. The
+/// base template is available as `BASE`, each fragment as a constant named
+/// after the fragment in upper case, with characters that are not allowed in
+/// identifiers replaced by `_`, e.g., `footer-links` as `FOOTER_LINKS`.
+/// Fragments whose constants would clash are reported as compile errors.
+///
+/// In addition, `FRAGMENTS` lists the sorted fragment names and
+/// `fragment(name)` looks up a fragment by its name.
+#[proc_macro_derive(TemplateFragments, attributes(template))]
+pub fn derive_template_fragments(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    derive::expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Embed all templates of a directory tree as `EmbeddedTemplates`
+///
+/// The templates are keyed by `path#fragment`, where `path` is relative to
+/// the directory, as with `template_fragments::split_directory`. The crate is
+/// recompiled when embedded templates change, but not when files are added to
+/// the directory.
+#[proc_macro]
+pub fn embed_template_dir(input: TokenStream) -> TokenStream {
+    let root = parse_macro_input!(input as LitStr);
+    embed::expand(root)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Resolve a path relative to the manifest directory of the calling crate
+fn resolve_path(path: &LitStr) -> syn::Result<PathBuf> {
+    let root = std::env::var_os("CARGO_MANIFEST_DIR")
+        .ok_or_else(|| syn::Error::new(path.span(), "CARGO_MANIFEST_DIR is not set"))?;
+    Ok(PathBuf::from(root).join(path.value()))
+}
+
+/// Read and split a template, errors are reported at the span of `path`
+fn split_template(
+    path: &LitStr,
+    full_path: &std::path::Path,
+) -> syn::Result<Vec<(String, String)>> {
+    let src = std::fs::read_to_string(full_path).map_err(|err| {
+        syn::Error::new(
+            path.span(),
+            format!("cannot read {}: {err}", full_path.display()),
+        )
+    })?;
+    let templates = template_fragments::Config::for_path(path.value())
+        .split_templates(&src)
+        .map_err(|err| syn::Error::new(path.span(), format!("{}: {err}", path.value())))?;
+
+    let mut templates: Vec<_> = templates.into_iter().collect();
+    templates.sort();
+    Ok(templates)
+}
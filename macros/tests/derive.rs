@@ -0,0 +1,39 @@
+use template_fragments_macros::TemplateFragments;
+
+#[derive(TemplateFragments)]
+#[template(path = "tests/templates/index.html")]
+struct Index;
+
+#[derive(TemplateFragments)]
+#[template(path = "tests/templates/index.html")]
+#[allow(dead_code)]
+struct Generic<'a, T: Clone>(&'a T);
+
+#[test]
+fn constants() {
+    assert_eq!(
+        Index::BASE,
+        "<ul>\n<li>{{ item }}</li>\n</ul>\n<a href=\"/\">home</a>\n"
+    );
+    assert_eq!(Index::ITEM, "<li>{{ item }}</li>\n");
+    assert_eq!(Index::FOOTER_LINKS, "<a href=\"/\">home</a>\n");
+    assert_eq!(Index::FRAGMENTS, ["", "footer-links", "item"]);
+}
+
+#[test]
+fn matches_split_templates() {
+    let templates =
+        template_fragments::split_templates(include_str!("templates/index.html")).unwrap();
+
+    assert_eq!(Index::FRAGMENTS.len(), templates.len());
+    for (fragment, content) in templates {
+        assert_eq!(Index::fragment(&fragment), Some(content.as_str()));
+    }
+    assert_eq!(Index::fragment("missing"), None);
+}
+
+#[test]
+fn generics() {
+    assert_eq!(Generic::<'static, String>::ITEM, Index::ITEM);
+    assert_eq!(Generic::<'static, u8>::fragment(""), Some(Index::BASE));
+}
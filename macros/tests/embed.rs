@@ -0,0 +1,33 @@
+use template_fragments::EmbeddedTemplates;
+use template_fragments_macros::embed_template_dir;
+
+static TEMPLATES: EmbeddedTemplates = embed_template_dir!("tests/templates");
+
+#[test]
+fn matches_split_directory() {
+    let root = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/templates");
+    let mut templates: Vec<_> = template_fragments::split_directory(root)
+        .unwrap()
+        .into_iter()
+        .collect();
+    templates.sort();
+
+    assert_eq!(
+        TEMPLATES
+            .iter()
+            .map(|(name, content)| (name.to_owned(), content.to_owned()))
+            .collect::<Vec<_>>(),
+        templates
+    );
+}
+
+#[test]
+fn lookup() {
+    assert_eq!(
+        TEMPLATES.get("index.html#item"),
+        Some("<li>{{ item }}</li>\n")
+    );
+    assert_eq!(TEMPLATES.get("pages/report.tex#title"), Some("Report\n"));
+    assert!(TEMPLATES.contains("pages/report.tex"));
+    assert!(!TEMPLATES.contains("report.tex"));
+}
@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+
+use crate::{
+    defines::LineMap, get_ending, iterate_with_endings, parse_fragment_tag, verbatim::Verbatim,
+    Config, Tag,
+};
+
+impl Config {
+    /// If `true`, annotate the output with comments to trace it back to the
+    /// template
+    ///
+    /// Each fragment starts with a comment that names the fragment and the
+    /// lines of the template it was taken from, e.g., `{# fragment "item"
+    /// lines 2-4 #}`. If the path of the template is known, e.g., with
+    /// [Config::split_templates_prefixed], it is included as well. In the base
+    /// template, the removed fragment tags are replaced with comments that mark
+    /// the start and end of each fragment. The comment markers of the syntax
+    /// are used. The annotations are meant for development, they change the
+    /// output.
+    ///
+    /// ```rust
+    /// # use template_fragments::Config;
+    /// let source = concat!(
+    ///     "<ul>\n",
+    ///     "  {% fragment item %}\n",
+    ///     "  <li>{{ item }}</li>\n",
+    ///     "  {% endfragment %}\n",
+    ///     "</ul>\n",
+    /// );
+    ///
+    /// let config = Config::default().debug_annotations(true);
+    /// let templates = config.split_templates_prefixed("index.html", source).unwrap();
+    /// assert_eq!(
+    ///     templates["index.html#item"],
+    ///     "{# fragment \"item\" from \"index.html\" lines 2-4 #}\n  <li>{{ item }}</li>\n",
+    /// );
+    /// assert_eq!(
+    ///     templates["index.html"],
+    ///     concat!(
+    ///         "<ul>\n",
+    ///         "  {# begin fragment \"item\" #}\n",
+    ///         "  <li>{{ item }}</li>\n",
+    ///         "  {# end fragment \"item\" #}\n",
+    ///         "</ul>\n",
+    ///     ),
+    /// );
+    /// ```
+    pub fn debug_annotations(mut self, value: bool) -> Self {
+        self.debug_annotations = value;
+        self
+    }
+
+    /// The comment that replaces a start or end tag line in the base template
+    pub(crate) fn boundary_annotation<'a>(
+        &self,
+        kind: &str,
+        line: &str,
+        fragments: impl IntoIterator<Item = &'a str>,
+    ) -> String {
+        let (open, close) = self.syntax.comment_markers();
+        let prefix = &line[..line.len() - line.trim_start().len()];
+        let ending = get_ending(line);
+        let mut fragments: Vec<String> = fragments
+            .into_iter()
+            .map(|fragment| format!("{fragment:?}"))
+            .collect();
+        fragments.sort();
+
+        let fragments = fragments.join(", ");
+        format!("{prefix}{open} {kind} fragment {fragments} {close}{ending}")
+    }
+
+    /// Collect the header comment of each fragment, if annotations are enabled
+    ///
+    /// Invalid tags are skipped, they are reported when processing the
+    /// template.
+    pub(crate) fn fragment_annotations(
+        &self,
+        src: &str,
+        lines: &LineMap,
+    ) -> HashMap<String, String> {
+        let mut res = HashMap::new();
+        if !self.debug_annotations {
+            return res;
+        }
+
+        // the 1-based lines of all occurrences of each fragment
+        let mut ranges: HashMap<&str, Vec<(usize, usize)>> = HashMap::new();
+        let mut open: Vec<(usize, Vec<&str>)> = Vec::new();
+        let mut verbatim = Verbatim::default();
+        for (line_idx, text) in iterate_with_endings(src).enumerate() {
+            let line = lines.original(line_idx) + 1;
+            if verbatim.skip(text, self.syntax.tag_markers()) {
+                continue;
+            }
+            match parse_fragment_tag(text, self.syntax.tag_markers()) {
+                Ok(Some(Tag::Start(tag))) => open.push((line, tag.fragments.into_iter().collect())),
+                Ok(Some(Tag::StartBlock(tag))) => open.push((line, vec![tag.fragment])),
+                Ok(Some(Tag::End(_) | Tag::EndBlock(_))) => {
+                    if let Some((start, fragments)) = open.pop() {
+                        for fragment in fragments {
+                            ranges.entry(fragment).or_default().push((start, line));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let (open, close) = self.syntax.comment_markers();
+        let path = match &self.debug_path {
+            Some(path) => format!(" from {path:?}"),
+            None => String::new(),
+        };
+        for (fragment, ranges) in ranges {
+            let ranges: Vec<String> = ranges
+                .into_iter()
+                .map(|(start, end)| format!("{start}-{end}"))
+                .collect();
+            let header = format!(
+                "{open} fragment {fragment:?}{path} lines {} {close}\n",
+                ranges.join(", ")
+            );
+            res.insert(fragment.to_owned(), header);
+        }
+        res
+    }
+}
+
+/// Prefix the content of a fragment with its header comment, if any
+pub(crate) fn annotate(
+    annotations: &HashMap<String, String>,
+    fragment: &str,
+    content: String,
+) -> String {
+    match annotations.get(fragment) {
+        Some(header) if !content.is_empty() => format!("{header}{content}"),
+        _ => content,
+    }
+}
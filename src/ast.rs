@@ -0,0 +1,299 @@
+use std::{borrow::Cow, ops::Range};
+
+use crate::{decode::leading_bom, iterate_with_endings, Config, ErrorWithLine, Processor, Tag};
+
+/// The parsed structure of an annotated template
+///
+/// The template is represented as a tree of text and fragment nodes that keep
+/// the original lines, including the fragment tags. Rendering the tree
+/// reproduces the source exactly. This way, tooling can inspect and transform
+/// templates without processing the lines itself.
+///
+/// ```rust
+/// # use template_fragments::{AstNode, TemplateAst};
+/// let source = concat!(
+///     "<body>\n",
+///     "  {% fragment item %}\n",
+///     "    <div>{{ item }}</div>\n",
+///     "  {% endfragment %}\n",
+///     "<body>\n",
+/// );
+///
+/// let ast = TemplateAst::parse(source).unwrap();
+/// assert_eq!(ast.nodes.len(), 3);
+///
+/// let AstNode::Fragment(fragment) = &ast.nodes[1] else {
+///     panic!();
+/// };
+/// assert_eq!(fragment.names, vec!["item"]);
+/// assert_eq!(&source[fragment.span.clone()], fragment.render());
+///
+/// assert_eq!(ast.render(), source);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TemplateAst {
+    /// The top-level nodes of the template
+    pub nodes: Vec<AstNode>,
+}
+
+/// A node of a [TemplateAst]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AstNode {
+    /// Consecutive lines without fragment tags
+    Text(TextNode),
+    /// A fragment including its start and end tags
+    Fragment(FragmentNode),
+}
+
+/// Consecutive lines without fragment tags
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextNode {
+    /// The lines including their line endings
+    pub content: String,
+    /// The byte range of the lines in the source
+    pub span: Range<usize>,
+}
+
+/// A fragment including its start and end tags
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FragmentNode {
+    /// Whether the fragment was defined with `fragment-block`
+    pub block: bool,
+    /// The sorted names of the fragment
+    pub names: Vec<String>,
+    /// The attributes of the start tag in order
+    pub attributes: Vec<(String, String)>,
+    /// The line of the start tag including its line ending
+    pub start_tag: String,
+    /// The line of the end tag including its line ending
+    pub end_tag: String,
+    /// The nodes between the start and end tags
+    pub children: Vec<AstNode>,
+    /// The byte range of the fragment in the source, including its tags
+    pub span: Range<usize>,
+}
+
+/// A visitor over the nodes of a [TemplateAst]
+///
+/// All methods do nothing by default. Line indices are zero-based, as in
+/// [crate::ErrorWithLine].
+///
+/// ```rust
+/// # use template_fragments::{FragmentNode, TemplateAst, Visitor};
+/// #[derive(Default)]
+/// struct MaxDepth {
+///     depth: usize,
+///     max_depth: usize,
+/// }
+///
+/// impl Visitor for MaxDepth {
+///     fn enter_fragment(&mut self, _line_idx: usize, _node: &FragmentNode) {
+///         self.depth += 1;
+///         self.max_depth = self.max_depth.max(self.depth);
+///     }
+///
+///     fn leave_fragment(&mut self, _line_idx: usize, _node: &FragmentNode) {
+///         self.depth -= 1;
+///     }
+/// }
+///
+/// let source = concat!(
+///     "{% fragment items %}\n",
+///     "  {% fragment item %}\n",
+///     "  {% endfragment %}\n",
+///     "{% endfragment %}\n",
+/// );
+///
+/// let mut visitor = MaxDepth::default();
+/// TemplateAst::parse(source).unwrap().walk(&mut visitor);
+/// assert_eq!(visitor.max_depth, 2);
+/// ```
+pub trait Visitor {
+    /// Called for the start tag of a fragment, before its children
+    fn enter_fragment(&mut self, line_idx: usize, node: &FragmentNode) {
+        let _ = (line_idx, node);
+    }
+
+    /// Called for each line of a text node, including its line ending
+    fn text_line(&mut self, line_idx: usize, line: &str) {
+        let _ = (line_idx, line);
+    }
+
+    /// Called for the end tag of a fragment, after its children
+    fn leave_fragment(&mut self, line_idx: usize, node: &FragmentNode) {
+        let _ = (line_idx, node);
+    }
+}
+
+impl TemplateAst {
+    /// Parse a template with the default configuration
+    ///
+    /// See [Config::parse_ast].
+    pub fn parse(src: &str) -> Result<Self, ErrorWithLine> {
+        Config::default().parse_ast(src)
+    }
+
+    /// Render the template including all fragment tags
+    pub fn render(&self) -> String {
+        let mut res = String::new();
+        for node in &self.nodes {
+            node.render_into(&mut res);
+        }
+        res
+    }
+
+    /// Visit all nodes in source order
+    pub fn walk(&self, visitor: &mut impl Visitor) {
+        let mut line_idx = 0;
+        walk_nodes(&self.nodes, visitor, &mut line_idx);
+    }
+}
+
+fn walk_nodes(nodes: &[AstNode], visitor: &mut impl Visitor, line_idx: &mut usize) {
+    for node in nodes {
+        match node {
+            AstNode::Text(node) => {
+                for line in iterate_with_endings(&node.content) {
+                    visitor.text_line(*line_idx, line);
+                    *line_idx += 1;
+                }
+            }
+            AstNode::Fragment(node) => {
+                visitor.enter_fragment(*line_idx, node);
+                *line_idx += 1;
+                walk_nodes(&node.children, visitor, line_idx);
+                visitor.leave_fragment(*line_idx, node);
+                *line_idx += 1;
+            }
+        }
+    }
+}
+
+impl AstNode {
+    /// The byte range of the node in the source
+    pub fn span(&self) -> Range<usize> {
+        match self {
+            Self::Text(node) => node.span.clone(),
+            Self::Fragment(node) => node.span.clone(),
+        }
+    }
+
+    /// Render the node including all fragment tags
+    pub fn render(&self) -> String {
+        let mut res = String::new();
+        self.render_into(&mut res);
+        res
+    }
+
+    fn render_into(&self, res: &mut String) {
+        match self {
+            Self::Text(node) => res.push_str(&node.content),
+            Self::Fragment(node) => node.render_into(res),
+        }
+    }
+}
+
+impl FragmentNode {
+    /// Render the fragment including its tags
+    pub fn render(&self) -> String {
+        let mut res = String::new();
+        self.render_into(&mut res);
+        res
+    }
+
+    fn render_into(&self, res: &mut String) {
+        res.push_str(&self.start_tag);
+        for child in &self.children {
+            child.render_into(res);
+        }
+        res.push_str(&self.end_tag);
+    }
+}
+
+impl Config {
+    /// Parse the template into its structure
+    ///
+    /// The template is validated as in [Config::split_templates].
+    pub fn parse_ast(&self, src: &str) -> Result<TemplateAst, ErrorWithLine> {
+        // the byte order mark is removed by preprocessing, keep it in the
+        // first line
+        let bom = leading_bom(src).unwrap_or_default();
+        let (src, lines) = self.preprocess(src)?;
+        let mut processor = Processor::new(self);
+        let mut nodes: Vec<AstNode> = Vec::new();
+        let mut open: Vec<(FragmentNode, Vec<AstNode>)> = Vec::new();
+        let mut offset = 0;
+
+        for (line_idx, line) in iterate_with_endings(&src).enumerate() {
+            let source_line = match line_idx {
+                0 if !bom.is_empty() => Cow::Owned(format!("{bom}{line}")),
+                _ => Cow::Borrowed(line),
+            };
+            let span = offset..offset + source_line.len();
+            offset = span.end;
+
+            let tag = processor
+                .parse(line)
+                .map_err(|err| lines.restore(err.at(line_idx)))?;
+            let started = start_node(&tag, &source_line, span.clone());
+            let is_end = matches!(tag, Some(Tag::End(_) | Tag::EndBlock(_)));
+            processor
+                .apply(line_idx, line, tag, |_, _| {})
+                .map_err(|err| lines.restore(err))?;
+
+            if let Some(node) = started {
+                open.push((node, std::mem::take(&mut nodes)));
+            } else if is_end {
+                // the processor verified that there is a matching start tag
+                let (mut node, parent) = open.pop().unwrap();
+                node.end_tag = line.to_owned();
+                node.span.end = span.end;
+                node.children = std::mem::replace(&mut nodes, parent);
+                nodes.push(AstNode::Fragment(node));
+            } else if let Some(AstNode::Text(text)) = nodes.last_mut() {
+                text.content.push_str(line);
+                text.span.end = span.end;
+            } else {
+                nodes.push(AstNode::Text(TextNode {
+                    content: source_line.into_owned(),
+                    span,
+                }));
+            }
+        }
+        processor.done().map_err(|err| lines.restore(err))?;
+        if src.is_empty() && !bom.is_empty() {
+            nodes.push(AstNode::Text(TextNode {
+                content: bom.to_owned(),
+                span: 0..bom.len(),
+            }));
+        }
+
+        Ok(TemplateAst { nodes })
+    }
+}
+
+fn start_node(tag: &Option<Tag<'_>>, line: &str, span: Range<usize>) -> Option<FragmentNode> {
+    let (block, mut names, attributes) = match tag {
+        Some(Tag::Start(tag)) => (
+            false,
+            tag.fragments.iter().map(|&f| f.to_owned()).collect(),
+            &tag.attributes,
+        ),
+        Some(Tag::StartBlock(tag)) => (true, vec![tag.fragment.to_owned()], &tag.attributes),
+        _ => return None,
+    };
+    names.sort();
+
+    Some(FragmentNode {
+        block,
+        names,
+        attributes: attributes
+            .iter()
+            .map(|&(key, value)| (key.to_owned(), value.to_owned()))
+            .collect(),
+        start_tag: line.to_owned(),
+        end_tag: String::new(),
+        children: Vec::new(),
+        span,
+    })
+}
@@ -0,0 +1,112 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use tokio::io::{AsyncBufRead, AsyncReadExt};
+
+use crate::{directory::relative_name, join_path, Config, DirectoryError};
+
+/// Read a template from an async reader and split it into all fragments
+///
+/// See [Config::split_templates_async].
+pub async fn split_templates_async(
+    reader: impl AsyncBufRead + Unpin,
+) -> std::io::Result<HashMap<String, String>> {
+    Config::default().split_templates_async(reader).await
+}
+
+/// Split all templates in a directory tree without blocking the runtime
+///
+/// The result is the same as for [crate::split_directory].
+///
+/// ```rust,no_run
+/// # use template_fragments::split_directory_async;
+/// # async fn load() -> Result<(), template_fragments::DirectoryError> {
+/// let templates = split_directory_async("templates").await?;
+/// let item = &templates["index.html#item"];
+/// # Ok(())
+/// # }
+/// ```
+pub async fn split_directory_async(
+    root: impl AsRef<Path>,
+) -> Result<HashMap<String, String>, DirectoryError> {
+    let root = root.as_ref();
+    let mut res = HashMap::new();
+
+    for path in list_files(root).await? {
+        let src = tokio::fs::read_to_string(&path)
+            .await
+            .map_err(|err| DirectoryError::Io(path.clone(), err))?;
+        let name = relative_name(root, &path);
+
+        let templates = Config::for_path(&path)
+            .split_templates(&src)
+            .map_err(|err| DirectoryError::Template(err.in_path(&name)))?;
+        for (fragment, content) in templates {
+            res.insert(join_path(&name, &fragment), content);
+        }
+    }
+
+    Ok(res)
+}
+
+impl Config {
+    /// Read a template from an async reader and split it into all fragments
+    ///
+    /// Requires the `tokio` feature. The template is read completely before
+    /// it is processed. Invalid templates result in an error with kind
+    /// `InvalidData` that wraps the [crate::ErrorWithLine].
+    ///
+    /// ```rust
+    /// # use template_fragments::{Config, ErrorWithLine};
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// let source = "{% fragment item %}\n<li></li>\n{% endfragment %}\n";
+    /// let templates = Config::default()
+    ///     .split_templates_async(source.as_bytes())
+    ///     .await
+    ///     .unwrap();
+    /// assert_eq!(templates["item"], "<li></li>\n");
+    ///
+    /// let err = Config::default()
+    ///     .split_templates_async("{% fragment item %}\n".as_bytes())
+    ///     .await
+    ///     .unwrap_err();
+    /// let err = err.get_ref().unwrap().downcast_ref::<ErrorWithLine>().unwrap();
+    /// assert_eq!(err.0, 0);
+    /// # });
+    /// ```
+    pub async fn split_templates_async(
+        &self,
+        mut reader: impl AsyncBufRead + Unpin,
+    ) -> std::io::Result<HashMap<String, String>> {
+        let mut src = String::new();
+        reader.read_to_string(&mut src).await?;
+
+        self.split_templates(&src)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))
+    }
+}
+
+/// List all files below `root` in a stable order, as for [crate::split_directory]
+async fn list_files(root: &Path) -> Result<Vec<PathBuf>, DirectoryError> {
+    let mut res = Vec::new();
+    let mut dirs = vec![root.to_path_buf()];
+
+    while let Some(dir) = dirs.pop() {
+        let io_error = |err| DirectoryError::Io(dir.clone(), err);
+
+        let mut entries = tokio::fs::read_dir(&dir).await.map_err(io_error)?;
+        while let Some(entry) = entries.next_entry().await.map_err(io_error)? {
+            let path = entry.path();
+            if tokio::fs::metadata(&path).await.map_err(io_error)?.is_dir() {
+                dirs.push(path);
+            } else {
+                res.push(path);
+            }
+        }
+    }
+    res.sort();
+
+    Ok(res)
+}
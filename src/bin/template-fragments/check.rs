@@ -0,0 +1,50 @@
+use template_fragments::{Config, Severity};
+
+use crate::{display_path, Args, Format, Reporter, UsageError};
+
+/// Validate all files and report their errors
+///
+/// With `--diagnostics`, errors and lint warnings are written to stdout as
+/// one JSON object per line, see [template_fragments::Diagnostic::to_json].
+/// Returns whether all files are valid, warnings do not fail the check.
+pub fn run(args: &[String]) -> Result<bool, UsageError> {
+    let args = Args::parse(args, &["--diagnostics"], &[])?;
+    if args.has("--diagnostics") {
+        return diagnostics(&args);
+    }
+    let mut reporter = Reporter::new(args.format);
+
+    let results = reporter.process_templates(&args, |path, src| {
+        Config::for_path(path).split_templates(src).err()
+    });
+    for (path, src, err) in results {
+        if let Some(err) = err {
+            reporter.template_error(path, &src, &err);
+        }
+    }
+
+    Ok(reporter.finish(true, Vec::new()))
+}
+
+/// Print the diagnostics of all files, fails only for errors
+fn diagnostics(args: &Args<'_>) -> Result<bool, UsageError> {
+    if args.format == Format::Json {
+        return Err(UsageError(String::from(
+            "--diagnostics cannot be combined with --format json",
+        )));
+    }
+    let mut reporter = Reporter::new(args.format);
+
+    let results = reporter.process_templates(args, |path, src| {
+        Config::for_path(path).validate_template(src)
+    });
+    let mut valid = true;
+    for (path, _, diagnostics) in results {
+        for diagnostic in diagnostics {
+            valid &= diagnostic.severity != Severity::Error;
+            println!("{}", diagnostic.in_path(display_path(path)).to_json());
+        }
+    }
+
+    Ok(reporter.finish(valid, Vec::new()))
+}
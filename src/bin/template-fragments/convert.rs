@@ -0,0 +1,67 @@
+use template_fragments::{Config, Syntax};
+
+use crate::{display_path, json::Json, Args, Format, Reporter, UsageError, STDIN};
+
+/// Rewrite the fragment tags of another syntax in place
+///
+/// The target syntax of each file is selected by [Config::for_path]. Templates
+/// read from stdin are written to stdout. Returns whether all files could be
+/// converted.
+pub fn run(args: &[String]) -> Result<bool, UsageError> {
+    let args = Args::parse(args, &[], &["--from"])?;
+    let from = match args.option("--from").unwrap_or("html") {
+        "html" => Syntax::html_comments(),
+        "c" => Syntax::c_comments(),
+        "erb" => Syntax::erb(),
+        "go" => Syntax::golang(),
+        "jinja" => Syntax::jinja(),
+        from => return Err(UsageError(format!("unknown syntax {from:?}"))),
+    };
+    let mut reporter = Reporter::new(args.format);
+
+    let mut files = Vec::new();
+    let results = reporter.process_templates(&args, |path, src| {
+        Config::for_path(path).convert_template(src, &from)
+    });
+    for (path, src, res) in results {
+        let converted = match res {
+            Ok(converted) => converted,
+            Err(err) => {
+                reporter.template_error(path, &src, &err);
+                continue;
+            }
+        };
+
+        let status = if converted == src {
+            "unchanged"
+        } else if path == STDIN {
+            "converted"
+        } else if let Err(err) = std::fs::write(path, &converted) {
+            reporter.error(path, None, &err.to_string());
+            continue;
+        } else {
+            "converted"
+        };
+
+        // templates read from stdin are always written to stdout
+        let output = (path == STDIN).then_some(converted);
+
+        match (args.format, output) {
+            (Format::Text, Some(output)) => print!("{output}"),
+            (Format::Text, None) if status != "unchanged" => {
+                println!("{}: {status}", display_path(path))
+            }
+            (Format::Text, None) => {}
+            (Format::Json, output) => files.push(Json::object([
+                ("path", Json::from(display_path(path))),
+                ("status", Json::from(status)),
+                (
+                    "output",
+                    output.map(|output| Json::from(output.as_str())).into(),
+                ),
+            ])),
+        }
+    }
+
+    Ok(reporter.finish(true, vec![("files", Json::Array(files))]))
+}
@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+
+use template_fragments::{join_path, Config, TemplateDiff};
+
+use crate::{display_path, json::Json, Args, Format, Reporter, UsageError};
+
+/// Report the fragments that differ between two versions of a template
+///
+/// Fragments are listed with the hashes of their content. As for `diff`,
+/// returns whether both versions contain the same fragments.
+pub fn run(args: &[String]) -> Result<bool, UsageError> {
+    let args = Args::parse(args, &[], &[])?;
+    if args.files.len() != 2 {
+        return Err(UsageError(String::from(
+            "diff requires exactly two files, the old and the new version",
+        )));
+    }
+    let mut reporter = Reporter::new(args.format);
+
+    let results = reporter.process_templates(&args, |path, src| {
+        Config::for_path(path).split_templates(src)
+    });
+    let mut versions = Vec::new();
+    for (path, src, res) in results {
+        match res {
+            Ok(templates) => versions.push(templates),
+            Err(err) => reporter.template_error(path, &src, &err),
+        }
+    }
+    let [old, new] = versions.as_slice() else {
+        return Ok(reporter.finish(false, Vec::new()));
+    };
+
+    let diff = TemplateDiff::between(old, new);
+    let path = display_path(&args.files[1]);
+    let hash = |templates: &HashMap<String, String>, fragment: &str| {
+        format!("{:016x}", fnv1a(&templates[fragment]))
+    };
+
+    if args.format == Format::Text {
+        for fragment in &diff.added {
+            println!(
+                "added {} {}",
+                join_path(path, fragment),
+                hash(new, fragment)
+            );
+        }
+        for fragment in &diff.removed {
+            println!(
+                "removed {} {}",
+                join_path(path, fragment),
+                hash(old, fragment)
+            );
+        }
+        for fragment in &diff.changed {
+            println!(
+                "changed {} {} -> {}",
+                join_path(path, fragment),
+                hash(old, fragment),
+                hash(new, fragment),
+            );
+        }
+    }
+
+    let entries =
+        |fragments: &[String], old: Option<&HashMap<_, _>>, new: Option<&HashMap<_, _>>| {
+            let entries = fragments.iter().map(|fragment| {
+                Json::object([
+                    ("fragment", Json::from(fragment.as_str())),
+                    (
+                        "old_hash",
+                        old.map(|old| Json::from(hash(old, fragment).as_str()))
+                            .into(),
+                    ),
+                    (
+                        "new_hash",
+                        new.map(|new| Json::from(hash(new, fragment).as_str()))
+                            .into(),
+                    ),
+                ])
+            });
+            Json::Array(entries.collect())
+        };
+    let results = vec![
+        ("added", entries(&diff.added, None, Some(new))),
+        ("removed", entries(&diff.removed, Some(old), None)),
+        ("changed", entries(&diff.changed, Some(old), Some(new))),
+    ];
+    Ok(reporter.finish(diff.is_empty(), results))
+}
+
+/// The 64 bit FNV-1a hash of the content, which is stable across versions
+fn fnv1a(content: &str) -> u64 {
+    content.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
@@ -0,0 +1,42 @@
+use template_fragments::Config;
+
+use crate::{display_path, json::Json, Args, Format, Reporter, UsageError};
+
+/// Print a single fragment of all files, as it is served
+///
+/// Unknown fragments are reported as errors. Returns whether the fragment
+/// could be extracted from all files.
+pub fn run(args: &[String]) -> Result<bool, UsageError> {
+    let args = Args::parse(args, &[], &["--fragment"])?;
+    let Some(fragment) = args.option("--fragment") else {
+        return Err(UsageError(String::from("missing option \"--fragment\"")));
+    };
+    let mut reporter = Reporter::new(args.format);
+
+    let mut fragments = Vec::new();
+    let results = reporter.process_templates(&args, |path, src| {
+        Config::for_path(path)
+            .strict_fragments(true)
+            .filter_template(src, fragment)
+    });
+    for (path, src, res) in results {
+        let content = match res {
+            Ok(content) => content,
+            Err(err) => {
+                reporter.template_error(path, &src, &err);
+                continue;
+            }
+        };
+
+        match args.format {
+            Format::Text => print!("{content}"),
+            Format::Json => fragments.push(Json::object([
+                ("path", Json::from(display_path(path))),
+                ("fragment", Json::from(fragment)),
+                ("content", Json::from(content.as_str())),
+            ])),
+        }
+    }
+
+    Ok(reporter.finish(true, vec![("fragments", Json::Array(fragments))]))
+}
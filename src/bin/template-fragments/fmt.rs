@@ -0,0 +1,64 @@
+use template_fragments::Config;
+
+use crate::{display_path, json::Json, Args, Format, Reporter, UsageError, STDIN};
+
+/// Format all files in place, or only check them with `--check`
+///
+/// Templates read from stdin are written to stdout. Returns whether all files could be formatted or, with `--check`, are
+/// already formatted.
+pub fn run(args: &[String]) -> Result<bool, UsageError> {
+    let args = Args::parse(args, &["--check"], &[])?;
+    let check = args.has("--check");
+    let mut reporter = Reporter::new(args.format);
+
+    let mut success = true;
+    let mut files = Vec::new();
+    let results = reporter.process_templates(&args, |path, src| {
+        Config::for_path(path).format_template(src)
+    });
+    for (path, src, res) in results {
+        let formatted = match res {
+            Ok(formatted) => formatted,
+            Err(err) => {
+                reporter.template_error(path, &src, &err);
+                continue;
+            }
+        };
+
+        let changed = formatted != src;
+        let status = if !changed {
+            "unchanged"
+        } else if check {
+            success = false;
+            "unformatted"
+        } else if path == STDIN {
+            "formatted"
+        } else if let Err(err) = std::fs::write(path, &formatted) {
+            reporter.error(path, None, &err.to_string());
+            continue;
+        } else {
+            "formatted"
+        };
+
+        // templates read from stdin are always written to stdout
+        let output = (path == STDIN && !check).then_some(formatted);
+
+        match (args.format, output) {
+            (Format::Text, Some(output)) => print!("{output}"),
+            (Format::Text, None) if status != "unchanged" => {
+                println!("{}: {status}", display_path(path))
+            }
+            (Format::Text, None) => {}
+            (Format::Json, output) => files.push(Json::object([
+                ("path", Json::from(display_path(path))),
+                ("status", Json::from(status)),
+                (
+                    "output",
+                    output.map(|output| Json::from(output.as_str())).into(),
+                ),
+            ])),
+        }
+    }
+
+    Ok(reporter.finish(success, vec![("files", Json::Array(files))]))
+}
@@ -0,0 +1,188 @@
+//! Portable expansion of glob patterns in file arguments
+//!
+//! Supported are `*` (any characters of a file name), `?` (a single
+//! character), `[abc]`, `[a-z]` and `[!abc]` (a character class), and `**` as
+//! a complete path component (any number of directories). Wildcards do not
+//! match a leading `.` of a file name. On Windows, `\` is treated as a path
+//! separator.
+use std::path::Path;
+
+/// Whether the argument contains any glob characters
+pub fn is_pattern(arg: &str) -> bool {
+    arg.contains(['*', '?', '['])
+}
+
+/// Expand the pattern into the sorted list of matching files
+pub fn expand(pattern: &str) -> Vec<String> {
+    let pattern = normalize(pattern);
+    let parts: Vec<&str> = pattern.split('/').collect();
+
+    let mut res = Vec::new();
+    match parts.split_first() {
+        // absolute paths start with an empty component
+        Some((&"", rest)) => walk("/", rest, &mut res),
+        _ => walk("", &parts, &mut res),
+    }
+    res.sort();
+    res.dedup();
+    res
+}
+
+/// Whether the path matches the pattern
+///
+/// Patterns without separators are matched against the file name, e.g.,
+/// `*.min.html` matches `templates/base.min.html`.
+pub fn matches(pattern: &str, path: &str) -> bool {
+    let pattern = normalize(pattern);
+    let path = normalize(path);
+    let pattern = pattern.strip_prefix("./").unwrap_or(&pattern);
+    let path = path.strip_prefix("./").unwrap_or(&path);
+
+    if !pattern.contains('/') {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        return match_name(pattern, name);
+    }
+
+    let pattern: Vec<&str> = pattern.split('/').collect();
+    let path: Vec<&str> = path.split('/').collect();
+    match_parts(&pattern, &path)
+}
+
+fn normalize(path: &str) -> String {
+    if cfg!(windows) {
+        path.replace('\\', "/")
+    } else {
+        path.to_owned()
+    }
+}
+
+fn walk(prefix: &str, parts: &[&str], res: &mut Vec<String>) {
+    let Some((&part, rest)) = parts.split_first() else {
+        return;
+    };
+
+    if part == "**" {
+        // `**` matches zero directories or descends into every directory
+        if rest.is_empty() {
+            walk(prefix, &["*"], res);
+        } else {
+            walk(prefix, rest, res);
+        }
+        for (name, path) in read_dir(prefix) {
+            if !name.starts_with('.') && Path::new(&path).is_dir() {
+                walk(&path, parts, res);
+            }
+        }
+    } else if !is_pattern(part) {
+        descend(join(prefix, part), rest, res);
+    } else {
+        for (name, path) in read_dir(prefix) {
+            if (!name.starts_with('.') || part.starts_with('.')) && match_name(part, &name) {
+                descend(path, rest, res);
+            }
+        }
+    }
+}
+
+fn descend(path: String, rest: &[&str], res: &mut Vec<String>) {
+    if rest.is_empty() {
+        if Path::new(&path).is_file() {
+            res.push(path);
+        }
+    } else if Path::new(&path).is_dir() {
+        walk(&path, rest, res);
+    }
+}
+
+/// The sorted names and paths of all entries of a directory, errors are
+/// treated as an empty directory
+fn read_dir(prefix: &str) -> Vec<(String, String)> {
+    let dir = if prefix.is_empty() { "." } else { prefix };
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut res: Vec<(String, String)> = entries
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .map(|name| {
+            let path = join(prefix, &name);
+            (name, path)
+        })
+        .collect();
+    res.sort();
+    res
+}
+
+fn join(prefix: &str, name: &str) -> String {
+    if prefix.is_empty() {
+        name.to_owned()
+    } else if prefix.ends_with('/') {
+        format!("{prefix}{name}")
+    } else {
+        format!("{prefix}/{name}")
+    }
+}
+
+fn match_parts(pattern: &[&str], path: &[&str]) -> bool {
+    match (pattern.split_first(), path.split_first()) {
+        (None, None) => true,
+        (Some((&"**", rest)), _) => {
+            match_parts(rest, path) || (!path.is_empty() && match_parts(pattern, &path[1..]))
+        }
+        (Some((&part, pattern)), Some((&name, path))) => {
+            match_name(part, name) && match_parts(pattern, path)
+        }
+        _ => false,
+    }
+}
+
+/// Match a single path component against a pattern without separators
+fn match_name(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    match_chars(&pattern, &name)
+}
+
+fn match_chars(pattern: &[char], name: &[char]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some(('*', rest)) => (0..=name.len()).any(|skip| match_chars(rest, &name[skip..])),
+        Some(('?', rest)) => !name.is_empty() && match_chars(rest, &name[1..]),
+        Some(('[', rest)) => match (match_class(rest), name.split_first()) {
+            (Some((matcher, rest)), Some((&ch, name))) => matcher(ch) && match_chars(rest, name),
+            // an unclosed class matches the literal `[`
+            (None, Some(('[', name))) => match_chars(rest, name),
+            _ => false,
+        },
+        Some((&ch, rest)) => name.first() == Some(&ch) && match_chars(rest, &name[1..]),
+    }
+}
+
+/// Parse a character class after the opening `[`
+///
+/// Returns a function that tests characters and the pattern after the class.
+fn match_class(pattern: &[char]) -> Option<(impl Fn(char) -> bool + '_, &[char])> {
+    let (negated, class) = match pattern.split_first() {
+        Some(('!', rest)) => (true, rest),
+        _ => (false, pattern),
+    };
+    // a `]` directly after the opening bracket is part of the class
+    let end = 1 + class.get(1..)?.iter().position(|&ch| ch == ']')?;
+    let (class, rest) = (&class[..end], &class[end + 1..]);
+
+    let matcher = move |ch: char| {
+        let mut idx = 0;
+        let mut found = false;
+        while idx < class.len() {
+            if idx + 2 < class.len() && class[idx + 1] == '-' {
+                found |= (class[idx]..=class[idx + 2]).contains(&ch);
+                idx += 3;
+            } else {
+                found |= class[idx] == ch;
+                idx += 1;
+            }
+        }
+        found != negated
+    };
+    Some((matcher, rest))
+}
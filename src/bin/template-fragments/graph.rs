@@ -0,0 +1,154 @@
+use template_fragments::{join_path, Config, FragmentNode, Visitor};
+
+use crate::{display_path, json::Json, Args, Format, Reporter, UsageError};
+
+/// The language of the emitted graph
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Language {
+    Dot,
+    Mermaid,
+}
+
+/// The fragments of a template and the edges from the containing to the
+/// contained fragments, the base template is `""`
+#[derive(Default)]
+struct Containment {
+    fragments: Vec<String>,
+    edges: Vec<(String, String)>,
+    open: Vec<Vec<String>>,
+}
+
+impl Visitor for Containment {
+    fn enter_fragment(&mut self, _line_idx: usize, node: &FragmentNode) {
+        let parents = self
+            .open
+            .last()
+            .cloned()
+            .unwrap_or_else(|| vec![String::new()]);
+        for name in &node.names {
+            if !self.fragments.contains(name) {
+                self.fragments.push(name.clone());
+            }
+            for parent in &parents {
+                let edge = (parent.clone(), name.clone());
+                if !self.edges.contains(&edge) {
+                    self.edges.push(edge);
+                }
+            }
+        }
+        self.open.push(node.names.clone());
+    }
+
+    fn leave_fragment(&mut self, _line_idx: usize, _node: &FragmentNode) {
+        self.open.pop();
+    }
+}
+
+/// Emit the graph of which fragments contain which other fragments
+///
+/// Each template is drawn as a cluster with its base template as root. Returns
+/// whether all files are valid.
+pub fn run(args: &[String]) -> Result<bool, UsageError> {
+    let args = Args::parse(args, &[], &["--graph"])?;
+    let language = match args.option("--graph").unwrap_or("dot") {
+        "dot" => Language::Dot,
+        "mermaid" => Language::Mermaid,
+        language => return Err(UsageError(format!("unknown graph {language:?}"))),
+    };
+    let mut reporter = Reporter::new(args.format);
+
+    let mut templates = Vec::new();
+    let results =
+        reporter.process_templates(&args, |path, src| Config::for_path(path).parse_ast(src));
+    for (path, src, res) in results {
+        match res {
+            Ok(ast) => {
+                let mut containment = Containment::default();
+                ast.walk(&mut containment);
+                templates.push((display_path(path), containment));
+            }
+            Err(err) => reporter.template_error(path, &src, &err),
+        }
+    }
+
+    match (args.format, language) {
+        (Format::Text, Language::Dot) => print_dot(&templates),
+        (Format::Text, Language::Mermaid) => print_mermaid(&templates),
+        (Format::Json, _) => {}
+    }
+    let results = match args.format {
+        Format::Text => Vec::new(),
+        Format::Json => to_json(&templates),
+    };
+    Ok(reporter.finish(true, results))
+}
+
+/// The nodes and edges of all templates, keyed by the full path of fragments
+fn to_json(templates: &[(&str, Containment)]) -> Vec<(&'static str, Json)> {
+    let mut nodes = Vec::new();
+    let mut edges = Vec::new();
+    for (path, containment) in templates {
+        nodes.push(Json::from(*path));
+        for fragment in &containment.fragments {
+            nodes.push(Json::from(join_path(path, fragment).as_str()));
+        }
+        for (from, to) in &containment.edges {
+            edges.push(Json::object([
+                ("from", Json::from(join_path(path, from).as_str())),
+                ("to", Json::from(join_path(path, to).as_str())),
+            ]));
+        }
+    }
+    vec![("nodes", Json::Array(nodes)), ("edges", Json::Array(edges))]
+}
+
+fn print_dot(templates: &[(&str, Containment)]) {
+    println!("digraph fragments {{");
+    for (idx, (path, containment)) in templates.iter().enumerate() {
+        println!("  subgraph cluster_{idx} {{");
+        println!("    label={};", quote(path));
+        println!("    {} [label={}];", quote(path), quote(path));
+        for fragment in &containment.fragments {
+            let id = quote(&join_path(path, fragment));
+            println!("    {id} [label={}];", quote(fragment));
+        }
+        for (from, to) in &containment.edges {
+            let from = quote(&join_path(path, from));
+            let to = quote(&join_path(path, to));
+            println!("    {from} -> {to};");
+        }
+        println!("  }}");
+    }
+    println!("}}");
+}
+
+fn print_mermaid(templates: &[(&str, Containment)]) {
+    println!("flowchart LR");
+    for (idx, (path, containment)) in templates.iter().enumerate() {
+        // mermaid ids are restricted, use the position of the node instead
+        let id = |fragment: &str| match containment.fragments.iter().position(|f| f == fragment) {
+            Some(pos) => format!("t{idx}f{pos}"),
+            None => format!("t{idx}"),
+        };
+
+        println!("  subgraph s{idx} [{}]", mermaid_label(path));
+        println!("    t{idx}[{}]", mermaid_label(path));
+        for fragment in &containment.fragments {
+            println!("    {}[{}]", id(fragment), mermaid_label(fragment));
+        }
+        for (from, to) in &containment.edges {
+            println!("    {} --> {}", id(from), id(to));
+        }
+        println!("  end");
+    }
+}
+
+/// Quote a DOT label or id, escaping quotes and backslashes
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Quote a mermaid label, quotes are written as entity codes
+fn mermaid_label(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "#quot;"))
+}
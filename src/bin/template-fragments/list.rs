@@ -0,0 +1,117 @@
+use template_fragments::{Config, FragmentNode, Visitor};
+
+use crate::{display_path, json::Json, Args, Format, Reporter, UsageError};
+
+/// A fragment of the tree with its one-based lines
+struct Entry {
+    names: Vec<String>,
+    block: bool,
+    start_line: usize,
+    end_line: usize,
+    children: Vec<Entry>,
+}
+
+impl Entry {
+    fn print(&self, depth: usize) {
+        let kind = if self.block { " [block]" } else { "" };
+        println!(
+            "{:indent$}{}{kind} (lines {}-{})",
+            "",
+            self.names.join(", "),
+            self.start_line,
+            self.end_line,
+            indent = 2 * depth,
+        );
+        for child in &self.children {
+            child.print(depth + 1);
+        }
+    }
+
+    fn to_json(&self) -> Json {
+        Json::object([
+            (
+                "names",
+                Json::Array(self.names.iter().map(|n| Json::from(n.as_str())).collect()),
+            ),
+            ("block", Json::Bool(self.block)),
+            ("start_line", Json::from(self.start_line)),
+            ("end_line", Json::from(self.end_line)),
+            (
+                "children",
+                Json::Array(self.children.iter().map(Entry::to_json).collect()),
+            ),
+        ])
+    }
+}
+
+/// Collect the fragments of a template as a tree
+#[derive(Default)]
+struct Tree {
+    roots: Vec<Entry>,
+    open: Vec<Entry>,
+}
+
+impl Visitor for Tree {
+    fn enter_fragment(&mut self, line_idx: usize, node: &FragmentNode) {
+        self.open.push(Entry {
+            names: node.names.clone(),
+            block: node.block,
+            start_line: line_idx + 1,
+            end_line: line_idx + 1,
+            children: Vec::new(),
+        });
+    }
+
+    fn leave_fragment(&mut self, line_idx: usize, _node: &FragmentNode) {
+        let Some(mut entry) = self.open.pop() else {
+            return;
+        };
+        entry.end_line = line_idx + 1;
+        match self.open.last_mut() {
+            Some(parent) => parent.children.push(entry),
+            None => self.roots.push(entry),
+        }
+    }
+}
+
+/// List the fragments of all files as a tree
+///
+/// Returns whether all files are valid.
+pub fn run(args: &[String]) -> Result<bool, UsageError> {
+    let args = Args::parse(args, &[], &[])?;
+    let mut reporter = Reporter::new(args.format);
+
+    let mut templates = Vec::new();
+    let results =
+        reporter.process_templates(&args, |path, src| Config::for_path(path).parse_ast(src));
+    for (path, src, res) in results {
+        let ast = match res {
+            Ok(ast) => ast,
+            Err(err) => {
+                reporter.template_error(path, &src, &err);
+                continue;
+            }
+        };
+
+        let mut tree = Tree::default();
+        ast.walk(&mut tree);
+
+        match args.format {
+            Format::Text => {
+                println!("{}", display_path(path));
+                for entry in &tree.roots {
+                    entry.print(1);
+                }
+            }
+            Format::Json => templates.push(Json::object([
+                ("path", Json::from(display_path(path))),
+                (
+                    "fragments",
+                    Json::Array(tree.roots.iter().map(Entry::to_json).collect()),
+                ),
+            ])),
+        }
+    }
+
+    Ok(reporter.finish(true, vec![("templates", Json::Array(templates))]))
+}
@@ -0,0 +1,17 @@
+use crate::UsageError;
+
+/// Run the language server on stdin and stdout
+///
+/// Returns whether the server exited without errors.
+pub fn run(args: &[String]) -> Result<bool, UsageError> {
+    if let Some(arg) = args.first() {
+        return Err(UsageError(format!("unexpected argument {arg:?}")));
+    }
+    match template_fragments::lsp::run_stdio() {
+        Ok(()) => Ok(true),
+        Err(err) => {
+            eprintln!("error: {err}");
+            Ok(false)
+        }
+    }
+}
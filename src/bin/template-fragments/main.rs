@@ -0,0 +1,347 @@
+//! Command line interface of `template-fragments`
+//!
+//! Usage: `template-fragments COMMAND [OPTIONS] FILES...`. The markers of each
+//! template are selected based on its file extension, see
+//! [template_fragments::Config::for_path]. The file `-` refers to stdin. Glob
+//! patterns in file arguments are expanded, see [glob].
+use std::{io::Read, process::ExitCode};
+
+use template_fragments::{Config, ErrorWithLine};
+
+use json::Json;
+
+mod check;
+mod convert;
+mod diff;
+mod extract;
+mod fmt;
+mod glob;
+mod graph;
+mod json;
+mod list;
+#[cfg(feature = "lsp")]
+mod lsp;
+mod parallel;
+mod split;
+mod stats;
+
+const USAGE: &str = "\
+Usage: template-fragments <COMMAND> [OPTIONS] <FILES>...
+
+Commands:
+  check    Validate templates and report all errors
+           --diagnostics  print errors and lint warnings as one JSON
+                          object per line with file, line, column, code,
+                          severity, and message
+  convert  Rewrite fragment tags of another syntax in place
+           --from <html|c|erb|go|jinja>  the syntax to convert from
+  diff     Report the fragments that differ between an old and a new version
+           of a template with the hashes of their content, fails if any
+           fragment was added, removed, or changed
+  extract  Print a single fragment of templates, as it is served
+           --fragment <NAME>  the fragment to print, empty for the base
+  fmt      Normalize the fragment tags of templates in place
+           --check  only report templates that are not formatted
+  graph    Emit the graph of which fragments contain which other fragments
+           --graph <dot|mermaid>  the graph language, by default dot
+  list     Show the fragments of templates as a tree with their lines
+  lsp      Run the language server on stdin and stdout, requires the lsp
+           feature
+  split    Write all fragments into an output directory
+           --out-dir <DIR>            the directory to write the fragments to
+           --naming <hash|dot|dir>    the output paths of fragments, i.e.,
+                                      index.html#item, index.item.html or
+                                      index/item.html, by default hash
+  stats    Report the size and structure of all fragments
+
+Options:
+  --format <text|json>  the output format, by default text
+  --exclude <PATTERN>   skip files matching the pattern, can be repeated
+  --jobs <N>            the number of files processed concurrently, by
+                        default the number of cores
+
+Use - as file to read a template from stdin. `fmt -` and `convert -` write
+the resulting template to stdout. Glob patterns like templates/**/*.html are
+expanded, quote them to avoid expansion by the shell.
+";
+
+/// The file name that refers to stdin
+const STDIN: &str = "-";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let Some((command, args)) = args.split_first() else {
+        eprint!("{USAGE}");
+        return ExitCode::from(2);
+    };
+
+    let res = match command.as_str() {
+        "check" => check::run(args),
+        "convert" => convert::run(args),
+        "diff" => diff::run(args),
+        "extract" => extract::run(args),
+        "fmt" => fmt::run(args),
+        "graph" => graph::run(args),
+        "list" => list::run(args),
+        #[cfg(feature = "lsp")]
+        "lsp" => lsp::run(args),
+        "split" => split::run(args),
+        "stats" => stats::run(args),
+        "-h" | "--help" | "help" => {
+            print!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        _ => Err(UsageError(format!("unknown command {command:?}"))),
+    };
+
+    match res {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(UsageError(msg)) => {
+            eprintln!("error: {msg}\n");
+            eprint!("{USAGE}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// An invalid invocation of the CLI
+struct UsageError(String);
+
+/// The output format of a command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Text,
+    Json,
+}
+
+/// The parsed options and files of a command
+struct Args<'a> {
+    flags: Vec<&'a str>,
+    options: Vec<(&'a str, &'a str)>,
+    format: Format,
+    jobs: usize,
+    files: Vec<String>,
+}
+
+impl<'a> Args<'a> {
+    /// Split the arguments into flags, options with values, the output format
+    /// and files, only the given flags and options are accepted
+    ///
+    /// Glob patterns are expanded and files matching any `--exclude` pattern
+    /// are removed.
+    fn parse(
+        args: &'a [String],
+        known_flags: &[&str],
+        known_options: &[&str],
+    ) -> Result<Self, UsageError> {
+        let mut flags = Vec::new();
+        let mut options = Vec::new();
+        let mut format = Format::Text;
+        let mut jobs = parallel::default_jobs();
+        let mut files = Vec::new();
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if arg.starts_with("--") {
+                let (name, value) = match arg.split_once('=') {
+                    Some((name, value)) => (name, Some(value)),
+                    None => (arg.as_str(), None),
+                };
+
+                if matches!(name, "--format" | "--exclude" | "--jobs")
+                    || known_options.contains(&name)
+                {
+                    let Some(value) = value.or_else(|| args.next().map(String::as_str)) else {
+                        return Err(UsageError(format!("missing value for {name:?}")));
+                    };
+                    match name {
+                        "--format" => {
+                            format = match value {
+                                "text" => Format::Text,
+                                "json" => Format::Json,
+                                _ => return Err(UsageError(format!("unknown format {value:?}"))),
+                            };
+                        }
+                        "--jobs" => {
+                            jobs = match value.parse() {
+                                Ok(jobs) if jobs > 0 => jobs,
+                                _ => return Err(UsageError(format!("invalid jobs {value:?}"))),
+                            };
+                        }
+                        _ => options.push((name, value)),
+                    }
+                } else if value.is_none() && known_flags.contains(&name) {
+                    flags.push(name);
+                } else {
+                    return Err(UsageError(format!("unknown option {arg:?}")));
+                }
+            } else {
+                files.push(arg.as_str());
+            }
+        }
+        if files.is_empty() {
+            return Err(UsageError(String::from("no files given")));
+        }
+        if files.iter().filter(|&&file| file == STDIN).count() > 1 {
+            return Err(UsageError(String::from("stdin can only be read once")));
+        }
+
+        let mut expanded = Vec::new();
+        for file in files {
+            if file == STDIN || !glob::is_pattern(file) {
+                expanded.push(file.to_owned());
+                continue;
+            }
+            let matches = glob::expand(file);
+            if matches.is_empty() {
+                return Err(UsageError(format!("no files match {file:?}")));
+            }
+            expanded.extend(matches);
+        }
+        expanded.retain(|file| {
+            file == STDIN
+                || !options
+                    .iter()
+                    .any(|&(name, pattern)| name == "--exclude" && glob::matches(pattern, file))
+        });
+
+        Ok(Self {
+            flags,
+            options,
+            format,
+            jobs,
+            files: expanded,
+        })
+    }
+
+    fn has(&self, flag: &str) -> bool {
+        self.flags.contains(&flag)
+    }
+
+    /// The value of the last occurrence of the given option
+    fn option(&self, name: &str) -> Option<&'a str> {
+        self.options
+            .iter()
+            .rev()
+            .find(|(option, _)| *option == name)
+            .map(|(_, value)| *value)
+    }
+}
+
+/// Collects the errors of a command and writes them in the selected format
+///
+/// For text output, errors are written to stderr as `PATH:LINE: ERROR`
+/// immediately, errors of templates are followed by a hint how to fix them.
+/// For JSON output, a single object with the errors and the
+/// results of the command is written to stdout by [Reporter::finish].
+struct Reporter {
+    format: Format,
+    failed: bool,
+    errors: Vec<Json>,
+}
+
+impl Reporter {
+    fn new(format: Format) -> Self {
+        Self {
+            format,
+            failed: false,
+            errors: Vec::new(),
+        }
+    }
+
+    /// Read the templates of all files and process them concurrently
+    ///
+    /// Up to `--jobs` files are processed at the same time. Files that cannot
+    /// be read are reported and skipped. The results are returned with the
+    /// path and source of each template in the order of the files.
+    fn process_templates<'f, T: Send>(
+        &mut self,
+        args: &'f Args<'_>,
+        process: impl Fn(&str, &str) -> T + Sync,
+    ) -> Vec<(&'f str, String, T)> {
+        let results = parallel::map(&args.files, args.jobs, |path| {
+            read_template(path).map(|src| {
+                let res = process(path, &src);
+                (src, res)
+            })
+        });
+
+        let mut processed = Vec::new();
+        for (path, res) in args.files.iter().zip(results) {
+            match res {
+                Ok((src, res)) => processed.push((path.as_str(), src, res)),
+                Err(err) => self.error(path, None, &err.to_string()),
+            }
+        }
+        processed
+    }
+
+    /// Report an error of a template with a hint how to fix it
+    fn template_error(&mut self, path: &str, src: &str, err: &ErrorWithLine) {
+        let help = Config::for_path(path).error_help(src, err);
+        self.report(path, Some(err.0), &err.1.to_string(), Some(&help));
+    }
+
+    /// Report an error with an optional zero-based line
+    fn error(&mut self, path: &str, line: Option<usize>, message: &str) {
+        self.report(path, line, message, None);
+    }
+
+    fn report(&mut self, path: &str, line: Option<usize>, message: &str, help: Option<&str>) {
+        self.failed = true;
+        let path = display_path(path);
+        match self.format {
+            Format::Text => {
+                match line {
+                    Some(line) => eprintln!("{path}:{}: {message}", line + 1),
+                    None => eprintln!("{path}: {message}"),
+                }
+                if let Some(help) = help {
+                    eprintln!("  help: {help}");
+                }
+            }
+            Format::Json => self.errors.push(Json::object([
+                ("path", Json::from(path)),
+                ("line", line.map(|line| Json::from(line + 1)).into()),
+                ("message", Json::from(message)),
+                ("help", help.map(Json::from).into()),
+            ])),
+        }
+    }
+
+    /// Write the JSON output with the given results, does nothing for text
+    /// output
+    ///
+    /// Returns whether the command succeeded, i.e., `success` and no errors
+    /// were reported.
+    fn finish(self, success: bool, results: Vec<(&str, Json)>) -> bool {
+        let success = success && !self.failed;
+        if self.format == Format::Json {
+            let mut fields = vec![("success", Json::Bool(success))];
+            fields.extend(results);
+            fields.push(("errors", Json::Array(self.errors)));
+            println!("{}", Json::object(fields));
+        }
+        success
+    }
+}
+
+/// Read a template from a file or stdin
+fn read_template(path: &str) -> std::io::Result<String> {
+    if path == STDIN {
+        let mut src = String::new();
+        std::io::stdin().read_to_string(&mut src).map(|_| src)
+    } else {
+        std::fs::read_to_string(path)
+    }
+}
+
+/// The path of a file for messages, stdin is shown as `<stdin>`
+fn display_path(path: &str) -> &str {
+    if path == STDIN {
+        "<stdin>"
+    } else {
+        path
+    }
+}
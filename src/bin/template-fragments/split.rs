@@ -0,0 +1,110 @@
+use std::path::{Component, Path, PathBuf};
+
+use template_fragments::Config;
+
+use crate::{json::Json, Args, Format, Reporter, UsageError, STDIN};
+
+/// How fragments are mapped to output paths
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Naming {
+    /// `index.html#item`, as returned by [template_fragments::join_path]
+    Hash,
+    /// `index.item.html`
+    Dot,
+    /// `index/item.html`
+    Directory,
+}
+
+impl Naming {
+    /// The output path of a fragment, the base template keeps its path
+    fn path(self, path: &Path, fragment: &str) -> PathBuf {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if fragment.is_empty() {
+            return path.to_owned();
+        }
+
+        let (stem, extension) = match name.rsplit_once('.') {
+            Some((stem, extension)) if !stem.is_empty() => (stem, Some(extension)),
+            _ => (name.as_ref(), None),
+        };
+        match (self, extension) {
+            (Self::Hash, _) => path.with_file_name(format!("{name}#{fragment}")),
+            (Self::Dot, Some(extension)) => {
+                path.with_file_name(format!("{stem}.{fragment}.{extension}"))
+            }
+            (Self::Dot, None) => path.with_file_name(format!("{stem}.{fragment}")),
+            (Self::Directory, Some(extension)) => path
+                .with_file_name(stem)
+                .join(format!("{fragment}.{extension}")),
+            (Self::Directory, None) => path.with_file_name(stem).join(fragment),
+        }
+    }
+}
+
+/// Write all fragments of the files into the output directory
+///
+/// The relative paths of the files are kept below the output directory.
+/// Returns whether all files could be split.
+pub fn run(args: &[String]) -> Result<bool, UsageError> {
+    let args = Args::parse(args, &[], &["--out-dir", "--naming"])?;
+    let Some(out_dir) = args.option("--out-dir") else {
+        return Err(UsageError(String::from("missing option \"--out-dir\"")));
+    };
+    let naming = match args.option("--naming").unwrap_or("hash") {
+        "hash" => Naming::Hash,
+        "dot" => Naming::Dot,
+        "dir" => Naming::Directory,
+        naming => return Err(UsageError(format!("unknown naming {naming:?}"))),
+    };
+    if args.files.iter().any(|path| path == STDIN) {
+        return Err(UsageError(String::from("split cannot read from stdin")));
+    }
+    let mut reporter = Reporter::new(args.format);
+
+    let mut outputs = Vec::new();
+    let results = reporter.process_templates(&args, |path, src| {
+        Config::for_path(path).split_templates(src)
+    });
+    for (path, src, res) in results {
+        let templates = match res {
+            Ok(templates) => templates,
+            Err(err) => {
+                reporter.template_error(path, &src, &err);
+                continue;
+            }
+        };
+
+        // only keep the normal components to stay inside the output directory
+        let relative: PathBuf = Path::new(path)
+            .components()
+            .filter(|component| matches!(component, Component::Normal(_)))
+            .collect();
+
+        let mut templates: Vec<_> = templates.into_iter().collect();
+        templates.sort();
+
+        for (fragment, content) in templates {
+            let output = Path::new(out_dir).join(naming.path(&relative, &fragment));
+            let res = match output.parent() {
+                Some(parent) => std::fs::create_dir_all(parent),
+                None => Ok(()),
+            };
+            if let Err(err) = res.and_then(|_| std::fs::write(&output, content)) {
+                reporter.error(path, None, &format!("{}: {err}", output.display()));
+                continue;
+            }
+
+            let output = output.to_string_lossy();
+            match args.format {
+                Format::Text => println!("{output}"),
+                Format::Json => outputs.push(Json::object([
+                    ("path", Json::from(path)),
+                    ("fragment", Json::from(fragment.as_str())),
+                    ("output", Json::from(output.as_ref())),
+                ])),
+            }
+        }
+    }
+
+    Ok(reporter.finish(true, vec![("outputs", Json::Array(outputs))]))
+}
@@ -0,0 +1,61 @@
+use template_fragments::{join_path, Config};
+
+use crate::{display_path, json::Json, Args, Format, Reporter, UsageError};
+
+/// Report the statistics of all fragments
+///
+/// Returns whether all files are valid.
+pub fn run(args: &[String]) -> Result<bool, UsageError> {
+    let args = Args::parse(args, &[], &[])?;
+    let mut reporter = Reporter::new(args.format);
+
+    let mut fragments = Vec::new();
+    let results = reporter.process_templates(&args, |path, src| {
+        Config::for_path(path).fragment_stats(src)
+    });
+    for (path, src, res) in results {
+        let stats = match res {
+            Ok(stats) => stats,
+            Err(err) => {
+                reporter.template_error(path, &src, &err);
+                continue;
+            }
+        };
+
+        let mut stats: Vec<_> = stats.into_iter().collect();
+        stats.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        for (fragment, stats) in stats {
+            match args.format {
+                Format::Text => println!(
+                    "{}: {} occurrences, {} lines, {} bytes, depth {}",
+                    join_path(display_path(path), &fragment),
+                    stats.occurrences,
+                    stats.lines,
+                    stats.bytes,
+                    stats.depth,
+                ),
+                Format::Json => fragments.push(Json::object([
+                    ("path", Json::from(display_path(path))),
+                    ("fragment", Json::from(fragment.as_str())),
+                    ("occurrences", Json::from(stats.occurrences)),
+                    ("lines", Json::from(stats.lines)),
+                    ("bytes", Json::from(stats.bytes)),
+                    ("depth", Json::from(stats.depth)),
+                    (
+                        "contains",
+                        Json::Array(
+                            stats
+                                .contains
+                                .iter()
+                                .map(|f| Json::from(f.as_str()))
+                                .collect(),
+                        ),
+                    ),
+                ])),
+            }
+        }
+    }
+
+    Ok(reporter.finish(true, vec![("fragments", Json::Array(fragments))]))
+}
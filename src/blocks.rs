@@ -0,0 +1,289 @@
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+};
+
+use crate::{
+    defines::LineMap,
+    get_ending, is_valid_fragment_name, iterate_with_endings, parse_base, parse_fragment_tag,
+    verbatim::{tags, Verbatim},
+    Config, Tag,
+};
+
+impl Config {
+    /// If `true`, every `{% block NAME %}` of the template is also a fragment
+    /// named `NAME`
+    ///
+    /// This matches the semantics of jinja2-fragments and django-render-block:
+    /// the blocks of existing templates can be rendered on their own without
+    /// adding fragment tags. Blocks are treated like `{% fragment-block NAME
+    /// %}`, i.e., the fragment contains the block with its tags. Only blocks
+    /// whose start and end tags are on lines of their own are extracted.
+    /// Blocks with modifiers, e.g., `scoped`, or whitespace control, e.g., `{%-
+    /// block NAME %}`, are kept as they are.
+    ///
+    /// ```rust
+    /// # use template_fragments::Config;
+    /// let source = concat!(
+    ///     "{% extends \"layout.html\" %}\n",
+    ///     "{% block title %}Items{% endblock %}\n",
+    ///     "{% block content %}\n",
+    ///     "<ul>\n",
+    ///     "  {% block items %}\n",
+    ///     "  <li>{{ item }}</li>\n",
+    ///     "  {% endblock items %}\n",
+    ///     "</ul>\n",
+    ///     "{% endblock %}\n",
+    /// );
+    ///
+    /// let config = Config::default().blocks_as_fragments(true);
+    /// let templates = config.split_templates(source).unwrap();
+    /// assert_eq!(
+    ///     templates["items"],
+    ///     "  {% block items %}\n  <li>{{ item }}</li>\n  {% endblock %}\n",
+    /// );
+    /// assert!(templates["content"].starts_with("{% block content %}\n<ul>\n"));
+    /// assert!(!templates.contains_key("title"));
+    /// ```
+    pub fn blocks_as_fragments(mut self, value: bool) -> Self {
+        self.blocks_as_fragments = value;
+        self
+    }
+
+    /// If `true`, the body of every `{% macro NAME(...) %}` of the template is
+    /// also a fragment named `NAME`
+    ///
+    /// This way, component macros can be rendered standalone, e.g., for HTMX
+    /// requests, with the arguments of the macro passed as variables. The
+    /// fragment contains the lines between the macro tags, the macro itself is
+    /// kept in the enclosing fragments. Only macros whose start and end tags
+    /// are on lines of their own are extracted. Errors of fragment tags inside
+    /// macros are reported at the lines of the original template.
+    ///
+    /// ```rust
+    /// # use template_fragments::Config;
+    /// let source = concat!(
+    ///     "{% macro card(title) %}\n",
+    ///     "<div class=\"card\">{{ title }}</div>\n",
+    ///     "{% endmacro %}\n",
+    ///     "{{ card(\"Items\") }}\n",
+    /// );
+    ///
+    /// let config = Config::default().macros_as_fragments(true);
+    /// let templates = config.split_templates(source).unwrap();
+    /// assert_eq!(templates[""], source);
+    /// assert_eq!(templates["card"], "<div class=\"card\">{{ title }}</div>\n");
+    /// ```
+    pub fn macros_as_fragments(mut self, value: bool) -> Self {
+        self.macros_as_fragments = value;
+        self
+    }
+
+    /// If `true`, every `{% fragment NAME %}` of the template is emitted as a
+    /// block, as if it was written as `{% fragment-block NAME %}`
+    ///
+    /// This way, the base template defines a `{% block %}` for each fragment
+    /// and engines that only know blocks, e.g., Django with
+    /// django-render-block, can render the same fragments. Start tags with
+    /// multiple names result in an [crate::Error::MultipleNamesBlock], as
+    /// blocks have a single name. See also [Config::django_render_block].
+    ///
+    /// ```rust
+    /// # use template_fragments::Config;
+    /// let source = "<ul>\n{% fragment item %}\n<li>\n{% endfragment %}\n</ul>\n";
+    ///
+    /// let config = Config::default().fragments_as_blocks(true);
+    /// let templates = config.split_templates(source).unwrap();
+    /// assert_eq!(
+    ///     templates[""],
+    ///     "<ul>\n{% block item %}\n<li>\n{% endblock %}\n</ul>\n",
+    /// );
+    /// assert_eq!(templates["item"], "{% block item %}\n<li>\n{% endblock %}\n");
+    /// ```
+    pub fn fragments_as_blocks(mut self, value: bool) -> Self {
+        self.fragments_as_blocks = value;
+        self
+    }
+
+    /// Rewrite the fragment tags into fragment block tags
+    ///
+    /// Invalid tags are kept, they are reported when processing the template.
+    pub(crate) fn expand_fragment_blocks<'s>(&self, src: &'s str) -> Cow<'s, str> {
+        let tag_markers = self.syntax.tag_markers();
+        if !self.fragments_as_blocks || !src.contains(tag_markers.0) {
+            return Cow::Borrowed(src);
+        }
+        let (open, close) = tag_markers;
+
+        let mut res = String::with_capacity(src.len());
+        let mut verbatim = Verbatim::default();
+        for line in iterate_with_endings(src) {
+            let keyword = match parse_fragment_tag(line, tag_markers) {
+                _ if verbatim.skip(line, tag_markers) => None,
+                Ok(Some(Tag::Start(_))) => Some("fragment-block"),
+                Ok(Some(Tag::End(_))) => Some("endfragment-block"),
+                _ => None,
+            };
+            match (keyword, parse_base(line, tag_markers)) {
+                (Some(keyword), Some(parts)) => res.push_str(&format!(
+                    "{}{open} {keyword} {}{close}{}",
+                    parts.head, parts.data, parts.tail
+                )),
+                _ => res.push_str(line),
+            }
+        }
+        Cow::Owned(res)
+    }
+
+    /// Rewrite the block tags on lines of their own into fragment block tags
+    pub(crate) fn expand_blocks<'s>(&self, src: &'s str) -> Cow<'s, str> {
+        let tag_markers = self.syntax.tag_markers();
+        if !self.blocks_as_fragments || !src.contains(tag_markers.0) {
+            return Cow::Borrowed(src);
+        }
+        let (open, close) = tag_markers;
+
+        let mut res = String::with_capacity(src.len());
+        let mut verbatim = Verbatim::default();
+        // whether the start tag of each open block was rewritten
+        let mut blocks: Vec<bool> = Vec::new();
+
+        for line in iterate_with_endings(src) {
+            if verbatim.skip(line, tag_markers) || !line.contains(open) {
+                res.push_str(line);
+                continue;
+            }
+
+            let indent = &line[..line.len() - line.trim_start().len()];
+            let ending = get_ending(line);
+            match own_line_tag(line, tag_markers).as_deref() {
+                Some(["block", name]) if is_valid_fragment_name(name) => {
+                    blocks.push(true);
+                    res.push_str(&format!(
+                        "{indent}{open} fragment-block {name} {close}{ending}"
+                    ));
+                }
+                Some(["endblock"] | ["endblock", _]) if blocks.last() == Some(&true) => {
+                    blocks.pop();
+                    res.push_str(&format!("{indent}{open} endfragment-block {close}{ending}"));
+                }
+                _ => {
+                    for words in tags(line, tag_markers) {
+                        match words.first() {
+                            Some(&"block") => blocks.push(false),
+                            Some(&"endblock") => {
+                                blocks.pop();
+                            }
+                            _ => {}
+                        }
+                    }
+                    res.push_str(line);
+                }
+            }
+        }
+        Cow::Owned(res)
+    }
+
+    /// Wrap the bodies of macros in fragment tags
+    ///
+    /// The returned map refers to the lines of the source.
+    pub(crate) fn expand_macros<'s>(&self, src: &'s str) -> (Cow<'s, str>, LineMap) {
+        let tag_markers = self.syntax.tag_markers();
+        if !self.macros_as_fragments || !src.contains("macro") {
+            return (Cow::Borrowed(src), LineMap::default());
+        }
+        let (open, close) = tag_markers;
+
+        // the name of the macro started by a line and the macros ended by a line
+        let mut starts: HashMap<usize, &str> = HashMap::new();
+        let mut ends: HashSet<usize> = HashSet::new();
+        let mut macros: Vec<Option<(usize, &str)>> = Vec::new();
+        let mut verbatim = Verbatim::default();
+
+        for (line_idx, line) in iterate_with_endings(src).enumerate() {
+            if verbatim.skip(line, tag_markers) || !line.contains(open) {
+                continue;
+            }
+            if let Some(name) = own_line_macro(line, tag_markers) {
+                macros.push(Some((line_idx, name)));
+                continue;
+            }
+            for words in tags(line, tag_markers) {
+                match words.first() {
+                    Some(&"macro") => macros.push(None),
+                    Some(&"endmacro") => match macros.pop() {
+                        Some(Some((start, name))) if is_own_line(line, tag_markers) => {
+                            starts.insert(start, name);
+                            ends.insert(line_idx);
+                        }
+                        _ => {}
+                    },
+                    _ => {}
+                }
+            }
+        }
+        if starts.is_empty() {
+            return (Cow::Borrowed(src), LineMap::default());
+        }
+
+        let mut res = String::with_capacity(src.len());
+        let mut lines = Vec::new();
+        let mut endings = Vec::new();
+        for (line_idx, line) in iterate_with_endings(src).enumerate() {
+            let indent = &line[..line.len() - line.trim_start().len()];
+            if ends.contains(&line_idx) {
+                let ending = endings.pop().unwrap_or_default();
+                res.push_str(&format!("{indent}{open} endfragment {close}{ending}"));
+                lines.push(line_idx);
+            }
+            res.push_str(line);
+            lines.push(line_idx);
+            if let Some(name) = starts.get(&line_idx) {
+                let ending = get_ending(line);
+                res.push_str(&format!("{indent}{open} fragment {name} {close}{ending}"));
+                lines.push(line_idx);
+                endings.push(ending);
+            }
+        }
+        (Cow::Owned(res), LineMap::new(lines))
+    }
+}
+
+/// The name of the macro started by a line that consists of a single tag
+fn own_line_macro<'l>(line: &'l str, tag_markers: (&str, &str)) -> Option<&'l str> {
+    if !is_own_line(line, tag_markers) {
+        return None;
+    }
+    let (open, close) = tag_markers;
+    let content = line.trim().strip_prefix(open)?.strip_suffix(close)?;
+    let content = content.strip_prefix('-').unwrap_or(content);
+    let (name, _) = content
+        .trim_start()
+        .strip_prefix("macro")?
+        .strip_prefix(char::is_whitespace)?
+        .split_once('(')?;
+    let name = name.trim();
+    is_valid_fragment_name(name).then_some(name)
+}
+
+/// Whether the line consists of a single tag
+fn is_own_line(line: &str, tag_markers: (&str, &str)) -> bool {
+    let line = line.trim();
+    line.starts_with(tag_markers.0)
+        && line.ends_with(tag_markers.1)
+        && line.matches(tag_markers.0).count() == 1
+}
+
+/// The words of a line that consists of a single tag without whitespace
+/// control
+fn own_line_tag<'l>(line: &'l str, tag_markers: (&str, &str)) -> Option<Vec<&'l str>> {
+    let (open, close) = tag_markers;
+    let content = line.trim().strip_prefix(open)?.strip_suffix(close)?;
+    if !content.starts_with(char::is_whitespace)
+        || !content.ends_with(char::is_whitespace)
+        || content.contains(open)
+    {
+        return None;
+    }
+    Some(content.split_whitespace().collect())
+}
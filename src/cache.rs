@@ -0,0 +1,152 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+};
+
+use crate::{filter_template, ErrorWithLine};
+
+/// A bounded cache for the results of [filter_template]
+///
+/// Results are cached per `(path, source_hash, fragment)`. This way the source
+/// can be passed on every request and is only processed again, if it changed.
+/// When the cache is full, the least recently used entry is evicted. Errors
+/// are not cached.
+///
+/// ```rust
+/// # use template_fragments::FragmentCache;
+/// let source = concat!(
+///     "<body>\n",
+///     "  {% fragment item %}\n",
+///     "    <div>{{ item }}</div>\n",
+///     "  {% endfragment %}\n",
+///     "<body>\n",
+/// );
+///
+/// let mut cache = FragmentCache::new(128);
+/// assert_eq!(
+///     cache.filter_template("index.html", source, "item").unwrap(),
+///     "    <div>{{ item }}</div>\n",
+/// );
+/// assert_eq!(cache.len(), 1);
+///
+/// cache.invalidate("index.html");
+/// assert!(cache.is_empty());
+/// ```
+#[derive(Debug, Clone)]
+pub struct FragmentCache {
+    capacity: usize,
+    tick: u64,
+    entries: HashMap<CacheKey, CacheEntry>,
+}
+
+/// The path, source hash, and fragment of an entry
+type CacheKey = (String, u64, String);
+
+#[derive(Debug, Clone)]
+struct CacheEntry {
+    content: String,
+    last_used: u64,
+}
+
+impl FragmentCache {
+    /// Construct a new cache that holds at most `capacity` fragments
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "FragmentCache requires a non-zero capacity");
+        Self {
+            capacity,
+            tick: 0,
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Filter the template, reusing a previous result if available
+    ///
+    /// The `path` is only used to allow invalidating all entries of a template
+    /// with [FragmentCache::invalidate]. Templates with the same source under
+    /// different paths are cached separately.
+    pub fn filter_template(
+        &mut self,
+        path: &str,
+        src: &str,
+        fragment: &str,
+    ) -> Result<&str, ErrorWithLine> {
+        let key = cache_key(path, src, fragment);
+        self.tick += 1;
+
+        if !self.entries.contains_key(&key) {
+            let content = filter_template(src, fragment)?;
+
+            while self.entries.len() >= self.capacity {
+                self.evict_least_recently_used();
+            }
+
+            self.entries.insert(
+                key.clone(),
+                CacheEntry {
+                    content,
+                    last_used: self.tick,
+                },
+            );
+        }
+
+        let entry = self.entries.get_mut(&key).unwrap();
+        entry.last_used = self.tick;
+        Ok(&entry.content)
+    }
+
+    /// Whether the result of filtering the template is cached
+    ///
+    /// The entry is not marked as used.
+    pub fn contains(&self, path: &str, src: &str, fragment: &str) -> bool {
+        self.entries.contains_key(&cache_key(path, src, fragment))
+    }
+
+    /// Remove all cached fragments of the given template path
+    pub fn invalidate(&mut self, path: &str) {
+        self.entries.retain(|(other, _, _), _| other != path);
+    }
+
+    /// Remove all cached fragments
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// The number of cached fragments
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the cache is empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The maximum number of cached fragments
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    fn evict_least_recently_used(&mut self) {
+        let Some(key) = self
+            .entries
+            .iter()
+            .min_by_key(|(_, entry)| entry.last_used)
+            .map(|(key, _)| key.clone())
+        else {
+            return;
+        };
+        self.entries.remove(&key);
+    }
+}
+
+fn cache_key(path: &str, src: &str, fragment: &str) -> CacheKey {
+    (path.to_owned(), hash_source(src), fragment.to_owned())
+}
+
+pub(crate) fn hash_source(src: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    src.hash(&mut hasher);
+    hasher.finish()
+}
@@ -0,0 +1,77 @@
+use std::borrow::Cow;
+
+use crate::{iterate_with_endings, parse_base, verbatim::Verbatim, Config};
+
+impl Config {
+    /// Accept fragment tags inside line comments that start with the given
+    /// prefix, e.g., `-- {% fragment recent_orders %}` for `--`
+    ///
+    /// Only lines that consist of the prefix and a fragment tag are affected,
+    /// other comments are kept as they are. The tag lines are removed from
+    /// the output including their prefix. See [Config::sql].
+    ///
+    /// ```rust
+    /// # use template_fragments::Config;
+    /// let source = concat!(
+    ///     "with\n",
+    ///     "-- {% fragment active_users %}\n",
+    ///     "active_users as (select * from users where active),\n",
+    ///     "-- {% endfragment %}\n",
+    ///     "-- keep this comment\n",
+    ///     "select count(*) from active_users\n",
+    /// );
+    ///
+    /// let config = Config::default().line_comment_prefix("--");
+    /// let templates = config.split_templates(source).unwrap();
+    /// assert_eq!(
+    ///     templates["active_users"],
+    ///     "active_users as (select * from users where active),\n",
+    /// );
+    /// assert_eq!(
+    ///     templates[""],
+    ///     concat!(
+    ///         "with\n",
+    ///         "active_users as (select * from users where active),\n",
+    ///         "-- keep this comment\n",
+    ///         "select count(*) from active_users\n",
+    ///     ),
+    /// );
+    /// ```
+    pub fn line_comment_prefix(mut self, prefix: &str) -> Self {
+        self.line_comment_prefix = Some(prefix.to_owned());
+        self
+    }
+
+    /// Replace the comment prefixes of fragment tag lines by spaces
+    ///
+    /// The offsets of the lines do not change, i.e., errors refer to the
+    /// columns of the original template.
+    pub(crate) fn strip_comment_prefixes<'s>(&self, src: &'s str) -> Cow<'s, str> {
+        let Some(prefix) = self.line_comment_prefix.as_deref() else {
+            return Cow::Borrowed(src);
+        };
+        let tag_markers = self.syntax.tag_markers();
+        if prefix.is_empty() || !src.contains(prefix) {
+            return Cow::Borrowed(src);
+        }
+
+        let mut res = String::with_capacity(src.len());
+        let mut verbatim = Verbatim::default();
+        for line in iterate_with_endings(src) {
+            let indent = &line[..line.len() - line.trim_start().len()];
+            let is_tag = !verbatim.skip(line, tag_markers)
+                && line[indent.len()..]
+                    .strip_prefix(prefix)
+                    .and_then(|rest| parse_base(rest, tag_markers))
+                    .is_some_and(|parts| parts.head.trim().is_empty());
+            if is_tag {
+                res.push_str(indent);
+                res.push_str(&" ".repeat(prefix.len()));
+                res.push_str(&line[indent.len() + prefix.len()..]);
+            } else {
+                res.push_str(line);
+            }
+        }
+        Cow::Owned(res)
+    }
+}
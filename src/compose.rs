@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+
+use crate::{
+    get_ending, is_valid_fragment_name, iterate_with_endings, process_template, Config, Error,
+    ErrorWithLine, DEFAULT_TAG_MARKERS,
+};
+
+/// Compose an annotated template from a skeleton and the content of its
+/// fragments
+///
+/// This function is the inverse of [crate::split_templates]: each `{%
+/// fragment-slot NAME %}` line of the skeleton is replaced by the content of
+/// the fragment, wrapped in `{% fragment NAME %}` and `{% endfragment %}`
+/// tags with the indentation of the slot. All other lines are kept as they
+/// are. This way, templates can be generated programmatically and still be
+/// split with this crate.
+///
+/// Slots without content result in an [Error::UnresolvedInclude]. The composed
+/// template is validated as in [crate::split_templates], errors refer to the
+/// lines of the composed template.
+///
+/// ```rust
+/// # use std::collections::HashMap;
+/// # use template_fragments::{merge_fragments, split_templates};
+/// let skeleton = concat!(
+///     "<body>\n",
+///     "  {% fragment-slot item %}\n",
+///     "<body>\n",
+/// );
+/// let fragments = HashMap::from([(
+///     String::from("item"),
+///     String::from("    <div>{{ item }}</div>\n"),
+/// )]);
+///
+/// let source = merge_fragments(skeleton, &fragments).unwrap();
+/// assert_eq!(
+///     source,
+///     concat!(
+///         "<body>\n",
+///         "  {% fragment item %}\n",
+///         "    <div>{{ item }}</div>\n",
+///         "  {% endfragment %}\n",
+///         "<body>\n",
+///     ),
+/// );
+/// assert_eq!(split_templates(&source).unwrap()["item"], fragments["item"]);
+/// ```
+pub fn merge_fragments(
+    skeleton: &str,
+    fragments: &HashMap<String, String>,
+) -> Result<String, ErrorWithLine> {
+    let (open, close) = DEFAULT_TAG_MARKERS;
+    let mut res = String::new();
+
+    for (line_idx, line) in iterate_with_endings(skeleton).enumerate() {
+        let Some((prefix, fragment)) = parse_fragment_slot(line, DEFAULT_TAG_MARKERS) else {
+            res.push_str(line);
+            continue;
+        };
+        let content = fragments
+            .get(fragment)
+            .ok_or_else(|| Error::UnresolvedInclude(fragment.to_owned()).at(line_idx))?;
+
+        let ending = get_ending(line);
+        let tag_ending = if ending.is_empty() { "\n" } else { ending };
+
+        res.push_str(&format!(
+            "{prefix}{open} fragment {fragment} {close}{tag_ending}"
+        ));
+        res.push_str(content);
+        if !content.is_empty() && get_ending(content).is_empty() {
+            res.push_str(tag_ending);
+        }
+        res.push_str(&format!("{prefix}{open} endfragment {close}{ending}"));
+    }
+
+    process_template(&Config::default(), &res, |_, _| {})?;
+    Ok(res)
+}
+
+/// Parse a line of the form `{% fragment-slot NAME %}` and return the
+/// indentation and the fragment
+fn parse_fragment_slot<'l>(line: &'l str, tag_markers: (&str, &str)) -> Option<(&'l str, &'l str)> {
+    let prefix = &line[..line.len() - line.trim_start().len()];
+    let line = line.trim();
+    let line = line
+        .strip_prefix(tag_markers.0)?
+        .strip_suffix(tag_markers.1)?;
+    let line = line.strip_prefix(char::is_whitespace)?.trim_start();
+    let line = line.strip_prefix("fragment-slot")?;
+    let fragment = line.strip_prefix(char::is_whitespace)?.trim();
+
+    if fragment.is_empty() || !is_valid_fragment_name(fragment) {
+        return None;
+    }
+    Some((prefix, fragment))
+}
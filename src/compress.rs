@@ -0,0 +1,137 @@
+use std::{collections::HashMap, io::Write};
+
+use flate2::{write::GzEncoder, Compression};
+
+use crate::{Config, ErrorWithLine};
+
+/// The content of a fragment with gzip and brotli compressed copies
+///
+/// Requires the `compress` feature. Both encodings use the highest
+/// compression level, as they are computed only once when the templates are
+/// split. Use [CompressedFragment::encode] to select the body of a response
+/// for the `Accept-Encoding` header of a request.
+///
+/// ```rust
+/// # use template_fragments::CompressedFragment;
+/// let fragment = CompressedFragment::new(String::from("<li>{{ item }}</li>\n"));
+///
+/// assert_eq!(fragment.encode("gzip, br").0, Some("br"));
+/// assert_eq!(fragment.encode("gzip").0, Some("gzip"));
+/// assert_eq!(fragment.encode(""), (None, fragment.content.as_bytes()));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompressedFragment {
+    pub content: String,
+    pub gzip: Vec<u8>,
+    pub brotli: Vec<u8>,
+}
+
+impl CompressedFragment {
+    pub fn new(content: String) -> Self {
+        let gzip = compress_gzip(content.as_bytes());
+        let brotli = compress_brotli(content.as_bytes());
+        Self {
+            content,
+            gzip,
+            brotli,
+        }
+    }
+
+    /// Select the encoding for the value of an `Accept-Encoding` header
+    ///
+    /// Returns the value of the `Content-Encoding` header, if any, and the
+    /// body. Brotli is preferred over gzip. Encodings with `q=0` are not used,
+    /// even if they are matched by `*`.
+    pub fn encode(&self, accept_encoding: &str) -> (Option<&'static str>, &[u8]) {
+        // whether the encoding is accepted, `None` if it is not listed
+        let mut gzip = None;
+        let mut brotli = None;
+        let mut any = false;
+        for item in accept_encoding.split(',') {
+            let mut parts = item.split(';').map(str::trim);
+            let encoding = parts.next().unwrap_or_default();
+            let accepted = !parts.any(|param| {
+                param
+                    .strip_prefix("q=")
+                    .and_then(|q| q.parse::<f32>().ok())
+                    .is_some_and(|q| q <= 0.0)
+            });
+
+            match encoding.to_ascii_lowercase().as_str() {
+                "gzip" | "x-gzip" => gzip = Some(accepted),
+                "br" => brotli = Some(accepted),
+                "*" => any = accepted,
+                _ => {}
+            }
+        }
+
+        if brotli.unwrap_or(any) {
+            (Some("br"), &self.brotli)
+        } else if gzip.unwrap_or(any) {
+            (Some("gzip"), &self.gzip)
+        } else {
+            (None, self.content.as_bytes())
+        }
+    }
+}
+
+fn compress_gzip(data: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+    encoder
+        .write_all(data)
+        .expect("writing to a Vec cannot fail");
+    encoder.finish().expect("writing to a Vec cannot fail")
+}
+
+fn compress_brotli(data: &[u8]) -> Vec<u8> {
+    let mut res = Vec::new();
+    {
+        let mut encoder = brotli::CompressorWriter::new(&mut res, 4096, 11, 22);
+        encoder
+            .write_all(data)
+            .expect("writing to a Vec cannot fail");
+    }
+    res
+}
+
+/// Split the template into all fragments and compress each of them
+///
+/// See [CompressedFragment] and [crate::split_templates].
+///
+/// ```rust
+/// # use template_fragments::split_templates_compressed;
+/// let source = concat!(
+///     "<ul>\n",
+///     "{% fragment item %}\n",
+///     "  <li>{{ item }}</li>\n",
+///     "{% endfragment %}\n",
+///     "</ul>\n",
+/// );
+/// let fragments = split_templates_compressed(source).unwrap();
+///
+/// let (encoding, body) = fragments["item"].encode("gzip");
+/// assert_eq!(encoding, Some("gzip"));
+/// assert_eq!(body, fragments["item"].gzip);
+/// ```
+pub fn split_templates_compressed(
+    src: &str,
+) -> Result<HashMap<String, CompressedFragment>, ErrorWithLine> {
+    Config::default().split_templates_compressed(src)
+}
+
+impl Config {
+    /// Split the template into all fragments and compress each of them
+    ///
+    /// The fragments are compressed after all output options were applied.
+    /// See [crate::split_templates_compressed].
+    pub fn split_templates_compressed(
+        &self,
+        src: &str,
+    ) -> Result<HashMap<String, CompressedFragment>, ErrorWithLine> {
+        Ok(self
+            .split_templates(src)?
+            .into_iter()
+            .map(|(fragment, content)| (fragment, CompressedFragment::new(content)))
+            .collect())
+    }
+}
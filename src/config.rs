@@ -0,0 +1,1076 @@
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    path::Path,
+};
+
+use crate::{
+    annotate::annotate,
+    context::Context,
+    custom::TagHandler,
+    decode::strip_bom,
+    defines::LineMap,
+    instrument::add_data_fragment,
+    iterate_with_endings, join_path,
+    minify::minify_html,
+    numbered::{fragment_of_key, process_numbered},
+    parse_fragment_tag, process_template,
+    trace::{finish_span, start_span},
+    verbatim::Verbatim,
+    BlockStyle, Error, ErrorWithLine, OutputProfile, Syntax, Tag,
+};
+
+/// Configuration of how templates are processed
+///
+/// The free functions [crate::filter_template] and [crate::split_templates]
+/// use the default configuration. Options are set with builder methods:
+///
+/// ```rust
+/// # use template_fragments::Config;
+/// let source = concat!(
+///     "{% fragment item %}\n",
+///     "  <div>{{ item }}</div>\n",
+///     "{% endfragment %}\n",
+///     "{% fragment item %}\n",
+///     "  <div>{{ item }}</div>\n",
+///     "{% endfragment %}\n",
+/// );
+///
+/// let config = Config::default().unique_fragments(true);
+/// assert!(config.split_templates(source).is_err());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Config {
+    pub(crate) syntax: Syntax,
+    pub(crate) block_style: BlockStyle,
+    pub(crate) named_endblocks: bool,
+    pub(crate) scoped_blocks: bool,
+    pub(crate) unique_fragments: bool,
+    pub(crate) max_depth: Option<usize>,
+    pub(crate) max_fragments: Option<usize>,
+    pub(crate) macros: bool,
+    pub(crate) name_case: NameCase,
+    pub(crate) named_end_tags: bool,
+    pub(crate) minify_html: bool,
+    pub(crate) tag_handlers: Vec<TagHandler>,
+    pub(crate) extends: Option<(String, String)>,
+    pub(crate) strict_fragments: bool,
+    pub(crate) keep_fragment_tags: bool,
+    pub(crate) skip_base: bool,
+    pub(crate) defines: Option<BTreeMap<String, String>>,
+    pub(crate) debug_annotations: bool,
+    /// The path of the template included in debug annotations
+    pub(crate) debug_path: Option<String>,
+    pub(crate) data_fragment_attribute: bool,
+    pub(crate) blocks_as_fragments: bool,
+    pub(crate) fragments_as_blocks: bool,
+    pub(crate) tag_colons: bool,
+    pub(crate) line_comment_prefix: Option<String>,
+    pub(crate) macros_as_fragments: bool,
+    pub(crate) number_repeated_fragments: bool,
+    pub(crate) fragment_separator: Option<String>,
+    pub(crate) enclosing_control_tags: bool,
+    pub(crate) enclosing_with_tags: bool,
+    #[cfg(feature = "minijinja")]
+    pub(crate) verify: bool,
+}
+
+/// The canonical form of fragment names
+///
+/// ```rust
+/// # use template_fragments::{Config, NameCase};
+/// let source = concat!(
+///     "{% fragment Item %}\n",
+///     "  <div>{{ item }}</div>\n",
+///     "{% endfragment %}\n",
+///     "{% fragment ITEM %}\n",
+///     "  <span>{{ item }}</span>\n",
+///     "{% endfragment %}\n",
+/// );
+///
+/// let config = Config::default().name_case(NameCase::Lower);
+/// let templates = config.split_templates(source).unwrap();
+/// assert_eq!(
+///     templates["item"],
+///     "  <div>{{ item }}</div>\n  <span>{{ item }}</span>\n",
+/// );
+/// assert_eq!(
+///     config.filter_template(source, "iTeM").unwrap(),
+///     templates["item"],
+/// );
+/// assert_eq!(config.join_path("index.html", "Item"), "index.html#item");
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NameCase {
+    /// Keep names as they are, i.e., names are case-sensitive (the default)
+    #[default]
+    Preserve,
+    /// Convert names to lowercase
+    Lower,
+    /// Convert names to uppercase
+    Upper,
+}
+
+impl NameCase {
+    /// Convert the name into its canonical form
+    pub fn normalize<'n>(&self, name: &'n str) -> Cow<'n, str> {
+        match self {
+            Self::Preserve => Cow::Borrowed(name),
+            Self::Lower if !name.chars().any(char::is_uppercase) => Cow::Borrowed(name),
+            Self::Upper if !name.chars().any(char::is_lowercase) => Cow::Borrowed(name),
+            Self::Lower => Cow::Owned(name.to_lowercase()),
+            Self::Upper => Cow::Owned(name.to_uppercase()),
+        }
+    }
+}
+
+impl Config {
+    /// A configuration for Go templates
+    ///
+    /// It uses [Syntax::golang] and [BlockStyle::Go].
+    pub fn golang() -> Self {
+        Self::default()
+            .syntax(Syntax::golang())
+            .block_style(BlockStyle::Go)
+    }
+
+    /// A configuration for Liquid templates, e.g., of Shopify themes
+    ///
+    /// It uses [Syntax::liquid] and [BlockStyle::Liquid].
+    pub fn liquid() -> Self {
+        Self::default()
+            .syntax(Syntax::liquid())
+            .block_style(BlockStyle::Liquid)
+    }
+
+    /// A configuration for handlebars templates
+    ///
+    /// It uses [Syntax::handlebars] and [BlockStyle::Handlebars].
+    pub fn handlebars() -> Self {
+        Self::default()
+            .syntax(Syntax::handlebars())
+            .block_style(BlockStyle::Handlebars)
+    }
+
+    /// A configuration for LaTeX templates following the Jinja-LaTeX
+    /// conventions
+    ///
+    /// It uses [Syntax::latex], i.e., `((* fragment NAME *))`. For templates
+    /// with `\BLOCK{fragment NAME}` markers, use [Syntax::latex_commands]
+    /// instead. Emitted tags, e.g., the blocks of block fragments, use the same
+    /// markers.
+    ///
+    /// ```rust
+    /// # use template_fragments::{Config, Syntax};
+    /// let source = concat!(
+    ///     "\\begin{itemize}\n",
+    ///     "((* for item in items *))\n",
+    ///     "((* fragment-block item *))\n",
+    ///     "  \\item ((( item )))\n",
+    ///     "((* endfragment-block *))\n",
+    ///     "((* endfor *))\n",
+    ///     "\\end{itemize}\n",
+    /// );
+    ///
+    /// let templates = Config::latex().split_templates(source).unwrap();
+    /// assert_eq!(
+    ///     templates["item"],
+    ///     "((* block item *))\n  \\item ((( item )))\n((* endblock *))\n",
+    /// );
+    ///
+    /// let source = source.replace("((* ", "\\BLOCK{").replace(" *))", "}");
+    /// let config = Config::latex().syntax(Syntax::latex_commands());
+    /// assert_eq!(
+    ///     config.split_templates(&source).unwrap()["item"],
+    ///     "\\BLOCK{ block item }\n  \\item ((( item )))\n\\BLOCK{ endblock }\n",
+    /// );
+    /// ```
+    pub fn latex() -> Self {
+        Self::default().syntax(Syntax::latex())
+    }
+
+    /// A configuration for Markdown documents, e.g., of docs sites that
+    /// render Markdown through Jinja
+    ///
+    /// Fragments are marked with HTML comments, e.g., `<!-- fragment: intro
+    /// -->` and `<!-- endfragment -->`, see [Syntax::html_comments] and
+    /// [Config::tag_colons]. As the markers are comments, annotated documents
+    /// still render as plain Markdown.
+    ///
+    /// ```rust
+    /// # use template_fragments::Config;
+    /// let source = concat!(
+    ///     "# Guide\n",
+    ///     "\n",
+    ///     "<!-- fragment: intro -->\n",
+    ///     "Welcome to {{ project }}.\n",
+    ///     "<!-- endfragment -->\n",
+    ///     "\n",
+    ///     "## Installation\n",
+    /// );
+    ///
+    /// let templates = Config::markdown().split_templates(source).unwrap();
+    /// assert_eq!(templates["intro"], "Welcome to {{ project }}.\n");
+    /// assert_eq!(templates[""], "# Guide\n\nWelcome to {{ project }}.\n\n## Installation\n");
+    /// ```
+    pub fn markdown() -> Self {
+        Self::default()
+            .syntax(Syntax::html_comments())
+            .tag_colons(true)
+    }
+
+    /// A configuration for Jinja-SQL templates, e.g., dbt models
+    ///
+    /// Fragment tags may be written in SQL line comments, e.g., `-- {%
+    /// fragment recent_orders %}`, see [Config::line_comment_prefix]. This
+    /// way, named parts of a model, e.g., CTEs or filters, can be extracted
+    /// and rendered on their own.
+    ///
+    /// ```rust
+    /// # use template_fragments::Config;
+    /// let source = concat!(
+    ///     "select *\n",
+    ///     "from {{ ref('orders') }}\n",
+    ///     "where\n",
+    ///     "    -- {% fragment recent %}\n",
+    ///     "    ordered_at > current_date - interval '7 days'\n",
+    ///     "    -- {% endfragment %}\n",
+    /// );
+    ///
+    /// let templates = Config::sql().split_templates(source).unwrap();
+    /// assert_eq!(templates["recent"], "    ordered_at > current_date - interval '7 days'\n");
+    /// ```
+    pub fn sql() -> Self {
+        Self::default().line_comment_prefix("--")
+    }
+
+    /// A configuration compatible with django-render-block
+    ///
+    /// Every fragment is emitted as a Django block and every `{% block %}` of
+    /// the template is also a fragment, see [Config::fragments_as_blocks] and
+    /// [Config::blocks_as_fragments]. The fragments are keyed by block name
+    /// and keep their block tags, like the blocks rendered by
+    /// `render_block_to_string`. It uses [OutputProfile::Django] and
+    /// [Config::unique_fragments], as block names must be unique in Django.
+    ///
+    /// ```rust
+    /// # use template_fragments::Config;
+    /// let source = concat!(
+    ///     "{% block content %}\n",
+    ///     "<ul>\n",
+    ///     "  {% fragment item %}\n",
+    ///     "  <li>{{ item }}</li>\n",
+    ///     "  {% endfragment %}\n",
+    ///     "</ul>\n",
+    ///     "{% endblock %}\n",
+    /// );
+    ///
+    /// let templates = Config::django_render_block().split_templates(source).unwrap();
+    /// assert_eq!(
+    ///     templates[""],
+    ///     concat!(
+    ///         "{% block content %}\n",
+    ///         "<ul>\n",
+    ///         "  {% block item %}\n",
+    ///         "  <li>{{ item }}</li>\n",
+    ///         "  {% endblock item %}\n",
+    ///         "</ul>\n",
+    ///         "{% endblock content %}\n",
+    ///     ),
+    /// );
+    /// assert_eq!(
+    ///     templates["item"],
+    ///     "  {% block item %}\n  <li>{{ item }}</li>\n  {% endblock item %}\n",
+    /// );
+    /// assert!(templates["content"].starts_with("{% block content %}\n"));
+    /// ```
+    pub fn django_render_block() -> Self {
+        Self::default()
+            .output_profile(OutputProfile::Django)
+            .blocks_as_fragments(true)
+            .fragments_as_blocks(true)
+            .unique_fragments(true)
+    }
+
+    /// Select a configuration suitable for the file extension of the template
+    ///
+    /// Engine specific extensions (`.j2`, `.jinja`, `.jinja2`) are ignored,
+    /// i.e., `report.tex.j2` is treated as a LaTeX template. The supported
+    /// extensions are:
+    ///
+    /// - `.tex`, `.latex`: [Config::latex]
+    /// - `.hbs`, `.handlebars`: [Config::handlebars]
+    /// - `.liquid`: [Config::liquid]
+    /// - `.md`, `.markdown`: [Config::markdown]
+    /// - `.sql`: [Config::sql]
+    /// - all other extensions (e.g., `.html`): the default markers `{% ... %}`
+    ///   and `{# ... #}`
+    ///
+    /// ```rust
+    /// # use template_fragments::Config;
+    /// let source = concat!(
+    ///     "\\section{Report}\n",
+    ///     "((* fragment summary *))\n",
+    ///     "\\textbf{ ((( summary ))) }\n",
+    ///     "((* endfragment *))\n",
+    /// );
+    ///
+    /// let templates = Config::for_path("report.tex.j2").split_templates(source).unwrap();
+    /// assert_eq!(templates["summary"], "\\textbf{ ((( summary ))) }\n");
+    /// ```
+    pub fn for_path(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        let mut extension = path.extension().and_then(|ext| ext.to_str());
+
+        if matches!(extension, Some("j2" | "jinja" | "jinja2")) {
+            extension = path
+                .file_stem()
+                .map(Path::new)
+                .and_then(|stem| stem.extension())
+                .and_then(|ext| ext.to_str());
+        }
+
+        match extension {
+            Some("tex" | "latex") => Self::latex(),
+            Some("hbs" | "handlebars") => Self::handlebars(),
+            Some("liquid") => Self::liquid(),
+            Some("md" | "markdown") => Self::markdown(),
+            Some("sql") => Self::sql(),
+            _ => Self::default(),
+        }
+    }
+
+    /// Set the syntax of tags and comments, by default [Syntax::jinja]
+    pub fn syntax(mut self, syntax: Syntax) -> Self {
+        self.syntax = syntax;
+        self
+    }
+
+    /// Set how fragment blocks are emitted, by default [BlockStyle::Jinja]
+    pub fn block_style(mut self, block_style: BlockStyle) -> Self {
+        self.block_style = block_style;
+        self
+    }
+
+    /// Set the markers of fragment tags, by default `{%` and `%}`
+    ///
+    /// The markers are also used for the generated `block` tags of block
+    /// fragments.
+    pub fn tag_markers(mut self, open: &str, close: &str) -> Self {
+        self.syntax.tag_markers = (open.to_owned(), close.to_owned());
+        self
+    }
+
+    /// Set the markers of comments, by default `{#` and `#}`
+    pub fn comment_markers(mut self, open: &str, close: &str) -> Self {
+        self.syntax.comment_markers = (open.to_owned(), close.to_owned());
+        self
+    }
+
+    /// If `true`, fragments that occur multiple times result in an
+    /// [crate::Error::RepeatedFragment] instead of concatenating their parts
+    pub fn unique_fragments(mut self, value: bool) -> Self {
+        self.unique_fragments = value;
+        self
+    }
+
+    /// If `true`, filtering a fragment that is not defined in the template
+    /// results in an [crate::Error::UnknownFragment] instead of an empty string
+    ///
+    /// The base template `""` is always defined. The error is reported at the
+    /// first line and lists the fragments that are available.
+    ///
+    /// ```rust
+    /// # use template_fragments::{Config, Error, ErrorWithLine};
+    /// let source = concat!(
+    ///     "{% fragment item %}\n",
+    ///     "  <div>{{ item }}</div>\n",
+    ///     "{% endfragment %}\n",
+    /// );
+    ///
+    /// let config = Config::default().strict_fragments(true);
+    /// assert_eq!(
+    ///     config.filter_template(source, "itme"),
+    ///     Err(ErrorWithLine(
+    ///         0,
+    ///         Error::UnknownFragment(String::from("itme"), vec![String::from("item")]),
+    ///     )),
+    /// );
+    /// ```
+    pub fn strict_fragments(mut self, value: bool) -> Self {
+        self.strict_fragments = value;
+        self
+    }
+
+    /// If `true`, keep the `fragment` and `endfragment` tags in the base
+    /// template instead of removing them
+    ///
+    /// This option is intended for engines that were extended with a no-op
+    /// `fragment` tag, such that the annotations stay visible at runtime. The
+    /// tags are never part of the other fragments. Block fragments are
+    /// converted as usual.
+    ///
+    /// ```rust
+    /// # use template_fragments::Config;
+    /// let source = concat!(
+    ///     "<ul>\n",
+    ///     "{% fragment item %}\n",
+    ///     "  <li>{{ item }}</li>\n",
+    ///     "{% endfragment %}\n",
+    ///     "</ul>\n",
+    /// );
+    ///
+    /// let config = Config::default().keep_fragment_tags(true);
+    /// assert_eq!(config.filter_template(source, "").unwrap(), source);
+    /// assert_eq!(config.filter_template(source, "item").unwrap(), "  <li>{{ item }}</li>\n");
+    /// ```
+    pub fn keep_fragment_tags(mut self, value: bool) -> Self {
+        self.keep_fragment_tags = value;
+        self
+    }
+
+    /// If `true`, omit the base template `""` when splitting templates
+    ///
+    /// The base template is usually the largest output. This option avoids
+    /// building it, e.g., if it is served separately. It applies to
+    /// [Config::split_templates] and its variants, [Config::filter_template]
+    /// still returns the base template.
+    ///
+    /// ```rust
+    /// # use template_fragments::Config;
+    /// let source = concat!(
+    ///     "<ul>\n",
+    ///     "{% fragment item %}\n",
+    ///     "  <li>{{ item }}</li>\n",
+    ///     "{% endfragment %}\n",
+    ///     "</ul>\n",
+    /// );
+    ///
+    /// let templates = Config::default().skip_base(true).split_templates(source).unwrap();
+    /// assert!(!templates.contains_key(""));
+    /// assert_eq!(templates["item"], "  <li>{{ item }}</li>\n");
+    /// ```
+    pub fn skip_base(mut self, value: bool) -> Self {
+        self.skip_base = value;
+        self
+    }
+
+    /// Insert a separator between the occurrences of repeated fragments
+    ///
+    /// By default, the occurrences are concatenated directly. The separator is
+    /// inserted as is, e.g., `"\n"` for a blank line or `"{# --- #}\n"` for a
+    /// comment line, and only between occurrences with any output. It is not
+    /// inserted if [Config::number_repeated_fragments] is enabled.
+    ///
+    /// ```rust
+    /// # use template_fragments::Config;
+    /// let source = concat!(
+    ///     "{% fragment item %}\n",
+    ///     "<li>{{ first }}</li>\n",
+    ///     "{% endfragment %}\n",
+    ///     "{% fragment item %}\n",
+    ///     "<li>{{ second }}</li>\n",
+    ///     "{% endfragment %}\n",
+    /// );
+    ///
+    /// let config = Config::default().fragment_separator("{# --- #}\n");
+    /// assert_eq!(
+    ///     config.filter_template(source, "item").unwrap(),
+    ///     "<li>{{ first }}</li>\n{# --- #}\n<li>{{ second }}</li>\n",
+    /// );
+    /// ```
+    pub fn fragment_separator(mut self, separator: impl Into<String>) -> Self {
+        self.fragment_separator = Some(separator.into());
+        self
+    }
+
+    /// Limit how deeply fragments can be nested
+    ///
+    /// Exceeding the limit results in an [crate::Error::NestingTooDeep]. This
+    /// option, together with [Config::max_fragments], protects against
+    /// pathological inputs, e.g., when processing user-supplied templates.
+    pub fn max_depth(mut self, value: usize) -> Self {
+        self.max_depth = Some(value);
+        self
+    }
+
+    /// Limit the number of distinct fragments in a template
+    ///
+    /// The base fragment `""` is not counted. Exceeding the limit results in
+    /// an [crate::Error::TooManyFragments].
+    pub fn max_fragments(mut self, value: usize) -> Self {
+        self.max_fragments = Some(value);
+        self
+    }
+
+    /// If `true`, [Config::split_templates] additionally returns all named
+    /// fragments as Jinja macros in the `"_macros"` template
+    ///
+    /// The base template can then import the macros and call the fragments,
+    /// while the fragments can still be rendered standalone. Dashes in
+    /// fragment names are replaced by underscores to form valid macro names.
+    /// Fragments that result in the same macro name, e.g., `list-item` and
+    /// `list_item`, are reported as [crate::Error::MacroNameCollision].
+    ///
+    /// ```rust
+    /// # use template_fragments::Config;
+    /// let source = concat!(
+    ///     "<ul>\n",
+    ///     "{% fragment list-item %}\n",
+    ///     "  <li>{{ item }}</li>\n",
+    ///     "{% endfragment %}\n",
+    ///     "</ul>\n",
+    /// );
+    ///
+    /// let templates = Config::default().macros(true).split_templates(source).unwrap();
+    /// assert_eq!(
+    ///     templates["_macros"],
+    ///     concat!(
+    ///         "{% macro list_item() %}\n",
+    ///         "  <li>{{ item }}</li>\n",
+    ///         "{% endmacro %}\n",
+    ///     ),
+    /// );
+    /// ```
+    pub fn macros(mut self, value: bool) -> Self {
+        self.macros = value;
+        self
+    }
+
+    /// If `true`, end tags may repeat the names of their start tag, e.g.,
+    /// `{% endfragment item %}`
+    ///
+    /// The names are checked against the innermost start tag and mismatches
+    /// result in an [crate::Error::MismatchedEndTag]. For start tags with
+    /// multiple names, a subset of the names is sufficient. Without this
+    /// option, names on end tags result in an [crate::Error::EndTagWithData].
+    ///
+    /// ```rust
+    /// # use template_fragments::{Config, Error, ErrorWithLine};
+    /// let source = concat!(
+    ///     "{% fragment items %}\n",
+    ///     "  {% fragment item %}\n",
+    ///     "  {% endfragment items %}\n",
+    ///     "{% endfragment item %}\n",
+    /// );
+    ///
+    /// let config = Config::default().named_end_tags(true);
+    /// assert_eq!(
+    ///     config.split_templates(source),
+    ///     Err(ErrorWithLine(
+    ///         2,
+    ///         Error::MismatchedEndTag(vec![String::from("items")], vec![String::from("item")]),
+    ///     )),
+    /// );
+    /// ```
+    pub fn named_end_tags(mut self, value: bool) -> Self {
+        self.named_end_tags = value;
+        self
+    }
+
+    /// Treat fragment names case-insensitively by converting them into the
+    /// given canonical form, by default names are case-sensitive
+    ///
+    /// The names of fragment tags are converted while parsing. Therefore, the
+    /// tags of [crate::TemplateAst] and [crate::Layout] use the canonical
+    /// names. Requested fragment names are converted when filtering and in
+    /// [Config::join_path]. See [NameCase].
+    pub fn name_case(mut self, name_case: NameCase) -> Self {
+        self.name_case = name_case;
+        self
+    }
+
+    /// If `true`, collapse insignificant whitespace of the extracted
+    /// fragments, as HTML
+    ///
+    /// Whitespace at the start of lines and empty lines are removed, other runs
+    /// of whitespace are collapsed into a single space. The content of `pre`,
+    /// `textarea`, `script`, and `style` elements is kept as it is. Applies to
+    /// [Config::filter_template], [Config::split_templates], and
+    /// [Config::parse_template].
+    ///
+    /// ```rust
+    /// # use template_fragments::Config;
+    /// let source = concat!(
+    ///     "<ul>\n",
+    ///     "  {% fragment item %}\n",
+    ///     "  <li>\n",
+    ///     "    <a href=\"{{ item.url }}\">   {{ item.name }}</a>\n",
+    ///     "\n",
+    ///     "    <pre>{{ item.code }}\n  </pre>\n",
+    ///     "  </li>\n",
+    ///     "  {% endfragment %}\n",
+    ///     "</ul>\n",
+    /// );
+    ///
+    /// let config = Config::default().minify_html(true);
+    /// assert_eq!(
+    ///     config.filter_template(source, "item").unwrap(),
+    ///     concat!(
+    ///         "<li>\n",
+    ///         "<a href=\"{{ item.url }}\"> {{ item.name }}</a>\n",
+    ///         "<pre>{{ item.code }}\n  </pre>\n",
+    ///         "</li>\n",
+    ///     ),
+    /// );
+    /// ```
+    pub fn minify_html(mut self, value: bool) -> Self {
+        self.minify_html = value;
+        self
+    }
+
+    /// Let all fragments except the base fragment extend the given layout,
+    /// with their content as the given block
+    ///
+    /// This way, fragments requested directly, e.g., on a full page load of
+    /// an HTMX endpoint, still render a complete page. Applies to the same
+    /// functions as [Config::minify_html]. Empty fragments are kept empty.
+    ///
+    /// ```rust
+    /// # use template_fragments::Config;
+    /// let source = concat!(
+    ///     "<ul>\n",
+    ///     "{% fragment items %}\n",
+    ///     "  <li>{{ item }}</li>\n",
+    ///     "{% endfragment %}\n",
+    ///     "</ul>\n",
+    /// );
+    ///
+    /// let config = Config::default().extends("layout.html", "content");
+    /// let templates = config.split_templates(source).unwrap();
+    /// assert_eq!(
+    ///     templates["items"],
+    ///     concat!(
+    ///         "{% extends \"layout.html\" %}\n",
+    ///         "{% block content %}\n",
+    ///         "  <li>{{ item }}</li>\n",
+    ///         "{% endblock %}\n",
+    ///     ),
+    /// );
+    /// assert_eq!(templates[""], "<ul>\n  <li>{{ item }}</li>\n</ul>\n");
+    /// ```
+    pub fn extends(mut self, layout: &str, block: &str) -> Self {
+        self.extends = Some((layout.to_owned(), block.to_owned()));
+        self
+    }
+
+    /// Join a template path and a fragment name with the canonical fragment
+    /// name
+    ///
+    /// See [crate::join_path].
+    pub fn join_path(&self, path: &str, fragment: &str) -> String {
+        join_path(path, &self.name_case.normalize(fragment.trim()))
+    }
+
+    /// Prepare the source for processing: evaluate directives, expand custom
+    /// tags, blocks, and macros, and normalize fragment names
+    ///
+    /// A leading byte order mark is removed. Errors of processing the result
+    /// are reported at the original lines with the returned [LineMap].
+    pub(crate) fn preprocess<'s>(
+        &self,
+        src: &'s str,
+    ) -> Result<(Cow<'s, str>, LineMap), ErrorWithLine> {
+        start_span!(timer, "preprocess", bytes = src.len());
+        let (src, lines) = self.expand_directives(strip_bom(src))?;
+        let src = match src {
+            Cow::Borrowed(src) => self.expand_custom_tags(src),
+            Cow::Owned(src) => self
+                .expand_custom_tags(&src)
+                .map(|src| Cow::Owned(src.into_owned())),
+        }
+        .map_err(|err| lines.restore(err))?;
+        let src = match src {
+            Cow::Borrowed(src) => self.strip_tag_colons(src),
+            Cow::Owned(src) => Cow::Owned(self.strip_tag_colons(&src).into_owned()),
+        };
+        let src = match src {
+            Cow::Borrowed(src) => self.strip_comment_prefixes(src),
+            Cow::Owned(src) => Cow::Owned(self.strip_comment_prefixes(&src).into_owned()),
+        };
+        let src = match src {
+            Cow::Borrowed(src) => self.expand_blocks(src),
+            Cow::Owned(src) => Cow::Owned(self.expand_blocks(&src).into_owned()),
+        };
+        let src = match src {
+            Cow::Borrowed(src) => self.expand_fragment_blocks(src),
+            Cow::Owned(src) => Cow::Owned(self.expand_fragment_blocks(&src).into_owned()),
+        };
+        let (src, lines) = match src {
+            Cow::Borrowed(src) => {
+                let (src, macro_lines) = self.expand_macros(src);
+                (src, lines.then(macro_lines))
+            }
+            Cow::Owned(src) => {
+                let (expanded, macro_lines) = self.expand_macros(&src);
+                (Cow::Owned(expanded.into_owned()), lines.then(macro_lines))
+            }
+        };
+        let src = match src {
+            Cow::Borrowed(src) => self.normalize_names(src),
+            Cow::Owned(src) => Cow::Owned(self.normalize_names(&src).into_owned()),
+        };
+        finish_span!(timer, expanded = matches!(src, Cow::Owned(_)));
+        Ok((src, lines))
+    }
+
+    /// Convert the names of all fragment tags into their canonical form
+    pub(crate) fn normalize_names<'s>(&self, src: &'s str) -> Cow<'s, str> {
+        if self.name_case == NameCase::Preserve {
+            return Cow::Borrowed(src);
+        }
+
+        let mut res = String::with_capacity(src.len());
+        let mut verbatim = Verbatim::default();
+        for line in iterate_with_endings(src) {
+            if verbatim.skip(line, self.syntax.tag_markers()) {
+                res.push_str(line);
+                continue;
+            }
+            // invalid tags are reported when processing the normalized source
+            let names: Vec<&str> = match parse_fragment_tag(line, self.syntax.tag_markers()) {
+                Ok(Some(Tag::Start(tag))) => tag.fragments.into_iter().collect(),
+                Ok(Some(Tag::StartBlock(tag))) => vec![tag.fragment],
+                Ok(Some(Tag::End(tag))) => tag.data.split_whitespace().collect(),
+                Ok(Some(Tag::EndBlock(tag))) => tag.data.split_whitespace().collect(),
+                _ => Vec::new(),
+            };
+
+            let mut ranges: Vec<(usize, usize)> = names
+                .into_iter()
+                .map(|name| {
+                    // names are sub-slices of the line
+                    let start = name.as_ptr() as usize - line.as_ptr() as usize;
+                    (start, start + name.len())
+                })
+                .collect();
+            ranges.sort();
+
+            let mut pos = 0;
+            for (start, end) in ranges {
+                res.push_str(&line[pos..start]);
+                res.push_str(&self.name_case.normalize(&line[start..end]));
+                pos = end;
+            }
+            res.push_str(&line[pos..]);
+        }
+        Cow::Owned(res)
+    }
+
+    /// Process the template and return all parts for the given fragment
+    ///
+    /// See [crate::filter_template].
+    pub fn filter_template(&self, src: &str, fragment: &str) -> Result<String, ErrorWithLine> {
+        self.filter_template_cow(src, fragment).map(Cow::into_owned)
+    }
+
+    /// Process the template and return all parts for the given fragment,
+    /// borrowing from the source if it does not contain fragment tags
+    ///
+    /// See [crate::filter_template_cow].
+    pub fn filter_template_cow<'s>(
+        &self,
+        src: &'s str,
+        fragment: &str,
+    ) -> Result<Cow<'s, str>, ErrorWithLine> {
+        start_span!(timer, "filter_template", fragment, bytes = src.len());
+        let (src, lines) = self.preprocess(src)?;
+        let fragment = self.name_case.normalize(fragment);
+
+        if let Cow::Borrowed(src) = src {
+            if !self.minify_html && !self.has_fragments(src) {
+                if fragment.is_empty() {
+                    return Ok(Cow::Borrowed(src));
+                } else if !self.strict_fragments {
+                    return Ok(Cow::Borrowed(""));
+                }
+            }
+        }
+
+        let mut res = String::new();
+        let mut found = false;
+        if self.number_repeated_fragments {
+            process_numbered(self, &src, |active, keys, line| {
+                let key = |&fragment| keys.get(fragment).map_or(fragment, String::as_str);
+                if active.iter().any(|f| key(f) == fragment) {
+                    found = true;
+                    res.push_str(line);
+                }
+            })
+        } else {
+            process_template(self, &src, |active, line| {
+                if active.contains(fragment.as_ref()) {
+                    found = true;
+                    res.push_str(line);
+                }
+            })
+        }
+        .map_err(|err| lines.restore(err))?;
+
+        if self.strict_fragments && !fragment.is_empty() && !found {
+            let available = self.fragment_names(&src);
+            if !available.contains(fragment.as_ref()) {
+                let available = available.into_iter().map(str::to_owned).collect();
+                return Err(Error::UnknownFragment(fragment.into_owned(), available).at(0));
+            }
+        }
+
+        // numbered keys use the contexts and annotations of their fragment
+        let name = fragment_of_key(&fragment);
+        let contexts = self.fragment_contexts(&src);
+        let res = self.finish_fragment(name, contexts.get(name), res);
+        finish_span!(timer, output_bytes = res.len());
+        Ok(Cow::Owned(annotate(
+            &self.fragment_annotations(&src, &lines),
+            name,
+            res,
+        )))
+    }
+
+    /// Split the template into all fragments available
+    ///
+    /// See [crate::split_templates].
+    pub fn split_templates(&self, src: &str) -> Result<HashMap<String, String>, ErrorWithLine> {
+        self.split_templates_transformed(src, |_, line| Some(Cow::Borrowed(line)))
+    }
+
+    /// Split the template into all fragments, keyed by their full path
+    ///
+    /// The keys are constructed with [Config::join_path]. See
+    /// [crate::split_templates_prefixed].
+    pub fn split_templates_prefixed(
+        &self,
+        path: &str,
+        src: &str,
+    ) -> Result<HashMap<String, String>, ErrorWithLine> {
+        start_span!(_timer, "split_template", path);
+        let templates = if self.debug_annotations {
+            let config = Config {
+                debug_path: Some(path.trim().to_owned()),
+                ..self.clone()
+            };
+            config.split_templates(src)?
+        } else {
+            self.split_templates(src)?
+        };
+        Ok(templates
+            .into_iter()
+            .map(|(fragment, content)| (self.join_path(path, &fragment), content))
+            .collect())
+    }
+
+    /// Split the template into an existing map, reusing its buffers
+    ///
+    /// See [crate::split_templates_into].
+    pub fn split_templates_into(
+        &self,
+        src: &str,
+        templates: &mut HashMap<String, String>,
+    ) -> Result<(), ErrorWithLine> {
+        self.split_transformed_into(src, |_, line| Some(Cow::Borrowed(line)), templates)
+    }
+
+    /// Split the template and pass each fragment to a callback
+    ///
+    /// See [crate::split_templates_with].
+    pub fn split_templates_with(
+        &self,
+        src: &str,
+        mut f: impl FnMut(&str, String),
+    ) -> Result<(), ErrorWithLine> {
+        start_span!(timer, "split_templates", bytes = src.len());
+        let (src, lines) = self.preprocess(src)?;
+
+        let mut templates = BTreeMap::new();
+        if self.number_repeated_fragments {
+            process_numbered(self, &src, |active, keys, output| {
+                self.push_output(&mut templates, active, keys, output)
+            })
+        } else {
+            process_template(self, &src, |active, output| {
+                self.push_output(&mut templates, active, &HashMap::new(), output)
+            })
+        }
+        .map_err(|err| lines.restore(err))?;
+
+        let macros = self
+            .macros
+            .then(|| {
+                self.render_macros(
+                    &src,
+                    templates.iter().map(|(k, v)| (k.as_ref(), v.as_str())),
+                )
+            })
+            .transpose()
+            .map_err(|err| lines.restore(err))?;
+        self.finish_fragments(
+            &src,
+            &lines,
+            templates.iter_mut().map(|(k, v)| (k.as_ref(), v)),
+        );
+        if let Some(macros) = macros {
+            templates.insert(
+                Cow::Borrowed("_macros"),
+                self.finish_fragment("", None, macros),
+            );
+        }
+        #[cfg(feature = "minijinja")]
+        if self.verify {
+            self.verify_fragments(
+                &src,
+                templates.iter().map(|(k, v)| (k.as_ref(), v.as_str())),
+            )
+            .map_err(|err| lines.restore(err))?;
+        }
+        finish_span!(timer, fragments = templates.len());
+
+        for (key, content) in templates {
+            f(&key, content);
+        }
+        Ok(())
+    }
+
+    /// Append output to the fragments it belongs to, keyed by their name or
+    /// their numbered key
+    ///
+    /// Only numbered keys are allocated, names borrow from the template.
+    fn push_output<'a>(
+        &self,
+        templates: &mut BTreeMap<Cow<'a, str>, String>,
+        active: &HashSet<&'a str>,
+        keys: &HashMap<&'a str, String>,
+        output: &str,
+    ) {
+        for &fragment in active {
+            if self.skip_base && fragment.is_empty() {
+                continue;
+            }
+            let key = keys.get(fragment);
+            match templates.get_mut(key.map_or(fragment, String::as_str)) {
+                Some(content) => content.push_str(output),
+                None => {
+                    let key = key.map_or(Cow::Borrowed(fragment), |key| Cow::Owned(key.clone()));
+                    templates.insert(key, output.to_owned());
+                }
+            }
+        }
+    }
+
+    /// The sorted names of all fragments with a start tag in a valid template
+    pub(crate) fn fragment_names<'s>(&self, src: &'s str) -> BTreeSet<&'s str> {
+        let mut res = BTreeSet::new();
+        let mut verbatim = Verbatim::default();
+        for line in iterate_with_endings(src) {
+            if verbatim.skip(line, self.syntax.tag_markers()) {
+                continue;
+            }
+            match parse_fragment_tag(line, self.syntax.tag_markers()) {
+                Ok(Some(Tag::Start(tag))) => res.extend(tag.fragments),
+                Ok(Some(Tag::StartBlock(tag))) => {
+                    res.insert(tag.fragment);
+                }
+                _ => {}
+            }
+        }
+        res
+    }
+
+    /// Apply the output options to the content of a fragment
+    ///
+    /// The context is collected with [Config::fragment_contexts].
+    pub(crate) fn finish_fragment(
+        &self,
+        fragment: &str,
+        context: Option<&Context>,
+        content: String,
+    ) -> String {
+        let content = if self.minify_html {
+            minify_html(&content)
+        } else {
+            content
+        };
+        let content = if self.data_fragment_attribute && !fragment.is_empty() {
+            add_data_fragment(fragment, content)
+        } else {
+            content
+        };
+        let content = match context {
+            Some(context) if !fragment.is_empty() && !content.is_empty() => {
+                self.wrap_context(context, content)
+            }
+            _ => content,
+        };
+
+        match &self.extends {
+            Some((layout, block)) if !fragment.is_empty() && !content.is_empty() => {
+                let (open, close) = self.syntax.tag_markers();
+                let mut res = format!("{open} extends {layout:?} {close}\n");
+                res.push_str(&format!("{open} block {block} {close}\n"));
+                res.push_str(&content);
+                if !content.ends_with('\n') {
+                    res.push('\n');
+                }
+                res.push_str(&self.endblock_tag(block));
+                res.push('\n');
+                res
+            }
+            _ => content,
+        }
+    }
+
+    /// Render the fragments as macros, fragments whose macro names collide are
+    /// reported at the later of their first start tags
+    pub(crate) fn render_macros<'t>(
+        &self,
+        src: &str,
+        templates: impl Iterator<Item = (&'t str, &'t str)>,
+    ) -> Result<String, ErrorWithLine> {
+        let (open, close) = self.syntax.tag_markers();
+
+        let mut fragments: Vec<(&str, &str)> = templates.filter(|(f, _)| !f.is_empty()).collect();
+        fragments.sort();
+
+        let mut names: HashMap<String, &str> = HashMap::new();
+        let mut res = String::new();
+        for (fragment, content) in fragments {
+            let name = fragment.replace(['-', '.'], "_");
+            if let Some(other) = names.insert(name.clone(), fragment) {
+                let line = std::cmp::max(
+                    self.start_line(src, fragment_of_key(other)),
+                    self.start_line(src, fragment_of_key(fragment)),
+                );
+                let mut fragments = vec![other.to_owned(), fragment.to_owned()];
+                fragments.sort();
+                return Err(Error::MacroNameCollision(fragments).at(line));
+            }
+
+            res.push_str(&format!("{open} macro {name}() {close}\n"));
+            res.push_str(content);
+            if !content.ends_with('\n') {
+                res.push('\n');
+            }
+            res.push_str(&format!("{open} endmacro {close}\n"));
+        }
+        Ok(res)
+    }
+
+    /// The line of the first start tag of the fragment, the first line for
+    /// the base template
+    pub(crate) fn start_line(&self, src: &str, fragment: &str) -> usize {
+        let mut verbatim = Verbatim::default();
+        for (line_idx, line) in iterate_with_endings(src).enumerate() {
+            if verbatim.skip(line, self.syntax.tag_markers()) {
+                continue;
+            }
+            let found = match parse_fragment_tag(line, self.syntax.tag_markers()) {
+                Ok(Some(Tag::Start(tag))) => tag.fragments.contains(fragment),
+                Ok(Some(Tag::StartBlock(tag))) => tag.fragment == fragment,
+                _ => false,
+            };
+            if found {
+                return line_idx;
+            }
+        }
+        0
+    }
+}
@@ -0,0 +1,226 @@
+use std::collections::HashMap;
+
+use crate::{
+    iterate_with_endings, parse_fragment_tag,
+    verbatim::{tags, Verbatim},
+    Config, Tag,
+};
+
+/// The attribute of start tags with the default context of a fragment
+const CONTEXT_ATTRIBUTE: &str = "with";
+
+/// What a fragment needs to be rendered standalone
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Context {
+    /// The `with` attribute of the fragment
+    pub(crate) defaults: Option<String>,
+    /// The start and end tags of the enclosing control tags, outermost first
+    pub(crate) enclosing: Vec<(String, String)>,
+}
+
+/// An open control tag while scanning the template
+struct OpenTag {
+    keyword: String,
+    start_tag: String,
+    /// Whether an `else` or `elif` branch of the tag was entered
+    in_else: bool,
+}
+
+impl Config {
+    /// If `true`, repeat the enclosing `{% for %}` and `{% if %}` tags around
+    /// the content of a fragment
+    ///
+    /// Fragments inside loops or conditionals then render standalone with the
+    /// same variables as in the full template, without providing defaults
+    /// with the `with` attribute. The tags are copied from the first
+    /// occurrence of the fragment and are closed with the corresponding end
+    /// tags. Conditionals are skipped if the fragment is inside their `else`
+    /// or `elif` branch, as are loops for their `else` branch. The base
+    /// template is not changed.
+    ///
+    /// ```rust
+    /// # use template_fragments::Config;
+    /// let source = concat!(
+    ///     "<ul>\n",
+    ///     "{% if items %}\n",
+    ///     "  {% for item in items %}\n",
+    ///     "  {% fragment item %}\n",
+    ///     "  <li>{{ item }}</li>\n",
+    ///     "  {% endfragment %}\n",
+    ///     "  {% endfor %}\n",
+    ///     "{% endif %}\n",
+    ///     "</ul>\n",
+    /// );
+    ///
+    /// let config = Config::default().enclosing_control_tags(true);
+    /// assert_eq!(
+    ///     config.filter_template(source, "item").unwrap(),
+    ///     concat!(
+    ///         "{% if items %}\n",
+    ///         "{% for item in items %}\n",
+    ///         "  <li>{{ item }}</li>\n",
+    ///         "{% endfor %}\n",
+    ///         "{% endif %}\n",
+    ///     ),
+    /// );
+    /// ```
+    pub fn enclosing_control_tags(mut self, value: bool) -> Self {
+        self.enclosing_control_tags = value;
+        self
+    }
+
+    /// If `true`, repeat the enclosing `{% with %}` tags around the content of
+    /// a fragment
+    ///
+    /// Names bound locally by `with` blocks then keep working if the fragment
+    /// is rendered standalone. As for [Config::enclosing_control_tags], the
+    /// tags of the first occurrence of the fragment are used. Both options can
+    /// be combined, the tags are then repeated in the order of the template.
+    ///
+    /// ```rust
+    /// # use template_fragments::Config;
+    /// let source = concat!(
+    ///     "{% with title = page.title | upper %}\n",
+    ///     "<h1>\n",
+    ///     "  {% fragment title %}\n",
+    ///     "  {{ title }}\n",
+    ///     "  {% endfragment %}\n",
+    ///     "</h1>\n",
+    ///     "{% endwith %}\n",
+    /// );
+    ///
+    /// let config = Config::default().enclosing_with_tags(true);
+    /// assert_eq!(
+    ///     config.filter_template(source, "title").unwrap(),
+    ///     "{% with title = page.title | upper %}\n  {{ title }}\n{% endwith %}\n",
+    /// );
+    /// ```
+    pub fn enclosing_with_tags(mut self, value: bool) -> Self {
+        self.enclosing_with_tags = value;
+        self
+    }
+
+    /// Collect the context of each fragment, see [Context]
+    ///
+    /// For fragments with multiple start tags, the first `with` attribute is
+    /// used and the enclosing tags of the first occurrence. Invalid tags are
+    /// skipped, they are reported when processing the template.
+    pub(crate) fn fragment_contexts(&self, src: &str) -> HashMap<String, Context> {
+        let mut res: HashMap<String, Context> = HashMap::new();
+        let keywords = self.enclosing_keywords();
+        if !src.contains(CONTEXT_ATTRIBUTE) && keywords.is_empty() {
+            return res;
+        }
+
+        let tag_markers = self.syntax.tag_markers();
+        let mut open: Vec<OpenTag> = Vec::new();
+        let mut verbatim = Verbatim::default();
+        for line in iterate_with_endings(src) {
+            if verbatim.skip(line, tag_markers) {
+                continue;
+            }
+            let (fragments, attributes): (Vec<&str>, _) =
+                match parse_fragment_tag(line, tag_markers) {
+                    Ok(Some(Tag::Start(tag))) => {
+                        (tag.fragments.into_iter().collect(), tag.attributes)
+                    }
+                    Ok(Some(Tag::StartBlock(tag))) => (vec![tag.fragment], tag.attributes),
+                    Ok(None) if !keywords.is_empty() && line.contains(tag_markers.0) => {
+                        self.update_open_tags(&mut open, line, &keywords);
+                        continue;
+                    }
+                    _ => continue,
+                };
+
+            let defaults = attributes
+                .iter()
+                .find(|(key, _)| *key == CONTEXT_ATTRIBUTE)
+                .map(|&(_, context)| context);
+            for fragment in fragments {
+                let is_first = !res.contains_key(fragment);
+                let context = res.entry(fragment.to_owned()).or_default();
+                if context.defaults.is_none() {
+                    context.defaults = defaults.map(str::to_owned);
+                }
+                if is_first {
+                    context.enclosing = self.enclosing_tags(&open);
+                }
+            }
+        }
+        res.retain(|_, context| *context != Context::default());
+        res
+    }
+
+    /// The keywords of the enclosing tags to repeat
+    pub(crate) fn enclosing_keywords(&self) -> Vec<&'static str> {
+        let mut res = Vec::new();
+        if self.enclosing_control_tags {
+            res.extend(["for", "if"]);
+        }
+        if self.enclosing_with_tags {
+            res.push("with");
+        }
+        res
+    }
+
+    fn update_open_tags(&self, open: &mut Vec<OpenTag>, line: &str, keywords: &[&str]) {
+        let (start, end) = self.syntax.tag_markers();
+        for words in tags(line, self.syntax.tag_markers()) {
+            let Some(&keyword) = words.first() else {
+                continue;
+            };
+            if keywords.contains(&keyword) {
+                open.push(OpenTag {
+                    keyword: keyword.to_owned(),
+                    start_tag: format!("{start} {} {end}", words.join(" ")),
+                    in_else: false,
+                });
+            } else if matches!(keyword, "else" | "elif") {
+                if let Some(tag) = open.last_mut() {
+                    tag.in_else = true;
+                }
+            } else if let Some(keyword) = keyword.strip_prefix("end") {
+                if open.last().is_some_and(|tag| tag.keyword == keyword) {
+                    open.pop();
+                }
+            }
+        }
+    }
+
+    fn enclosing_tags(&self, open: &[OpenTag]) -> Vec<(String, String)> {
+        let (start, end) = self.syntax.tag_markers();
+        open.iter()
+            .filter(|tag| !tag.in_else)
+            .map(|tag| {
+                let end_tag = format!("{start} end{} {end}", tag.keyword);
+                (tag.start_tag.clone(), end_tag)
+            })
+            .collect()
+    }
+
+    /// Wrap the content of a fragment with its context
+    pub(crate) fn wrap_context(&self, context: &Context, content: String) -> String {
+        let (open, close) = self.syntax.tag_markers();
+
+        let mut res = String::new();
+        if let Some(defaults) = &context.defaults {
+            res.push_str(&format!("{open} with {defaults} {close}\n"));
+        }
+        for (start_tag, _) in &context.enclosing {
+            res.push_str(start_tag);
+            res.push('\n');
+        }
+        res.push_str(&content);
+        if !content.ends_with('\n') {
+            res.push('\n');
+        }
+        for (_, end_tag) in context.enclosing.iter().rev() {
+            res.push_str(end_tag);
+            res.push('\n');
+        }
+        if context.defaults.is_some() {
+            res.push_str(&format!("{open} endwith {close}\n"));
+        }
+        res
+    }
+}
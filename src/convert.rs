@@ -0,0 +1,154 @@
+use std::borrow::Cow;
+
+use crate::{
+    details::parse_doc_comment, get_ending, iterate_with_endings, parse_base, process_template,
+    Config, ErrorWithLine, FragmentType, Syntax,
+};
+
+/// Rewrite the fragment tags of a template from another syntax into the
+/// default syntax
+///
+/// See [Config::convert_template].
+///
+/// ```rust
+/// # use template_fragments::{convert_template, Syntax};
+/// let source = concat!(
+///     "<ul>\n",
+///     "  <!-- fragment: item -->\n",
+///     "  <li>{{ item }}</li>\n",
+///     "  <!-- endfragment -->\n",
+///     "</ul>\n",
+/// );
+///
+/// assert_eq!(
+///     convert_template(source, &Syntax::html_comments()).unwrap(),
+///     concat!(
+///         "<ul>\n",
+///         "  {% fragment item %}\n",
+///         "  <li>{{ item }}</li>\n",
+///         "  {% endfragment %}\n",
+///         "</ul>\n",
+///     ),
+/// );
+/// ```
+pub fn convert_template(src: &str, from: &Syntax) -> Result<String, ErrorWithLine> {
+    Config::default().convert_template(src, from)
+}
+
+impl Config {
+    /// Rewrite the fragment tags of a template from another syntax into the
+    /// syntax of this config
+    ///
+    /// Fragment tags and doc comments written with the markers of `from` are
+    /// rewritten with the markers of this config. As a common convention of
+    /// annotated HTML, a colon after the tag name is accepted, e.g., `<!--
+    /// fragment: item -->`. All other lines are kept as they are. The
+    /// template is validated with the `from` syntax before rewriting it.
+    pub fn convert_template(&self, src: &str, from: &Syntax) -> Result<String, ErrorWithLine> {
+        let from_markers = from.tag_markers();
+
+        let mut normalized = String::with_capacity(src.len());
+        for line in iterate_with_endings(src) {
+            normalized.push_str(&strip_tag_colon(line, from_markers));
+        }
+        process_template(&self.clone().syntax(from.clone()), &normalized, |_, _| {})?;
+
+        let (open, close) = self.syntax.tag_markers();
+        let (comment_open, comment_close) = self.syntax.comment_markers();
+
+        let mut res = String::with_capacity(normalized.len());
+        for line in iterate_with_endings(&normalized) {
+            if let Some(parts) = parse_base(line, from_markers) {
+                let keyword = match parts.fragment_type {
+                    FragmentType::Start => "fragment",
+                    FragmentType::End => "endfragment",
+                    FragmentType::BlockStart => "fragment-block",
+                    FragmentType::BlockEnd => "endfragment-block",
+                };
+                let data = parts.data.trim();
+                let data = if data.is_empty() {
+                    String::new()
+                } else {
+                    format!("{data} ")
+                };
+                res.push_str(&format!(
+                    "{}{open} {keyword} {data}{close}{}",
+                    parts.head, parts.tail
+                ));
+            } else if let Some(doc) = parse_doc_comment(line, from.comment_markers()) {
+                let indent = &line[..line.len() - line.trim_start().len()];
+                res.push_str(&format!(
+                    "{indent}{comment_open} doc: {doc} {comment_close}{}",
+                    get_ending(line)
+                ));
+            } else {
+                res.push_str(line);
+            }
+        }
+        Ok(res)
+    }
+}
+
+impl Config {
+    /// If `true`, a colon directly after the keyword of fragment tags is
+    /// accepted, e.g., `<!-- fragment: intro -->`
+    ///
+    /// This is a common convention of markers in HTML or Markdown comments,
+    /// see [Config::markdown]. Without this option, such lines are not
+    /// recognized as fragment tags, i.e., the end tag below is unbalanced.
+    ///
+    /// ```rust
+    /// # use template_fragments::{Config, Syntax};
+    /// let source = "<!-- fragment: intro -->\nHello\n<!-- endfragment -->\n";
+    ///
+    /// let config = Config::default().syntax(Syntax::html_comments());
+    /// assert!(config.split_templates(source).is_err());
+    ///
+    /// let templates = config.tag_colons(true).split_templates(source).unwrap();
+    /// assert_eq!(templates["intro"], "Hello\n");
+    /// ```
+    pub fn tag_colons(mut self, value: bool) -> Self {
+        self.tag_colons = value;
+        self
+    }
+
+    /// Remove the colons after the keywords of all fragment tags
+    ///
+    /// The colons are replaced by spaces, i.e., the offsets of the lines do
+    /// not change.
+    pub(crate) fn strip_tag_colons<'s>(&self, src: &'s str) -> Cow<'s, str> {
+        if !self.tag_colons || !src.contains(':') {
+            return Cow::Borrowed(src);
+        }
+        let tag_markers = self.syntax.tag_markers();
+        Cow::Owned(
+            iterate_with_endings(src)
+                .map(|line| strip_tag_colon(line, tag_markers))
+                .collect(),
+        )
+    }
+}
+
+/// Remove a colon directly after the tag name, e.g., `<!-- fragment: item -->`
+fn strip_tag_colon(line: &str, tag_markers: (&str, &str)) -> String {
+    let rest = line.trim_start();
+    let Some(rest) = rest.strip_prefix(tag_markers.0) else {
+        return line.to_owned();
+    };
+    let rest = rest.trim_start();
+
+    for keyword in [
+        "fragment-block",
+        "fragment",
+        "endfragment-block",
+        "endfragment",
+    ] {
+        if let Some(after) = rest.strip_prefix(keyword) {
+            if after.starts_with(':') {
+                let colon = line.len() - after.len();
+                return format!("{} {}", &line[..colon], &line[colon + 1..]);
+            }
+        }
+    }
+    line.to_owned()
+}
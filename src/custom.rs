@@ -0,0 +1,167 @@
+use std::{borrow::Cow, sync::Arc};
+
+use crate::{get_ending, iterate_with_endings, verbatim::Verbatim, Config, Error, ErrorWithLine};
+
+/// A custom tag passed to the handler registered with [Config::custom_tag]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CustomTag<'a> {
+    /// The keyword of the tag, e.g., `cachefragment`
+    pub keyword: &'a str,
+    /// The trimmed content of the tag after the keyword
+    pub data: &'a str,
+}
+
+type HandlerFn = dyn Fn(&CustomTag<'_>) -> Result<String, String> + Send + Sync;
+
+/// A registered handler of custom tags
+#[derive(Clone)]
+pub(crate) struct TagHandler {
+    keyword: String,
+    handler: Arc<HandlerFn>,
+}
+
+impl std::fmt::Debug for TagHandler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TagHandler")
+            .field("keyword", &self.keyword)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PartialEq for TagHandler {
+    fn eq(&self, other: &Self) -> bool {
+        self.keyword == other.keyword && Arc::ptr_eq(&self.handler, &other.handler)
+    }
+}
+
+impl Eq for TagHandler {}
+
+impl Config {
+    /// Register a handler for tags with the given keyword, e.g., `{% keyword
+    /// ... %}`
+    ///
+    /// Custom tags are preprocessing directives: before processing the
+    /// template, the handler replaces the tag with a single line, such that
+    /// errors still refer to the lines of the original template. The
+    /// replacement may be a fragment tag, that then starts or ends fragments
+    /// as usual, or any other content. The indentation and line ending of the
+    /// tag are kept. As fragment tags, custom tags must be on their own line. Errors
+    /// returned by the handler are reported as [Error::CustomTag]. The keywords
+    /// of fragment tags cannot be overwritten. Registering the same keyword
+    /// again replaces the previous handler.
+    ///
+    /// ```rust
+    /// # use template_fragments::Config;
+    /// let source = concat!(
+    ///     "<ul>\n",
+    ///     "  {% cachefragment items 60 %}\n",
+    ///     "  <li>{{ item }}</li>\n",
+    ///     "  {% endcachefragment %}\n",
+    ///     "</ul>\n",
+    /// );
+    ///
+    /// let config = Config::default()
+    ///     .custom_tag("cachefragment", |tag| match tag.data.split_once(' ') {
+    ///         Some((name, seconds)) => Ok(format!("{{% fragment {name} cache={seconds} %}}")),
+    ///         None => Err(String::from("expected a name and a timeout")),
+    ///     })
+    ///     .custom_tag("endcachefragment", |_| Ok(String::from("{% endfragment %}")));
+    ///
+    /// let fragments = config.split_templates_detailed(source).unwrap();
+    /// assert_eq!(fragments["items"].content, "  <li>{{ item }}</li>\n");
+    /// assert_eq!(fragments["items"].attributes["cache"], "60");
+    /// ```
+    pub fn custom_tag(
+        mut self,
+        keyword: &str,
+        handler: impl Fn(&CustomTag<'_>) -> Result<String, String> + Send + Sync + 'static,
+    ) -> Self {
+        self.tag_handlers
+            .retain(|handler| handler.keyword != keyword);
+        self.tag_handlers.push(TagHandler {
+            keyword: keyword.to_owned(),
+            handler: Arc::new(handler),
+        });
+        self
+    }
+
+    /// Replace all custom tags with the output of their handlers
+    pub(crate) fn expand_custom_tags<'s>(
+        &self,
+        src: &'s str,
+    ) -> Result<Cow<'s, str>, ErrorWithLine> {
+        if self.tag_handlers.is_empty() {
+            return Ok(Cow::Borrowed(src));
+        }
+
+        let mut res = String::with_capacity(src.len());
+        let mut verbatim = Verbatim::default();
+        for (line_idx, line) in iterate_with_endings(src).enumerate() {
+            if verbatim.skip(line, self.syntax.tag_markers()) {
+                res.push_str(line);
+                continue;
+            }
+            match self.expand_custom_tag(line) {
+                Ok(Some(expanded)) => res.push_str(&expanded),
+                Ok(None) => res.push_str(line),
+                Err(err) => return Err(err.at(line_idx)),
+            }
+        }
+        Ok(Cow::Owned(res))
+    }
+
+    fn expand_custom_tag(&self, line: &str) -> Result<Option<String>, Error> {
+        let (open, close) = self.syntax.tag_markers();
+
+        let Some((head, rest)) = line.split_once(open) else {
+            return Ok(None);
+        };
+        let Some(rest) = rest.strip_prefix(char::is_whitespace) else {
+            return Ok(None);
+        };
+        let Some((content, tail)) = rest.split_once(close) else {
+            return Ok(None);
+        };
+        let (keyword, data) = content
+            .trim()
+            .split_once(char::is_whitespace)
+            .unwrap_or((content.trim(), ""));
+
+        let Some(tag_handler) = self
+            .tag_handlers
+            .iter()
+            .find(|handler| handler.keyword == keyword && !is_fragment_keyword(keyword))
+        else {
+            return Ok(None);
+        };
+
+        if !head.trim().is_empty() {
+            return Err(Error::LeadingContent(head.to_owned()));
+        }
+        if !tail.trim().is_empty() {
+            return Err(Error::TrailingContent(tail.to_owned()));
+        }
+
+        let tag = CustomTag {
+            keyword,
+            data: data.trim(),
+        };
+        let replacement = (tag_handler.handler)(&tag)
+            .map_err(|message| Error::CustomTag(keyword.to_owned(), message))?;
+        if replacement.contains('\n') {
+            return Err(Error::CustomTag(
+                keyword.to_owned(),
+                String::from("the replacement must be a single line"),
+            ));
+        }
+
+        Ok(Some(format!("{head}{replacement}{}", get_ending(line))))
+    }
+}
+
+fn is_fragment_keyword(keyword: &str) -> bool {
+    matches!(
+        keyword,
+        "fragment" | "endfragment" | "fragment-block" | "endfragment-block"
+    )
+}
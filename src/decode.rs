@@ -0,0 +1,83 @@
+use std::{borrow::Cow, collections::HashMap};
+
+use crate::{Config, ErrorWithLine};
+
+/// The byte order mark, as a character
+const BOM: char = '\u{feff}';
+
+/// Remove a leading byte order mark
+pub(crate) fn strip_bom(src: &str) -> &str {
+    src.strip_prefix(BOM).unwrap_or(src)
+}
+
+/// The leading byte order mark of the template, if any
+pub(crate) fn leading_bom(src: &str) -> Option<&str> {
+    src.starts_with(BOM).then(|| &src[..BOM.len_utf8()])
+}
+
+/// Decode the bytes of a template, as saved by common editors
+///
+/// The encoding is detected from the byte order mark. Without a byte order
+/// mark, UTF-16 is assumed if one of the first two bytes is zero, otherwise
+/// UTF-8. Invalid sequences are replaced with `U+FFFD`. The byte order mark
+/// is not part of the result.
+///
+/// ```rust
+/// # use template_fragments::decode_template;
+/// assert_eq!(decode_template(b"\xef\xbb\xbf<ul>\n"), "<ul>\n");
+/// assert_eq!(decode_template(b"\xff\xfe<\0u\0l\0>\0\n\0"), "<ul>\n");
+/// assert_eq!(decode_template(b"\0<\0u\0l\0>\0\n"), "<ul>\n");
+/// assert_eq!(decode_template(b"<ul\xff>\n"), "<ul\u{fffd}>\n");
+/// ```
+pub fn decode_template(bytes: &[u8]) -> Cow<'_, str> {
+    match bytes {
+        [0xef, 0xbb, 0xbf, rest @ ..] => String::from_utf8_lossy(rest),
+        [0xff, 0xfe, rest @ ..] => Cow::Owned(decode_utf16(rest, u16::from_le_bytes)),
+        [0xfe, 0xff, rest @ ..] => Cow::Owned(decode_utf16(rest, u16::from_be_bytes)),
+        [first, 0, ..] if *first != 0 => Cow::Owned(decode_utf16(bytes, u16::from_le_bytes)),
+        [0, second, ..] if *second != 0 => Cow::Owned(decode_utf16(bytes, u16::from_be_bytes)),
+        _ => String::from_utf8_lossy(bytes),
+    }
+}
+
+fn decode_utf16(bytes: &[u8], to_unit: fn([u8; 2]) -> u16) -> String {
+    let units = bytes.chunks(2).map(|chunk| match *chunk {
+        [a, b] => to_unit([a, b]),
+        // a trailing odd byte is invalid
+        _ => 0xdc00,
+    });
+    char::decode_utf16(units)
+        .map(|ch| ch.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect()
+}
+
+/// Decode the bytes of a template and split it into all fragments
+///
+/// See [decode_template] for the supported encodings and
+/// [crate::split_templates] for the result.
+///
+/// ```rust
+/// # use template_fragments::split_templates_lossy;
+/// let source: Vec<u8> = "\u{feff}{% fragment item %}\n<li>\n{% endfragment %}\n"
+///     .encode_utf16()
+///     .flat_map(u16::to_le_bytes)
+///     .collect();
+///
+/// let templates = split_templates_lossy(&source).unwrap();
+/// assert_eq!(templates["item"], "<li>\n");
+/// ```
+pub fn split_templates_lossy(bytes: &[u8]) -> Result<HashMap<String, String>, ErrorWithLine> {
+    Config::default().split_templates_lossy(bytes)
+}
+
+impl Config {
+    /// Decode the bytes of a template and split it into all fragments
+    ///
+    /// See [crate::split_templates_lossy].
+    pub fn split_templates_lossy(
+        &self,
+        bytes: &[u8],
+    ) -> Result<HashMap<String, String>, ErrorWithLine> {
+        self.split_templates(&decode_template(bytes))
+    }
+}
@@ -0,0 +1,196 @@
+use std::{borrow::Cow, collections::BTreeMap};
+
+use crate::{iterate_with_endings, verbatim::Verbatim, Config, Error, ErrorWithLine};
+
+impl Config {
+    /// Evaluate `ifdef` directives against the given definitions
+    ///
+    /// Lines between `{% ifdef KEY %}` and `{% endifdef %}` are only kept if
+    /// `KEY` is defined, lines between `{% ifndef KEY %}` and `{% endifdef %}`
+    /// only if it is not defined. With `{% ifdef KEY=VALUE %}`, the key must be
+    /// defined with the given value. Directives can be nested and must be on
+    /// their own line. The directives and all excluded lines, including any
+    /// fragment tags, are removed before processing the template. Errors still
+    /// refer to the lines of the original template. Without definitions,
+    /// directives are kept as any other content.
+    ///
+    /// ```rust
+    /// # use template_fragments::Config;
+    /// let source = concat!(
+    ///     "<body>\n",
+    ///     "{% ifdef DEBUG %}\n",
+    ///     "  <pre>{{ context }}</pre>\n",
+    ///     "{% endifdef %}\n",
+    ///     "{% ifdef THEME=dark %}\n",
+    ///     "  <link rel=\"stylesheet\" href=\"dark.css\">\n",
+    ///     "{% endifdef %}\n",
+    ///     "</body>\n",
+    /// );
+    ///
+    /// let config = Config::default().defines([("THEME", "dark")]);
+    /// assert_eq!(
+    ///     config.filter_template(source, "").unwrap(),
+    ///     "<body>\n  <link rel=\"stylesheet\" href=\"dark.css\">\n</body>\n",
+    /// );
+    /// ```
+    pub fn defines<K: Into<String>, V: Into<String>>(
+        mut self,
+        defines: impl IntoIterator<Item = (K, V)>,
+    ) -> Self {
+        let defines = defines
+            .into_iter()
+            .map(|(key, value)| (key.into(), value.into()));
+        self.defines
+            .get_or_insert_with(BTreeMap::new)
+            .extend(defines);
+        self
+    }
+
+    /// Remove all directives and the lines they exclude
+    pub(crate) fn expand_directives<'s>(
+        &self,
+        src: &'s str,
+    ) -> Result<(Cow<'s, str>, LineMap), ErrorWithLine> {
+        if self.defines.is_none() || !(src.contains("ifdef") || src.contains("ifndef")) {
+            return Ok((Cow::Borrowed(src), LineMap::default()));
+        }
+
+        let mut res = String::with_capacity(src.len());
+        let mut lines = Vec::new();
+        // the line, the content, and whether to keep the lines of each open
+        // directive
+        let mut open: Vec<(usize, &str, bool)> = Vec::new();
+        let mut verbatim = Verbatim::default();
+
+        for (line_idx, line) in iterate_with_endings(src).enumerate() {
+            let directive = if verbatim.skip(line, self.syntax.tag_markers()) {
+                None
+            } else {
+                self.parse_directive(line).map_err(|err| err.at(line_idx))?
+            };
+            match directive {
+                Some(Directive::Start(content, keep)) => open.push((line_idx, content, keep)),
+                Some(Directive::End) if open.pop().is_none() => {
+                    return Err(Error::UnbalancedDirective.at(line_idx));
+                }
+                Some(Directive::End) => {}
+                None if open.iter().all(|&(_, _, keep)| keep) => {
+                    res.push_str(line);
+                    lines.push(line_idx);
+                }
+                None => {}
+            }
+        }
+        if let Some(&(line_idx, content, _)) = open.last() {
+            return Err(Error::UnclosedDirective(content.to_owned()).at(line_idx));
+        }
+
+        Ok((Cow::Owned(res), LineMap(Some(lines))))
+    }
+
+    fn parse_directive<'l>(&self, line: &'l str) -> Result<Option<Directive<'l>>, Error> {
+        let (open, close) = self.syntax.tag_markers();
+
+        let Some((head, rest)) = line.split_once(open) else {
+            return Ok(None);
+        };
+        let Some(rest) = rest.strip_prefix(char::is_whitespace) else {
+            return Ok(None);
+        };
+        let Some((content, tail)) = rest.split_once(close) else {
+            return Ok(None);
+        };
+        let content = content.trim();
+        let (keyword, data) = content
+            .split_once(char::is_whitespace)
+            .unwrap_or((content, ""));
+        if !matches!(keyword, "ifdef" | "ifndef" | "endifdef") {
+            return Ok(None);
+        }
+
+        if !head.trim().is_empty() {
+            return Err(Error::LeadingContent(head.to_owned()));
+        }
+        if !tail.trim().is_empty() {
+            return Err(Error::TrailingContent(tail.to_owned()));
+        }
+
+        let data = data.trim();
+        if keyword == "endifdef" {
+            return if data.is_empty() {
+                Ok(Some(Directive::End))
+            } else {
+                Err(Error::InvalidDirective(content.to_owned()))
+            };
+        }
+
+        let (key, value) = match data.split_once('=') {
+            Some((key, value)) => (key.trim(), Some(value.trim())),
+            None => (data, None),
+        };
+        if key.is_empty() || key.contains(char::is_whitespace) {
+            return Err(Error::InvalidDirective(content.to_owned()));
+        }
+
+        let defined = match (self.defines.as_ref().and_then(|d| d.get(key)), value) {
+            (Some(actual), Some(expected)) => actual == expected,
+            (Some(_), None) => true,
+            (None, _) => false,
+        };
+        Ok(Some(Directive::Start(
+            content,
+            defined == (keyword == "ifdef"),
+        )))
+    }
+}
+
+enum Directive<'l> {
+    /// An `ifdef` or `ifndef` directive with its content and whether the
+    /// lines up to its end are kept
+    Start(&'l str, bool),
+    End,
+}
+
+/// The lines of the original template that were kept by preprocessing
+///
+/// Used to report errors at the lines of the original template.
+#[derive(Debug, Default)]
+pub(crate) struct LineMap(Option<Vec<usize>>);
+
+impl LineMap {
+    /// The map of a source whose lines correspond to the given original lines
+    pub(crate) fn new(lines: Vec<usize>) -> Self {
+        Self(Some(lines))
+    }
+
+    /// Report the error at the corresponding line of the original template
+    pub(crate) fn restore(&self, err: ErrorWithLine) -> ErrorWithLine {
+        // not reported at a line of the template
+        if matches!(err.1, Error::UnknownFragment(..)) {
+            return err;
+        }
+        ErrorWithLine(self.original(err.0), err.1)
+    }
+
+    /// The line of the original template for a line of the preprocessed one
+    pub(crate) fn original(&self, line: usize) -> usize {
+        match &self.0 {
+            Some(lines) => lines
+                .get(line)
+                .or(lines.last())
+                .copied()
+                .unwrap_or_default(),
+            None => line,
+        }
+    }
+
+    /// Combine the map with the one of a later preprocessing step
+    pub(crate) fn then(self, later: LineMap) -> LineMap {
+        match later.0 {
+            Some(lines) => LineMap(Some(
+                lines.into_iter().map(|line| self.original(line)).collect(),
+            )),
+            None => self,
+        }
+    }
+}
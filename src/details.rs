@@ -0,0 +1,218 @@
+use std::{collections::HashMap, ops::Range};
+
+use crate::{iterate_with_endings, Config, ErrorWithLine, Processor, Tag};
+
+/// A fragment with its content and metadata
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Fragment {
+    /// The content of the fragment, as returned by [crate::split_templates]
+    ///
+    /// Repeated fragments are concatenated without [Config::fragment_separator].
+    pub content: String,
+    /// The `key=value` attributes of the start tags of this fragment
+    ///
+    /// If a fragment occurs multiple times, the attributes of all start tags
+    /// are merged and later values override earlier ones.
+    pub attributes: HashMap<String, String>,
+    /// The description given by `{# doc: ... #}` comments directly before a
+    /// start tag of this fragment
+    ///
+    /// Consecutive doc comments are joined by newlines. If a fragment occurs
+    /// multiple times, the last description is used.
+    pub description: Option<String>,
+    /// The individual occurrences of the fragment in the order of the template
+    ///
+    /// The content of the fragment is the concatenation of all parts. The base
+    /// template, i.e., the fragment `""`, consists of a single part.
+    pub parts: Vec<FragmentPart>,
+}
+
+/// A single occurrence of a fragment in the template
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FragmentPart {
+    /// The content of this occurrence
+    pub content: String,
+    /// The lines of the original template from the start tag to the end tag,
+    /// zero-based as the lines of [ErrorWithLine]
+    pub lines: Range<usize>,
+}
+
+/// Split the template into all fragments including their metadata
+///
+/// In contrast to [crate::split_templates], fragments without any content are
+/// included as well.
+///
+/// ```rust
+/// # use template_fragments::split_templates_detailed;
+/// let source = concat!(
+///     "<body>\n",
+///     "  {% fragment item cache=60 role=admin %}\n",
+///     "    <div>{{ item }}</div>\n",
+///     "  {% endfragment %}\n",
+///     "<body>\n",
+/// );
+/// let fragments = split_templates_detailed(source).unwrap();
+///
+/// assert_eq!(fragments["item"].content, "    <div>{{ item }}</div>\n");
+/// assert_eq!(fragments["item"].attributes["cache"], "60");
+/// assert_eq!(fragments["item"].attributes["role"], "admin");
+/// assert!(fragments[""].attributes.is_empty());
+/// ```
+///
+/// Fragments can be documented with `{# doc: ... #}` comments directly before
+/// their start tag:
+///
+/// ```rust
+/// # use template_fragments::split_templates_detailed;
+/// let source = concat!(
+///     "{# doc: A single item #}\n",
+///     "{% fragment item %}\n",
+///     "  <div>{{ item }}</div>\n",
+///     "{% endfragment %}\n",
+/// );
+/// let fragments = split_templates_detailed(source).unwrap();
+///
+/// assert_eq!(fragments["item"].description.as_deref(), Some("A single item"));
+/// ```
+///
+/// Fragments that occur multiple times are concatenated. Their individual
+/// occurrences are available as parts:
+///
+/// ```rust
+/// # use template_fragments::split_templates_detailed;
+/// let source = concat!(
+///     "{% fragment item %}\n",
+///     "  <div>{{ item }}</div>\n",
+///     "{% endfragment %}\n",
+///     "<hr>\n",
+///     "{% fragment item %}\n",
+///     "  <div>{{ other }}</div>\n",
+///     "{% endfragment %}\n",
+/// );
+/// let fragments = split_templates_detailed(source).unwrap();
+///
+/// let parts = &fragments["item"].parts;
+/// assert_eq!(parts.len(), 2);
+/// assert_eq!(parts[1].content, "  <div>{{ other }}</div>\n");
+/// assert_eq!(parts[1].lines, 4..7);
+/// assert_eq!(fragments[""].parts[0].lines, 0..7);
+/// ```
+pub fn split_templates_detailed(src: &str) -> Result<HashMap<String, Fragment>, ErrorWithLine> {
+    Config::default().split_templates_detailed(src)
+}
+
+impl Config {
+    /// Split the template into all fragments including their metadata
+    ///
+    /// See [crate::split_templates_detailed].
+    pub fn split_templates_detailed(
+        &self,
+        src: &str,
+    ) -> Result<HashMap<String, Fragment>, ErrorWithLine> {
+        let (src, lines) = self.preprocess(src)?;
+        let mut processor = Processor::new(self);
+        let mut res: HashMap<String, Fragment> = Default::default();
+        let mut doc: Option<String> = None;
+
+        for (line_idx, line) in iterate_with_endings(&src).enumerate() {
+            let line_doc = parse_doc_comment(line, self.syntax.comment_markers());
+            let tag = processor
+                .parse(line)
+                .map_err(|err| lines.restore(err.at(line_idx)))?;
+
+            let original = lines.original(line_idx);
+            match &tag {
+                Some(Tag::Start(tag)) => {
+                    for &fragment in &tag.fragments {
+                        set_attributes(&mut res, fragment, &tag.attributes);
+                        set_description(&mut res, fragment, &doc);
+                        start_part(&mut res, fragment, original);
+                    }
+                }
+                Some(Tag::StartBlock(tag)) => {
+                    set_attributes(&mut res, tag.fragment, &tag.attributes);
+                    set_description(&mut res, tag.fragment, &doc);
+                    start_part(&mut res, tag.fragment, original);
+                }
+                Some(Tag::End(_) | Tag::EndBlock(_)) => {
+                    for &fragment in processor.stack.innermost().unwrap_or_default() {
+                        if let Some(part) = res.get_mut(fragment).and_then(|f| f.parts.last_mut()) {
+                            part.lines.end = original + 1;
+                        }
+                    }
+                }
+                None => {}
+            }
+
+            doc = match (doc, line_doc) {
+                (Some(doc), Some(line_doc)) => Some(format!("{doc}\n{line_doc}")),
+                (_, line_doc) => line_doc.map(str::to_owned),
+            };
+
+            processor
+                .apply(line_idx, line, tag, |active, line| {
+                    for &fragment in active {
+                        let fragment = res.entry(fragment.to_owned()).or_default();
+                        fragment.content.push_str(line);
+                        if let Some(part) = fragment.parts.last_mut() {
+                            part.content.push_str(line);
+                        }
+                    }
+                })
+                .map_err(|err| lines.restore(err))?;
+        }
+        processor.done().map_err(|err| lines.restore(err))?;
+
+        if let Some(base) = res.get_mut("") {
+            let num_lines = match iterate_with_endings(&src).count() {
+                0 => 0,
+                num_lines => lines.original(num_lines - 1) + 1,
+            };
+            base.parts = vec![FragmentPart {
+                content: base.content.clone(),
+                lines: 0..num_lines,
+            }];
+        }
+        Ok(res)
+    }
+}
+
+fn start_part(res: &mut HashMap<String, Fragment>, fragment: &str, line: usize) {
+    res.entry(fragment.to_owned())
+        .or_default()
+        .parts
+        .push(FragmentPart {
+            content: String::new(),
+            lines: line..line + 1,
+        });
+}
+
+fn set_description(res: &mut HashMap<String, Fragment>, fragment: &str, doc: &Option<String>) {
+    if let Some(doc) = doc {
+        res.entry(fragment.to_owned()).or_default().description = Some(doc.clone());
+    }
+}
+
+/// Parse a line of the form `{# doc: TEXT #}` and return the text
+pub(crate) fn parse_doc_comment<'l>(
+    line: &'l str,
+    comment_markers: (&str, &str),
+) -> Option<&'l str> {
+    let line = line.trim();
+    let line = line
+        .strip_prefix(comment_markers.0)?
+        .strip_suffix(comment_markers.1)?;
+    let line = line.trim_start().strip_prefix("doc:")?;
+    Some(line.trim())
+}
+
+fn set_attributes(
+    res: &mut HashMap<String, Fragment>,
+    fragment: &str,
+    attributes: &[(&str, &str)],
+) {
+    let target = &mut res.entry(fragment.to_owned()).or_default().attributes;
+    for &(key, value) in attributes {
+        target.insert(key.to_owned(), value.to_owned());
+    }
+}
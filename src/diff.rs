@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+
+use crate::{cache::hash_source, split_templates, ErrorWithLine};
+
+/// The fragments that differ between two versions of a template
+///
+/// All names are sorted.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TemplateDiff {
+    /// Fragments only contained in the new version
+    pub added: Vec<String>,
+    /// Fragments only contained in the old version
+    pub removed: Vec<String>,
+    /// Fragments contained in both versions with different content
+    pub changed: Vec<String>,
+}
+
+impl TemplateDiff {
+    /// Compare two sets of already split fragments
+    pub fn between(old: &HashMap<String, String>, new: &HashMap<String, String>) -> Self {
+        let old = hash_fragments(old);
+        let new = hash_fragments(new);
+
+        let mut res = Self::default();
+        for (fragment, hash) in &new {
+            match old.get(fragment) {
+                None => res.added.push(fragment.to_string()),
+                Some(old_hash) if old_hash != hash => res.changed.push(fragment.to_string()),
+                Some(_) => {}
+            }
+        }
+        for fragment in old.keys() {
+            if !new.contains_key(fragment) {
+                res.removed.push(fragment.to_string());
+            }
+        }
+
+        res.added.sort();
+        res.removed.sort();
+        res.changed.sort();
+        res
+    }
+
+    /// Whether both versions contain the same fragments
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// All fragments that were added, removed, or changed, e.g., to
+    /// invalidate their cached results
+    pub fn affected(&self) -> Vec<&str> {
+        let mut res: Vec<&str> = self
+            .added
+            .iter()
+            .chain(&self.removed)
+            .chain(&self.changed)
+            .map(String::as_str)
+            .collect();
+        res.sort();
+        res
+    }
+}
+
+/// Split two versions of a template and compare their fragments
+///
+/// Fragments are compared by the hash of their content. This way, deployment
+/// tooling can only invalidate the cached results of affected fragments.
+///
+/// ```rust
+/// # use template_fragments::diff_templates;
+/// let old = concat!(
+///     "<body>\n",
+///     "  {% fragment item %}\n",
+///     "    <div>{{ item }}</div>\n",
+///     "  {% endfragment %}\n",
+///     "  {% fragment footer %}\n",
+///     "    <footer></footer>\n",
+///     "  {% endfragment %}\n",
+///     "<body>\n",
+/// );
+/// let new = concat!(
+///     "<body>\n",
+///     "  {% fragment item %}\n",
+///     "    <div class=\"item\">{{ item }}</div>\n",
+///     "  {% endfragment %}\n",
+///     "  {% fragment header %}\n",
+///     "    <header></header>\n",
+///     "  {% endfragment %}\n",
+///     "<body>\n",
+/// );
+///
+/// let diff = diff_templates(old, new).unwrap();
+/// assert_eq!(diff.added, vec!["header"]);
+/// assert_eq!(diff.removed, vec!["footer"]);
+/// assert_eq!(diff.changed, vec!["", "item"]);
+/// ```
+pub fn diff_templates(old: &str, new: &str) -> Result<TemplateDiff, ErrorWithLine> {
+    let old = split_templates(old)?;
+    let new = split_templates(new)?;
+    Ok(TemplateDiff::between(&old, &new))
+}
+
+fn hash_fragments(templates: &HashMap<String, String>) -> HashMap<&str, u64> {
+    templates
+        .iter()
+        .map(|(fragment, content)| (fragment.as_str(), hash_source(content)))
+        .collect()
+}
@@ -0,0 +1,101 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    trace::{finish_span, start_span},
+    Config, ErrorWithPath,
+};
+
+/// Split all templates in a directory tree
+///
+/// The result is keyed by `join_path(path, fragment)`, where `path` is the
+/// path of the template relative to `root` with `/` as separator. The markers
+/// of each template are selected with [Config::for_path].
+///
+/// ```rust,no_run
+/// # use template_fragments::split_directory;
+/// let templates = split_directory("templates").unwrap();
+/// let item = &templates["index.html#item"];
+/// ```
+pub fn split_directory(root: impl AsRef<Path>) -> Result<HashMap<String, String>, DirectoryError> {
+    let root = root.as_ref();
+    start_span!(timer, "split_directory", root = %root.display());
+    let mut res = HashMap::new();
+
+    for path in list_files(root)? {
+        let src =
+            std::fs::read_to_string(&path).map_err(|err| DirectoryError::Io(path.clone(), err))?;
+        let name = relative_name(root, &path);
+
+        let templates = Config::for_path(&path)
+            .split_templates_prefixed(&name, &src)
+            .map_err(|err| DirectoryError::Template(err.in_path(&name)))?;
+        res.extend(templates);
+    }
+
+    finish_span!(timer, fragments = res.len());
+    Ok(res)
+}
+
+/// List all files below `root` in a stable order
+pub(crate) fn list_files(root: &Path) -> Result<Vec<PathBuf>, DirectoryError> {
+    let mut res = Vec::new();
+    let mut dirs = vec![root.to_path_buf()];
+
+    while let Some(dir) = dirs.pop() {
+        let entries =
+            std::fs::read_dir(&dir).map_err(|err| DirectoryError::Io(dir.clone(), err))?;
+        for entry in entries {
+            let path = entry
+                .map_err(|err| DirectoryError::Io(dir.clone(), err))?
+                .path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else {
+                res.push(path);
+            }
+        }
+    }
+    res.sort();
+
+    Ok(res)
+}
+
+/// The path relative to `root` with `/` as separator
+pub(crate) fn relative_name(root: &Path, path: &Path) -> String {
+    let path = path.strip_prefix(root).unwrap_or(path);
+    let parts: Vec<_> = path
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect();
+    parts.join("/")
+}
+
+/// Errors that can occur while processing a directory
+#[derive(Debug)]
+pub enum DirectoryError {
+    /// A file or directory that could not be read
+    Io(PathBuf, std::io::Error),
+    /// An invalid template
+    Template(ErrorWithPath),
+}
+
+impl std::fmt::Display for DirectoryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(path, err) => write!(f, "{err} for {}", path.display()),
+            Self::Template(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for DirectoryError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(_, err) => Some(err),
+            Self::Template(err) => Some(err),
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use crate::{join_path, split_path};
+
+/// A static table of split templates, keyed by `path#fragment`
+///
+/// Usually created at compile time with the `embed_template_dir!` macro of
+/// the `template-fragments-macros` crate. Lookups use a binary search over
+/// the sorted names.
+///
+/// ```rust
+/// # use template_fragments::EmbeddedTemplates;
+/// static TEMPLATES: EmbeddedTemplates = EmbeddedTemplates::from_sorted(&[
+///     ("index.html", "<ul>\n<li>{{ item }}</li>\n</ul>\n"),
+///     ("index.html#item", "<li>{{ item }}</li>\n"),
+/// ]);
+///
+/// assert_eq!(TEMPLATES.get("index.html#item"), Some("<li>{{ item }}</li>\n"));
+/// assert_eq!(TEMPLATES.get_fragment("index.html", "item"), TEMPLATES.get("index.html#item"));
+/// assert_eq!(TEMPLATES.get("index.html#missing"), None);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct EmbeddedTemplates {
+    entries: &'static [(&'static str, &'static str)],
+}
+
+impl EmbeddedTemplates {
+    /// Create the table from `(name, content)` pairs sorted by name
+    ///
+    /// Lookups may fail for unsorted entries.
+    pub const fn from_sorted(entries: &'static [(&'static str, &'static str)]) -> Self {
+        Self { entries }
+    }
+
+    /// Look up a fragment by its full name, e.g., `index.html#item`
+    ///
+    /// The base template is returned for names without fragment.
+    pub fn get(&self, name: &str) -> Option<&'static str> {
+        let (path, fragment) = split_path(name);
+        self.get_fragment(path, fragment)
+    }
+
+    /// Look up a fragment of a template
+    pub fn get_fragment(&self, path: &str, fragment: &str) -> Option<&'static str> {
+        let name = join_path(path, fragment);
+        let idx = self
+            .entries
+            .binary_search_by(|(other, _)| other.cmp(&name.as_str()))
+            .ok()?;
+        Some(self.entries[idx].1)
+    }
+
+    /// Whether the full name, e.g., `index.html#item`, is defined
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// Iterate over all full names and contents, sorted by name
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &'static str)> {
+        self.entries.iter().copied()
+    }
+
+    /// The number of fragments of all templates
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
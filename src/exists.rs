@@ -0,0 +1,123 @@
+use memchr::{memchr, memmem, memrchr};
+
+use crate::{
+    decode::strip_bom, iterate_with_endings, numbered::occurrence_of_key, parse_fragment_tag,
+    verbatim::Verbatim, Config, NameCase, Tag,
+};
+
+/// Check whether the template defines the given fragment
+///
+/// In contrast to [crate::filter_template], the template is not validated and
+/// scanning stops at the first start tag of the fragment. Therefore, this
+/// check is cheap enough to reject requests for unknown fragments before
+/// filtering the template. The base template `""` always exists.
+///
+/// ```rust
+/// # use template_fragments::fragment_exists;
+/// let source = concat!(
+///     "<ul>\n",
+///     "  {% fragment item %}\n",
+///     "  <li>{{ item }}</li>\n",
+///     "  {% endfragment %}\n",
+///     "</ul>\n",
+/// );
+///
+/// assert!(fragment_exists(source, "item"));
+/// assert!(fragment_exists(source, ""));
+/// assert!(!fragment_exists(source, "itme"));
+/// ```
+pub fn fragment_exists(src: &str, fragment: &str) -> bool {
+    Config::default().fragment_exists(src, fragment)
+}
+
+/// Check whether the template contains any fragment tags
+///
+/// The check only searches for the tag markers and parses the lines they occur
+/// on. Templates without fragment tags are returned unchanged as the base
+/// template, therefore callers can skip processing and caching them. Invalid
+/// tags count as fragment tags, so that their errors are not skipped.
+///
+/// ```rust
+/// # use template_fragments::has_fragments;
+/// assert!(has_fragments("<ul>\n{% fragment item %}\n<li></li>\n{% endfragment %}\n</ul>\n"));
+/// assert!(!has_fragments("<ul>\n{% for item in items %}\n<li></li>\n{% endfor %}\n</ul>\n"));
+/// ```
+pub fn has_fragments(src: &str) -> bool {
+    Config::default().has_fragments(src)
+}
+
+impl Config {
+    /// Check whether the template contains any fragment tags
+    ///
+    /// The tag markers of the config are used. Custom tags and `ifdef`
+    /// directives are not expanded, templates that use them should always be
+    /// processed. See [crate::has_fragments].
+    pub fn has_fragments(&self, src: &str) -> bool {
+        let tag_markers = self.syntax.tag_markers();
+        let src = self.expand_blocks(strip_bom(src));
+        let (src, _) = self.expand_macros(&src);
+        let bytes = src.as_bytes();
+        let mut verbatim = Verbatim::default();
+        let mut line_end = 0;
+
+        memmem::find_iter(bytes, tag_markers.0).any(|pos| {
+            // each line is only checked once
+            if pos < line_end {
+                return false;
+            }
+            let start = memrchr(b'\n', &bytes[..pos]).map_or(0, |idx| idx + 1);
+            line_end = memchr(b'\n', &bytes[pos..]).map_or(src.len(), |idx| pos + idx + 1);
+
+            let line = &src[start..line_end];
+            !verbatim.skip(line, tag_markers)
+                && !matches!(parse_fragment_tag(line, tag_markers), Ok(None))
+        })
+    }
+
+    /// Check whether the template defines the given fragment
+    ///
+    /// Fragment names are compared in their canonical form, see
+    /// [Config::name_case]. With [Config::number_repeated_fragments], the
+    /// numbered keys `NAME.N` of repeated fragments exist as well. Fragment
+    /// tags produced by custom tags are not considered. See
+    /// [crate::fragment_exists].
+    pub fn fragment_exists(&self, src: &str, fragment: &str) -> bool {
+        let fragment = self.name_case.normalize(fragment.trim());
+        if fragment.is_empty() || self.has_start_tags(src, &fragment, 1) {
+            return true;
+        }
+        match occurrence_of_key(&fragment) {
+            Some((fragment, occurrence)) if self.number_repeated_fragments => {
+                self.has_start_tags(src, fragment, occurrence + 1)
+            }
+            _ => false,
+        }
+    }
+
+    /// Check whether the template contains at least `count` start tags of the
+    /// fragment, scanning stops at the last required tag
+    fn has_start_tags(&self, src: &str, fragment: &str, count: usize) -> bool {
+        let tag_markers = self.syntax.tag_markers();
+        let mut verbatim = Verbatim::default();
+
+        let src = self.expand_blocks(strip_bom(src));
+        let (src, _) = self.expand_macros(&src);
+
+        let mut lines = iterate_with_endings(&src).filter(|line| {
+            if verbatim.skip(line, tag_markers) {
+                return false;
+            }
+            // for case-sensitive names, skip lines without the name quickly
+            if self.name_case == NameCase::Preserve && !line.contains(fragment) {
+                return false;
+            }
+            let is_defined = |name: &str| self.name_case.normalize(name) == fragment;
+            match parse_fragment_tag(line, tag_markers) {
+                Ok(Some(Tag::Start(tag))) => tag.fragments.iter().any(|&name| is_defined(name)),
+                Ok(Some(Tag::StartBlock(tag))) => is_defined(tag.fragment),
+                _ => false,
+            }
+        });
+        lines.nth(count - 1).is_some()
+    }
+}
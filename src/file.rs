@@ -0,0 +1,60 @@
+use std::{collections::HashMap, fs::File, path::Path};
+
+use memmap2::Mmap;
+
+use crate::{trace::start_span, Config, DirectoryError};
+
+/// Split a template file into all fragments available, without reading it
+/// into memory first
+///
+/// The markers are selected with [Config::for_path]. The file must not be
+/// modified while it is processed, otherwise the behavior is undefined. See
+/// [Config::split_template_file].
+///
+/// ```rust,no_run
+/// # use template_fragments::split_template_file;
+/// let templates = split_template_file("templates/report.html").unwrap();
+/// let summary = &templates["summary"];
+/// ```
+pub fn split_template_file(
+    path: impl AsRef<Path>,
+) -> Result<HashMap<String, String>, DirectoryError> {
+    let path = path.as_ref();
+    Config::for_path(path).split_template_file(path)
+}
+
+impl Config {
+    /// Split a template file into all fragments available, without reading it
+    /// into memory first
+    ///
+    /// The file is memory-mapped and processed in place, which avoids copying
+    /// very large templates, e.g., generated reports. Requires the `mmap`
+    /// feature. Files that are not valid UTF-8 result in an
+    /// [DirectoryError::Io] error with kind `InvalidData`.
+    ///
+    /// The file must not be modified while it is processed. If another process
+    /// truncates or writes to the file in the meantime, the behavior is
+    /// undefined: the process may be killed with `SIGBUS` or the template may
+    /// no longer be valid UTF-8 after it was validated. Use it only for files
+    /// that are written once, e.g., generated reports, and
+    /// [Config::split_templates] with [std::fs::read_to_string] otherwise.
+    pub fn split_template_file(
+        &self,
+        path: impl AsRef<Path>,
+    ) -> Result<HashMap<String, String>, DirectoryError> {
+        let path = path.as_ref();
+        start_span!(_timer, "split_template_file", path = %path.display());
+        let io_error = |err| DirectoryError::Io(path.to_path_buf(), err);
+
+        let file = File::open(path).map_err(io_error)?;
+        // SAFETY: the mapping is only sound if the file is not modified while
+        // it is mapped. This is not enforced, callers are required to ensure
+        // it, as documented above.
+        let data = unsafe { Mmap::map(&file) }.map_err(io_error)?;
+        let src = std::str::from_utf8(&data)
+            .map_err(|err| io_error(std::io::Error::new(std::io::ErrorKind::InvalidData, err)))?;
+
+        self.split_templates(src)
+            .map_err(|err| DirectoryError::Template(err.in_path(&path.to_string_lossy())))
+    }
+}
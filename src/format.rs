@@ -0,0 +1,121 @@
+use crate::{
+    get_ending, iterate_with_endings, parse_fragment_tag, AstNode, Config, ErrorWithLine,
+    FragmentNode, Tag,
+};
+
+/// Normalize the fragment tags of a template
+///
+/// The formatter
+///
+/// - normalizes the whitespace inside fragment tags
+/// - sorts the names of start tags with multiple names, attributes follow the
+///   names in their original order
+/// - indents the tags of a fragment as its first non-empty line. Fragments
+///   without content keep the indentation of their start tag
+///
+/// All other lines are kept as they are. End tags keep their kind, even if it
+/// does not match the start tag, as this changes the output. Formatting a
+/// formatted template does not change it.
+///
+/// ```rust
+/// # use template_fragments::format_template;
+/// let source = concat!(
+///     "<ul>\n",
+///     "{%\tfragment   list item  %}\n",
+///     "  <li>{{ item }}</li>\n",
+///     "    {%\tendfragment %}\n",
+///     "</ul>\n",
+/// );
+///
+/// assert_eq!(
+///     format_template(source).unwrap(),
+///     concat!(
+///         "<ul>\n",
+///         "  {% fragment item list %}\n",
+///         "  <li>{{ item }}</li>\n",
+///         "  {% endfragment %}\n",
+///         "</ul>\n",
+///     ),
+/// );
+/// ```
+pub fn format_template(src: &str) -> Result<String, ErrorWithLine> {
+    Config::default().format_template(src)
+}
+
+impl Config {
+    /// Normalize the fragment tags of a template
+    ///
+    /// See [crate::format_template].
+    pub fn format_template(&self, src: &str) -> Result<String, ErrorWithLine> {
+        let ast = self.parse_ast(src)?;
+
+        let mut res = String::with_capacity(src.len());
+        for node in &ast.nodes {
+            self.format_node(node, &mut res);
+        }
+        Ok(res)
+    }
+
+    fn format_node(&self, node: &AstNode, res: &mut String) {
+        match node {
+            AstNode::Text(node) => res.push_str(&node.content),
+            AstNode::Fragment(node) => self.format_fragment(node, res),
+        }
+    }
+
+    fn format_fragment(&self, node: &FragmentNode, res: &mut String) {
+        let (open, close) = self.syntax.tag_markers();
+        let kind = if node.block {
+            "fragment-block"
+        } else {
+            "fragment"
+        };
+        let indent = first_indent(&node.children).unwrap_or_else(|| indent_of(&node.start_tag));
+
+        let mut data = node.names.join(" ");
+        for (key, value) in &node.attributes {
+            data.push_str(&format!(" {key}={value}"));
+        }
+        res.push_str(&format!(
+            "{indent}{open} {kind} {data} {close}{}",
+            get_ending(&node.start_tag)
+        ));
+
+        for child in &node.children {
+            self.format_node(child, res);
+        }
+
+        // the kind of the end tag is kept, mismatched end tags change the output
+        let (end_kind, mut end_names): (_, Vec<&str>) =
+            match parse_fragment_tag(&node.end_tag, (open, close)) {
+                Ok(Some(Tag::End(tag))) => ("fragment", tag.data.split_whitespace().collect()),
+                Ok(Some(Tag::EndBlock(tag))) => {
+                    ("fragment-block", tag.data.split_whitespace().collect())
+                }
+                _ => (kind, Vec::new()),
+            };
+        end_names.sort();
+        end_names.dedup();
+
+        let mut end = format!("{indent}{open} end{end_kind} ");
+        for name in end_names {
+            end.push_str(name);
+            end.push(' ');
+        }
+        res.push_str(&format!("{end}{close}{}", get_ending(&node.end_tag)));
+    }
+}
+
+/// The indentation of the first non-empty text line of the nodes
+fn first_indent(nodes: &[AstNode]) -> Option<&str> {
+    nodes.iter().find_map(|node| match node {
+        AstNode::Text(node) => iterate_with_endings(&node.content)
+            .find(|line| !line.trim().is_empty())
+            .map(indent_of),
+        AstNode::Fragment(node) => first_indent(&node.children),
+    })
+}
+
+fn indent_of(line: &str) -> &str {
+    &line[..line.len() - line.trim_start().len()]
+}
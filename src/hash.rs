@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+
+use xxhash_rust::xxh3::xxh3_128;
+
+use crate::{Config, ErrorWithLine};
+
+/// The content of a fragment with a stable hash of it
+///
+/// Requires the `hash` feature. The hash is computed with XXH3 (128 bits) and
+/// does not depend on the platform or the version of this crate. It can be
+/// used as a strong `ETag` for HTTP caching without hashing the content on
+/// every request.
+///
+/// ```rust
+/// # use template_fragments::HashedFragment;
+/// let fragment = HashedFragment::new(String::from("<li>{{ item }}</li>\n"));
+/// let etag = fragment.etag();
+///
+/// assert!(fragment.matches(&etag));
+/// assert!(fragment.matches(&format!("W/{etag}, \"other\"")));
+/// assert!(!fragment.matches("\"other\""));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HashedFragment {
+    pub content: String,
+    pub hash: u128,
+}
+
+impl HashedFragment {
+    pub fn new(content: String) -> Self {
+        let hash = xxh3_128(content.as_bytes());
+        Self { content, hash }
+    }
+
+    /// The hash as a quoted entity tag, e.g., for the `ETag` header
+    pub fn etag(&self) -> String {
+        format!("\"{:032x}\"", self.hash)
+    }
+
+    /// Check whether the value of an `If-None-Match` header matches the
+    /// fragment, i.e., whether a `304 Not Modified` response can be sent
+    ///
+    /// Following the semantics of `If-None-Match`, weak tags are compared as
+    /// well and `*` matches any fragment.
+    pub fn matches(&self, if_none_match: &str) -> bool {
+        let etag = self.etag();
+        if_none_match
+            .split(',')
+            .map(str::trim)
+            .any(|tag| tag == "*" || tag.strip_prefix("W/").unwrap_or(tag) == etag)
+    }
+}
+
+/// Split the template into all fragments and hash each of them
+///
+/// See [HashedFragment] and [crate::split_templates].
+///
+/// ```rust
+/// # use template_fragments::split_templates_hashed;
+/// let source = concat!(
+///     "<ul>\n",
+///     "{% fragment item %}\n",
+///     "  <li>{{ item }}</li>\n",
+///     "{% endfragment %}\n",
+///     "</ul>\n",
+/// );
+/// let fragments = split_templates_hashed(source).unwrap();
+///
+/// assert_eq!(fragments["item"].content, "  <li>{{ item }}</li>\n");
+/// assert_ne!(fragments["item"].hash, fragments[""].hash);
+/// ```
+pub fn split_templates_hashed(src: &str) -> Result<HashMap<String, HashedFragment>, ErrorWithLine> {
+    Config::default().split_templates_hashed(src)
+}
+
+impl Config {
+    /// Split the template into all fragments and hash each of them
+    ///
+    /// The hashes are computed after all output options were applied. See
+    /// [crate::split_templates_hashed].
+    pub fn split_templates_hashed(
+        &self,
+        src: &str,
+    ) -> Result<HashMap<String, HashedFragment>, ErrorWithLine> {
+        Ok(self
+            .split_templates(src)?
+            .into_iter()
+            .map(|(fragment, content)| (fragment, HashedFragment::new(content)))
+            .collect())
+    }
+}
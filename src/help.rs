@@ -0,0 +1,454 @@
+use std::ops::Range;
+
+use crate::{
+    iterate_with_endings, parse_base, parse_fragment_tag, validate::line_text, verbatim::Verbatim,
+    Config, Error, ErrorWithLine, Tag,
+};
+
+/// A tag that is open at a line: its line, end keyword, and sorted fragments
+type OpenTag = (usize, &'static str, Vec<String>);
+
+impl Error {
+    /// A short hint how to fix the error
+    ///
+    /// Tags are shown in the default syntax. Use [Config::error_help] to refer
+    /// to the syntax of the template and to the lines of open tags.
+    ///
+    /// ```rust
+    /// # use template_fragments::split_templates;
+    /// let err = split_templates("{% fragment items %}\n").unwrap_err();
+    /// assert_eq!(err.1.help(), "did you forget `{% endfragment %}` for `items`?");
+    /// ```
+    pub fn help(&self) -> String {
+        match self {
+            Self::LeadingContent(_) => String::from(
+                "move the content before the tag to a separate line, fragment tags must be on a line of their own",
+            ),
+            Self::TrailingContent(_) => String::from(
+                "move the content after the tag to a separate line, fragment tags must be on a line of their own",
+            ),
+            Self::EndTagWithData(_) => String::from(
+                "remove the names from the end tag or enable `Config::named_end_tags`",
+            ),
+            Self::StartTagWithoutData => {
+                String::from("add the name of the fragment, e.g., `{% fragment item %}`")
+            }
+            Self::ReentrantFragment(fragments) => format!(
+                "{} already open, close it before reopening or use a different name",
+                quoted(fragments)
+            ),
+            Self::UnclosedTag(fragments) => format!(
+                "did you forget `{{% endfragment %}}` for {}?",
+                quoted(fragments)
+            ),
+            Self::UnbalancedEndTag => String::from(
+                "remove the end tag or add the missing `{% fragment ... %}` before it",
+            ),
+            Self::MismatchedEndTag(_, expected) => format!(
+                "the innermost open tag is for {}, close it first",
+                quoted(expected)
+            ),
+            Self::InvalidFragmentName(_) => String::from(
+                "fragment names may only contain alphanumeric characters, `-`, and `_`, `block` is reserved",
+            ),
+            Self::UnnamedBlock => {
+                String::from("add the name of the block, e.g., `{% fragment-block item %}`")
+            }
+            Self::MultipleNamesBlock(_) => String::from(
+                "blocks have a single name, use `{% fragment ... %}` for multiple names",
+            ),
+            Self::RepeatedFragment(fragment, first_line) => format!(
+                "`{fragment}` is first defined on line {}, rename one of the fragments",
+                first_line + 1
+            ),
+            Self::NestingTooDeep(max_depth) => format!(
+                "nest at most {max_depth} fragments or increase `Config::max_depth`"
+            ),
+            Self::TooManyFragments(max_fragments) => format!(
+                "define at most {max_fragments} fragments or increase `Config::max_fragments`"
+            ),
+            Self::InvalidAttribute(_) => {
+                String::from("write attributes as `key=value` with a non-empty key and value")
+            }
+            Self::UnresolvedInclude(path) => {
+                format!("check that `{path}` exists and is spelled correctly")
+            }
+            Self::RecursiveInclude(path) => {
+                format!("break the cycle of includes through `{path}`")
+            }
+            Self::CustomTag(keyword, _) => format!("check the arguments of the `{keyword}` tag"),
+            Self::UnknownFragment(_, available) => match self.suggestions().as_slice() {
+                [] if available.is_empty() => {
+                    String::from("the template does not define any fragments")
+                }
+                [] => format!("available fragments: {}", available.join(", ")),
+                suggestions => format!("did you mean {}?", quoted(suggestions)),
+            },
+            Self::InvalidDirective(_) => String::from(
+                "write directives as `ifdef KEY`, `ifdef KEY=VALUE`, `ifndef KEY`, or `endifdef`",
+            ),
+            Self::UnclosedDirective(content) => {
+                format!("did you forget `{{% endifdef %}}` for `{content}`?")
+            }
+            Self::UnbalancedDirective => String::from(
+                "remove the `endifdef` or add the missing `{% ifdef ... %}` before it",
+            ),
+            Self::InvalidFragmentSyntax(fragment, _) => format!(
+                "move the boundaries of `{fragment}` so that it contains complete tags, e.g., both `for` and `endfor`"
+            ),
+            Self::MacroNameCollision(_) => String::from(
+                "`-` and `.` are replaced by `_` in macro names, rename one of the fragments",
+            ),
+        }
+    }
+
+    /// A stable identifier of the kind of error, e.g., for diagnostics
+    ///
+    /// ```rust
+    /// # use template_fragments::split_templates;
+    /// let err = split_templates("{% fragment items %}\n").unwrap_err();
+    /// assert_eq!(err.1.code(), "unclosed-tag");
+    /// ```
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::LeadingContent(_) => "leading-content",
+            Self::TrailingContent(_) => "trailing-content",
+            Self::EndTagWithData(_) => "end-tag-with-data",
+            Self::StartTagWithoutData => "start-tag-without-data",
+            Self::ReentrantFragment(_) => "reentrant-fragment",
+            Self::UnclosedTag(_) => "unclosed-tag",
+            Self::UnbalancedEndTag => "unbalanced-end-tag",
+            Self::MismatchedEndTag(_, _) => "mismatched-end-tag",
+            Self::InvalidFragmentName(_) => "invalid-fragment-name",
+            Self::UnnamedBlock => "unnamed-block",
+            Self::MultipleNamesBlock(_) => "multiple-names-block",
+            Self::RepeatedFragment(_, _) => "repeated-fragment",
+            Self::NestingTooDeep(_) => "nesting-too-deep",
+            Self::TooManyFragments(_) => "too-many-fragments",
+            Self::InvalidAttribute(_) => "invalid-attribute",
+            Self::UnresolvedInclude(_) => "unresolved-include",
+            Self::RecursiveInclude(_) => "recursive-include",
+            Self::CustomTag(_, _) => "custom-tag",
+            Self::UnknownFragment(_, _) => "unknown-fragment",
+            Self::InvalidDirective(_) => "invalid-directive",
+            Self::UnclosedDirective(_) => "unclosed-directive",
+            Self::UnbalancedDirective => "unbalanced-directive",
+            Self::InvalidFragmentSyntax(_, _) => "invalid-fragment-syntax",
+            Self::MacroNameCollision(_) => "macro-name-collision",
+        }
+    }
+
+    /// A short description of the error for template authors
+    pub(crate) fn summary(&self) -> String {
+        match self {
+            Self::LeadingContent(_) => String::from("content before a fragment tag"),
+            Self::TrailingContent(_) => String::from("content after a fragment tag"),
+            Self::EndTagWithData(_) => String::from("end tag with fragment names"),
+            Self::StartTagWithoutData => String::from("fragment tag without names"),
+            Self::ReentrantFragment(fragments) => {
+                format!("{} already open", quoted(fragments))
+            }
+            Self::UnclosedTag(fragments) => format!("unclosed fragment {}", quoted(fragments)),
+            Self::UnbalancedEndTag => String::from("end tag without a matching start tag"),
+            Self::MismatchedEndTag(found, expected) => format!(
+                "end tag for {} does not match the open tag for {}",
+                quoted(found),
+                quoted(expected)
+            ),
+            Self::InvalidFragmentName(fragments) => {
+                format!("invalid fragment name {}", quoted(fragments))
+            }
+            Self::UnnamedBlock => String::from("fragment block without a name"),
+            Self::MultipleNamesBlock(fragments) => {
+                format!("fragment block with multiple names {}", quoted(fragments))
+            }
+            Self::RepeatedFragment(fragment, _) => {
+                format!("fragment `{fragment}` is defined more than once")
+            }
+            Self::NestingTooDeep(max_depth) => {
+                format!("fragments nested more than {max_depth} levels deep")
+            }
+            Self::TooManyFragments(max_fragments) => {
+                format!("more than {max_fragments} fragments")
+            }
+            Self::InvalidAttribute(attribute) => format!("invalid attribute `{attribute}`"),
+            Self::UnresolvedInclude(path) => format!("cannot resolve the include `{path}`"),
+            Self::RecursiveInclude(path) => format!("recursive include of `{path}`"),
+            Self::CustomTag(keyword, message) => format!("invalid `{keyword}` tag: {message}"),
+            Self::UnknownFragment(fragment, _) => format!("unknown fragment `{fragment}`"),
+            Self::InvalidDirective(content) => format!("invalid directive `{content}`"),
+            Self::UnclosedDirective(content) => format!("unclosed directive `{content}`"),
+            Self::UnbalancedDirective => String::from("`endifdef` without a matching `ifdef`"),
+            Self::InvalidFragmentSyntax(fragment, message) => {
+                format!("fragment `{fragment}` is not a valid template: {message}")
+            }
+            Self::MacroNameCollision(fragments) => {
+                format!(
+                    "fragments {} result in the same macro name",
+                    quoted(fragments)
+                )
+            }
+        }
+    }
+
+    /// Similar names for errors that refer to a name that does not exist
+    ///
+    /// The suggestions are ordered by similarity. For other errors, an empty
+    /// list is returned.
+    ///
+    /// ```rust
+    /// # use template_fragments::Config;
+    /// let source = "{% fragment item %}\n{% endfragment %}\n{% fragment footer %}\n{% endfragment %}\n";
+    /// let err = Config::default()
+    ///     .strict_fragments(true)
+    ///     .filter_template(source, "itme")
+    ///     .unwrap_err();
+    ///
+    /// assert_eq!(err.1.suggestions(), ["item"]);
+    /// assert_eq!(err.1.help(), "did you mean `item`?");
+    /// ```
+    pub fn suggestions(&self) -> Vec<&str> {
+        let Self::UnknownFragment(name, available) = self else {
+            return Vec::new();
+        };
+
+        let max_distance = (name.chars().count() / 3).max(1);
+        let mut res: Vec<(usize, &str)> = available
+            .iter()
+            .map(|candidate| (edit_distance(name, candidate), candidate.as_str()))
+            .filter(|&(distance, _)| distance <= max_distance)
+            .collect();
+        res.sort();
+        res.into_iter().map(|(_, candidate)| candidate).collect()
+    }
+}
+
+impl Config {
+    /// A short hint how to fix an error of the given template
+    ///
+    /// In contrast to [Error::help], tags are shown in the syntax of the
+    /// config and unclosed tags refer to the line they were opened on.
+    ///
+    /// ```rust
+    /// # use template_fragments::Config;
+    /// let source = "((* fragment items *))\n((* fragment item *))\n((* endfragment *))\n";
+    /// let config = Config::for_path("report.tex");
+    /// let err = config.split_templates(source).unwrap_err();
+    ///
+    /// assert_eq!(
+    ///     config.error_help(source, &err),
+    ///     "did you forget `((* endfragment *))` for `items` opened on line 1?",
+    /// );
+    /// ```
+    pub fn error_help(&self, src: &str, err: &ErrorWithLine) -> String {
+        if let Error::UnclosedTag(_) = &err.1 {
+            if let Some(help) = self.unclosed_tag_help(src, err.0) {
+                return help;
+            }
+        }
+        err.1.help()
+    }
+
+    /// Render an error of the given template for template authors
+    ///
+    /// The rendered error contains a description, the offending line with a
+    /// caret under the problem, the fragments open at this line, and the
+    /// hint of [Config::error_help]. Unclosed tags are shown at the line they
+    /// were opened on.
+    ///
+    /// ```rust
+    /// # use template_fragments::Config;
+    /// let source = concat!(
+    ///     "{% fragment items %}\n",
+    ///     "  {% fragment item %} <li>\n",
+    ///     "  {% endfragment %}\n",
+    ///     "{% endfragment %}\n",
+    /// );
+    /// let config = Config::default();
+    /// let err = config.split_templates(source).unwrap_err();
+    ///
+    /// assert_eq!(
+    ///     config.render_error(source, &err),
+    ///     concat!(
+    ///         "error: content after a fragment tag\n",
+    ///         " --> line 2\n",
+    ///         "  |\n",
+    ///         "2 |   {% fragment item %} <li>\n",
+    ///         "  |                       ^^^^\n",
+    ///         "  = inside: `items` (line 1)\n",
+    ///         "  = help: move the content after the tag to a separate line, ",
+    ///         "fragment tags must be on a line of their own\n",
+    ///     ),
+    /// );
+    /// ```
+    pub fn render_error(&self, src: &str, err: &ErrorWithLine) -> String {
+        let (line_idx, open) = self.error_context(src, err);
+
+        let number = (line_idx + 1).to_string();
+        let gutter = " ".repeat(number.len());
+        let mut res = format!("error: {}\n", err.1.summary());
+        res.push_str(&format!("{gutter}--> line {number}\n"));
+
+        if let Some(line) = iterate_with_endings(src).nth(line_idx) {
+            let line = line.trim_end_matches(['\r', '\n']);
+            res.push_str(&format!("{gutter} |\n{number} | {line}\n"));
+
+            let span = self.error_span(line, &err.1);
+            if !span.is_empty() {
+                // keep tabs to align the caret with the line
+                let indent: String = line[..span.start]
+                    .chars()
+                    .map(|c| if c == '\t' { '\t' } else { ' ' })
+                    .collect();
+                let caret = "^".repeat(line[span].chars().count());
+                res.push_str(&format!("{gutter} | {indent}{caret}\n"));
+            }
+        }
+
+        if !open.is_empty() {
+            let open: Vec<String> = open
+                .iter()
+                .map(|(line_idx, _, fragments)| {
+                    format!("{} (line {})", quoted(fragments), line_idx + 1)
+                })
+                .collect();
+            res.push_str(&format!("{gutter} = inside: {}\n", open.join(" > ")));
+        }
+        res.push_str(&format!("{gutter} = help: {}\n", self.error_help(src, err)));
+        res
+    }
+
+    /// The line to show an error at and the fragment tags open before it
+    ///
+    /// Unclosed tags are shown at the line of the innermost unclosed tag.
+    fn error_context(&self, src: &str, err: &ErrorWithLine) -> (usize, Vec<OpenTag>) {
+        match &err.1 {
+            Error::UnclosedTag(_) => {
+                let mut open = self.open_tags(src, err.0 + 1);
+                let line_idx = open.pop().map_or(err.0, |(line_idx, _, _)| line_idx);
+                (line_idx, open)
+            }
+            _ => (err.0, self.open_tags(src, err.0)),
+        }
+    }
+
+    /// The line to show an error at and the byte range of the problem in it
+    pub(crate) fn error_location(&self, src: &str, err: &ErrorWithLine) -> (usize, Range<usize>) {
+        let (line_idx, _) = self.error_context(src, err);
+        (line_idx, self.error_span(line_text(src, line_idx), &err.1))
+    }
+
+    /// The byte range of the line to mark for an error
+    fn error_span(&self, line: &str, err: &Error) -> Range<usize> {
+        let Some(parts) = parse_base(line, self.syntax.tag_markers()) else {
+            return trimmed_span(0, line);
+        };
+
+        let tail_start = line.len() - parts.tail.len();
+        match err {
+            Error::LeadingContent(_) => trimmed_span(0, parts.head),
+            Error::TrailingContent(_) => trimmed_span(tail_start, parts.tail),
+            _ => self.tag_span(line),
+        }
+    }
+
+    /// The byte range of the fragment tag of the line or, without tag, of the
+    /// line without surrounding whitespace
+    pub(crate) fn tag_span(&self, line: &str) -> Range<usize> {
+        match parse_base(line, self.syntax.tag_markers()) {
+            Some(parts) => parts.head.len()..line.len() - parts.tail.len(),
+            None => trimmed_span(0, line),
+        }
+    }
+
+    /// The fragment tags that are open before the given line
+    fn open_tags(&self, src: &str, line_idx: usize) -> Vec<OpenTag> {
+        let Ok((src, lines)) = self.preprocess(src) else {
+            return Vec::new();
+        };
+
+        let mut open: Vec<OpenTag> = Vec::new();
+        let mut verbatim = Verbatim::default();
+        for (idx, line) in iterate_with_endings(&src).enumerate() {
+            let original = lines.original(idx);
+            if original >= line_idx {
+                break;
+            }
+            if verbatim.skip(line, self.syntax.tag_markers()) {
+                continue;
+            }
+            match parse_fragment_tag(line, self.syntax.tag_markers()) {
+                Ok(Some(Tag::Start(tag))) => {
+                    let mut fragments: Vec<String> =
+                        tag.fragments.into_iter().map(str::to_owned).collect();
+                    fragments.sort();
+                    open.push((original, "endfragment", fragments));
+                }
+                Ok(Some(Tag::StartBlock(tag))) => {
+                    open.push((original, "endfragment-block", vec![tag.fragment.to_owned()]));
+                }
+                Ok(Some(Tag::End(_) | Tag::EndBlock(_))) => {
+                    open.pop();
+                }
+                _ => {}
+            }
+        }
+        open
+    }
+
+    /// Find the innermost tag that is still open at the given line
+    fn unclosed_tag_help(&self, src: &str, line_idx: usize) -> Option<String> {
+        let (start_line, keyword, fragments) = self.open_tags(src, line_idx + 1).pop()?;
+        let (start, end) = self.syntax.tag_markers();
+        Some(format!(
+            "did you forget `{start} {keyword} {end}` for {} opened on line {}?",
+            quoted(&fragments),
+            start_line + 1
+        ))
+    }
+}
+
+/// The byte range of the text without surrounding whitespace, the text
+/// starting at the given offset
+fn trimmed_span(offset: usize, text: &str) -> Range<usize> {
+    let trimmed = text.trim_start();
+    let start = offset + text.len() - trimmed.len();
+    start..start + trimmed.trim_end().len()
+}
+
+/// Format names as a list of inline code spans
+fn quoted<S: AsRef<str>>(names: &[S]) -> String {
+    let names: Vec<String> = names
+        .iter()
+        .map(|name| format!("`{}`", name.as_ref()))
+        .collect();
+    names.join(", ")
+}
+
+/// The number of edits to turn one string into the other, counting swapped
+/// adjacent characters as a single edit
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    // distances[i][j] is the distance between a[..i] and b[..j]
+    let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+    distances[0] = (0..=b.len()).collect();
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = (distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1)
+                .min(distances[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
+            }
+            distances[i][j] = distance;
+        }
+    }
+    distances[a.len()][b.len()]
+}
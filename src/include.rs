@@ -0,0 +1,218 @@
+use crate::{
+    get_ending, is_valid_fragment_name, iterate_with_endings, join_path, split_templates, Error,
+    ErrorWithLine, DEFAULT_TAG_MARKERS,
+};
+
+/// Expand `{% include "PATH" %}` directives before splitting the template
+///
+/// The `resolver` is called with the path of each include and should return
+/// the source of the included template, or `None` if it cannot be found.
+/// Included templates are expanded recursively. This way, fragments that span
+/// includes result in self-contained outputs.
+///
+/// Like fragment tags, include directives are only expanded if they are
+/// contained in a single line without any other non-whitespace content. All
+/// other includes are kept as they are. Note that line numbers of errors in
+/// the expanded source refer to the expanded source.
+///
+/// ```rust
+/// # use template_fragments::{resolve_includes, split_templates};
+/// let source = concat!(
+///     "<body>\n",
+///     "  {% fragment item %}\n",
+///     "  {% include \"item.html\" %}\n",
+///     "  {% endfragment %}\n",
+///     "<body>\n",
+/// );
+///
+/// let source = resolve_includes(source, |path| match path {
+///     "item.html" => Some(String::from("    <div>{{ item }}</div>\n")),
+///     _ => None,
+/// })
+/// .unwrap();
+///
+/// assert_eq!(
+///     split_templates(&source).unwrap()["item"],
+///     "    <div>{{ item }}</div>\n",
+/// );
+/// ```
+pub fn resolve_includes(
+    src: &str,
+    mut resolver: impl FnMut(&str) -> Option<String>,
+) -> Result<String, ErrorWithLine> {
+    let mut res = String::new();
+    let mut active_paths = Vec::new();
+
+    for (line_idx, line) in iterate_with_endings(src).enumerate() {
+        expand_line(line, &mut resolver, &mut active_paths, &mut res)
+            .map_err(|err| err.at(line_idx))?;
+    }
+
+    Ok(res)
+}
+
+fn expand_line(
+    line: &str,
+    resolver: &mut impl FnMut(&str) -> Option<String>,
+    active_paths: &mut Vec<String>,
+    res: &mut String,
+) -> Result<(), Error> {
+    let Some(path) = parse_include(line, DEFAULT_TAG_MARKERS) else {
+        res.push_str(line);
+        return Ok(());
+    };
+
+    if active_paths.iter().any(|active| active == path) {
+        return Err(Error::RecursiveInclude(path.to_owned()));
+    }
+    let included = resolver(path).ok_or_else(|| Error::UnresolvedInclude(path.to_owned()))?;
+
+    active_paths.push(path.to_owned());
+    for included_line in iterate_with_endings(&included) {
+        expand_line(included_line, resolver, active_paths, res)?;
+    }
+    active_paths.pop();
+
+    if !included.is_empty() && get_ending(&included).is_empty() {
+        res.push_str(get_ending(line));
+    }
+
+    Ok(())
+}
+
+/// Expand `{% fragment-use NAME from "PATH" %}` tags before splitting the
+/// template
+///
+/// The `loader` is called with the path of each used template and should
+/// return its source, or `None` if it cannot be found. The tag is replaced by
+/// the content of the named fragment in the loaded template. Fragment uses in
+/// loaded templates are expanded recursively. This way, a library of shared
+/// components can be expressed purely in templates.
+///
+/// Errors in loaded templates are reported at the line of the `fragment-use`
+/// tag. Missing templates or fragments result in an
+/// [Error::UnresolvedInclude].
+///
+/// ```rust
+/// # use template_fragments::resolve_fragment_uses;
+/// let source = concat!(
+///     "<body>\n",
+///     "  {% fragment-use card from \"components.html\" %}\n",
+///     "<body>\n",
+/// );
+/// let components = concat!(
+///     "{% fragment card %}\n",
+///     "  <div class=\"card\"></div>\n",
+///     "{% endfragment %}\n",
+/// );
+///
+/// let source = resolve_fragment_uses(source, |path| match path {
+///     "components.html" => Some(String::from(components)),
+///     _ => None,
+/// })
+/// .unwrap();
+///
+/// assert_eq!(
+///     source,
+///     concat!(
+///         "<body>\n",
+///         "  <div class=\"card\"></div>\n",
+///         "<body>\n",
+///     ),
+/// );
+/// ```
+pub fn resolve_fragment_uses(
+    src: &str,
+    mut loader: impl FnMut(&str) -> Option<String>,
+) -> Result<String, ErrorWithLine> {
+    let mut res = String::new();
+    let mut active_uses = Vec::new();
+
+    for (line_idx, line) in iterate_with_endings(src).enumerate() {
+        expand_fragment_use(line, &mut loader, &mut active_uses, &mut res)
+            .map_err(|err| err.at(line_idx))?;
+    }
+
+    Ok(res)
+}
+
+fn expand_fragment_use(
+    line: &str,
+    loader: &mut impl FnMut(&str) -> Option<String>,
+    active_uses: &mut Vec<String>,
+    res: &mut String,
+) -> Result<(), Error> {
+    let Some((fragment, path)) = parse_fragment_use(line, DEFAULT_TAG_MARKERS) else {
+        res.push_str(line);
+        return Ok(());
+    };
+
+    let key = join_path(path, fragment);
+    if active_uses.contains(&key) {
+        return Err(Error::RecursiveInclude(key));
+    }
+    let loaded = loader(path).ok_or_else(|| Error::UnresolvedInclude(path.to_owned()))?;
+
+    let content = split_templates(&loaded)
+        .map_err(|err| err.1)?
+        .remove(fragment)
+        .ok_or_else(|| Error::UnresolvedInclude(key.clone()))?;
+
+    active_uses.push(key);
+    for content_line in iterate_with_endings(&content) {
+        expand_fragment_use(content_line, loader, active_uses, res)?;
+    }
+    active_uses.pop();
+
+    if !content.is_empty() && get_ending(&content).is_empty() {
+        res.push_str(get_ending(line));
+    }
+
+    Ok(())
+}
+
+/// Parse a line of the form `{% fragment-use NAME from "PATH" %}` and return
+/// the fragment and the path
+fn parse_fragment_use<'l>(line: &'l str, tag_markers: (&str, &str)) -> Option<(&'l str, &'l str)> {
+    let line = line.trim();
+    let line = line
+        .strip_prefix(tag_markers.0)?
+        .strip_suffix(tag_markers.1)?;
+    let line = line.strip_prefix(char::is_whitespace)?.trim_start();
+    let line = line.strip_prefix("fragment-use")?;
+    let line = line.strip_prefix(char::is_whitespace)?.trim();
+
+    let (fragment, line) = line.split_once(char::is_whitespace)?;
+    let line = line.trim_start().strip_prefix("from")?;
+    let line = line.strip_prefix(char::is_whitespace)?.trim();
+
+    if !is_valid_fragment_name(fragment) {
+        return None;
+    }
+    Some((fragment, parse_string_literal(line)?))
+}
+
+/// Parse a line of the form `{% include "PATH" %}` and return the path
+fn parse_include<'l>(line: &'l str, tag_markers: (&str, &str)) -> Option<&'l str> {
+    let line = line.trim();
+    let line = line
+        .strip_prefix(tag_markers.0)?
+        .strip_suffix(tag_markers.1)?;
+    let line = line.strip_prefix(char::is_whitespace)?.trim_start();
+    let line = line.strip_prefix("include")?;
+    let line = line.strip_prefix(char::is_whitespace)?.trim();
+
+    parse_string_literal(line)
+}
+
+/// Parse a single or double quoted string without escapes
+fn parse_string_literal(s: &str) -> Option<&str> {
+    let quote = s.chars().next().filter(|&c| c == '"' || c == '\'')?;
+    let value = s.strip_prefix(quote)?.strip_suffix(quote)?;
+
+    if value.contains(quote) {
+        None
+    } else {
+        Some(value)
+    }
+}
@@ -0,0 +1,315 @@
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+};
+
+use crate::{defines::LineMap, iterate_with_endings, push_line, Config, ErrorWithLine, Processor};
+
+/// A split template that can be updated incrementally
+///
+/// The template is processed in regions that end with a top-level end tag.
+/// When the source changes, only the regions that cover the changed lines are
+/// processed again and only the fragments with output in these regions are
+/// rebuilt. All other fragments are kept as they are. This is designed for
+/// dev servers that re-split templates on every edit.
+///
+/// Options that depend on the whole template, e.g.,
+/// [Config::unique_fragments], [Config::number_repeated_fragments],
+/// [Config::macros], or [Config::enclosing_control_tags], and options that
+/// rewrite the source before processing it, e.g., [Config::defines] or
+/// [Config::name_case], fall back to splitting the full template on every
+/// update.
+///
+/// ```rust
+/// # use template_fragments::IncrementalSplit;
+/// let source = concat!(
+///     "<body>\n",
+///     "  {% fragment item %}\n",
+///     "    <div>{{ item }}</div>\n",
+///     "  {% endfragment %}\n",
+///     "  {% fragment footer %}\n",
+///     "    <footer></footer>\n",
+///     "  {% endfragment %}\n",
+///     "<body>\n",
+/// );
+///
+/// let mut split = IncrementalSplit::new(source).unwrap();
+/// let changed = split
+///     .update(source.replace("<div>", "<div class=\"item\">"))
+///     .unwrap();
+///
+/// assert_eq!(changed, vec!["", "item"]);
+/// assert_eq!(
+///     split.templates()["item"],
+///     "    <div class=\"item\">{{ item }}</div>\n",
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IncrementalSplit {
+    config: Config,
+    src: String,
+    templates: HashMap<String, String>,
+    /// The regions of the source, `None` if it is split fully
+    regions: Option<Vec<Region>>,
+}
+
+/// Consecutive lines of the source that start and end outside of any tag
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Region {
+    /// The number of lines
+    lines: usize,
+    /// The output of each fragment before the fragments are finished
+    output: HashMap<String, String>,
+}
+
+impl Config {
+    /// Split the template fully and keep the regions to update it
+    /// incrementally
+    ///
+    /// See [IncrementalSplit].
+    pub fn incremental_split(
+        &self,
+        src: impl Into<String>,
+    ) -> Result<IncrementalSplit, ErrorWithLine> {
+        let mut split = IncrementalSplit {
+            config: self.clone(),
+            src: String::new(),
+            templates: HashMap::new(),
+            regions: None,
+        };
+        split.update(src)?;
+        Ok(split)
+    }
+
+    /// Whether the output of the lines of a region only depends on the
+    /// region itself
+    fn splits_incrementally(&self) -> bool {
+        #[cfg(feature = "minijinja")]
+        if self.verify {
+            return false;
+        }
+        !(self.unique_fragments
+            || self.max_fragments.is_some()
+            || self.macros
+            || self.name_case != crate::NameCase::Preserve
+            || !self.tag_handlers.is_empty()
+            || self.defines.is_some()
+            || self.debug_annotations
+            || self.enclosing_control_tags
+            || self.enclosing_with_tags
+            || self.blocks_as_fragments
+            || self.fragments_as_blocks
+            || self.tag_colons
+            || self.line_comment_prefix.is_some()
+            || self.macros_as_fragments
+            || self.number_repeated_fragments
+            || self.fragment_separator.is_some())
+    }
+}
+
+impl IncrementalSplit {
+    /// Split the template fully with the default configuration
+    ///
+    /// See [Config::incremental_split].
+    pub fn new(src: impl Into<String>) -> Result<Self, ErrorWithLine> {
+        Config::default().incremental_split(src)
+    }
+
+    /// The current source of the template
+    pub fn source(&self) -> &str {
+        &self.src
+    }
+
+    /// The current fragments of the template
+    pub fn templates(&self) -> &HashMap<String, String> {
+        &self.templates
+    }
+
+    /// Consume the split and return the fragments
+    pub fn into_templates(self) -> HashMap<String, String> {
+        self.templates
+    }
+
+    /// Replace the source and re-derive the affected fragments
+    ///
+    /// Returns the sorted names of all fragments that were added, removed or
+    /// modified. On errors, the split is left unchanged.
+    pub fn update(&mut self, src: impl Into<String>) -> Result<Vec<String>, ErrorWithLine> {
+        let src = src.into();
+
+        // a leading byte order mark is removed by preprocessing the source
+        let changed = if self.config.splits_incrementally() && !src.starts_with('\u{feff}') {
+            self.rebuild(&src)?
+        } else {
+            let templates = self.config.split_templates(&src)?;
+            let changed = changed_fragments(&self.templates, &templates);
+            self.templates = templates;
+            self.regions = None;
+            changed
+        };
+        self.src = src;
+
+        Ok(changed)
+    }
+
+    /// Replace the given byte range of the source and re-derive the affected
+    /// fragments
+    ///
+    /// Panics if the range is out of bounds or does not fall on char
+    /// boundaries, as [String::replace_range].
+    pub fn edit(
+        &mut self,
+        range: Range<usize>,
+        replacement: &str,
+    ) -> Result<Vec<String>, ErrorWithLine> {
+        let mut src = self.src.clone();
+        src.replace_range(range, replacement);
+        self.update(src)
+    }
+
+    /// Process the regions of the changed lines and rebuild the fragments
+    /// with output in the old or new regions
+    fn rebuild(&mut self, src: &str) -> Result<Vec<String>, ErrorWithLine> {
+        let new_lines: Vec<&str> = iterate_with_endings(src).collect();
+        let empty = Vec::new();
+        let (old_regions, old_lines) = match &self.regions {
+            Some(regions) => (regions, iterate_with_endings(&self.src).collect()),
+            None => (&empty, Vec::new()),
+        };
+
+        let changed = if self.regions.is_some() {
+            match changed_lines(&old_lines, &new_lines) {
+                Some(changed) => changed,
+                None => return Ok(Vec::new()),
+            }
+        } else {
+            0..new_lines.len()
+        };
+
+        // the end line of each old region
+        let ends: Vec<usize> = old_regions
+            .iter()
+            .scan(0, |end, region| {
+                *end += region.lines;
+                Some(*end)
+            })
+            .collect();
+        let first = ends.partition_point(|&end| end <= changed.start);
+        let first = first.min(old_regions.len().saturating_sub(1));
+        let start = if first == 0 { 0 } else { ends[first - 1] };
+
+        // the lines after the changed lines are shared with the old source,
+        // stop once the regions match again
+        let shift = |new_line: usize| (new_line + old_lines.len()).checked_sub(new_lines.len());
+        let mut processor = Processor::new(&self.config);
+        let mut regions = Vec::new();
+        let mut region = Region {
+            lines: 0,
+            output: HashMap::new(),
+        };
+        let mut last = old_regions.len();
+        for (line_idx, &line) in new_lines.iter().enumerate().skip(start) {
+            let tag = processor.parse(line).map_err(|err| err.at(line_idx))?;
+            let is_tag = tag.is_some();
+            processor.apply(line_idx, line, tag, |active, output| {
+                for &fragment in active {
+                    push_line(&mut region.output, fragment, output);
+                }
+            })?;
+            region.lines += 1;
+
+            if !(is_tag && processor.stack.is_top_level()) {
+                continue;
+            }
+            regions.push(std::mem::replace(
+                &mut region,
+                Region {
+                    lines: 0,
+                    output: HashMap::new(),
+                },
+            ));
+            if line_idx + 1 >= changed.end {
+                if let Some(idx) = shift(line_idx + 1).and_then(|end| ends.binary_search(&end).ok())
+                {
+                    last = idx + 1;
+                    break;
+                }
+            }
+        }
+        if last == old_regions.len() {
+            processor.done()?;
+            if region.lines > 0 {
+                regions.push(region);
+            }
+        }
+
+        let mut affected: HashSet<String> = HashSet::new();
+        if self.regions.is_none() {
+            affected.extend(self.templates.keys().cloned());
+        }
+        for region in old_regions[first..last].iter().chain(&regions) {
+            affected.extend(region.output.keys().cloned());
+        }
+        let mut all_regions = self.regions.take().unwrap_or_default();
+        all_regions.splice(first..last, regions);
+
+        let mut rebuilt: HashMap<String, String> = HashMap::new();
+        for region in &all_regions {
+            for (fragment, output) in &region.output {
+                if affected.contains(fragment) && !(self.config.skip_base && fragment.is_empty()) {
+                    push_line(&mut rebuilt, fragment, output);
+                }
+            }
+        }
+        self.config.finish_fragments(
+            src,
+            &LineMap::default(),
+            rebuilt.iter_mut().map(|(k, v)| (k.as_str(), v)),
+        );
+        self.regions = Some(all_regions);
+
+        let mut changed = Vec::new();
+        for fragment in affected {
+            let new = rebuilt.remove(&fragment).filter(|new| !new.is_empty());
+            if self.templates.get(&fragment) != new.as_ref() {
+                changed.push(fragment.clone());
+            }
+            match new {
+                Some(new) => self.templates.insert(fragment, new),
+                None => self.templates.remove(&fragment),
+            };
+        }
+        changed.sort();
+
+        Ok(changed)
+    }
+}
+
+/// The range of the changed lines in the new source, `None` if the sources
+/// are equal
+fn changed_lines(old: &[&str], new: &[&str]) -> Option<Range<usize>> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    if prefix == old.len() && prefix == new.len() {
+        return None;
+    }
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    Some(prefix..new.len() - suffix)
+}
+
+fn changed_fragments(old: &HashMap<String, String>, new: &HashMap<String, String>) -> Vec<String> {
+    let mut changed: Vec<String> = old
+        .keys()
+        .chain(new.keys())
+        .filter(|&fragment| old.get(fragment) != new.get(fragment))
+        .cloned()
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    changed.sort();
+    changed
+}
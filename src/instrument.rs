@@ -0,0 +1,87 @@
+use crate::Config;
+
+/// The attribute that names the fragment of an element
+const ATTRIBUTE: &str = "data-fragment";
+
+impl Config {
+    /// If `true`, add a `data-fragment="NAME"` attribute to the first HTML
+    /// element of each extracted fragment
+    ///
+    /// This way, browser devtools and end-to-end tests can identify which
+    /// fragment rendered a part of the page. HTML comments are skipped and
+    /// elements that already have the attribute are kept as they are. The base
+    /// template is not changed. Applies to [Config::filter_template],
+    /// [Config::split_templates], and [Config::parse_template].
+    ///
+    /// ```rust
+    /// # use template_fragments::Config;
+    /// let source = concat!(
+    ///     "<ul>\n",
+    ///     "  {% fragment item %}\n",
+    ///     "  <!-- one item -->\n",
+    ///     "  <li class=\"item\">{{ item }}</li>\n",
+    ///     "  {% endfragment %}\n",
+    ///     "</ul>\n",
+    /// );
+    ///
+    /// let config = Config::default().data_fragment_attribute(true);
+    /// assert_eq!(
+    ///     config.filter_template(source, "item").unwrap(),
+    ///     "  <!-- one item -->\n  <li data-fragment=\"item\" class=\"item\">{{ item }}</li>\n",
+    /// );
+    /// ```
+    pub fn data_fragment_attribute(mut self, value: bool) -> Self {
+        self.data_fragment_attribute = value;
+        self
+    }
+}
+
+/// Add the `data-fragment` attribute to the first element of the content
+pub(crate) fn add_data_fragment(fragment: &str, content: String) -> String {
+    let Some(pos) = first_element_name_end(&content) else {
+        return content;
+    };
+    let tag_end = content[pos..]
+        .find('>')
+        .map_or(content.len(), |idx| pos + idx);
+    if content[pos..tag_end].contains(ATTRIBUTE) {
+        return content;
+    }
+
+    let mut res = String::with_capacity(content.len() + ATTRIBUTE.len() + fragment.len() + 4);
+    res.push_str(&content[..pos]);
+    res.push_str(&format!(" {ATTRIBUTE}=\"{}\"", escape_attribute(fragment)));
+    res.push_str(&content[pos..]);
+    res
+}
+
+/// The position after the tag name of the first element, skipping comments,
+/// doctypes, and end tags
+fn first_element_name_end(src: &str) -> Option<usize> {
+    let mut pos = 0;
+    while let Some(idx) = src[pos..].find('<') {
+        let start = pos + idx;
+        let rest = &src[start + 1..];
+
+        if rest.starts_with("!--") {
+            pos = rest
+                .find("-->")
+                .map_or(src.len(), |end| start + 1 + end + 3);
+        } else if rest.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            let len = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == ':'))
+                .unwrap_or(rest.len());
+            return Some(start + 1 + len);
+        } else {
+            pos = start + 1;
+        }
+    }
+    None
+}
+
+fn escape_attribute(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+}
@@ -0,0 +1,86 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use crate::{Config, ErrorWithLine};
+
+/// A set of shared fragment names
+///
+/// When splitting many templates, the same fragment names occur in many
+/// results. With [split_templates_interned], equal names share a single
+/// allocation.
+///
+/// ```rust
+/// # use std::sync::Arc;
+/// # use template_fragments::{split_templates_interned, NameInterner};
+/// let mut names = NameInterner::new();
+/// let a = split_templates_interned("{% fragment item %}\na\n{% endfragment %}\n", &mut names).unwrap();
+/// let b = split_templates_interned("{% fragment item %}\nb\n{% endfragment %}\n", &mut names).unwrap();
+///
+/// let (a_name, _) = a.get_key_value("item").unwrap();
+/// let (b_name, _) = b.get_key_value("item").unwrap();
+/// assert!(Arc::ptr_eq(a_name, b_name));
+/// assert_eq!(names.len(), 2);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct NameInterner {
+    names: HashSet<Arc<str>>,
+}
+
+impl NameInterner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the shared instance of the name, adding it if required
+    pub fn intern(&mut self, name: &str) -> Arc<str> {
+        if let Some(name) = self.names.get(name) {
+            return Arc::clone(name);
+        }
+        let name: Arc<str> = Arc::from(name);
+        self.names.insert(Arc::clone(&name));
+        name
+    }
+
+    /// The number of distinct names
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Remove all names that are not used outside of the interner
+    pub fn shrink(&mut self) {
+        self.names.retain(|name| Arc::strong_count(name) > 1);
+    }
+}
+
+/// Split the template into all fragments, keyed by interned names
+///
+/// See [NameInterner] and [crate::split_templates].
+pub fn split_templates_interned(
+    src: &str,
+    names: &mut NameInterner,
+) -> Result<HashMap<Arc<str>, String>, ErrorWithLine> {
+    Config::default().split_templates_interned(src, names)
+}
+
+impl Config {
+    /// Split the template into all fragments, keyed by interned names
+    ///
+    /// See [crate::split_templates_interned].
+    pub fn split_templates_interned(
+        &self,
+        src: &str,
+        names: &mut NameInterner,
+    ) -> Result<HashMap<Arc<str>, String>, ErrorWithLine> {
+        Ok(self
+            .split_templates(src)?
+            .into_iter()
+            .map(|(fragment, content)| (names.intern(&fragment), content))
+            .collect())
+    }
+}
@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+
+use crate::{join_path, split_templates, Config, ErrorWithPath};
+
+/// Split the templates of multiple layers and merge their fragments
+///
+/// Each layer is a collection of `(path, source)` pairs, e.g., a base theme
+/// followed by its overrides. The result is keyed by `join_path(path,
+/// fragment)`. A fragment defined in a later layer replaces the fragment with
+/// the same path and name of earlier layers. All other fragments are kept.
+/// The base template of each path is taken from the first layer that defines
+/// the path, later layers only override its fragments.
+///
+/// ```rust
+/// # use template_fragments::merge_layers;
+/// let base = [(
+///     "index.html",
+///     concat!(
+///         "<body>\n",
+///         "  {% fragment item %}\n",
+///         "    <div>{{ item }}</div>\n",
+///         "  {% endfragment %}\n",
+///         "<body>\n",
+///     ),
+/// )];
+/// let theme = [(
+///     "index.html",
+///     concat!(
+///         "{% fragment item %}\n",
+///         "<div class=\"themed\">{{ item }}</div>\n",
+///         "{% endfragment %}\n",
+///     ),
+/// )];
+///
+/// let templates = merge_layers([base, theme]).unwrap();
+/// assert_eq!(templates["index.html#item"], "<div class=\"themed\">{{ item }}</div>\n");
+/// assert_eq!(templates["index.html"], "<body>\n    <div>{{ item }}</div>\n<body>\n");
+/// ```
+pub fn merge_layers<L, P, S>(
+    layers: impl IntoIterator<Item = L>,
+) -> Result<HashMap<String, String>, ErrorWithPath>
+where
+    L: IntoIterator<Item = (P, S)>,
+    P: AsRef<str>,
+    S: AsRef<str>,
+{
+    let mut res = HashMap::new();
+    for layer in layers {
+        for (path, src) in layer {
+            let path = path.as_ref();
+            let templates = split_templates(src.as_ref()).map_err(|err| err.in_path(path))?;
+
+            for (fragment, content) in templates {
+                let key = join_path(path, &fragment);
+                if fragment.is_empty() {
+                    res.entry(key).or_insert(content);
+                } else {
+                    res.insert(key, content);
+                }
+            }
+        }
+    }
+    Ok(res)
+}
+
+/// Split multiple templates into a single map of fragments
+///
+/// The result is keyed by `join_path(path, fragment)`. Errors are reported
+/// with the path of the template. See [Config::split_many].
+///
+/// ```rust
+/// # use template_fragments::split_many;
+/// let templates = split_many([
+///     ("index.html", "<body>\n{% fragment item %}\n<item>\n{% endfragment %}\n</body>\n"),
+///     ("other.html", "<other>\n"),
+/// ])
+/// .unwrap();
+///
+/// assert_eq!(templates["index.html#item"], "<item>\n");
+/// assert_eq!(templates["other.html"], "<other>\n");
+///
+/// let err = split_many([("broken.html", "{% endfragment %}\n")]).unwrap_err();
+/// assert_eq!(err.0, "broken.html");
+/// ```
+pub fn split_many<P, S>(
+    templates: impl IntoIterator<Item = (P, S)>,
+) -> Result<HashMap<String, String>, ErrorWithPath>
+where
+    P: AsRef<str>,
+    S: AsRef<str>,
+{
+    Config::default().split_many(templates)
+}
+
+impl Config {
+    /// Split multiple templates into a single map of fragments
+    ///
+    /// See [crate::split_many].
+    pub fn split_many<P, S>(
+        &self,
+        templates: impl IntoIterator<Item = (P, S)>,
+    ) -> Result<HashMap<String, String>, ErrorWithPath>
+    where
+        P: AsRef<str>,
+        S: AsRef<str>,
+    {
+        let mut res = HashMap::new();
+        for (path, src) in templates {
+            let path = path.as_ref();
+            let templates = self
+                .split_templates(src.as_ref())
+                .map_err(|err| err.in_path(path))?;
+
+            for (fragment, content) in templates {
+                res.insert(join_path(path, &fragment), content);
+            }
+        }
+        Ok(res)
+    }
+}
@@ -0,0 +1,148 @@
+use std::{collections::HashMap, ops::Range};
+
+use crate::{
+    decode::leading_bom, iterate_with_endings, push_line, Config, ErrorWithLine, Processor,
+};
+
+/// How a template is assembled from its fragments
+///
+/// The layout records the fragment tags of a template verbatim and references
+/// all other lines by their position in the innermost fragment containing
+/// them. Together with the fragments, it allows reconstructing the annotated
+/// template byte-for-byte. This way, fragments can be stored separately while
+/// the annotated template can still be regenerated.
+///
+/// ```rust
+/// # use template_fragments::split_templates_with_layout;
+/// let source = concat!(
+///     "<body>\n",
+///     "  {% fragment item %}\n",
+///     "    <div>{{ item }}</div>\n",
+///     "  {% endfragment %}\n",
+///     "<body>\n",
+/// );
+///
+/// let (templates, layout) = split_templates_with_layout(source).unwrap();
+/// assert_eq!(templates["item"], "    <div>{{ item }}</div>\n");
+/// assert_eq!(layout.reassemble(&templates).unwrap(), source);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Layout {
+    /// The parts of the template in source order
+    pub parts: Vec<LayoutPart>,
+}
+
+/// A part of a [Layout]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LayoutPart {
+    /// A line with a fragment tag, including its line ending, or the leading
+    /// byte order mark of the template
+    Tag(String),
+    /// Consecutive lines, given by their byte range in the content of a
+    /// fragment
+    Text {
+        /// The name of the fragment
+        fragment: String,
+        /// The byte range in the content of the fragment
+        range: Range<usize>,
+    },
+}
+
+impl Layout {
+    /// Reconstruct the annotated template from its fragments
+    ///
+    /// Returns `None`, if a referenced fragment is missing or too short, e.g.,
+    /// because the fragments do not belong to this layout.
+    pub fn reassemble(&self, templates: &HashMap<String, String>) -> Option<String> {
+        let mut res = String::new();
+        for part in &self.parts {
+            match part {
+                LayoutPart::Tag(line) => res.push_str(line),
+                LayoutPart::Text { fragment, range } => {
+                    res.push_str(templates.get(fragment)?.get(range.clone())?)
+                }
+            }
+        }
+        Some(res)
+    }
+}
+
+/// Split the template into all fragments and record its layout
+///
+/// See [Layout] and [Config::split_templates_with_layout].
+pub fn split_templates_with_layout(
+    src: &str,
+) -> Result<(HashMap<String, String>, Layout), ErrorWithLine> {
+    Config::default().split_templates_with_layout(src)
+}
+
+impl Config {
+    /// Split the template into all fragments and record its layout
+    ///
+    /// The fragments are the same as returned by [Config::split_templates].
+    /// See [Layout].
+    pub fn split_templates_with_layout(
+        &self,
+        src: &str,
+    ) -> Result<(HashMap<String, String>, Layout), ErrorWithLine> {
+        // the byte order mark is removed by preprocessing and is not part of
+        // any fragment
+        let bom = leading_bom(src);
+        let (src, lines) = self.preprocess(src)?;
+        let mut processor = Processor::new(self);
+        let mut templates: HashMap<String, String> = HashMap::new();
+        let mut layout = Layout::default();
+        if let Some(bom) = bom {
+            layout.parts.push(LayoutPart::Tag(bom.to_owned()));
+        }
+
+        for (line_idx, line) in iterate_with_endings(&src).enumerate() {
+            let tag = processor
+                .parse(line)
+                .map_err(|err| lines.restore(err.at(line_idx)))?;
+            let is_tag = tag.is_some();
+
+            // text lines are owned by the innermost fragment, multiple names
+            // contain the same content, use the smallest (first) for determinism
+            let owner = match processor.stack.innermost() {
+                Some(fragments) if !is_tag => fragments.first().copied().unwrap_or(""),
+                _ => "",
+            };
+            let start = templates.get(owner).map(String::len).unwrap_or(0);
+
+            processor
+                .apply(line_idx, line, tag, |active, line| {
+                    for fragment in active {
+                        push_line(&mut templates, fragment, line);
+                    }
+                })
+                .map_err(|err| lines.restore(err))?;
+
+            if is_tag {
+                layout.parts.push(LayoutPart::Tag(line.to_owned()));
+            } else {
+                push_text(&mut layout, owner, start..start + line.len());
+            }
+        }
+        processor.done().map_err(|err| lines.restore(err))?;
+
+        Ok((templates, layout))
+    }
+}
+
+fn push_text(layout: &mut Layout, owner: &str, range: Range<usize>) {
+    if let Some(LayoutPart::Text {
+        fragment,
+        range: prev,
+    }) = layout.parts.last_mut()
+    {
+        if fragment == owner && prev.end == range.start {
+            prev.end = range.end;
+            return;
+        }
+    }
+    layout.parts.push(LayoutPart::Text {
+        fragment: owner.to_owned(),
+        range,
+    });
+}
@@ -32,6 +32,87 @@
 //!   is designed to be used when to extract all templates once at application
 //!   startup
 //!
+//! Both functions use the default configuration. Options, e.g., to forbid
+//! repeated fragments or to match names case-insensitively, are available via
+//! [Config]. Engines with a different tag syntax, e.g., ERB, Go, Liquid, or
+//! handlebars templates, are supported via [Syntax], Markdown documents with
+//! `<!-- fragment: NAME -->` markers via [Config::markdown], and LaTeX
+//! templates with `((* ... *))` or `\BLOCK{...}` markers via [Config::latex]
+//! and [Syntax::latex_commands]. [Config::sql] accepts fragment tags in SQL
+//! line comments, e.g., of dbt models. How fragment blocks are emitted is
+//! controlled by [BlockStyle], [OutputProfile] selects the output options for
+//! MiniJinja, Jinja2, Tera, or Django at once.
+//! [Config::django_render_block] emits every fragment as a block, so that
+//! django-render-block renders the same fragments.
+//!
+//! [split_many] splits multiple templates into a single map keyed by
+//! [join_path]. [split_directory] splits all templates of a directory tree. It
+//! selects the markers for each template based on its file extension with
+//! [Config::for_path], e.g., to process LaTeX and HTML templates in one call.
+//!
+//! For servers that filter templates dynamically per request, [Template]
+//! processes a template once for repeated filtering and [FragmentCache]
+//! caches the results of [filter_template]. Plain templates without any
+//! fragment tags can be detected cheaply with [has_fragments]. For dev servers
+//! that re-split templates on every edit, [IncrementalSplit] only rebuilds the
+//! fragments affected by a change. [diff_templates] reports which fragments
+//! differ between two versions of a template. [fragment_stats] reports the size
+//! and structure of each fragment, e.g., to keep partial responses within size
+//! budgets. [fragment_variables] reports the context variables each fragment
+//! references.
+//!
+//! Templates that include other templates can be expanded with
+//! [resolve_includes] before splitting. Similarly, [resolve_fragment_uses]
+//! expands `{% fragment-use NAME from "PATH" %}` tags with fragments of other
+//! templates. Themable applications can override fragments of a base theme
+//! with [merge_layers]. [FragmentRoutes] maps fragments to the URLs they are
+//! served under, e.g., for HTMX endpoints. Custom rewrites of the output, e.g.,
+//! of asset URLs, can be applied with [split_templates_transformed]. A
+//! [FragmentSink] receives the output of all fragments as it is produced,
+//! e.g., to stream it into files, and [split_templates_with] hands each
+//! fragment to a callback instead of returning a map. To reduce the size of
+//! fragments sent over the wire, [Config::minify_html] collapses insignificant
+//! whitespace. Projects can define their own directives with
+//! [Config::custom_tag]. Debug-only markup can be stripped with `ifdef`
+//! directives evaluated against [Config::defines]. During development,
+//! [Config::debug_annotations] traces the output back to the template and
+//! [Config::data_fragment_attribute] marks the elements of each fragment.
+//!
+//! Tooling that inspects or transforms templates can use the parsed structure
+//! of [TemplateAst] and walk it with a [Visitor]. To store fragments
+//! separately, [split_templates_with_layout] records a [Layout] that
+//! reassembles the annotated template from its fragments. Conversely,
+//! [merge_fragments] composes an annotated template from a skeleton and the
+//! content of its fragments. [format_template] normalizes the fragment tags of
+//! a template and [lint_template] reports suspicious, but valid constructs.
+//! [parse_tag] exposes the parsing rules of single tags with their spans, e.g.,
+//! for syntax highlighters. [Error::help] suggests how to fix an error, e.g.,
+//! for command line tools or editor integrations, [ErrorWithLine::with_span]
+//! locates it by byte offsets, and [Config::render_error] shows it with the
+//! offending line for template authors. [validate_template] reports errors and
+//! warnings as [Diagnostic]s with stable codes and a JSON form for CI systems.
+//! Templates annotated with another syntax, e.g., `<!-- fragment: item -->`,
+//! can be migrated with [convert_template]. With the `test-util` feature,
+//! `assert_fragments!` checks the fragments of templates in tests. The `poem`
+//! feature adds helpers to serve fragments with the poem web framework, see
+//! `template_fragments::poem`. With the `mmap` feature, `split_template_file`
+//! processes large template files without reading them into memory first. The
+//! `tokio` feature adds async variants to load templates, e.g.,
+//! `split_directory_async`. The `minijinja` feature adds a loader that filters
+//! fragments on demand, see `template_fragments::minijinja`, and
+//! `Config::verify` to compile each fragment after splitting. With the `notify`
+//! feature, `TemplateWatcher` keeps a [TemplateSet] of a directory up to date
+//! when its files change. The `hash` feature adds stable content hashes of
+//! fragments for HTTP caching, see `split_templates_hashed`. The `compress`
+//! feature pre-compresses fragments with gzip and brotli, see
+//! `split_templates_compressed`. The `lsp` feature adds a language server with
+//! diagnostics, symbols, and renames of fragments, see
+//! `template_fragments::lsp`. With the `tracing` feature, splitting and
+//! filtering templates emits debug spans and events with sizes, fragment
+//! counts, and durations. The `template-fragments-macros` crate splits
+//! templates at compile time, e.g., with `#[derive(TemplateFragments)]`, or
+//! embeds a whole directory as [EmbeddedTemplates].
+//!
 //! # Syntax
 //!
 //! - Fragments start with `{% fragment NAMES... %}` or `{% fragment-block NAMES
@@ -40,56 +121,180 @@
 //!   blocks: they are rendered as a block, if the fragment is included. This is
 //!   equivalent to wrapping a block with a fragment of the same name.
 //! - Fragments end with `{% endfragment %}` or `{% endfragment-block %}`
-//! - Fragments can occur multiple times in the document
+//! - Fragments can occur multiple times in the document. Their occurrences are
+//!   concatenated or, with [Config::number_repeated_fragments], split into
+//!   `NAME`, `NAME.1`, ...
 //! - Multiple fragments can be started in a single tag by using multiple
 //!   whitespace separated names in the start tag
 //! - Fragment tags must be contained in a single line and there must not be any
 //!   other non-whitespace content on the same line
 //! - Fragment names can contain any alphanumeric character and `'-'`, `'_'`.
+//!   User-provided names can be validated with [FragmentName]
+//! - Start tags can carry `key=value` attributes, e.g., `{% fragment item
+//!   cache=60 %}`. They apply to all fragments started by the tag and are
+//!   available via [split_templates_detailed]
+//! - The `with` attribute provides defaults for variables of fragments that are
+//!   rendered standalone, e.g., for loop variables: with `{% fragment item
+//!   with=item=none %}` the extracted fragment is wrapped in `{% with item=none
+//!   %}` and `{% endwith %}`. Alternatively, [Config::enclosing_control_tags]
+//!   repeats the enclosing `{% for %}` and `{% if %}` tags around the fragment,
+//!   [Config::enclosing_with_tags] the enclosing `{% with %}` tags
+//! - Fragments can be documented with `{# doc: ... #}` comments directly
+//!   before their start tag
+//! - Content between `{% raw %}` and `{% endraw %}` or Django's `{%
+//!   verbatim %}` and `{% endverbatim %}` is not scanned for fragment tags
+//! - With [Config::blocks_as_fragments], existing `{% block NAME %}` sections
+//!   are also fragment blocks, as in jinja2-fragments or django-render-block.
+//!   Similarly, [Config::macros_as_fragments] exposes the bodies of `{% macro
+//!   %}` definitions as fragments
 //!
 //! # Example using `minijinja`
 //!
-//! One way to use fragment tags with  `minijinja` is to build a template source
-//! with the split templates at application start up like this
+//! One way to use fragment tags with  `minijinja` is to add the split templates
+//! to the environment at application start up like this
 //!
 //! ```
-//! # mod minijinja {
-//! #   pub struct Source;
-//! #   impl Source {
-//! #     pub fn new() -> Self { Source }
-//! #     pub fn add_template(
-//! #       &mut self,
-//! #       path: String,
-//! #       fragment: &str,
-//! #     ) -> Result<(), template_fragments::ErrorWithLine> {
-//! #       Ok(())
-//! #     }
-//! #   }
-//! # }
-//! # fn main() -> Result<(), template_fragments::ErrorWithLine> {
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
 //! use template_fragments::{split_templates, join_path};
 //!
-//! let mut source = minijinja::Source::new();
+//! let mut env = minijinja::Environment::new();
 //!
 //! for (path, template) in [("index.html", include_str!("../examples/templates/index.html"))] {
 //!     for (fragment_name, template_fragment) in split_templates(template)? {
-//!         source.add_template(join_path(path, &fragment_name), &template_fragment)?;
+//!         env.add_template_owned(join_path(path, &fragment_name), template_fragment)?;
 //!     }
 //! }
 //! # Ok(())
 //! # }
 //! ```
 //!
+//! With the `minijinja` feature, `template_fragments::minijinja` provides
+//! helpers for this.
+//!
 //! Note the different fragments can be rendered by requesting the relevant
 //! template, e.g., `env.get_template("index.html")` or
 //! `env.get_template("index.html#fragment")`.
 //!
-use std::collections::{HashMap, HashSet};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    ops::Range,
+};
+
+use memchr::{memchr, memchr_iter, memmem, memrchr};
+
+use verbatim::Verbatim;
+
+mod annotate;
+mod ast;
+#[cfg(feature = "tokio")]
+mod asynchronous;
+mod blocks;
+mod cache;
+mod comments;
+mod compose;
+#[cfg(feature = "compress")]
+mod compress;
+mod config;
+mod context;
+mod convert;
+mod custom;
+mod decode;
+mod defines;
+mod details;
+mod diff;
+mod directory;
+mod embedded;
+mod exists;
+#[cfg(feature = "mmap")]
+mod file;
+mod format;
+#[cfg(feature = "hash")]
+mod hash;
+mod help;
+mod include;
+mod incremental;
+mod instrument;
+mod intern;
+mod layers;
+mod layout;
+mod lint;
+#[cfg(feature = "lsp")]
+pub mod lsp;
+mod minify;
+#[cfg(feature = "minijinja")]
+pub mod minijinja;
+mod name;
+mod numbered;
+mod output;
+#[cfg(feature = "poem")]
+pub mod poem;
+mod routes;
+mod set;
+mod sink;
+mod stats;
+mod syntax;
+mod tag;
+mod template;
+#[cfg(feature = "test-util")]
+mod testing;
+mod trace;
+mod transform;
+mod validate;
+mod variables;
+mod verbatim;
+#[cfg(feature = "notify")]
+mod watch;
 
 #[cfg(test)]
 mod test;
 
+pub use ast::{AstNode, FragmentNode, TemplateAst, TextNode, Visitor};
+#[cfg(feature = "tokio")]
+pub use asynchronous::{split_directory_async, split_templates_async};
+pub use cache::FragmentCache;
+pub use compose::merge_fragments;
+#[cfg(feature = "compress")]
+pub use compress::{split_templates_compressed, CompressedFragment};
+pub use config::{Config, NameCase};
+pub use convert::convert_template;
+pub use custom::CustomTag;
+pub use decode::{decode_template, split_templates_lossy};
+pub use details::{split_templates_detailed, Fragment, FragmentPart};
+pub use diff::{diff_templates, TemplateDiff};
+pub use directory::{split_directory, DirectoryError};
+pub use embedded::EmbeddedTemplates;
+pub use exists::{fragment_exists, has_fragments};
+#[cfg(feature = "mmap")]
+pub use file::split_template_file;
+pub use format::format_template;
+#[cfg(feature = "hash")]
+pub use hash::{split_templates_hashed, HashedFragment};
+pub use include::{resolve_fragment_uses, resolve_includes};
+pub use incremental::IncrementalSplit;
+pub use intern::{split_templates_interned, NameInterner};
+pub use layers::{merge_layers, split_many};
+pub use layout::{split_templates_with_layout, Layout, LayoutPart};
+pub use lint::{lint_template, LintKind, LintWarning};
+pub use name::FragmentName;
+pub use output::{BlockStyle, OutputProfile};
+pub use routes::FragmentRoutes;
+pub use set::{TemplateSet, TemplateSetError};
+pub use sink::FragmentSink;
+pub use stats::{fragment_stats, FragmentStats};
+pub use syntax::Syntax;
+pub use tag::{parse_tag, ParsedTag, TagKind, TagPart};
+pub use template::Template;
+#[cfg(feature = "test-util")]
+pub use testing::check_fragments;
+pub use transform::split_templates_transformed;
+pub use validate::{validate_template, Diagnostic, Severity};
+pub use variables::fragment_variables;
+#[cfg(feature = "notify")]
+pub use watch::{TemplateWatcher, WatchError};
+
 const DEFAULT_TAG_MARKERS: (&str, &str) = ("{%", "%}");
+const DEFAULT_COMMENT_MARKERS: (&str, &str) = ("{#", "#}");
 
 /// Split a template path with optional fragment into the path and fragment
 ///
@@ -166,49 +371,28 @@ pub fn join_path(path: &str, fragment: &str) -> String {
 /// ```
 ///
 pub fn filter_template(src: &str, fragment: &str) -> Result<String, ErrorWithLine> {
-    let mut stack: FragmentStack<'_> = Default::default();
-    let mut res = String::new();
-    let mut last_line_idx = 0;
-
-    for (line_idx, line) in iterate_with_endings(src).enumerate() {
-        last_line_idx = line_idx;
-
-        match parse_fragment_tag(line, DEFAULT_TAG_MARKERS).map_err(|err| err.at(line_idx))? {
-            Some(Tag::Start(tag)) => stack.push(tag.fragments).map_err(|err| err.at(line_idx))?,
-            Some(Tag::End(_)) => {
-                stack.pop().map_err(|err| err.at(line_idx))?;
-            }
-            Some(Tag::StartBlock(tag)) => {
-                stack
-                    .push(HashSet::from([tag.fragment]))
-                    .map_err(|err| err.at(line_idx))?;
-                let line = format!(
-                    "{}{{% block {} %}}{}",
-                    tag.prefix,
-                    tag.fragment,
-                    get_ending(line)
-                );
-                if stack.is_active(fragment) {
-                    res.push_str(&line);
-                }
-            }
-            Some(Tag::EndBlock(tag)) => {
-                let active = stack.pop().map_err(|err| err.at(line_idx))?;
-                let line = format!("{}{{% endblock %}}{}", tag.prefix, get_ending(line));
-                if active.contains(fragment) {
-                    res.push_str(&line);
-                }
-            }
-            None => {
-                if stack.is_active(fragment) {
-                    res.push_str(line);
-                }
-            }
-        }
-    }
-    stack.done().map_err(|err| err.at(last_line_idx))?;
+    Config::default().filter_template(src, fragment)
+}
 
-    Ok(res)
+/// Process the template and return all parts for the given fragment,
+/// borrowing from the source if it does not contain fragment tags
+///
+/// The result is the same as for [filter_template]. Templates without fragment
+/// tags are common, in this case no copy is made.
+///
+/// ```rust
+/// # use std::borrow::Cow;
+/// # use template_fragments::filter_template_cow;
+/// let source = "<body>\n  {{ content }}\n</body>\n";
+///
+/// assert!(matches!(filter_template_cow(source, ""), Ok(Cow::Borrowed(res)) if res == source));
+/// assert!(matches!(filter_template_cow(source, "item"), Ok(Cow::Borrowed(""))));
+/// ```
+pub fn filter_template_cow<'s>(
+    src: &'s str,
+    fragment: &str,
+) -> Result<Cow<'s, str>, ErrorWithLine> {
+    Config::default().filter_template_cow(src, fragment)
 }
 
 /// Split the template into all fragments available
@@ -241,50 +425,420 @@ pub fn filter_template(src: &str, fragment: &str) -> Result<String, ErrorWithLin
 /// );
 /// ```
 pub fn split_templates(src: &str) -> Result<HashMap<String, String>, ErrorWithLine> {
-    let mut stack: FragmentStack<'_> = Default::default();
-    let mut res: HashMap<String, String> = Default::default();
-    let mut last_line_idx = 0;
-
-    for (line_idx, line) in iterate_with_endings(src).enumerate() {
-        last_line_idx = line_idx;
-
-        match parse_fragment_tag(line, DEFAULT_TAG_MARKERS).map_err(|err| err.at(line_idx))? {
-            Some(Tag::Start(tag)) => stack.push(tag.fragments).map_err(|err| err.at(line_idx))?,
-            Some(Tag::End(_)) => {
-                stack.pop().map_err(|err| err.at(line_idx))?;
-            }
-            Some(Tag::StartBlock(tag)) => {
-                stack
-                    .push(HashSet::from([tag.fragment]))
-                    .map_err(|err| err.at(line_idx))?;
-                let line = format!(
-                    "{}{{% block {} %}}{}",
-                    tag.prefix,
-                    tag.fragment,
-                    get_ending(line)
-                );
-                for fragment in &stack.active_fragments {
-                    push_line(&mut res, fragment, &line);
+    Config::default().split_templates(src)
+}
+
+/// Split the template into all fragments, keyed by their full path
+///
+/// The keys are constructed with [join_path], the markers are selected with
+/// [Config::for_path].
+///
+/// ```rust
+/// # use template_fragments::split_templates_prefixed;
+/// let source = concat!(
+///     "<body>\n",
+///     "  {% fragment item %}\n",
+///     "    <div>{{ item }}</div>\n",
+///     "  {% endfragment %}\n",
+///     "<body>\n",
+/// );
+/// let templates = split_templates_prefixed("index.html", source).unwrap();
+///
+/// assert_eq!(templates["index.html"], "<body>\n    <div>{{ item }}</div>\n<body>\n");
+/// assert_eq!(templates["index.html#item"], "    <div>{{ item }}</div>\n");
+/// ```
+pub fn split_templates_prefixed(
+    path: &str,
+    src: &str,
+) -> Result<HashMap<String, String>, ErrorWithLine> {
+    Config::for_path(path).split_templates_prefixed(path, src)
+}
+
+/// Split the template into an existing map, reusing its buffers
+///
+/// The result is the same as for [split_templates], but the capacities of the
+/// existing strings are reused. This avoids reallocating all buffers, e.g.,
+/// when templates are split again on every reload. Fragments that do not occur
+/// in the template are removed. If an error is returned, the content of the
+/// map is unspecified.
+///
+/// ```rust
+/// # use std::collections::HashMap;
+/// # use template_fragments::split_templates_into;
+/// let mut templates = HashMap::new();
+///
+/// split_templates_into("{% fragment a %}\nfirst\n{% endfragment %}\n", &mut templates).unwrap();
+/// assert_eq!(templates["a"], "first\n");
+///
+/// split_templates_into("{% fragment b %}\nsecond\n{% endfragment %}\n", &mut templates).unwrap();
+/// assert_eq!(templates["b"], "second\n");
+/// assert!(!templates.contains_key("a"));
+/// ```
+pub fn split_templates_into(
+    src: &str,
+    templates: &mut HashMap<String, String>,
+) -> Result<(), ErrorWithLine> {
+    Config::default().split_templates_into(src, templates)
+}
+
+/// Split the template and pass each fragment to a callback
+///
+/// The callback is called once for each fragment with its name and content,
+/// after the whole template was processed, in the order of the names. The
+/// content is moved into the callback, e.g., to hand it off to a template
+/// engine without copying it. In contrast to [split_templates], the names are
+/// not allocated and no map is returned. If the template is invalid, the
+/// callback is not called.
+///
+/// ```rust
+/// # use template_fragments::split_templates_with;
+/// let source = concat!(
+///     "<body>\n",
+///     "  {% fragment item %}\n",
+///     "    <div>{{ item }}</div>\n",
+///     "  {% endfragment %}\n",
+///     "<body>\n",
+/// );
+///
+/// let mut env = minijinja::Environment::new();
+/// split_templates_with(source, |fragment, content| {
+///     let name = template_fragments::join_path("index.html", fragment);
+///     env.add_template_owned(name, content).unwrap();
+/// })
+/// .unwrap();
+///
+/// let template = env.get_template("index.html#item").unwrap();
+/// assert_eq!(template.render(minijinja::context! { item => 42 }).unwrap(), "    <div>42</div>");
+/// ```
+pub fn split_templates_with(src: &str, f: impl FnMut(&str, String)) -> Result<(), ErrorWithLine> {
+    Config::default().split_templates_with(src, f)
+}
+
+/// Process all lines of the template and call `emit` for the output with the
+/// fragments it belongs to
+///
+/// Each call contains one or more complete lines. Lines before the next tag
+/// open marker cannot be tags and are passed on without parsing them.
+fn process_template<'a>(
+    config: &Config,
+    src: &'a str,
+    mut emit: impl FnMut(&HashSet<&'a str>, &str),
+) -> Result<(), ErrorWithLine> {
+    let mut processor = Processor::new(config).separate_occurrences();
+    let finder = memmem::Finder::new(config.syntax.tag_markers().0);
+
+    let mut line_idx = 0;
+    let mut rest = src;
+    while !rest.is_empty() {
+        let plain_end = match finder.find(rest.as_bytes()) {
+            Some(pos) => memrchr(b'\n', &rest.as_bytes()[..pos]).map_or(0, |idx| idx + 1),
+            None => rest.len(),
+        };
+        if plain_end > 0 {
+            let (plain, tail) = rest.split_at(plain_end);
+            line_idx += processor.apply_plain(line_idx, plain, &mut emit);
+            rest = tail;
+            continue;
+        }
+
+        let line_end = memchr(b'\n', rest.as_bytes()).map_or(rest.len(), |idx| idx + 1);
+        let (line, tail) = rest.split_at(line_end);
+        let tag = processor.parse(line).map_err(|err| err.at(line_idx))?;
+        processor.apply(line_idx, line, tag, &mut emit)?;
+        line_idx += 1;
+        rest = tail;
+    }
+    processor.done()
+}
+
+/// The state of processing a template with a given config
+#[derive(Debug)]
+struct Processor<'c, 'a> {
+    config: &'c Config,
+    stack: FragmentStack<'a>,
+    first_seen: HashMap<&'a str, usize>,
+    last_line_idx: usize,
+    separator: Option<Separator<'c, 'a>>,
+}
+
+/// The state of inserting separators between the occurrences of fragments
+#[derive(Debug)]
+struct Separator<'c, 'a> {
+    separator: &'c str,
+    /// Fragments with a new occurrence that did not emit any output yet
+    pending: HashSet<&'a str>,
+    /// Fragments that emitted any output
+    emitted: HashSet<&'a str>,
+}
+
+impl<'a> Separator<'_, 'a> {
+    /// Record the start of fragments that may have occurred before
+    fn start(&mut self, fragments: impl IntoIterator<Item = &'a str>) {
+        for fragment in fragments {
+            if self.emitted.contains(fragment) {
+                self.pending.insert(fragment);
+            }
+        }
+    }
+
+    /// Emit the output, preceded by the separator for new occurrences
+    fn emit(
+        &mut self,
+        active: &HashSet<&'a str>,
+        lines: &str,
+        emit: &mut impl FnMut(&HashSet<&'a str>, &str),
+    ) {
+        if !self.pending.is_empty() {
+            for &fragment in active {
+                if self.pending.remove(fragment) {
+                    emit(&HashSet::from([fragment]), self.separator);
                 }
             }
-            Some(Tag::EndBlock(tag)) => {
-                let fragments = stack.pop().map_err(|err| err.at(line_idx))?;
-                let line = format!("{}{{% endblock %}}{}", tag.prefix, get_ending(line));
+        }
+        self.emitted.extend(active.iter().copied());
+        emit(active, lines);
+    }
+}
 
-                for fragment in fragments {
-                    push_line(&mut res, fragment, &line);
+impl<'c, 'a> Processor<'c, 'a> {
+    fn new(config: &'c Config) -> Self {
+        Self {
+            config,
+            stack: Default::default(),
+            first_seen: Default::default(),
+            last_line_idx: 0,
+            separator: None,
+        }
+    }
+
+    /// Insert the separator of the config between the occurrences of repeated
+    /// fragments
+    fn separate_occurrences(mut self) -> Self {
+        if !self.config.number_repeated_fragments {
+            self.separator = self
+                .config
+                .fragment_separator
+                .as_deref()
+                .map(|separator| Separator {
+                    separator,
+                    pending: HashSet::new(),
+                    emitted: HashSet::new(),
+                });
+        }
+        self
+    }
+
+    fn parse(&mut self, line: &'a str) -> Result<Option<Tag<'a>>, Error> {
+        let tag_markers = self.config.syntax.tag_markers();
+        if self.stack.verbatim.skip(line, tag_markers) {
+            return Ok(None);
+        }
+        parse_fragment_tag(line, tag_markers)
+    }
+
+    fn apply(
+        &mut self,
+        line_idx: usize,
+        line: &'a str,
+        tag: Option<Tag<'a>>,
+        mut emit: impl FnMut(&HashSet<&'a str>, &str),
+    ) -> Result<(), ErrorWithLine> {
+        self.last_line_idx = line_idx;
+
+        self.check_start(line_idx, &tag)
+            .map_err(|err| err.at(line_idx))?;
+        match &mut self.separator {
+            Some(separator) => {
+                match &tag {
+                    Some(Tag::Start(tag)) => separator.start(tag.fragments.iter().copied()),
+                    Some(Tag::StartBlock(tag)) => separator.start([tag.fragment]),
+                    _ => {}
                 }
+                apply_tag(self.config, &mut self.stack, line, tag, |active, line| {
+                    separator.emit(active, line, &mut emit)
+                })
             }
-            None => {
-                for fragment in &stack.active_fragments {
-                    push_line(&mut res, fragment, line);
+            None => apply_tag(self.config, &mut self.stack, line, tag, emit),
+        }
+        .map_err(|err| err.at(line_idx))?;
+
+        if let Some(max_depth) = self.config.max_depth {
+            if self.stack.depth() > max_depth {
+                return Err(Error::NestingTooDeep(max_depth).at(line_idx));
+            }
+        }
+        Ok(())
+    }
+
+    /// Emit complete lines that do not contain any tags and return the number
+    /// of lines
+    fn apply_plain(
+        &mut self,
+        line_idx: usize,
+        lines: &str,
+        mut emit: impl FnMut(&HashSet<&'a str>, &str),
+    ) -> usize {
+        let mut num_lines = memchr_iter(b'\n', lines.as_bytes()).count();
+        if !lines.ends_with('\n') {
+            num_lines += 1;
+        }
+        self.last_line_idx = line_idx + num_lines - 1;
+
+        match &mut self.separator {
+            Some(separator) => separator.emit(&self.stack.active_fragments, lines, &mut emit),
+            None => emit(&self.stack.active_fragments, lines),
+        }
+        num_lines
+    }
+
+    fn done(self) -> Result<(), ErrorWithLine> {
+        self.stack.done().map_err(|err| err.at(self.last_line_idx))
+    }
+
+    /// Check the fragments of start tags and record their first occurrence
+    fn check_start(&mut self, line_idx: usize, tag: &Option<Tag<'a>>) -> Result<(), Error> {
+        let fragments: Vec<&'a str> = match tag {
+            Some(Tag::Start(tag)) => tag.fragments.iter().copied().collect(),
+            Some(Tag::StartBlock(tag)) => vec![tag.fragment],
+            _ => return Ok(()),
+        };
+
+        let mut repeated: Vec<&'a str> = Vec::new();
+        for &fragment in &fragments {
+            if self.first_seen.contains_key(fragment) {
+                repeated.push(fragment);
+            }
+        }
+        if self.config.unique_fragments {
+            if let Some(&fragment) = repeated.iter().min() {
+                return Err(Error::RepeatedFragment(
+                    fragment.to_owned(),
+                    self.first_seen[fragment],
+                ));
+            }
+        }
+
+        for fragment in fragments {
+            self.first_seen.entry(fragment).or_insert(line_idx);
+        }
+
+        if let Some(max_fragments) = self.config.max_fragments {
+            if self.first_seen.len() > max_fragments {
+                return Err(Error::TooManyFragments(max_fragments));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Update the fragment stack with the parsed tag of a line and emit any output
+fn apply_tag<'a>(
+    config: &Config,
+    stack: &mut FragmentStack<'a>,
+    line: &'a str,
+    tag: Option<Tag<'a>>,
+    mut emit: impl FnMut(&HashSet<&'a str>, &str),
+) -> Result<(), Error> {
+    match tag {
+        Some(Tag::Start(tag)) => {
+            if config.debug_annotations {
+                let annotation =
+                    config.boundary_annotation("begin", line, tag.fragments.iter().copied());
+                emit(&HashSet::from([""]), &annotation);
+            }
+            stack.push(tag.fragments)?;
+            if config.keep_fragment_tags {
+                emit(&HashSet::from([""]), line);
+            }
+        }
+        Some(Tag::End(tag)) => {
+            check_end_tag(config, stack, tag.data)?;
+            if config.debug_annotations {
+                let fragments = stack.innermost().unwrap_or_default();
+                let annotation = config.boundary_annotation("end", line, fragments.iter().copied());
+                emit(&HashSet::from([""]), &annotation);
+            }
+            stack.pop()?;
+            if config.keep_fragment_tags {
+                emit(&HashSet::from([""]), line);
+            }
+        }
+        Some(Tag::StartBlock(tag)) => {
+            stack.push([tag.fragment])?;
+            let ending = get_ending(line);
+            let start_tag = config.block_start_tag(tag.fragment, false);
+            let line = format!("{}{}{}", tag.prefix, start_tag, ending);
+
+            if config.block_style.has_own_start_tag() {
+                let mut enclosing = stack.active_fragments.clone();
+                enclosing.remove(tag.fragment);
+                emit(&enclosing, &line);
+
+                if config.block_style.wraps_own_output() {
+                    let own_start_tag = config.block_start_tag(tag.fragment, true);
+                    let line = format!("{}{}{}", tag.prefix, own_start_tag, ending);
+                    emit(&HashSet::from([tag.fragment]), &line);
                 }
+            } else {
+                emit(&stack.active_fragments, &line);
+            }
+        }
+        Some(Tag::EndBlock(tag)) => {
+            check_end_tag(config, stack, tag.data)?;
+            if stack.depth() == 0 {
+                return Err(Error::UnbalancedEndTag);
             }
+
+            // the end tag belongs to the fragments it ends
+            let fragment = stack
+                .innermost()
+                .and_then(|fragments| fragments.first().copied())
+                .unwrap_or_default();
+            let end_tag = config.block_end_tag(fragment);
+            let line = format!("{}{}{}", tag.prefix, end_tag, get_ending(line));
+            if config.block_style.wraps_own_output() {
+                emit(&stack.active_fragments, &line);
+            } else {
+                let mut enclosing = stack.active_fragments.clone();
+                enclosing.remove(fragment);
+                emit(&enclosing, &line);
+            }
+            stack.pop()?;
         }
+        None => emit(&stack.active_fragments, line),
     }
-    stack.done().map_err(|err| err.at(last_line_idx))?;
+    Ok(())
+}
 
-    Ok(res)
+/// Check that the names of an end tag match the innermost start tag
+///
+/// Names are only allowed with [Config::named_end_tags].
+fn check_end_tag(config: &Config, stack: &FragmentStack<'_>, data: &str) -> Result<(), Error> {
+    if data.trim().is_empty() {
+        return Ok(());
+    }
+    if !config.named_end_tags {
+        return Err(Error::EndTagWithData(data.to_owned()));
+    }
+
+    let names: HashSet<&str> = data.split_whitespace().collect();
+    let invalid: Vec<&str> = names
+        .iter()
+        .copied()
+        .filter(|name| !is_valid_fragment_name(name))
+        .collect();
+    if !invalid.is_empty() {
+        return Err(Error::InvalidFragmentName(sorted_fragments(invalid)));
+    }
+
+    let expected = stack.innermost().ok_or(Error::UnbalancedEndTag)?;
+    if !names.iter().all(|name| expected.contains(name)) {
+        return Err(Error::MismatchedEndTag(
+            sorted_fragments(names),
+            expected.iter().map(|&name| name.to_owned()).collect(),
+        ));
+    }
+    Ok(())
 }
 
 fn push_line(res: &mut HashMap<String, String>, fragment: &str, line: &str) {
@@ -305,27 +859,42 @@ fn get_ending(line: &str) -> &str {
     }
 }
 
-#[derive(Debug)]
+/// The fragments of all open tags
+///
+/// The names of all open tags are stored in a single vector, to avoid
+/// allocating for each tag. The names of each tag are sorted, such that equal
+/// stacks compare equal.
+#[derive(Debug, Clone, PartialEq, Eq)]
 struct FragmentStack<'a> {
-    stack: Vec<HashSet<&'a str>>,
+    /// The names of all open tags, innermost last
+    fragments: Vec<&'a str>,
+    /// The start of the names of each open tag in `fragments`
+    levels: Vec<usize>,
     active_fragments: HashSet<&'a str>,
+    /// The `raw` or `verbatim` region the current line belongs to
+    verbatim: Verbatim,
 }
 
 impl<'a> std::default::Default for FragmentStack<'a> {
     fn default() -> Self {
         Self {
-            stack: Vec::new(),
+            fragments: Vec::new(),
+            levels: Vec::new(),
             active_fragments: HashSet::from([""]),
+            verbatim: Verbatim::default(),
         }
     }
 }
 
 impl<'a> FragmentStack<'a> {
     /// Add new fragments to the currently active fragments
-    fn push(&mut self, fragments: HashSet<&'a str>) -> Result<(), Error> {
-        let mut reentrant_fragments = Vec::new();
+    fn push(&mut self, fragments: impl IntoIterator<Item = &'a str>) -> Result<(), Error> {
+        let start = self.fragments.len();
+        self.fragments.extend(fragments);
+        self.fragments[start..].sort_unstable();
 
-        for &fragment in &fragments {
+        let mut reentrant_fragments = Vec::new();
+        for &fragment in &self.fragments[start..] {
             let not_seen = self.active_fragments.insert(fragment);
             if !not_seen {
                 reentrant_fragments.push(fragment);
@@ -337,41 +906,51 @@ impl<'a> FragmentStack<'a> {
             )));
         }
 
-        self.stack.push(fragments);
+        self.levels.push(start);
         Ok(())
     }
 
-    /// Pop the last addeed fragments and return the active fragments before
-    /// this op
-    fn pop(&mut self) -> Result<HashSet<&'a str>, Error> {
-        let fragments = self.active_fragments.clone();
-        for fragment in self.stack.pop().ok_or(Error::UnbalancedEndTag)? {
+    /// Remove the last added fragments from the active fragments
+    fn pop(&mut self) -> Result<(), Error> {
+        let start = self.levels.pop().ok_or(Error::UnbalancedEndTag)?;
+        for fragment in self.fragments.drain(start..) {
             self.active_fragments.remove(fragment);
         }
+        Ok(())
+    }
+
+    /// The number of open tags
+    fn depth(&self) -> usize {
+        self.levels.len()
+    }
 
-        Ok(fragments)
+    /// The sorted names of the innermost open tag
+    fn innermost(&self) -> Option<&[&'a str]> {
+        let start = *self.levels.last()?;
+        Some(&self.fragments[start..])
+    }
+
+    /// Whether no tag and no `raw` or `verbatim` region is open
+    fn is_top_level(&self) -> bool {
+        self.levels.is_empty() && self.verbatim == Verbatim::default()
     }
 
     fn done(&self) -> Result<(), Error> {
-        if !self.stack.is_empty() {
-            let fragments: HashSet<&str> = self.stack.iter().flatten().copied().collect();
+        if !self.levels.is_empty() {
+            let fragments: HashSet<&str> = self.fragments.iter().copied().collect();
             Err(Error::UnclosedTag(sorted_fragments(fragments)))
         } else {
             Ok(())
         }
     }
-
-    fn is_active(&self, fragment: &str) -> bool {
-        self.active_fragments.contains(fragment)
-    }
 }
 
 fn iterate_with_endings(mut s: &str) -> impl Iterator<Item = &str> {
     std::iter::from_fn(move || {
         let res;
-        match s.find('\n') {
+        match memchr(b'\n', s.as_bytes()) {
             Some(new_line_idx) => {
-                let split_idx = new_line_idx + '\n'.len_utf8();
+                let split_idx = new_line_idx + 1;
                 res = Some(&s[..split_idx]);
                 s = &s[split_idx..];
             }
@@ -390,7 +969,7 @@ fn iterate_with_endings(mut s: &str) -> impl Iterator<Item = &str> {
 #[derive(Debug, Clone, PartialEq, Eq)]
 enum Tag<'a> {
     Start(StartTag<'a>),
-    End(EndTag),
+    End(EndTag<'a>),
     StartBlock(StartBlockTag<'a>),
     EndBlock(EndBlockTag<'a>),
 }
@@ -398,21 +977,26 @@ enum Tag<'a> {
 #[derive(Debug, Clone, PartialEq, Eq)]
 struct StartTag<'a> {
     fragments: HashSet<&'a str>,
+    attributes: Vec<(&'a str, &'a str)>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 struct StartBlockTag<'a> {
     prefix: &'a str,
     fragment: &'a str,
+    attributes: Vec<(&'a str, &'a str)>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 struct EndBlockTag<'a> {
     prefix: &'a str,
+    data: &'a str,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-struct EndTag;
+struct EndTag<'a> {
+    data: &'a str,
+}
 
 fn parse_fragment_tag<'l>(
     line: &'l str,
@@ -440,7 +1024,21 @@ fn parse_fragment_tag<'l>(
 
             let block = matches!(parts.fragment_type, FragmentType::BlockStart);
 
-            let fragments: HashSet<&str> = data.split_whitespace().collect();
+            let mut fragments: HashSet<&str> = HashSet::new();
+            let mut attributes = Vec::new();
+            for part in data.split_whitespace() {
+                match part.split_once('=') {
+                    Some((key, value)) => {
+                        if key.is_empty() || value.is_empty() || !is_valid_fragment_name(key) {
+                            return Err(Error::InvalidAttribute(part.to_owned()));
+                        }
+                        attributes.push((key, value));
+                    }
+                    None => {
+                        fragments.insert(part);
+                    }
+                }
+            }
 
             let mut invalid_fragments = Vec::new();
             for &fragment in &fragments {
@@ -455,7 +1053,13 @@ fn parse_fragment_tag<'l>(
             }
 
             if !block {
-                Ok(Some(Tag::Start(StartTag { fragments })))
+                if fragments.is_empty() {
+                    return Err(Error::StartTagWithoutData);
+                }
+                Ok(Some(Tag::Start(StartTag {
+                    fragments,
+                    attributes,
+                })))
             } else {
                 if fragments.len() > 1 {
                     return Err(Error::MultipleNamesBlock(sorted_fragments(fragments)));
@@ -467,28 +1071,31 @@ fn parse_fragment_tag<'l>(
                 Ok(Some(Tag::StartBlock(StartBlockTag {
                     prefix: parts.head,
                     fragment,
+                    attributes,
                 })))
             }
         }
-        FragmentType::End => {
-            if !parts.data.trim().is_empty() {
-                return Err(Error::EndTagWithData(parts.data.to_owned()));
-            }
-            Ok(Some(Tag::End(EndTag)))
-        }
-        FragmentType::BlockEnd => {
-            if !parts.data.trim().is_empty() {
-                return Err(Error::EndTagWithData(parts.data.to_owned()));
-            }
-            Ok(Some(Tag::EndBlock(EndBlockTag { prefix: parts.head })))
-        }
+        // names of end tags are only checked when applying the tag, as they
+        // depend on the config
+        FragmentType::End => Ok(Some(Tag::End(EndTag { data: parts.data }))),
+        FragmentType::BlockEnd => Ok(Some(Tag::EndBlock(EndBlockTag {
+            prefix: parts.head,
+            data: parts.data,
+        }))),
     }
 }
 
 fn parse_base<'l>(line: &'l str, tag_markers: (&str, &str)) -> Option<LineParts<'l>> {
     // "(?P<head>[^\{]*)\{%\s+(?P<tag>fragment|endfragment)(?P<data>[^%]+)%\}(?P<tail>.*)
     let (head, line) = line.split_once(tag_markers.0)?;
-    let line = line.strip_prefix(char::is_whitespace)?;
+    // LaTeX command markers are directly followed by the tag, e.g.,
+    // `\BLOCK{endfragment}`
+    let is_command = is_command_marker(tag_markers.0);
+    let line = match line.strip_prefix(char::is_whitespace) {
+        Some(line) => line,
+        None if is_command => line,
+        None => return None,
+    };
 
     use FragmentType as T;
 
@@ -505,7 +1112,11 @@ fn parse_base<'l>(line: &'l str, tag_markers: (&str, &str)) -> Option<LineParts<
         .or_else(|| line.strip_prefix("fragment").map(|l| (T::Start, l)))
         .or_else(|| line.strip_prefix("endfragment").map(|l| (T::End, l)))?;
 
-    let line = line.strip_prefix(char::is_whitespace)?;
+    let line = match line.strip_prefix(char::is_whitespace) {
+        Some(line) => line,
+        None if is_command && line.starts_with(tag_markers.1) => line,
+        None => return None,
+    };
     let (data, line) = line.split_once(tag_markers.1)?;
     let tail = line;
 
@@ -517,6 +1128,11 @@ fn parse_base<'l>(line: &'l str, tag_markers: (&str, &str)) -> Option<LineParts<
     })
 }
 
+/// Whether the marker is a LaTeX command, e.g., `\BLOCK{`
+fn is_command_marker(marker: &str) -> bool {
+    marker.starts_with('\\') && marker.ends_with('{')
+}
+
 fn is_valid_fragment_name(name: &str) -> bool {
     let is_reserved = matches!(name, "block");
     let only_valid_chars = name
@@ -542,15 +1158,10 @@ pub(crate) enum FragmentType {
     BlockEnd,
 }
 
-fn sorted_fragments<'a, I: IntoIterator<Item = &'a str>>(fragments: I) -> String {
-    let mut fragments = fragments.into_iter().collect::<Vec<_>>();
+fn sorted_fragments<'a, I: IntoIterator<Item = &'a str>>(fragments: I) -> Vec<String> {
+    let mut fragments = fragments.into_iter().map(str::to_owned).collect::<Vec<_>>();
     fragments.sort();
-
-    let mut res = String::new();
-    for fragment in fragments {
-        push_join(&mut res, fragment);
-    }
-    res
+    fragments
 }
 
 /// Errors that can occurs during processing
@@ -566,23 +1177,84 @@ pub enum Error {
     /// Fragment tag without names
     StartTagWithoutData,
     /// Fragment tag with a fragment that is already active
-    ReentrantFragment(String),
+    ReentrantFragment(Vec<String>),
     /// Tag without end tag
-    UnclosedTag(String),
+    UnclosedTag(Vec<String>),
     /// End tag without corresponding start
     UnbalancedEndTag,
+    /// End tag whose names do not match the names of the innermost start tag,
+    /// with the names of the end and start tag (only reported with
+    /// [Config::named_end_tags])
+    MismatchedEndTag(Vec<String>, Vec<String>),
     /// Reserved fragment names (at the moment only `block`) or invalid characters
-    InvalidFragmentName(String),
+    InvalidFragmentName(Vec<String>),
     /// A block fragment without a name
     UnnamedBlock,
     /// A block fragmen with too many names
-    MultipleNamesBlock(String),
+    MultipleNamesBlock(Vec<String>),
+    /// A fragment that occurs multiple times, with the line of its first
+    /// occurrence (only reported with [Config::unique_fragments])
+    RepeatedFragment(String, usize),
+    /// Fragments nested deeper than allowed by [Config::max_depth]
+    NestingTooDeep(usize),
+    /// More fragments than allowed by [Config::max_fragments]
+    TooManyFragments(usize),
+    /// An attribute of a start tag without key or value
+    InvalidAttribute(String),
+    /// An include, fragment use or fragment slot that could not be resolved
+    UnresolvedInclude(String),
+    /// An include or fragment use that (indirectly) includes itself
+    RecursiveInclude(String),
+    /// A custom tag whose handler failed, with the keyword and the message
+    /// (see [Config::custom_tag])
+    CustomTag(String, String),
+    /// A requested fragment that is not defined in the template, with the
+    /// sorted names of the available fragments (only reported with
+    /// [Config::strict_fragments])
+    UnknownFragment(String, Vec<String>),
+    /// An `ifdef` directive that is malformed, e.g., without key (see
+    /// [Config::defines])
+    InvalidDirective(String),
+    /// An `ifdef` directive without `endifdef`
+    UnclosedDirective(String),
+    /// An `endifdef` directive without corresponding `ifdef`
+    UnbalancedDirective,
+    /// A fragment that is not a valid template on its own, with the fragment
+    /// and the message of the engine (only reported with `Config::verify`)
+    InvalidFragmentSyntax(String, String),
+    /// Fragments whose names result in the same macro name, sorted (only
+    /// reported with [Config::macros])
+    MacroNameCollision(Vec<String>),
 }
 
 impl Error {
     pub fn at(self, line: usize) -> ErrorWithLine {
         ErrorWithLine(line, self)
     }
+
+    /// The sorted names of the fragments involved in the error
+    ///
+    /// For errors that do not refer to fragments, an empty slice is returned.
+    ///
+    /// ```rust
+    /// # use template_fragments::split_templates;
+    /// let err = split_templates("{% fragment foo bar %}\n").unwrap_err();
+    /// assert_eq!(err.1.fragments(), ["bar", "foo"]);
+    /// ```
+    pub fn fragments(&self) -> &[String] {
+        match self {
+            Self::RepeatedFragment(fragment, _)
+            | Self::UnknownFragment(fragment, _)
+            | Self::InvalidFragmentSyntax(fragment, _) => std::slice::from_ref(fragment),
+            Self::ReentrantFragment(fragments)
+            | Self::UnclosedTag(fragments)
+            | Self::InvalidFragmentName(fragments)
+            | Self::MultipleNamesBlock(fragments)
+            | Self::MismatchedEndTag(fragments, _)
+            | Self::MacroNameCollision(fragments) => fragments,
+            _ => &[],
+        }
+    }
 }
 
 impl std::fmt::Display for Error {
@@ -592,15 +1264,58 @@ impl std::fmt::Display for Error {
             Self::TrailingContent(content) => write!(f, "Error::TrailingContent({content:?})"),
             Self::EndTagWithData(data) => write!(f, "Error::EndTagWithData({data:?})"),
             Self::StartTagWithoutData => write!(f, "Error::StartTagWithoutData"),
-            Self::ReentrantFragment(fragments) => write!(f, "Error::ReentrantFragment({fragments}"),
+            Self::ReentrantFragment(fragments) => {
+                write!(f, "Error::ReentrantFragment({})", fragments.join(", "))
+            }
             Self::UnbalancedEndTag => write!(f, "Error::UnbalancedTags"),
-            Self::UnclosedTag(fragments) => write!(f, "Error::UnclosedTag({fragments})"),
+            Self::MismatchedEndTag(found, expected) => write!(
+                f,
+                "Error::MismatchedEndTag({}; expected {})",
+                found.join(", "),
+                expected.join(", ")
+            ),
+            Self::UnclosedTag(fragments) => {
+                write!(f, "Error::UnclosedTag({})", fragments.join(", "))
+            }
             Self::InvalidFragmentName(fragments) => {
-                write!(f, "Error::InvalidFragmentName({fragments}")
+                write!(f, "Error::InvalidFragmentName({})", fragments.join(", "))
             }
             Self::UnnamedBlock => write!(f, "Error::UnnamedBlock"),
             Self::MultipleNamesBlock(fragments) => {
-                write!(f, "Error::MultipleNamesBlock({fragments}")
+                write!(f, "Error::MultipleNamesBlock({})", fragments.join(", "))
+            }
+            Self::RepeatedFragment(fragment, first_line) => write!(
+                f,
+                "Error::RepeatedFragment({fragment}, first at line {})",
+                first_line + 1
+            ),
+            Self::NestingTooDeep(max_depth) => write!(f, "Error::NestingTooDeep({max_depth})"),
+            Self::TooManyFragments(max_fragments) => {
+                write!(f, "Error::TooManyFragments({max_fragments})")
+            }
+            Self::InvalidAttribute(attribute) => {
+                write!(f, "Error::InvalidAttribute({attribute:?})")
+            }
+            Self::UnresolvedInclude(path) => write!(f, "Error::UnresolvedInclude({path:?})"),
+            Self::RecursiveInclude(path) => write!(f, "Error::RecursiveInclude({path:?})"),
+            Self::CustomTag(keyword, message) => {
+                write!(f, "Error::CustomTag({keyword}: {message})")
+            }
+            Self::UnknownFragment(fragment, available) => write!(
+                f,
+                "Error::UnknownFragment({fragment}; available {})",
+                available.join(", ")
+            ),
+            Self::InvalidDirective(content) => write!(f, "Error::InvalidDirective({content:?})"),
+            Self::UnclosedDirective(content) => {
+                write!(f, "Error::UnclosedDirective({content:?})")
+            }
+            Self::UnbalancedDirective => write!(f, "Error::UnbalancedDirective"),
+            Self::InvalidFragmentSyntax(fragment, message) => {
+                write!(f, "Error::InvalidFragmentSyntax({fragment}: {message})")
+            }
+            Self::MacroNameCollision(fragments) => {
+                write!(f, "Error::MacroNameCollision({})", fragments.join(", "))
             }
         }
     }
@@ -619,11 +1334,120 @@ impl std::fmt::Display for ErrorWithLine {
     }
 }
 
+impl ErrorWithLine {
+    pub fn in_path(self, path: &str) -> ErrorWithPath {
+        ErrorWithPath(path.to_owned(), self)
+    }
+
+    /// Capture the offending line of the source the error occurred in
+    ///
+    /// ```rust
+    /// # use template_fragments::split_templates;
+    /// let source = "{% fragment foo %} invalid\n{% endfragment %}\n";
+    /// let err = split_templates(source).unwrap_err().with_source(source);
+    ///
+    /// assert_eq!(err.line, "{% fragment foo %} invalid");
+    /// assert_eq!(err.tag.as_deref(), Some("{% fragment foo %}"));
+    /// ```
+    pub fn with_source(self, src: &str) -> ErrorWithSource {
+        let line = iterate_with_endings(src).nth(self.0).unwrap_or_default();
+        let line = line.trim_end_matches(['\r', '\n']);
+
+        let tag = parse_base(line, DEFAULT_TAG_MARKERS)
+            .map(|parts| line[parts.head.len()..line.len() - parts.tail.len()].to_owned());
+
+        ErrorWithSource {
+            error: self,
+            line: line.to_owned(),
+            tag,
+        }
+    }
+
+    /// Locate the offending line of the source as byte offsets
+    ///
+    /// ```rust
+    /// # use template_fragments::split_templates;
+    /// let source = "<div>\n  {% fragment foo %} invalid\n{% endfragment %}\n";
+    /// let err = split_templates(source).unwrap_err().with_span(source);
+    ///
+    /// assert_eq!(err.line, 6..34);
+    /// assert_eq!(err.tag, Some(8..26));
+    /// assert_eq!(&source[err.tag.unwrap()], "{% fragment foo %}");
+    /// ```
+    pub fn with_span(self, src: &str) -> ErrorWithSpan {
+        let mut start = 0;
+        let mut line = "";
+        for (line_idx, text) in iterate_with_endings(src).enumerate() {
+            if line_idx == self.0 {
+                line = text.trim_end_matches(['\r', '\n']);
+                break;
+            }
+            start += text.len();
+        }
+
+        let tag = parse_base(line, DEFAULT_TAG_MARKERS)
+            .map(|parts| start + parts.head.len()..start + line.len() - parts.tail.len());
+
+        ErrorWithSpan {
+            error: self,
+            line: start..start + line.len(),
+            tag,
+        }
+    }
+}
+
 impl std::error::Error for ErrorWithLine {}
 
-fn push_join(s: &mut String, t: &str) {
-    if !s.is_empty() {
-        s.push_str(", ");
+/// An error with the path of the template it occurred in
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorWithPath(pub String, pub ErrorWithLine);
+
+impl std::fmt::Display for ErrorWithPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} in {}", self.1, self.0)
+    }
+}
+
+impl std::error::Error for ErrorWithPath {}
+
+/// An error with the content of the line it occurred on
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorWithSource {
+    pub error: ErrorWithLine,
+    /// The offending line without its line ending
+    pub line: String,
+    /// The fragment tag on the offending line, if any
+    pub tag: Option<String>,
+}
+
+impl std::fmt::Display for ErrorWithSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {:?}", self.error, self.line)
+    }
+}
+
+impl std::error::Error for ErrorWithSource {}
+
+/// An error with the byte offsets of the line it occurred on
+///
+/// The offsets refer to the source passed to [ErrorWithLine::with_span] and
+/// can be used to slice it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorWithSpan {
+    pub error: ErrorWithLine,
+    /// The offending line without its line ending
+    pub line: Range<usize>,
+    /// The fragment tag on the offending line, if any
+    pub tag: Option<Range<usize>>,
+}
+
+impl std::fmt::Display for ErrorWithSpan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let span = self.tag.as_ref().unwrap_or(&self.line);
+        write!(f, "{} (bytes {}..{})", self.error, span.start, span.end)
     }
-    s.push_str(t);
 }
+
+impl std::error::Error for ErrorWithSpan {}
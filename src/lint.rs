@@ -0,0 +1,307 @@
+use std::collections::HashMap;
+
+use crate::{
+    iterate_with_endings,
+    variables::{bound_variables, referenced_variables, tag_bindings},
+    verbatim::tags,
+    AstNode, Config, ErrorWithLine, FragmentNode, TemplateAst, Visitor,
+};
+
+/// Fragments nested deeper than this are reported by [lint_template]
+const LINT_MAX_DEPTH: usize = 3;
+
+/// A non-fatal issue found by [lint_template]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintWarning {
+    /// The zero-based line of the start tag the warning refers to
+    pub line: usize,
+    /// The kind of the warning
+    pub kind: LintKind,
+}
+
+/// The kinds of warnings reported by [lint_template]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LintKind {
+    /// A fragment without any non-whitespace content
+    EmptyFragment(String),
+    /// A fragment that covers all content of the template
+    WholeTemplate(String),
+    /// A fragment nested deeper than 3 levels, with its depth
+    DeeplyNested(String, usize),
+    /// Two different names that only differ in case or in `-` vs. `_`, with
+    /// the name first seen
+    SimilarNames(String, String),
+    /// A fragment that references a variable bound outside of it by an
+    /// enclosing tag, e.g., a loop variable, with the variable
+    EnclosingVariable(String, String),
+}
+
+impl std::fmt::Display for LintWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at line {}", self.kind, self.line + 1)
+    }
+}
+
+impl LintKind {
+    /// A stable identifier of the kind of warning, e.g., for diagnostics
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::EmptyFragment(_) => "empty-fragment",
+            Self::WholeTemplate(_) => "whole-template",
+            Self::DeeplyNested(_, _) => "deeply-nested",
+            Self::SimilarNames(_, _) => "similar-names",
+            Self::EnclosingVariable(_, _) => "enclosing-variable",
+        }
+    }
+}
+
+impl std::fmt::Display for LintKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::EmptyFragment(name) => write!(f, "fragment {name:?} is empty"),
+            Self::WholeTemplate(name) => {
+                write!(f, "fragment {name:?} covers the whole template")
+            }
+            Self::DeeplyNested(name, depth) => {
+                write!(f, "fragment {name:?} is nested {depth} levels deep")
+            }
+            Self::SimilarNames(name, other) => {
+                write!(f, "fragment {name:?} is similar to {other:?}")
+            }
+            Self::EnclosingVariable(name, variable) => write!(
+                f,
+                "fragment {name:?} uses {variable:?}, which is bound outside of it"
+            ),
+        }
+    }
+}
+
+/// Check a template for suspicious, but valid constructs
+///
+/// Errors of the template are returned as errors, as in
+/// [crate::split_templates]. The warnings are sorted by line. Reported are
+///
+/// - fragments without any non-whitespace content
+/// - fragments that cover all content of the template
+/// - fragments nested deeper than 3 levels
+/// - names that only differ in case or in `-` vs. `_`, e.g., `list-item` and
+///   `list_item`
+/// - fragments that reference variables bound by enclosing `for`, `set`, or
+///   `with` tags outside of the fragment. Rendered standalone, these variables
+///   are undefined, unless they are given with the `with` attribute or the
+///   enclosing tags are repeated, see [Config::enclosing_control_tags] and
+///   [Config::enclosing_with_tags]
+///
+/// ```rust
+/// # use template_fragments::{lint_template, LintKind};
+/// let source = concat!(
+///     "<ul>\n",
+///     "{% fragment list-item %}\n",
+///     "  <li>{{ item }}</li>\n",
+///     "{% endfragment %}\n",
+///     "{% fragment list_item %}\n",
+///     "{% endfragment %}\n",
+///     "</ul>\n",
+/// );
+///
+/// let warnings = lint_template(source).unwrap();
+/// assert_eq!(warnings.len(), 2);
+/// assert_eq!(warnings[0].line, 4);
+/// assert_eq!(
+///     warnings[0].kind,
+///     LintKind::EmptyFragment(String::from("list_item")),
+/// );
+/// assert_eq!(
+///     warnings[1].kind,
+///     LintKind::SimilarNames(String::from("list_item"), String::from("list-item")),
+/// );
+/// ```
+pub fn lint_template(src: &str) -> Result<Vec<LintWarning>, ErrorWithLine> {
+    Config::default().lint_template(src)
+}
+
+impl Config {
+    /// Check a template for suspicious, but valid constructs
+    ///
+    /// See [crate::lint_template].
+    pub fn lint_template(&self, src: &str) -> Result<Vec<LintWarning>, ErrorWithLine> {
+        let ast = self.parse_ast(src)?;
+
+        let (open, close) = self.syntax.tag_markers();
+        let mut linter = Linter {
+            tag_markers: (open.to_owned(), close.to_owned()),
+            scopes: vec![Vec::new()],
+            repeated: self.enclosing_keywords(),
+            ..Default::default()
+        };
+        ast.walk(&mut linter);
+
+        let mut warnings = linter.warnings;
+        if let Some((line, name)) = whole_template_fragment(&ast) {
+            warnings.push(LintWarning {
+                line,
+                kind: LintKind::WholeTemplate(name),
+            });
+        }
+
+        // stable sort: keeps the order of warnings on the same line
+        warnings.sort_by_key(|warning| warning.line);
+        Ok(warnings)
+    }
+}
+
+#[derive(Default)]
+struct Linter {
+    tag_markers: (String, String),
+    /// The variables bound by the open `for` and `with` tags, the first scope
+    /// is the top-level of the template
+    scopes: Vec<Vec<String>>,
+    /// The keywords of tags that are repeated around fragments, see
+    /// [Config::enclosing_control_tags]
+    repeated: Vec<&'static str>,
+    depth: usize,
+    /// The distinct names in order of appearance for each normalized name
+    seen: HashMap<String, Vec<String>>,
+    warnings: Vec<LintWarning>,
+}
+
+impl Visitor for Linter {
+    fn enter_fragment(&mut self, line_idx: usize, node: &FragmentNode) {
+        self.depth += 1;
+
+        if is_empty(&node.children) {
+            for name in &node.names {
+                self.warn(line_idx, LintKind::EmptyFragment(name.clone()));
+            }
+        }
+        if self.depth > LINT_MAX_DEPTH {
+            for name in &node.names {
+                self.warn(line_idx, LintKind::DeeplyNested(name.clone(), self.depth));
+            }
+        }
+
+        for name in &node.names {
+            let similar = self.seen.entry(normalize_name(name)).or_default();
+            if similar.contains(name) {
+                continue;
+            }
+            if let Some(other) = similar.first() {
+                let kind = LintKind::SimilarNames(name.clone(), other.clone());
+                similar.push(name.clone());
+                self.warn(line_idx, kind);
+            } else {
+                similar.push(name.clone());
+            }
+        }
+
+        for variable in self.enclosing_variables(node) {
+            for name in &node.names {
+                self.warn(
+                    line_idx,
+                    LintKind::EnclosingVariable(name.clone(), variable.clone()),
+                );
+            }
+        }
+    }
+
+    fn text_line(&mut self, _line_idx: usize, line: &str) {
+        let tag_markers = (self.tag_markers.0.as_str(), self.tag_markers.1.as_str());
+        if !line.contains(tag_markers.0) {
+            return;
+        }
+        for words in tags(line, tag_markers) {
+            let bindings = tag_bindings(&words).into_iter().map(str::to_owned);
+            match words.first() {
+                // the variables are bound in the fragment output as well
+                Some(keyword) if self.repeated.contains(keyword) => self.scopes.push(Vec::new()),
+                Some(&("for" | "with")) => self.scopes.push(bindings.collect()),
+                Some(&("endfor" | "endwith")) if self.scopes.len() > 1 => {
+                    self.scopes.pop();
+                }
+                Some(&"set") => {
+                    if let Some(scope) = self.scopes.last_mut() {
+                        scope.extend(bindings);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn leave_fragment(&mut self, _line_idx: usize, _node: &FragmentNode) {
+        self.depth -= 1;
+    }
+}
+
+impl Linter {
+    fn warn(&mut self, line: usize, kind: LintKind) {
+        self.warnings.push(LintWarning { line, kind });
+    }
+
+    /// The variables of the fragment that are only bound by enclosing tags
+    fn enclosing_variables(&self, node: &FragmentNode) -> Vec<String> {
+        if self.scopes.iter().all(Vec::is_empty) {
+            return Vec::new();
+        }
+        let content: String = node.children.iter().map(AstNode::render).collect();
+        let tag_markers = (self.tag_markers.0.as_str(), self.tag_markers.1.as_str());
+        let bound = bound_variables(&content, tag_markers);
+
+        // variables with defaults given by the `with` attribute
+        let defaults: Vec<&str> = node
+            .attributes
+            .iter()
+            .filter(|(key, _)| key == "with")
+            .flat_map(|(_, value)| value.split(','))
+            .map(|default| default.split_once('=').map_or(default, |(name, _)| name))
+            .collect();
+
+        referenced_variables(&content)
+            .into_iter()
+            .filter(|variable| !bound.contains(variable) && !defaults.contains(variable))
+            .filter(|&variable| self.scopes.iter().flatten().any(|bound| bound == variable))
+            .map(str::to_owned)
+            .collect()
+    }
+}
+
+fn normalize_name(name: &str) -> String {
+    name.to_lowercase().replace('-', "_")
+}
+
+/// Whether the nodes do not contain any non-whitespace text
+fn is_empty(nodes: &[AstNode]) -> bool {
+    nodes.iter().all(|node| match node {
+        AstNode::Text(node) => node.content.trim().is_empty(),
+        AstNode::Fragment(node) => is_empty(&node.children),
+    })
+}
+
+/// The line and the first name of the single top-level fragment surrounded
+/// only by whitespace, if any
+fn whole_template_fragment(ast: &TemplateAst) -> Option<(usize, String)> {
+    let mut fragments = ast.nodes.iter().filter_map(|node| match node {
+        AstNode::Fragment(node) => Some(node),
+        AstNode::Text(_) => None,
+    });
+    let fragment = fragments.next()?;
+    if fragments.next().is_some() || is_empty(&fragment.children) {
+        return None;
+    }
+
+    let mut line = 0;
+    for node in &ast.nodes {
+        match node {
+            AstNode::Text(node) if node.content.trim().is_empty() => {
+                line += iterate_with_endings(&node.content).count();
+            }
+            AstNode::Text(_) => return None,
+            AstNode::Fragment(_) => break,
+        }
+    }
+    let only_whitespace = ast.nodes.iter().all(|node| match node {
+        AstNode::Text(node) => node.content.trim().is_empty(),
+        AstNode::Fragment(_) => true,
+    });
+    only_whitespace.then(|| (line, fragment.names[0].clone()))
+}
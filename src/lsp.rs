@@ -0,0 +1,473 @@
+//! A language server for annotated templates
+//!
+//! Requires the `lsp` feature. The server reports invalid fragment tags and
+//! lint warnings as diagnostics, lists fragments as document symbols, jumps
+//! between matching start and end tags, and renames fragments in all their
+//! tags. The markers of each document are selected with [Config::for_path].
+//! Editors start the server with `template-fragments lsp`, which calls
+//! [run_stdio].
+//!
+//! The features are also available as functions, e.g., to embed them into
+//! another language server:
+//!
+//! ```rust
+//! # use template_fragments::{lsp, Config};
+//! # use lsp_types::Position;
+//! let source = concat!(
+//!     "<ul>\n",
+//!     "{% fragment item %}\n",
+//!     "  <li>{{ item }}</li>\n",
+//!     "{% endfragment %}\n",
+//!     "</ul>\n",
+//! );
+//! let config = Config::default();
+//!
+//! let symbols = lsp::document_symbols(&config, source);
+//! assert_eq!(symbols[0].name, "item");
+//!
+//! let end_tag = lsp::definition(&config, source, Position::new(1, 5)).unwrap();
+//! assert_eq!(end_tag.start, Position::new(3, 0));
+//! ```
+use std::collections::HashMap;
+
+use ::lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use ::lsp_types::{
+    notification::{
+        DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, LogMessage,
+        Notification as NotificationType, PublishDiagnostics,
+    },
+    request::{DocumentSymbolRequest, GotoDefinition, Rename, Request as RequestType},
+    Diagnostic, DiagnosticSeverity, DocumentSymbol, DocumentSymbolResponse, GotoDefinitionResponse,
+    Location, LogMessageParams, MessageType, NumberOrString, OneOf, Position,
+    PublishDiagnosticsParams, Range, ServerCapabilities, SymbolKind, TextDocumentSyncCapability,
+    TextDocumentSyncKind, TextEdit, Uri, WorkspaceEdit,
+};
+
+use crate::{
+    is_valid_fragment_name, iterate_with_endings, validate::line_text, verbatim::Verbatim, Config,
+    ParsedTag, Severity, TagKind,
+};
+
+/// The errors of running the server
+pub type ServerError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Run the language server on stdin and stdout until the client exits
+pub fn run_stdio() -> Result<(), ServerError> {
+    let (connection, io_threads) = Connection::stdio();
+    serve(&connection)?;
+    drop(connection);
+    io_threads.join()?;
+    Ok(())
+}
+
+/// Run the language server on an existing connection until the client shuts
+/// it down
+pub fn serve(connection: &Connection) -> Result<(), ServerError> {
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        document_symbol_provider: Some(OneOf::Left(true)),
+        definition_provider: Some(OneOf::Left(true)),
+        rename_provider: Some(OneOf::Left(true)),
+        ..Default::default()
+    };
+    connection.initialize(serde_json::to_value(capabilities)?)?;
+
+    // keyed by the URI, as URIs have interior mutability
+    let mut documents: HashMap<String, String> = HashMap::new();
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) => {
+                if connection.handle_shutdown(&request)? {
+                    return Ok(());
+                }
+                let response = handle_request(&documents, request);
+                connection.sender.send(Message::Response(response))?;
+            }
+            Message::Notification(notification) => {
+                let uri = match handle_notification(&mut documents, notification) {
+                    Ok(uri) => uri,
+                    Err(err) => {
+                        // a malformed notification does not stop the server
+                        let params = LogMessageParams {
+                            typ: MessageType::ERROR,
+                            message: format!("invalid notification: {err}"),
+                        };
+                        connection
+                            .sender
+                            .send(Message::Notification(Notification::new(
+                                LogMessage::METHOD.to_owned(),
+                                params,
+                            )))?;
+                        None
+                    }
+                };
+                if let Some(uri) = uri {
+                    let diagnostics = match documents.get(uri.as_str()) {
+                        Some(src) => diagnostics(&document_config(&uri), src),
+                        None => Vec::new(),
+                    };
+                    let params = PublishDiagnosticsParams::new(uri, diagnostics, None);
+                    connection
+                        .sender
+                        .send(Message::Notification(Notification::new(
+                            PublishDiagnostics::METHOD.to_owned(),
+                            params,
+                        )))?;
+                }
+            }
+            Message::Response(_) => {}
+        }
+    }
+    Ok(())
+}
+
+/// Update the documents and return the document whose diagnostics changed
+fn handle_notification(
+    documents: &mut HashMap<String, String>,
+    notification: Notification,
+) -> Result<Option<Uri>, ServerError> {
+    match notification.method.as_str() {
+        DidOpenTextDocument::METHOD => {
+            let params = notification_params::<DidOpenTextDocument>(notification)?;
+            let uri = params.text_document.uri;
+            documents.insert(uri.as_str().to_owned(), params.text_document.text);
+            Ok(Some(uri))
+        }
+        DidChangeTextDocument::METHOD => {
+            let params = notification_params::<DidChangeTextDocument>(notification)?;
+            let uri = params.text_document.uri;
+            // the full text is synced, the last change is the current text
+            if let Some(change) = params.content_changes.into_iter().last() {
+                documents.insert(uri.as_str().to_owned(), change.text);
+            }
+            Ok(Some(uri))
+        }
+        DidCloseTextDocument::METHOD => {
+            let params = notification_params::<DidCloseTextDocument>(notification)?;
+            documents.remove(params.text_document.uri.as_str());
+            Ok(Some(params.text_document.uri))
+        }
+        _ => Ok(None),
+    }
+}
+
+fn handle_request(documents: &HashMap<String, String>, request: Request) -> Response {
+    let id = request.id.clone();
+    let document = |uri: &Uri| {
+        documents
+            .get(uri.as_str())
+            .map(|src| (document_config(uri), src.as_str()))
+    };
+
+    match request.method.as_str() {
+        DocumentSymbolRequest::METHOD => match request_params::<DocumentSymbolRequest>(request) {
+            Ok(params) => {
+                let symbols = document(&params.text_document.uri)
+                    .map(|(config, src)| document_symbols(&config, src));
+                Response::new_ok(id, symbols.map(DocumentSymbolResponse::Nested))
+            }
+            Err(response) => response,
+        },
+        GotoDefinition::METHOD => match request_params::<GotoDefinition>(request) {
+            Ok(params) => {
+                let params = params.text_document_position_params;
+                let uri = params.text_document.uri;
+                let range = document(&uri)
+                    .and_then(|(config, src)| definition(&config, src, params.position));
+                let location = range.map(|range| Location::new(uri, range));
+                Response::new_ok(id, location.map(GotoDefinitionResponse::Scalar))
+            }
+            Err(response) => response,
+        },
+        Rename::METHOD => match request_params::<Rename>(request) {
+            Ok(params)
+                if params.new_name.is_empty() || !is_valid_fragment_name(&params.new_name) =>
+            {
+                Response::new_err(
+                    id,
+                    ErrorCode::InvalidParams as i32,
+                    format!("invalid fragment name {:?}", params.new_name),
+                )
+            }
+            Ok(params) => {
+                let position = params.text_document_position;
+                let uri = position.text_document.uri;
+                let edits = document(&uri).and_then(|(config, src)| {
+                    rename(&config, src, position.position, &params.new_name)
+                });
+                let edit = edits.map(|edits| WorkspaceEdit::new(HashMap::from([(uri, edits)])));
+                Response::new_ok(id, edit)
+            }
+            Err(response) => response,
+        },
+        method => Response::new_err(
+            id,
+            ErrorCode::MethodNotFound as i32,
+            format!("unknown method {method:?}"),
+        ),
+    }
+}
+
+/// The params of a request or the error response for invalid params
+fn request_params<R: RequestType>(request: Request) -> Result<R::Params, Response> {
+    let id = request.id.clone();
+    request
+        .extract(R::METHOD)
+        .map(|(_, params)| params)
+        .map_err(|err| Response::new_err(id, ErrorCode::InvalidParams as i32, err.to_string()))
+}
+
+fn notification_params<N: NotificationType>(
+    notification: Notification,
+) -> Result<N::Params, ServerError> {
+    Ok(notification.extract(N::METHOD)?)
+}
+
+/// The config of a document selected by the extension of its path
+fn document_config(uri: &Uri) -> Config {
+    Config::for_path(uri.path().as_str())
+}
+
+/// The errors and lint warnings of a template as diagnostics
+///
+/// The diagnostics of [crate::validate_template] converted to the protocol,
+/// with their codes.
+pub fn diagnostics(config: &Config, src: &str) -> Vec<Diagnostic> {
+    config
+        .validate_template(src)
+        .into_iter()
+        .map(|found| {
+            let line_idx = found.line - 1;
+            let line = line_text(src, line_idx);
+            let column = |column: usize| {
+                let offset = line
+                    .char_indices()
+                    .nth(column - 1)
+                    .map_or(line.len(), |(offset, _)| offset);
+                position(line, line_idx, offset)
+            };
+            let message = match &found.help {
+                Some(help) => format!("{}\nhelp: {help}", found.message),
+                None => found.message,
+            };
+            let severity = match found.severity {
+                Severity::Error => DiagnosticSeverity::ERROR,
+                Severity::Warning => DiagnosticSeverity::WARNING,
+            };
+
+            let range = Range::new(column(found.column), column(found.end_column));
+            let mut diagnostic = Diagnostic::new_simple(range, message);
+            diagnostic.severity = Some(severity);
+            diagnostic.code = Some(NumberOrString::String(found.code.to_owned()));
+            diagnostic.source = Some(String::from("template-fragments"));
+            diagnostic
+        })
+        .collect()
+}
+
+/// The fragments of a template as nested symbols
+///
+/// Tags that are not closed extend to the end of the template, so that
+/// symbols are available while the template is edited.
+pub fn document_symbols(config: &Config, src: &str) -> Vec<DocumentSymbol> {
+    let pairs = scan(config, src);
+    let last_line = iterate_with_endings(src).count().saturating_sub(1);
+
+    let mut children: Vec<Vec<usize>> = vec![Vec::new(); pairs.len()];
+    let mut roots = Vec::new();
+    for (idx, pair) in pairs.iter().enumerate() {
+        match pair.parent {
+            Some(parent) => children[parent].push(idx),
+            None => roots.push(idx),
+        }
+    }
+
+    fn build(
+        pairs: &[TagPair<'_>],
+        children: &[Vec<usize>],
+        last_line: usize,
+        src: &str,
+        idx: usize,
+    ) -> DocumentSymbol {
+        let pair = &pairs[idx];
+        let start = &pair.start;
+        let end_line = pair.end.as_ref().map_or(last_line, |end| end.line_idx);
+        let end = line_text(src, end_line);
+        let selection_range = start
+            .tag
+            .names
+            .first()
+            .map_or(start.tag.span.clone(), |name| name.span.clone());
+        let names: Vec<&str> = start.tag.names.iter().map(|name| name.text).collect();
+
+        #[allow(deprecated)]
+        DocumentSymbol {
+            name: names.join(", "),
+            detail: (start.tag.kind == TagKind::StartBlock).then(|| String::from("block")),
+            kind: SymbolKind::NAMESPACE,
+            tags: None,
+            deprecated: None,
+            range: Range::new(
+                position(start.line, start.line_idx, 0),
+                position(end, end_line, end.len()),
+            ),
+            selection_range: Range::new(
+                position(start.line, start.line_idx, selection_range.start),
+                position(start.line, start.line_idx, selection_range.end),
+            ),
+            children: Some(
+                children[idx]
+                    .iter()
+                    .map(|&child| build(pairs, children, last_line, src, child))
+                    .collect(),
+            ),
+        }
+    }
+
+    roots
+        .into_iter()
+        .map(|idx| build(&pairs, &children, last_line, src, idx))
+        .collect()
+}
+
+/// The range of the tag matching the tag at the position
+///
+/// For start tags, the end tag is returned and vice versa.
+pub fn definition(config: &Config, src: &str, position: Position) -> Option<Range> {
+    let line_idx = position.line as usize;
+    let target = scan(config, src).into_iter().find_map(|pair| {
+        let end = pair.end?;
+        if pair.start.line_idx == line_idx {
+            Some(end)
+        } else if end.line_idx == line_idx {
+            Some(pair.start)
+        } else {
+            None
+        }
+    })?;
+    Some(Range::new(
+        self::position(target.line, target.line_idx, target.tag.span.start),
+        self::position(target.line, target.line_idx, target.tag.span.end),
+    ))
+}
+
+/// The edits to rename the fragment at the position in all its tags
+///
+/// Returns `None` if there is no fragment name at the position. The new name
+/// is not validated.
+pub fn rename(
+    config: &Config,
+    src: &str,
+    position: Position,
+    new_name: &str,
+) -> Option<Vec<TextEdit>> {
+    let tags = tag_lines(config, src);
+    let line = tags
+        .iter()
+        .find(|tag| tag.line_idx == position.line as usize)?;
+    let offset = byte_offset(line.line, position.character);
+    let name = line
+        .tag
+        .names
+        .iter()
+        .find(|name| name.span.start <= offset && offset <= name.span.end)?
+        .text;
+
+    let edits = tags
+        .iter()
+        .flat_map(|tag| {
+            tag.tag
+                .names
+                .iter()
+                .filter(|part| part.text == name)
+                .map(|part| {
+                    TextEdit::new(
+                        Range::new(
+                            self::position(tag.line, tag.line_idx, part.span.start),
+                            self::position(tag.line, tag.line_idx, part.span.end),
+                        ),
+                        new_name.to_owned(),
+                    )
+                })
+        })
+        .collect();
+    Some(edits)
+}
+
+/// A line with a valid fragment tag
+struct TagLine<'s> {
+    line_idx: usize,
+    line: &'s str,
+    tag: ParsedTag<'s>,
+}
+
+/// A start tag with its matching end tag, if any
+struct TagPair<'s> {
+    start: TagLine<'s>,
+    end: Option<TagLine<'s>>,
+    /// The index of the enclosing pair
+    parent: Option<usize>,
+}
+
+/// All lines with valid fragment tags outside of verbatim sections
+fn tag_lines<'s>(config: &Config, src: &'s str) -> Vec<TagLine<'s>> {
+    let mut verbatim = Verbatim::default();
+    iterate_with_endings(src)
+        .enumerate()
+        .filter(|(_, line)| !verbatim.skip(line, config.syntax.tag_markers()))
+        .filter_map(|(line_idx, line)| {
+            let line = line.trim_end_matches(['\r', '\n']);
+            let tag = config.parse_tag(line).ok()??;
+            Some(TagLine {
+                line_idx,
+                line,
+                tag,
+            })
+        })
+        .collect()
+}
+
+/// Match the start and end tags of a template in the order of the start tags
+///
+/// End tags without a start tag are skipped.
+fn scan<'s>(config: &Config, src: &'s str) -> Vec<TagPair<'s>> {
+    let mut pairs: Vec<TagPair<'s>> = Vec::new();
+    let mut open: Vec<usize> = Vec::new();
+    for tag in tag_lines(config, src) {
+        match tag.tag.kind {
+            TagKind::Start | TagKind::StartBlock => {
+                open.push(pairs.len());
+                pairs.push(TagPair {
+                    start: tag,
+                    end: None,
+                    parent: open.iter().rev().nth(1).copied(),
+                });
+            }
+            TagKind::End | TagKind::EndBlock => {
+                if let Some(idx) = open.pop() {
+                    pairs[idx].end = Some(tag);
+                }
+            }
+        }
+    }
+    pairs
+}
+
+/// The position of a byte offset of the line, in UTF-16 code units as
+/// expected by the protocol
+fn position(line: &str, line_idx: usize, offset: usize) -> Position {
+    let character = line[..offset].encode_utf16().count();
+    Position::new(line_idx as u32, character as u32)
+}
+
+/// The byte offset of a position in UTF-16 code units in the line
+fn byte_offset(line: &str, character: u32) -> usize {
+    let mut units = 0;
+    for (offset, c) in line.char_indices() {
+        if units >= character as usize {
+            return offset;
+        }
+        units += c.len_utf16();
+    }
+    line.len()
+}
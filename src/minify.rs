@@ -0,0 +1,70 @@
+/// Elements whose content is copied verbatim when minifying
+const PRESERVED_ELEMENTS: [&str; 4] = ["pre", "textarea", "script", "style"];
+
+/// Collapse insignificant whitespace of HTML
+///
+/// Runs of whitespace containing a line break are replaced by a single line
+/// ending, all other runs by a single space. Whitespace at the start of lines
+/// and empty lines are removed. The content of `pre`, `textarea`, `script`, and
+/// `style` elements is kept as it is.
+pub(crate) fn minify_html(src: &str) -> String {
+    let mut res = String::with_capacity(src.len());
+
+    let mut pos = 0;
+    while pos < src.len() {
+        let rest = &src[pos..];
+        let whitespace = rest.len() - rest.trim_start().len();
+
+        if whitespace != 0 {
+            let run = &rest[..whitespace];
+            let at_line_start = res.is_empty() || res.ends_with('\n');
+
+            if run.contains('\n') {
+                if !at_line_start {
+                    res.push_str(if run.contains("\r\n") { "\r\n" } else { "\n" });
+                }
+            } else if !at_line_start && whitespace != rest.len() {
+                res.push(' ');
+            }
+            pos += whitespace;
+        } else if let Some(len) = preserved_element_len(rest) {
+            res.push_str(&rest[..len]);
+            pos += len;
+        } else {
+            // always consume the first char, it may be a `<` of another element
+            let first = rest.chars().next().map_or(0, char::len_utf8);
+            let len = rest[first..]
+                .find(|c: char| c.is_whitespace() || c == '<')
+                .map_or(rest.len(), |idx| first + idx);
+            res.push_str(&rest[..len]);
+            pos += len;
+        }
+    }
+    res
+}
+
+/// The length of the preserved element at the start of `src`, if any
+///
+/// Unclosed elements extend to the end of `src`.
+fn preserved_element_len(src: &str) -> Option<usize> {
+    let name = PRESERVED_ELEMENTS.into_iter().find(|name| {
+        src.starts_with('<')
+            && starts_with_ignore_case(&src[1..], name)
+            && src[1 + name.len()..]
+                .chars()
+                .next()
+                .is_none_or(|c| c == '>' || c == '/' || c.is_whitespace())
+    })?;
+
+    let lower = src.to_ascii_lowercase();
+    let len = lower
+        .find(&format!("</{name}"))
+        .and_then(|start| lower[start..].find('>').map(|end| start + end + 1))
+        .unwrap_or(src.len());
+    Some(len)
+}
+
+fn starts_with_ignore_case(s: &str, prefix: &str) -> bool {
+    s.get(..prefix.len())
+        .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+}
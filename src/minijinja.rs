@@ -0,0 +1,250 @@
+//! Integration with the [minijinja](https://docs.rs/minijinja) template engine
+//!
+//! Requires the `minijinja` feature. [add_template_fragments] splits a template
+//! and registers all its fragments at once. Alternatively, with
+//! [fragment_loader], annotated templates are registered as they are and
+//! fragments are filtered lazily, when they are first requested, e.g., with
+//! `env.get_template("index.html#item")`.
+//!
+//! Environments with a custom [SyntaxConfig] are supported by converting it
+//! into a [Syntax], see [add_template_fragments].
+//! With [Config::verify], fragments that are not valid templates on their
+//! own are reported when splitting.
+//!
+//! ```rust
+//! # use template_fragments::{minijinja::fragment_loader, Config};
+//! # use minijinja::{context, Environment};
+//! let mut env = Environment::new();
+//! env.set_loader(fragment_loader(Config::default(), |path| {
+//!     Ok(match path {
+//!         "index.html" => Some(String::from(concat!(
+//!             "<ul>\n",
+//!             "{% for item in items %}\n",
+//!             "  {% fragment item %}\n",
+//!             "  <li>{{ item }}</li>\n",
+//!             "  {% endfragment %}\n",
+//!             "{% endfor %}\n",
+//!             "</ul>\n",
+//!         ))),
+//!         _ => None,
+//!     })
+//! }));
+//!
+//! let template = env.get_template("index.html#item").unwrap();
+//! assert_eq!(template.render(context!(item => "foo")).unwrap(), "  <li>foo</li>");
+//! ```
+
+use ::minijinja::{syntax::SyntaxConfig, Environment, Error, ErrorKind};
+
+use crate::{numbered::fragment_of_key, split_path, Config, ErrorWithLine, Syntax};
+
+/// Split the template and add all its fragments to the environment
+///
+/// The fragments are added as `join_path(path, fragment)`, e.g.,
+/// `index.html` and `index.html#item`. If the environment uses a custom
+/// syntax, fragment tags are written with its block and comment delimiters.
+/// Otherwise, the markers are selected with [Config::for_path]. See
+/// [Config::add_template_fragments].
+///
+/// ```rust
+/// # use template_fragments::minijinja::add_template_fragments;
+/// # use minijinja::{context, Environment};
+/// let source = concat!(
+///     "<ul>\n",
+///     "{% for item in items %}\n",
+///     "  {% fragment item %}\n",
+///     "  <li>{{ item }}</li>\n",
+///     "  {% endfragment %}\n",
+///     "{% endfor %}\n",
+///     "</ul>\n",
+/// );
+///
+/// let mut env = Environment::new();
+/// add_template_fragments(&mut env, "index.html", source).unwrap();
+///
+/// let template = env.get_template("index.html#item").unwrap();
+/// assert_eq!(template.render(context!(item => "foo")).unwrap(), "  <li>foo</li>");
+/// ```
+pub fn add_template_fragments(
+    env: &mut Environment<'_>,
+    path: &str,
+    src: &str,
+) -> Result<(), Error> {
+    let syntax = Syntax::from(env.syntax());
+    let config = if syntax != Syntax::jinja() {
+        Config::default().syntax(syntax)
+    } else {
+        Config::for_path(path)
+    };
+    config.add_template_fragments(env, path, src)
+}
+
+/// Use the block and comment delimiters of a minijinja syntax
+///
+/// ```rust
+/// # use template_fragments::{Config, Syntax};
+/// # use minijinja::syntax::SyntaxConfig;
+/// let syntax = SyntaxConfig::builder()
+///     .block_delimiters("<%", "%>")
+///     .comment_delimiters("<#", "#>")
+///     .build()
+///     .unwrap();
+///
+/// let config = Config::default().syntax(Syntax::from(&syntax));
+/// let templates = config
+///     .split_templates("<% fragment item %>\n<li>{{ item }}</li>\n<% endfragment %>\n")
+///     .unwrap();
+/// assert_eq!(templates["item"], "<li>{{ item }}</li>\n");
+/// ```
+impl From<&SyntaxConfig> for Syntax {
+    fn from(syntax: &SyntaxConfig) -> Self {
+        Syntax::new(syntax.block_delimiters(), syntax.comment_delimiters())
+    }
+}
+
+impl Config {
+    /// Split the template and add all its fragments to the environment
+    ///
+    /// Requires the `minijinja` feature. Invalid templates result in a syntax
+    /// error that mentions the path and the line, with the [ErrorWithLine] as
+    /// its source. No fragments are added for invalid templates.
+    pub fn add_template_fragments(
+        &self,
+        env: &mut Environment<'_>,
+        path: &str,
+        src: &str,
+    ) -> Result<(), Error> {
+        let templates = self
+            .split_templates_prefixed(path, src)
+            .map_err(|err| template_error(path, err))?;
+
+        for (name, content) in templates {
+            env.add_template_owned(name, content)?;
+        }
+        Ok(())
+    }
+}
+
+impl Config {
+    /// If `true`, every fragment is compiled with minijinja after splitting
+    /// and fragments that are not valid templates on their own result in an
+    /// [crate::Error::InvalidFragmentSyntax]
+    ///
+    /// Requires the `minijinja` feature. This way, fragment boundaries that
+    /// cut through other tags, e.g., a fragment that contains an `{% endfor
+    /// %}` but not its `{% for %}`, are caught when splitting instead of when
+    /// the fragment is first rendered. The error is reported at the first
+    /// start tag of the fragment. The fragments are compiled with the tag and
+    /// comment markers of the config, but are not rendered, i.e., unknown
+    /// filters or templates are not reported.
+    ///
+    /// ```rust
+    /// # use template_fragments::{Config, Error};
+    /// let source = concat!(
+    ///     "<ul>\n",
+    ///     "{% for item in items %}\n",
+    ///     "  {% fragment item %}\n",
+    ///     "  <li>{{ item }}</li>\n",
+    ///     "{% endfor %}\n",
+    ///     "  {% endfragment %}\n",
+    ///     "</ul>\n",
+    /// );
+    ///
+    /// assert!(Config::default().split_templates(source).is_ok());
+    ///
+    /// let err = Config::default().verify(true).split_templates(source).unwrap_err();
+    /// assert_eq!(err.0, 2);
+    /// assert_eq!(
+    ///     err.1,
+    ///     Error::InvalidFragmentSyntax(
+    ///         String::from("item"),
+    ///         String::from("unknown statement endfor at line 2"),
+    ///     ),
+    /// );
+    /// ```
+    pub fn verify(mut self, value: bool) -> Self {
+        self.verify = value;
+        self
+    }
+
+    /// Compile all fragments in the order of their keys and report the first
+    /// invalid one
+    pub(crate) fn verify_fragments<'t>(
+        &self,
+        src: &str,
+        templates: impl Iterator<Item = (&'t str, &'t str)>,
+    ) -> Result<(), ErrorWithLine> {
+        let mut env = Environment::new();
+        if self.syntax != Syntax::jinja() {
+            let (block_open, block_close) = self.syntax.tag_markers();
+            let (comment_open, comment_close) = self.syntax.comment_markers();
+            let syntax = SyntaxConfig::builder()
+                .block_delimiters(block_open.to_owned(), block_close.to_owned())
+                .comment_delimiters(comment_open.to_owned(), comment_close.to_owned())
+                .build()
+                .map_err(|err| syntax_error(String::new(), &err).at(0))?;
+            env.set_syntax(syntax);
+        }
+
+        let mut templates: Vec<(&str, &str)> = templates.collect();
+        templates.sort();
+        for (key, content) in templates {
+            if let Err(err) = env.template_from_named_str(key, content) {
+                let line = self.start_line(src, fragment_of_key(key));
+                return Err(syntax_error(key.to_owned(), &err).at(line));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Convert a minijinja error of a fragment into an error of the template
+fn syntax_error(fragment: String, err: &Error) -> crate::Error {
+    let detail = err
+        .detail()
+        .map_or_else(|| err.kind().to_string(), str::to_owned);
+    let message = match err.line() {
+        Some(line) => format!("{detail} at line {line}"),
+        None => detail,
+    };
+    crate::Error::InvalidFragmentSyntax(fragment, message)
+}
+
+/// Wrap a loader of annotated templates into a loader of their fragments
+///
+/// `load` is called with the path of the requested template, e.g.,
+/// `index.html` for `index.html#item`, and returns the annotated template.
+/// Requests without fragment return the base template. Fragments are
+/// filtered with the given config, as in [Config::parse_template]. Unknown
+/// fragments are reported as missing templates. Invalid templates result in
+/// syntax errors.
+///
+/// It can wrap the loaders of minijinja, e.g.,
+/// `fragment_loader(Config::default(), minijinja::path_loader("templates"))`.
+pub fn fragment_loader<F>(
+    config: Config,
+    load: F,
+) -> impl Fn(&str) -> Result<Option<String>, Error> + Send + Sync + 'static
+where
+    F: Fn(&str) -> Result<Option<String>, Error> + Send + Sync + 'static,
+{
+    move |name| {
+        let (path, fragment) = split_path(name);
+        let Some(src) = load(path)? else {
+            return Ok(None);
+        };
+
+        let template = config
+            .parse_template(&src)
+            .map_err(|err| template_error(path, err))?;
+        if !template.has(fragment) {
+            return Ok(None);
+        }
+        Ok(Some(template.filter(fragment).to_owned()))
+    }
+}
+
+/// Convert an error of a template into a minijinja syntax error
+pub(crate) fn template_error(path: &str, err: ErrorWithLine) -> Error {
+    Error::new(ErrorKind::SyntaxError, format!("{err} in {path}")).with_source(err)
+}
@@ -0,0 +1,72 @@
+use std::{fmt, str::FromStr};
+
+use crate::{is_valid_fragment_name, numbered::occurrence_of_key, Error};
+
+/// A validated fragment name
+///
+/// Names are validated with the same rules as the names of fragment tags:
+/// they may only contain alphanumeric characters, `-` and `_`, and must not be
+/// the reserved name `block`. The empty name refers to the base template. The
+/// keys `NAME.N` of repeated fragments are accepted as well, see
+/// [crate::Config::number_repeated_fragments]. This way, user-provided names,
+/// e.g., from URLs, can be validated before filtering a template.
+///
+/// ```rust
+/// # use template_fragments::{filter_template, FragmentName};
+/// let source = concat!(
+///     "<body>\n",
+///     "  {% fragment item %}\n",
+///     "    <div>{{ item }}</div>\n",
+///     "  {% endfragment %}\n",
+///     "<body>\n",
+/// );
+///
+/// let name: FragmentName = "item".parse().unwrap();
+/// assert_eq!(
+///     filter_template(source, name.as_str()).unwrap(),
+///     "    <div>{{ item }}</div>\n",
+/// );
+///
+/// assert!("../item".parse::<FragmentName>().is_err());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FragmentName(String);
+
+impl FragmentName {
+    /// The name as a string slice
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Whether the name refers to the base template
+    pub fn is_base(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl FromStr for FragmentName {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let is_valid = match occurrence_of_key(s) {
+            Some((fragment, _)) => !fragment.is_empty() && is_valid_fragment_name(fragment),
+            None => is_valid_fragment_name(s),
+        };
+        if !is_valid {
+            return Err(Error::InvalidFragmentName(vec![s.to_owned()]));
+        }
+        Ok(Self(s.to_owned()))
+    }
+}
+
+impl fmt::Display for FragmentName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl AsRef<str> for FragmentName {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
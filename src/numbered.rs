@@ -0,0 +1,86 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{iterate_with_endings, Config, ErrorWithLine, Processor, Tag};
+
+impl Config {
+    /// If `true`, repeated occurrences of a fragment are split into separate
+    /// fragments `NAME.1`, `NAME.2`, ... instead of being concatenated
+    ///
+    /// The first occurrence keeps the plain name. [Config::filter_template]
+    /// and [Config::parse_template] accept the numbered names as well.
+    /// Attributes and `with` contexts of a fragment apply to all its
+    /// occurrences.
+    ///
+    /// ```rust
+    /// # use template_fragments::Config;
+    /// let source = concat!(
+    ///     "{% fragment card %}\n",
+    ///     "<div>{{ first }}</div>\n",
+    ///     "{% endfragment %}\n",
+    ///     "{% fragment card %}\n",
+    ///     "<div>{{ second }}</div>\n",
+    ///     "{% endfragment %}\n",
+    /// );
+    ///
+    /// let config = Config::default().number_repeated_fragments(true);
+    /// let templates = config.split_templates(source).unwrap();
+    /// assert_eq!(templates["card"], "<div>{{ first }}</div>\n");
+    /// assert_eq!(templates["card.1"], "<div>{{ second }}</div>\n");
+    /// assert_eq!(config.filter_template(source, "card.1").unwrap(), templates["card.1"]);
+    /// ```
+    pub fn number_repeated_fragments(mut self, value: bool) -> Self {
+        self.number_repeated_fragments = value;
+        self
+    }
+}
+
+/// Process the template and emit the output with the keys of the current
+/// occurrence of each fragment
+///
+/// Fragments without an entry in the keys use their name as key.
+pub(crate) fn process_numbered<'a>(
+    config: &Config,
+    src: &'a str,
+    mut emit: impl FnMut(&HashSet<&'a str>, &HashMap<&'a str, String>, &str),
+) -> Result<(), ErrorWithLine> {
+    let mut processor = Processor::new(config);
+    let mut occurrences: HashMap<&'a str, usize> = HashMap::new();
+    let mut keys: HashMap<&'a str, String> = HashMap::new();
+
+    for (line_idx, line) in iterate_with_endings(src).enumerate() {
+        let tag = processor.parse(line).map_err(|err| err.at(line_idx))?;
+        let started: Vec<&'a str> = match &tag {
+            Some(Tag::Start(tag)) => tag.fragments.iter().copied().collect(),
+            Some(Tag::StartBlock(tag)) => vec![tag.fragment],
+            _ => Vec::new(),
+        };
+        for fragment in started {
+            let occurrence = occurrences.entry(fragment).or_default();
+            if *occurrence > 0 {
+                keys.insert(fragment, format!("{fragment}.{occurrence}"));
+            }
+            *occurrence += 1;
+        }
+
+        processor.apply(line_idx, line, tag, |active, line| {
+            emit(active, &keys, line)
+        })?;
+    }
+    processor.done()
+}
+
+/// The name of the fragment of a key produced by [process_numbered]
+pub(crate) fn fragment_of_key(key: &str) -> &str {
+    key.split_once('.').map_or(key, |(fragment, _)| fragment)
+}
+
+/// The fragment and occurrence of a numbered key `NAME.N`, `None` for plain
+/// names
+pub(crate) fn occurrence_of_key(key: &str) -> Option<(&str, usize)> {
+    let (fragment, occurrence) = key.split_once('.')?;
+    // only the keys as formatted by process_numbered, e.g., not `NAME.01`
+    if occurrence.starts_with('0') || !occurrence.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    Some((fragment, occurrence.parse().ok()?))
+}
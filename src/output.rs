@@ -0,0 +1,272 @@
+use crate::{Config, Syntax};
+
+/// How `fragment-block` regions are emitted
+///
+/// ```rust
+/// # use template_fragments::Config;
+/// let source = concat!(
+///     "<ul>\n",
+///     "{{ range .Items }}\n",
+///     "{{ fragment-block item }}\n",
+///     "  <li>{{ .Name }}</li>\n",
+///     "{{ endfragment-block }}\n",
+///     "{{ end }}\n",
+///     "</ul>\n",
+/// );
+/// let templates = Config::golang().split_templates(source).unwrap();
+///
+/// assert_eq!(
+///     templates[""],
+///     concat!(
+///         "<ul>\n",
+///         "{{ range .Items }}\n",
+///         "{{ block \"item\" . }}\n",
+///         "  <li>{{ .Name }}</li>\n",
+///         "{{ end }}\n",
+///         "{{ end }}\n",
+///         "</ul>\n",
+///     ),
+/// );
+/// assert_eq!(
+///     templates["item"],
+///     concat!(
+///         "{{ define \"item\" }}\n",
+///         "  <li>{{ .Name }}</li>\n",
+///         "{{ end }}\n",
+///     ),
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BlockStyle {
+    /// Jinja blocks: `{% block NAME %}` and `{% endblock %}` (the default)
+    #[default]
+    Jinja,
+    /// Go templates: the fragment itself defines a template named after the
+    /// fragment with `{{ define "NAME" }}` and `{{ end }}`, enclosing templates
+    /// define and execute it in place with `{{ block "NAME" . }}` and `{{ end
+    /// }}`
+    ///
+    /// This way, the fragment output can be parsed with Go-template-compatible
+    /// engines and executed by the name of the fragment.
+    Go,
+    /// Handlebars: enclosing templates define the fragment as an inline
+    /// partial with `{{#*inline "NAME"}}` and `{{/inline}}` and render it in
+    /// place with `{{> NAME}}`, the fragment itself is emitted as a plain
+    /// partial without these tags
+    ///
+    /// This way, the fragment output can be registered as partials with
+    /// handlebars engines, e.g., handlebars-rust. The tags use the handlebars
+    /// syntax regardless of the configured tag markers.
+    ///
+    /// ```rust
+    /// # use template_fragments::Config;
+    /// let source = concat!(
+    ///     "<ul>\n",
+    ///     "{{#each items}}\n",
+    ///     "{{!-- fragment-block item --}}\n",
+    ///     "  <li>{{name}}</li>\n",
+    ///     "{{!-- endfragment-block --}}\n",
+    ///     "{{/each}}\n",
+    ///     "</ul>\n",
+    /// );
+    /// let templates = Config::handlebars().split_templates(source).unwrap();
+    ///
+    /// assert_eq!(
+    ///     templates[""],
+    ///     concat!(
+    ///         "<ul>\n",
+    ///         "{{#each items}}\n",
+    ///         "{{#*inline \"item\"}}\n",
+    ///         "  <li>{{name}}</li>\n",
+    ///         "{{/inline}}{{> item}}\n",
+    ///         "{{/each}}\n",
+    ///         "</ul>\n",
+    ///     ),
+    /// );
+    /// assert_eq!(templates["item"], "  <li>{{name}}</li>\n");
+    /// ```
+    Handlebars,
+    /// Liquid: enclosing templates capture the fragment into a variable named
+    /// after the fragment with `{% capture NAME %}` and `{% endcapture %}` and
+    /// output it in place with `{{ NAME }}`, the fragment itself is emitted
+    /// without these tags
+    ///
+    /// This way, the block can be output again later in the template, e.g.,
+    /// in a layout.
+    ///
+    /// ```rust
+    /// # use template_fragments::Config;
+    /// let source = concat!(
+    ///     "{% for product in collection.products %}\n",
+    ///     "  {% fragment-block card %}\n",
+    ///     "  <li>{{ product.title }}</li>\n",
+    ///     "  {% endfragment-block %}\n",
+    ///     "{% endfor %}\n",
+    /// );
+    /// let templates = Config::liquid().split_templates(source).unwrap();
+    ///
+    /// assert_eq!(
+    ///     templates[""],
+    ///     concat!(
+    ///         "{% for product in collection.products %}\n",
+    ///         "  {% capture card %}\n",
+    ///         "  <li>{{ product.title }}</li>\n",
+    ///         "  {% endcapture %}{{ card }}\n",
+    ///         "{% endfor %}\n",
+    ///     ),
+    /// );
+    /// assert_eq!(templates["card"], "  <li>{{ product.title }}</li>\n");
+    /// ```
+    Liquid,
+}
+
+impl BlockStyle {
+    /// The tag starting a block, `own` is `true` for the output of the block
+    /// fragment itself
+    pub(crate) fn start_tag(&self, tag_markers: (&str, &str), fragment: &str, own: bool) -> String {
+        let (open, close) = tag_markers;
+        match self {
+            Self::Jinja => format!("{open} block {fragment} {close}"),
+            Self::Go if own => format!("{open} define \"{fragment}\" {close}"),
+            Self::Go => format!("{open} block \"{fragment}\" . {close}"),
+            Self::Handlebars => format!("{{{{#*inline \"{fragment}\"}}}}"),
+            Self::Liquid => format!("{open} capture {fragment} {close}"),
+        }
+    }
+
+    /// The tag ending the block of the given fragment
+    pub(crate) fn end_tag(&self, tag_markers: (&str, &str), fragment: &str) -> String {
+        let (open, close) = tag_markers;
+        match self {
+            Self::Jinja => format!("{open} endblock {close}"),
+            Self::Go => format!("{open} end {close}"),
+            Self::Handlebars => format!("{{{{/inline}}}}{{{{> {fragment}}}}}"),
+            Self::Liquid => format!("{open} endcapture {close}{{{{ {fragment} }}}}"),
+        }
+    }
+
+    /// Whether the fragment itself uses a different start tag than enclosing
+    /// fragments
+    pub(crate) fn has_own_start_tag(&self) -> bool {
+        matches!(self, Self::Go | Self::Handlebars | Self::Liquid)
+    }
+
+    /// Whether the output of the block fragment itself is enclosed in the
+    /// start and end tags
+    pub(crate) fn wraps_own_output(&self) -> bool {
+        !matches!(self, Self::Handlebars | Self::Liquid)
+    }
+}
+
+/// The template engine the output is rendered with, see
+/// [Config::output_profile]
+///
+/// All engines use the Jinja syntax with `{# ... #}` comments and emit block
+/// fragments as Jinja blocks with named end tags, e.g., `{% endblock item
+/// %}`, which makes mismatched blocks an error of the engine. Jinja2 blocks
+/// are additionally `scoped`, so that blocks inside loops can access the loop
+/// variables.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OutputProfile {
+    /// MiniJinja, its blocks are always scoped
+    MiniJinja,
+    /// Jinja2, blocks are emitted as `{% block NAME scoped %}`
+    Jinja2,
+    /// Tera, which does not support scoped blocks
+    Tera,
+    /// Django templates, which do not support scoped blocks
+    Django,
+}
+
+impl Config {
+    /// Select the emission options suitable for the given template engine
+    ///
+    /// Sets [Syntax::jinja], [BlockStyle::Jinja], [Config::named_endblocks],
+    /// and [Config::scoped_blocks] as described in [OutputProfile]. Options
+    /// set afterwards take precedence, e.g., other tag markers.
+    ///
+    /// ```rust
+    /// # use template_fragments::{Config, OutputProfile};
+    /// let source = concat!(
+    ///     "{% for item in items %}\n",
+    ///     "{% fragment-block item %}\n",
+    ///     "<li>{{ item }}</li>\n",
+    ///     "{% endfragment-block %}\n",
+    ///     "{% endfor %}\n",
+    /// );
+    ///
+    /// let config = Config::default().output_profile(OutputProfile::Jinja2);
+    /// assert_eq!(
+    ///     config.split_templates(source).unwrap()[""],
+    ///     concat!(
+    ///         "{% for item in items %}\n",
+    ///         "{% block item scoped %}\n",
+    ///         "<li>{{ item }}</li>\n",
+    ///         "{% endblock item %}\n",
+    ///         "{% endfor %}\n",
+    ///     ),
+    /// );
+    ///
+    /// let config = Config::default().output_profile(OutputProfile::Django);
+    /// assert_eq!(
+    ///     config.filter_template(source, "item").unwrap(),
+    ///     "{% block item %}\n<li>{{ item }}</li>\n{% endblock item %}\n",
+    /// );
+    /// ```
+    pub fn output_profile(self, profile: OutputProfile) -> Self {
+        self.syntax(Syntax::jinja())
+            .block_style(BlockStyle::Jinja)
+            .named_endblocks(true)
+            .scoped_blocks(profile == OutputProfile::Jinja2)
+    }
+
+    /// If `true`, the `endblock` tags of emitted Jinja blocks repeat the name
+    /// of the block, e.g., `{% endblock item %}`
+    ///
+    /// This also applies to the block of [Config::extends].
+    pub fn named_endblocks(mut self, value: bool) -> Self {
+        self.named_endblocks = value;
+        self
+    }
+
+    /// If `true`, emitted Jinja blocks are marked as `scoped`, e.g., `{% block
+    /// item scoped %}`
+    ///
+    /// Jinja2 blocks cannot access variables of enclosing loops otherwise.
+    /// Engines that do not support the modifier, e.g., Tera or Django,
+    /// reject such blocks.
+    pub fn scoped_blocks(mut self, value: bool) -> Self {
+        self.scoped_blocks = value;
+        self
+    }
+
+    /// The tag starting the block of a fragment in the configured style
+    pub(crate) fn block_start_tag(&self, fragment: &str, own: bool) -> String {
+        let tag_markers = self.syntax.tag_markers();
+        match self.block_style {
+            BlockStyle::Jinja if self.scoped_blocks => {
+                let (open, close) = tag_markers;
+                format!("{open} block {fragment} scoped {close}")
+            }
+            style => style.start_tag(tag_markers, fragment, own),
+        }
+    }
+
+    /// The tag ending the block of a fragment in the configured style
+    pub(crate) fn block_end_tag(&self, fragment: &str) -> String {
+        match self.block_style {
+            BlockStyle::Jinja => self.endblock_tag(fragment),
+            style => style.end_tag(self.syntax.tag_markers(), fragment),
+        }
+    }
+
+    /// The `endblock` tag of a Jinja block
+    pub(crate) fn endblock_tag(&self, block: &str) -> String {
+        let (open, close) = self.syntax.tag_markers();
+        if self.named_endblocks {
+            format!("{open} endblock {block} {close}")
+        } else {
+            format!("{open} endblock {close}")
+        }
+    }
+}
@@ -0,0 +1,163 @@
+//! Integration with the [poem](https://docs.rs/poem) web framework
+//!
+//! Requires the `poem` feature. [TemplateStore] shares split templates between
+//! handlers and selects the full page or the requested fragment depending on
+//! whether a request was sent by HTMX, see [FragmentRequest]. As this crate
+//! does not render templates, rendering is left to the template engine of the
+//! application.
+//!
+//! ```rust
+//! # use template_fragments::{poem::TemplateStore, split_templates, join_path};
+//! # use poem::{Route, get};
+//! let source = concat!(
+//!     "<ul>\n",
+//!     "{% fragment items %}\n",
+//!     "  <li>{{ item }}</li>\n",
+//!     "{% endfragment %}\n",
+//!     "</ul>\n",
+//! );
+//!
+//! let mut store = TemplateStore::default();
+//! store.add("index.html", source).unwrap();
+//!
+//! // full page loads render index.html, HTMX requests index.html#items
+//! let app = Route::new().at(
+//!     "/",
+//!     get(store.endpoint("index.html#items", |src, _req| Ok(src.to_owned()))),
+//! );
+//! ```
+use std::{collections::HashMap, sync::Arc};
+
+use ::poem::{
+    error::NotFoundError, http::header, web::Html, Endpoint, FromRequest, IntoResponse, Request,
+    RequestBody, Response, Result,
+};
+
+use crate::{split_path, Config, ErrorWithPath};
+
+/// Whether a request asks for a fragment or for the full page
+///
+/// Requests sent by HTMX carry the `HX-Request` header and ask for fragments.
+/// Boosted requests and history restores ask for the full page, even though
+/// they are sent by HTMX. It can be used as an extractor in handlers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FragmentRequest {
+    /// `true` if the fragment should be rendered, `false` for the full page
+    pub is_fragment: bool,
+}
+
+impl FragmentRequest {
+    /// Determine the kind of request from its headers
+    pub fn from_headers(req: &Request) -> Self {
+        let is_set = |name: &str| {
+            req.header(name)
+                .is_some_and(|value| value.eq_ignore_ascii_case("true"))
+        };
+        Self {
+            is_fragment: is_set("HX-Request")
+                && !is_set("HX-Boosted")
+                && !is_set("HX-History-Restore-Request"),
+        }
+    }
+}
+
+impl<'a> FromRequest<'a> for FragmentRequest {
+    async fn from_request(req: &'a Request, _body: &mut RequestBody) -> Result<Self> {
+        Ok(Self::from_headers(req))
+    }
+}
+
+/// Split templates that are shared between handlers
+///
+/// Templates are keyed by `join_path(path, fragment)`, as returned by
+/// [crate::split_directory]. Cloning the store is cheap.
+#[derive(Debug, Clone, Default)]
+pub struct TemplateStore {
+    config: Config,
+    templates: Arc<HashMap<String, String>>,
+}
+
+impl TemplateStore {
+    /// Build a store with the given config from already split templates
+    pub fn new(config: Config, templates: HashMap<String, String>) -> Self {
+        Self {
+            config,
+            templates: Arc::new(templates),
+        }
+    }
+
+    /// Split the template and add all its fragments under the given path
+    pub fn add(&mut self, path: &str, src: &str) -> Result<(), ErrorWithPath> {
+        let fragments = self
+            .config
+            .split_templates(src)
+            .map_err(|err| err.in_path(path))?;
+
+        let templates = Arc::make_mut(&mut self.templates);
+        for (fragment, content) in fragments {
+            templates.insert(self.config.join_path(path, &fragment), content);
+        }
+        Ok(())
+    }
+
+    /// Get a template by its name, e.g., `index.html#item`
+    pub fn get(&self, name: &str) -> Option<&str> {
+        let (path, fragment) = split_path(name);
+        self.templates
+            .get(&self.config.join_path(path, fragment))
+            .map(String::as_str)
+    }
+
+    /// Get the template for a request: the fragment of the name for fragment
+    /// requests, otherwise the full page
+    pub fn resolve(&self, name: &str, request: FragmentRequest) -> Option<&str> {
+        if request.is_fragment {
+            self.get(name)
+        } else {
+            self.get(split_path(name).0)
+        }
+    }
+
+    /// An endpoint that renders the template resolved for each request
+    ///
+    /// `render` is called with the source of the resolved template and the
+    /// request. The response is sent as HTML with `Vary: HX-Request`, as it
+    /// depends on the header. Unknown templates result in a 404 response.
+    pub fn endpoint<F>(&self, name: &str, render: F) -> FragmentEndpoint<F>
+    where
+        F: Fn(&str, &Request) -> Result<String> + Send + Sync,
+    {
+        FragmentEndpoint {
+            store: self.clone(),
+            name: name.to_owned(),
+            render,
+        }
+    }
+}
+
+/// The endpoint returned by [TemplateStore::endpoint]
+pub struct FragmentEndpoint<F> {
+    store: TemplateStore,
+    name: String,
+    render: F,
+}
+
+impl<F> Endpoint for FragmentEndpoint<F>
+where
+    F: Fn(&str, &Request) -> Result<String> + Send + Sync,
+{
+    type Output = Response;
+
+    async fn call(&self, req: Request) -> Result<Self::Output> {
+        let request = FragmentRequest::from_headers(&req);
+        let src = self
+            .store
+            .resolve(&self.name, request)
+            .ok_or(NotFoundError)?;
+        let html = (self.render)(src, &req)?;
+
+        Ok(Html(html)
+            .with_header(header::VARY, "HX-Request")
+            .into_response())
+    }
+}
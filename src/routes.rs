@@ -0,0 +1,109 @@
+use std::collections::BTreeMap;
+
+use crate::join_path;
+
+/// A mapping between template fragments and the URLs they are served under
+///
+/// Each named fragment of a template is mapped to `{prefix}/{stem}/{fragment}`,
+/// where the stem is the template path without its file extensions. For
+/// example, `index.html#item` is served under `/partials/index/item`. This way,
+/// `hx-get` attributes and the routes of the server can be generated from the
+/// same source.
+///
+/// ```rust
+/// # use template_fragments::FragmentRoutes;
+/// let mut routes = FragmentRoutes::default();
+/// routes.add("index.html", ["", "item", "footer"]);
+///
+/// assert_eq!(routes.url("index.html", "item"), Some("/partials/index/item"));
+/// assert_eq!(
+///     routes.lookup("/partials/index/item"),
+///     Some(("index.html", "item")),
+/// );
+/// assert_eq!(routes.name("/partials/index/item"), Some(String::from("index.html#item")));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FragmentRoutes {
+    prefix: String,
+    routes: BTreeMap<String, (String, String)>,
+}
+
+impl Default for FragmentRoutes {
+    fn default() -> Self {
+        Self::new("/partials")
+    }
+}
+
+impl FragmentRoutes {
+    /// Construct an empty mapping that serves fragments below `prefix`
+    ///
+    /// The default prefix is `/partials`.
+    pub fn new(prefix: &str) -> Self {
+        Self {
+            prefix: prefix.trim_end_matches('/').to_owned(),
+            routes: BTreeMap::new(),
+        }
+    }
+
+    /// Add the fragments of a template
+    ///
+    /// The base fragment `""` is skipped, as it is the full template. If
+    /// another fragment maps to the same URL, e.g., for `index.html` and
+    /// `index.txt`, it is replaced.
+    pub fn add<F: AsRef<str>>(&mut self, template: &str, fragments: impl IntoIterator<Item = F>) {
+        for fragment in fragments {
+            let fragment = fragment.as_ref().trim();
+            if fragment.is_empty() {
+                continue;
+            }
+
+            let url = self.route(template, fragment);
+            self.routes
+                .insert(url, (template.trim().to_owned(), fragment.to_owned()));
+        }
+    }
+
+    /// Compute the URL of a fragment without adding it to the mapping
+    pub fn route(&self, template: &str, fragment: &str) -> String {
+        let template = template.trim().trim_start_matches('/');
+        let stem = match template.rsplit_once('/') {
+            Some((dir, file)) => format!("{dir}/{}", file_stem(file)),
+            None => file_stem(template).to_owned(),
+        };
+        format!("{}/{}/{}", self.prefix, stem, fragment.trim())
+    }
+
+    /// The URL of a previously added fragment
+    pub fn url(&self, template: &str, fragment: &str) -> Option<&str> {
+        let (url, (added, _)) = self.routes.get_key_value(&self.route(template, fragment))?;
+        (added == template.trim()).then_some(url.as_str())
+    }
+
+    /// Find the template and fragment served under the given URL
+    pub fn lookup(&self, url: &str) -> Option<(&str, &str)> {
+        let (template, fragment) = self.routes.get(url)?;
+        Some((template, fragment))
+    }
+
+    /// The name of the fragment served under the given URL, as returned by
+    /// [crate::join_path]
+    pub fn name(&self, url: &str) -> Option<String> {
+        let (template, fragment) = self.lookup(url)?;
+        Some(join_path(template, fragment))
+    }
+
+    /// Iterate over all `(url, template, fragment)` triples sorted by URL
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str, &str)> {
+        self.routes
+            .iter()
+            .map(|(url, (template, fragment))| (url.as_str(), template.as_str(), fragment.as_str()))
+    }
+}
+
+fn file_stem(file: &str) -> &str {
+    // skip the leading dot of hidden files
+    match file.char_indices().skip(1).find(|&(_, c)| c == '.') {
+        Some((idx, _)) => &file[..idx],
+        None => file,
+    }
+}
@@ -0,0 +1,182 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{join_path, split_path, Config, ErrorWithPath};
+
+/// A collection of split templates, keyed by `path#fragment`
+///
+/// Templates are split when they are inserted. Inserting a template again
+/// replaces all its fragments. Names that are already defined by another
+/// template are reported as [TemplateSetError::Conflict]. A template set can
+/// be shared between threads, e.g., behind an `Arc`.
+///
+/// ```rust
+/// # use std::sync::Arc;
+/// # use template_fragments::TemplateSet;
+/// let mut templates = TemplateSet::new();
+/// templates
+///     .insert(
+///         "index.html",
+///         concat!(
+///             "<ul>\n",
+///             "{% fragment item %}\n",
+///             "  <li>{{ item }}</li>\n",
+///             "{% endfragment %}\n",
+///             "</ul>\n",
+///         ),
+///     )
+///     .unwrap();
+///
+/// let templates = Arc::new(templates);
+/// assert_eq!(templates.get("index.html#item"), Some("  <li>{{ item }}</li>\n"));
+/// assert_eq!(templates.get("index.html"), Some("<ul>\n  <li>{{ item }}</li>\n</ul>\n"));
+/// assert_eq!(templates.get("index.html#missing"), None);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TemplateSet {
+    config: Option<Config>,
+    templates: HashMap<String, String>,
+    /// The names of the fragments of each template
+    paths: BTreeMap<String, Vec<String>>,
+}
+
+impl TemplateSet {
+    /// A template set that selects the config of each template with
+    /// [Config::for_path]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A template set that uses the given config for all templates
+    pub fn with_config(config: Config) -> Self {
+        Self {
+            config: Some(config),
+            ..Self::default()
+        }
+    }
+
+    /// Split the template and add all its fragments
+    ///
+    /// Previous fragments of the same path are replaced. On errors, the set is
+    /// not modified.
+    pub fn insert(&mut self, path: &str, src: &str) -> Result<(), TemplateSetError> {
+        let path = path.trim();
+        let templates = match &self.config {
+            Some(config) => config.split_templates_prefixed(path, src),
+            None => Config::for_path(path).split_templates_prefixed(path, src),
+        };
+        let templates = templates.map_err(|err| TemplateSetError::Template(err.in_path(path)))?;
+
+        for name in templates.keys() {
+            if let Some(other) = self.owner(name) {
+                if other != path {
+                    return Err(TemplateSetError::Conflict(name.clone(), other.to_owned()));
+                }
+            }
+        }
+
+        self.remove(path);
+        let mut names: Vec<String> = templates.keys().cloned().collect();
+        names.sort();
+        self.templates.extend(templates);
+        self.paths.insert(path.to_owned(), names);
+        Ok(())
+    }
+
+    /// Remove all fragments of a template, returns whether it was present
+    pub fn remove(&mut self, path: &str) -> bool {
+        let Some(names) = self.paths.remove(path.trim()) else {
+            return false;
+        };
+        for name in names {
+            self.templates.remove(&name);
+        }
+        true
+    }
+
+    /// Look up a fragment by its full name, e.g., `index.html#item`
+    ///
+    /// The base template is returned for names without fragment.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        let (path, fragment) = split_path(name);
+        self.get_fragment(path, fragment)
+    }
+
+    /// Look up a fragment of a template
+    pub fn get_fragment(&self, path: &str, fragment: &str) -> Option<&str> {
+        let name = match &self.config {
+            Some(config) => config.join_path(path, fragment),
+            None => join_path(path, fragment),
+        };
+        self.templates.get(&name).map(String::as_str)
+    }
+
+    /// Whether the full name, e.g., `index.html#item`, is defined
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// The sorted paths of all templates
+    pub fn paths(&self) -> impl Iterator<Item = &str> {
+        self.paths.keys().map(String::as_str)
+    }
+
+    /// The sorted full names of the fragments of a template
+    pub fn fragments(&self, path: &str) -> Option<&[String]> {
+        self.paths.get(path.trim()).map(Vec::as_slice)
+    }
+
+    /// Iterate over all full names and contents in arbitrary order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.templates
+            .iter()
+            .map(|(name, content)| (name.as_str(), content.as_str()))
+    }
+
+    /// The number of fragments of all templates
+    pub fn len(&self) -> usize {
+        self.templates.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.templates.is_empty()
+    }
+
+    /// The path of the template that defines the full name
+    fn owner(&self, name: &str) -> Option<&str> {
+        if !self.templates.contains_key(name) {
+            return None;
+        }
+        self.paths
+            .iter()
+            .find(|(_, names)| names.binary_search_by(|n| n.as_str().cmp(name)).is_ok())
+            .map(|(path, _)| path.as_str())
+    }
+}
+
+/// Errors that can occur when inserting into a [TemplateSet]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateSetError {
+    /// An invalid template
+    Template(ErrorWithPath),
+    /// A full name that is already defined by another template, with the path
+    /// of the other template
+    Conflict(String, String),
+}
+
+impl std::fmt::Display for TemplateSetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Template(err) => write!(f, "{err}"),
+            Self::Conflict(name, other) => write!(f, "{name:?} is already defined by {other}"),
+        }
+    }
+}
+
+impl std::error::Error for TemplateSetError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Template(err) => Some(err),
+            Self::Conflict(_, _) => None,
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{numbered::process_numbered, process_template, push_line, Config, ErrorWithLine};
+
+/// A receiver of the output of [Config::split_templates_to_sink]
+///
+/// The output of each fragment is passed in order as chunks of one or more
+/// complete lines. This way, fragments can be streamed into files, databases,
+/// or the sources of template engines without collecting them first.
+///
+/// ```rust
+/// # use template_fragments::{Config, FragmentSink};
+/// /// Count the bytes of each fragment
+/// #[derive(Default)]
+/// struct Sizes(Vec<(String, usize)>);
+///
+/// impl FragmentSink for Sizes {
+///     fn emit(&mut self, fragment: &str, lines: &str) {
+///         match self.0.iter_mut().find(|(name, _)| name == fragment) {
+///             Some((_, size)) => *size += lines.len(),
+///             None => self.0.push((fragment.to_owned(), lines.len())),
+///         }
+///     }
+/// }
+///
+/// let source = "<ul>\n{% fragment item %}\n<li>{{ item }}</li>\n{% endfragment %}\n</ul>\n";
+/// let mut sizes = Sizes::default();
+/// Config::default().split_templates_to_sink(source, &mut sizes).unwrap();
+///
+/// assert_eq!(sizes.0, [(String::from(""), 31), (String::from("item"), 20)]);
+/// ```
+pub trait FragmentSink {
+    /// Receive consecutive output lines of a fragment, including their line
+    /// endings
+    fn emit(&mut self, fragment: &str, lines: &str);
+}
+
+/// Collect the output of each fragment, as [Config::split_templates] does
+/// without output options
+impl FragmentSink for HashMap<String, String> {
+    fn emit(&mut self, fragment: &str, lines: &str) {
+        push_line(self, fragment, lines);
+    }
+}
+
+impl<S: FragmentSink + ?Sized> FragmentSink for &mut S {
+    fn emit(&mut self, fragment: &str, lines: &str) {
+        (**self).emit(fragment, lines);
+    }
+}
+
+impl Config {
+    /// Split the template and stream the output of all fragments into a sink
+    ///
+    /// In contrast to [Config::split_templates], the output is passed on as
+    /// soon as it is produced. Options that transform the complete output of
+    /// a fragment are not applied, e.g., [Config::minify_html], `with`
+    /// contexts, [Config::extends], [Config::macros], or debug annotations.
+    /// [Config::skip_base], [Config::number_repeated_fragments], and
+    /// [Config::fragment_separator] are respected. If the template is
+    /// invalid, the sink may have received output before the error.
+    pub fn split_templates_to_sink(
+        &self,
+        src: &str,
+        mut sink: impl FragmentSink,
+    ) -> Result<(), ErrorWithLine> {
+        let (src, lines) = self.preprocess(src)?;
+
+        let mut emit = |active: &HashSet<&str>, keys: &HashMap<&str, String>, output: &str| {
+            for &fragment in active {
+                if !(self.skip_base && fragment.is_empty()) {
+                    sink.emit(keys.get(fragment).map_or(fragment, String::as_str), output);
+                }
+            }
+        };
+        if self.number_repeated_fragments {
+            process_numbered(self, &src, emit)
+        } else {
+            process_template(self, &src, |active, output| {
+                emit(active, &HashMap::new(), output)
+            })
+        }
+        .map_err(|err| lines.restore(err))
+    }
+}
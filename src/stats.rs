@@ -0,0 +1,114 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::{iterate_with_endings, Config, ErrorWithLine, Processor, Tag};
+
+/// Statistics of a single fragment
+///
+/// The lines and bytes refer to the output of the fragment, i.e., to the
+/// result of [crate::filter_template].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FragmentStats {
+    /// The number of start tags of the fragment, `1` for the base fragment
+    pub occurrences: usize,
+    /// The number of lines of the fragment
+    pub lines: usize,
+    /// The size of the fragment in bytes
+    pub bytes: usize,
+    /// The maximum nesting depth of the fragment, `1` for top-level fragments
+    /// and `0` for the base fragment
+    pub depth: usize,
+    /// The sorted names of all fragments nested inside of the fragment
+    pub contains: Vec<String>,
+}
+
+/// Compute the statistics of all fragments, including the base fragment `""`
+///
+/// ```rust
+/// # use template_fragments::fragment_stats;
+/// let source = concat!(
+///     "<body>\n",
+///     "{% fragment items %}\n",
+///     "{% for item in items %}\n",
+///     "  {% fragment item %}\n",
+///     "  <div>{{ item }}</div>\n",
+///     "  {% endfragment %}\n",
+///     "{% endfor %}\n",
+///     "{% endfragment %}\n",
+///     "<body>\n",
+/// );
+///
+/// let stats = fragment_stats(source).unwrap();
+/// assert_eq!(stats["items"].lines, 3);
+/// assert_eq!(stats["items"].contains, vec!["item"]);
+/// assert_eq!(stats["item"].bytes, "  <div>{{ item }}</div>\n".len());
+/// assert_eq!(stats["item"].depth, 2);
+/// ```
+pub fn fragment_stats(src: &str) -> Result<HashMap<String, FragmentStats>, ErrorWithLine> {
+    Config::default().fragment_stats(src)
+}
+
+impl Config {
+    /// Compute the statistics of all fragments
+    ///
+    /// See [crate::fragment_stats].
+    pub fn fragment_stats(
+        &self,
+        src: &str,
+    ) -> Result<HashMap<String, FragmentStats>, ErrorWithLine> {
+        let (src, lines) = self.preprocess(src)?;
+        let mut processor = Processor::new(self);
+        let mut stats: HashMap<String, FragmentStats> = HashMap::new();
+        let mut contains: HashMap<String, BTreeSet<String>> = HashMap::new();
+
+        stats.insert(
+            String::new(),
+            FragmentStats {
+                occurrences: 1,
+                ..Default::default()
+            },
+        );
+
+        for (line_idx, line) in iterate_with_endings(&src).enumerate() {
+            let tag = processor
+                .parse(line)
+                .map_err(|err| lines.restore(err.at(line_idx)))?;
+
+            let started: Vec<&str> = match &tag {
+                Some(Tag::Start(tag)) => tag.fragments.iter().copied().collect(),
+                Some(Tag::StartBlock(tag)) => vec![tag.fragment],
+                _ => Vec::new(),
+            };
+            let depth = processor.stack.depth() + 1;
+            for &fragment in &started {
+                let entry = stats.entry(fragment.to_owned()).or_default();
+                entry.occurrences += 1;
+                entry.depth = entry.depth.max(depth);
+
+                for &outer in &processor.stack.active_fragments {
+                    contains
+                        .entry(outer.to_owned())
+                        .or_default()
+                        .insert(fragment.to_owned());
+                }
+            }
+
+            processor
+                .apply(line_idx, line, tag, |active, line| {
+                    for &fragment in active {
+                        let entry = stats.entry(fragment.to_owned()).or_default();
+                        entry.lines += 1;
+                        entry.bytes += line.len();
+                    }
+                })
+                .map_err(|err| lines.restore(err))?;
+        }
+        processor.done().map_err(|err| lines.restore(err))?;
+
+        for (fragment, contained) in contains {
+            if let Some(entry) = stats.get_mut(&fragment) {
+                entry.contains = contained.into_iter().collect();
+            }
+        }
+        Ok(stats)
+    }
+}
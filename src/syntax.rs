@@ -0,0 +1,124 @@
+use crate::{DEFAULT_COMMENT_MARKERS, DEFAULT_TAG_MARKERS};
+
+/// The markers used to recognize fragment tags and comments
+///
+/// Fragment tags use the same keywords for all syntaxes, e.g., with
+/// [Syntax::erb] fragments are written as `<% fragment NAME %>` and `<%
+/// endfragment %>`.
+///
+/// ```rust
+/// # use template_fragments::{Config, Syntax};
+/// let source = concat!(
+///     "<ul>\n",
+///     "<% fragment item %>\n",
+///     "  <li><%= item %></li>\n",
+///     "<% endfragment %>\n",
+///     "</ul>\n",
+/// );
+///
+/// let templates = Config::default().syntax(Syntax::erb()).split_templates(source).unwrap();
+/// assert_eq!(templates["item"], "  <li><%= item %></li>\n");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Syntax {
+    pub(crate) tag_markers: (String, String),
+    pub(crate) comment_markers: (String, String),
+}
+
+impl std::default::Default for Syntax {
+    fn default() -> Self {
+        Self::jinja()
+    }
+}
+
+impl Syntax {
+    /// A syntax with custom markers for tags and comments
+    pub fn new(tag_markers: (&str, &str), comment_markers: (&str, &str)) -> Self {
+        Self {
+            tag_markers: (tag_markers.0.to_owned(), tag_markers.1.to_owned()),
+            comment_markers: (comment_markers.0.to_owned(), comment_markers.1.to_owned()),
+        }
+    }
+
+    /// Jinja-like engines (the default): `{% ... %}` and `{# ... #}`
+    pub fn jinja() -> Self {
+        Self::new(DEFAULT_TAG_MARKERS, DEFAULT_COMMENT_MARKERS)
+    }
+
+    /// Embedded Ruby like engines: `<% ... %>` and `<%# ... %>`
+    pub fn erb() -> Self {
+        Self::new(("<%", "%>"), ("<%#", "%>"))
+    }
+
+    /// Go templates: `{{ ... }}` and `{{/* ... */}}`
+    pub fn golang() -> Self {
+        Self::new(("{{", "}}"), ("{{/*", "*/}}"))
+    }
+
+    /// Liquid: `{% ... %}` and `{% comment %} ... {% endcomment %}`
+    pub fn liquid() -> Self {
+        Self::new(DEFAULT_TAG_MARKERS, ("{% comment %}", "{% endcomment %}"))
+    }
+
+    /// Handlebars: fragment tags in comments `{{!-- ... --}}`
+    ///
+    /// As the tags are comments, annotated templates can still be rendered
+    /// directly.
+    pub fn handlebars() -> Self {
+        Self::new(("{{!--", "--}}"), ("{{!--", "--}}"))
+    }
+
+    /// Fragment tags in HTML comments: `<!-- ... -->`
+    ///
+    /// This syntax can be used for engines without a dedicated tag syntax.
+    pub fn html_comments() -> Self {
+        Self::new(("<!--", "-->"), ("<!--", "-->"))
+    }
+
+    /// Fragment tags in C-style block comments: `/* ... */`
+    ///
+    /// This syntax can be used for CSS or JavaScript sources.
+    pub fn c_comments() -> Self {
+        Self::new(("/*", "*/"), ("/*", "*/"))
+    }
+
+    /// Jinja-LaTeX: `((* ... *))` and `((= ... =))`
+    ///
+    /// These delimiters do not clash with the braces and percent signs of
+    /// LaTeX. Use [Syntax::latex_commands] for `\BLOCK{...}` markers.
+    pub fn latex() -> Self {
+        Self::new(("((*", "*))"), ("((=", "=))"))
+    }
+
+    /// Jinja-LaTeX with command markers: `\BLOCK{...}` and `\#{...}`
+    ///
+    /// As common for these markers, the tags may be written without spaces,
+    /// e.g., `\BLOCK{fragment summary}` and `\BLOCK{endfragment}`.
+    ///
+    /// ```rust
+    /// # use template_fragments::{Config, Syntax};
+    /// let source = concat!(
+    ///     "\\section{Report}\n",
+    ///     "\\BLOCK{fragment summary}\n",
+    ///     "\\textbf{\\VAR{summary}}\n",
+    ///     "\\BLOCK{endfragment}\n",
+    /// );
+    ///
+    /// let config = Config::default().syntax(Syntax::latex_commands());
+    /// let templates = config.split_templates(source).unwrap();
+    /// assert_eq!(templates["summary"], "\\textbf{\\VAR{summary}}\n");
+    /// ```
+    pub fn latex_commands() -> Self {
+        Self::new(("\\BLOCK{", "}"), ("\\#{", "}"))
+    }
+
+    /// The markers of tags
+    pub fn tag_markers(&self) -> (&str, &str) {
+        (&self.tag_markers.0, &self.tag_markers.1)
+    }
+
+    /// The markers of comments
+    pub fn comment_markers(&self) -> (&str, &str) {
+        (&self.comment_markers.0, &self.comment_markers.1)
+    }
+}
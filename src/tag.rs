@@ -0,0 +1,111 @@
+use std::ops::Range;
+
+use crate::{parse_base, parse_fragment_tag, Config, Error, FragmentType};
+
+/// Parse a single line as a fragment tag
+///
+/// The same rules as for processing templates are used. Lines without a
+/// fragment tag result in `Ok(None)`, invalid tags in an error. The spans of
+/// the result are byte ranges into the line. This way, tools like formatters,
+/// syntax highlighters, or linters can reuse the exact parsing rules.
+///
+/// ```rust
+/// # use template_fragments::{parse_tag, TagKind};
+/// let line = "  {% fragment item footer %}\n";
+/// let tag = parse_tag(line).unwrap().unwrap();
+///
+/// assert_eq!(tag.kind, TagKind::Start);
+/// assert_eq!(&line[tag.span.clone()], "{% fragment item footer %}");
+/// assert_eq!(tag.names[0].text, "item");
+/// assert_eq!(tag.names[1].span, 19..25);
+///
+/// assert_eq!(parse_tag("<li>{{ item }}</li>\n"), Ok(None));
+/// assert!(parse_tag("<li>{% fragment item %}</li>\n").is_err());
+/// ```
+pub fn parse_tag(line: &str) -> Result<Option<ParsedTag<'_>>, Error> {
+    Config::default().parse_tag(line)
+}
+
+impl Config {
+    /// Parse a single line as a fragment tag with the markers of the config
+    ///
+    /// See [crate::parse_tag].
+    pub fn parse_tag<'l>(&self, line: &'l str) -> Result<Option<ParsedTag<'l>>, Error> {
+        let tag_markers = self.syntax.tag_markers();
+        if parse_fragment_tag(line, tag_markers)?.is_none() {
+            return Ok(None);
+        }
+        let Some(parts) = parse_base(line, tag_markers) else {
+            return Ok(None);
+        };
+
+        let kind = match parts.fragment_type {
+            FragmentType::Start => TagKind::Start,
+            FragmentType::End => TagKind::End,
+            FragmentType::BlockStart => TagKind::StartBlock,
+            FragmentType::BlockEnd => TagKind::EndBlock,
+        };
+
+        let mut names = Vec::new();
+        let mut attributes = Vec::new();
+        for part in parts.data.split_whitespace() {
+            match part.split_once('=') {
+                Some((key, value)) => {
+                    attributes.push((TagPart::new(line, key), TagPart::new(line, value)))
+                }
+                None => names.push(TagPart::new(line, part)),
+            }
+        }
+
+        Ok(Some(ParsedTag {
+            kind,
+            span: parts.head.len()..line.len() - parts.tail.len(),
+            names,
+            attributes,
+        }))
+    }
+}
+
+/// A fragment tag parsed with [crate::parse_tag]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedTag<'a> {
+    pub kind: TagKind,
+    /// The tag including its markers
+    pub span: Range<usize>,
+    /// The fragment names in the order they are written, for end tags the
+    /// names checked with [Config::named_end_tags]
+    pub names: Vec<TagPart<'a>>,
+    /// The keys and values of the attributes, e.g., `with=item`
+    pub attributes: Vec<(TagPart<'a>, TagPart<'a>)>,
+}
+
+/// The kind of a fragment tag
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TagKind {
+    /// `{% fragment NAMES... %}`
+    Start,
+    /// `{% endfragment %}`
+    End,
+    /// `{% fragment-block NAME %}`
+    StartBlock,
+    /// `{% endfragment-block %}`
+    EndBlock,
+}
+
+/// A part of a fragment tag with its byte range in the line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagPart<'a> {
+    pub text: &'a str,
+    pub span: Range<usize>,
+}
+
+impl<'a> TagPart<'a> {
+    /// Construct the part from a subslice of the line
+    fn new(line: &str, text: &'a str) -> Self {
+        let start = text.as_ptr() as usize - line.as_ptr() as usize;
+        Self {
+            text,
+            span: start..start + text.len(),
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use crate::{
+    numbered::process_numbered, process_template, push_line, Config, ErrorWithLine, NameCase,
+};
+
+/// A template that is processed once and can be filtered many times
+///
+/// All fragments are extracted when parsing the template. Afterwards,
+/// filtering only looks up the fragment. This way, servers that filter the same
+/// template for different fragments on every request do not process it
+/// repeatedly.
+///
+/// ```rust
+/// # use template_fragments::Template;
+/// let source = concat!(
+///     "<body>\n",
+///     "  {% fragment item %}\n",
+///     "    <div>{{ item }}</div>\n",
+///     "  {% endfragment %}\n",
+///     "<body>\n",
+/// );
+///
+/// let template = Template::parse(source).unwrap();
+/// assert_eq!(template.names(), ["item"]);
+/// assert!(template.has("item"));
+/// assert!(!template.has("footer"));
+/// assert_eq!(template.filter("item"), "    <div>{{ item }}</div>\n");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    name_case: NameCase,
+    names: Vec<String>,
+    templates: HashMap<String, String>,
+}
+
+impl Template {
+    /// Parse the template with the default configuration
+    ///
+    /// See [Config::parse_template].
+    pub fn parse(src: &str) -> Result<Self, ErrorWithLine> {
+        Config::default().parse_template(src)
+    }
+
+    /// Return all parts of the given fragment, as [crate::filter_template]
+    ///
+    /// As for [crate::filter_template], unknown fragments result in an empty
+    /// string.
+    pub fn filter(&self, fragment: &str) -> &str {
+        self.templates
+            .get(self.name_case.normalize(fragment).as_ref())
+            .map(String::as_str)
+            .unwrap_or_default()
+    }
+
+    /// The sorted names of all fragments, without the base fragment `""`
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Whether the template defines the given fragment
+    ///
+    /// The base fragment `""` is always defined.
+    pub fn has(&self, fragment: &str) -> bool {
+        let fragment = self.name_case.normalize(fragment);
+        fragment.is_empty()
+            || self
+                .names
+                .binary_search_by(|name| name.as_str().cmp(&fragment))
+                .is_ok()
+    }
+}
+
+impl Config {
+    /// Parse the template for repeated filtering
+    ///
+    /// See [Template].
+    pub fn parse_template(&self, src: &str) -> Result<Template, ErrorWithLine> {
+        let (src, lines) = self.preprocess(src)?;
+        let mut templates: HashMap<String, String> = HashMap::new();
+
+        let mut push = |active: &HashSet<&str>, keys: &HashMap<&str, String>, line: &str| {
+            for &fragment in active {
+                let key = keys.get(fragment).map_or(fragment, String::as_str);
+                push_line(&mut templates, key, line);
+            }
+        };
+        if self.number_repeated_fragments {
+            process_numbered(self, &src, push)
+        } else {
+            process_template(self, &src, |active, line| {
+                push(active, &HashMap::new(), line)
+            })
+        }
+        .map_err(|err| lines.restore(err))?;
+        self.finish_fragments(
+            &src,
+            &lines,
+            templates.iter_mut().map(|(k, v)| (k.as_str(), v)),
+        );
+
+        // numbered keys are only known once the occurrences are processed
+        let mut names: BTreeSet<String> = self
+            .fragment_names(&src)
+            .into_iter()
+            .map(str::to_owned)
+            .collect();
+        names.extend(templates.keys().filter(|key| !key.is_empty()).cloned());
+        let names: Vec<String> = names.into_iter().collect();
+
+        Ok(Template {
+            name_case: self.name_case,
+            names,
+            templates,
+        })
+    }
+}
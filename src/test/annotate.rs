@@ -0,0 +1,141 @@
+use crate::Config;
+
+const SOURCE: &str = concat!(
+    "<ul>\n",
+    "  {% fragment item %}\n",
+    "  <li>{{ item }}</li>\n",
+    "  {% endfragment %}\n",
+    "  {% fragment item footer %}\n",
+    "  <li>{{ footer }}</li>\n",
+    "  {% endfragment %}\n",
+    "</ul>\n",
+);
+
+#[test]
+fn headers() {
+    let config = Config::default().debug_annotations(true);
+    let templates = config.split_templates(SOURCE).unwrap();
+
+    assert_eq!(
+        templates["item"],
+        concat!(
+            "{# fragment \"item\" lines 2-4, 5-7 #}\n",
+            "  <li>{{ item }}</li>\n",
+            "  <li>{{ footer }}</li>\n",
+        )
+    );
+    assert_eq!(
+        templates["footer"],
+        "{# fragment \"footer\" lines 5-7 #}\n  <li>{{ footer }}</li>\n"
+    );
+    assert_eq!(
+        config.filter_template(SOURCE, "footer").unwrap(),
+        templates["footer"]
+    );
+    assert_eq!(
+        config.parse_template(SOURCE).unwrap().filter("footer"),
+        templates["footer"]
+    );
+}
+
+#[test]
+fn boundaries_in_base() {
+    let config = Config::default().debug_annotations(true);
+    assert_eq!(
+        config.filter_template(SOURCE, "").unwrap(),
+        concat!(
+            "<ul>\n",
+            "  {# begin fragment \"item\" #}\n",
+            "  <li>{{ item }}</li>\n",
+            "  {# end fragment \"item\" #}\n",
+            "  {# begin fragment \"footer\", \"item\" #}\n",
+            "  <li>{{ footer }}</li>\n",
+            "  {# end fragment \"footer\", \"item\" #}\n",
+            "</ul>\n",
+        )
+    );
+}
+
+#[test]
+fn nested_fragments() {
+    let source = concat!(
+        "{% fragment outer %}\n",
+        "<div>\n",
+        "{% fragment inner %}\n",
+        "<p></p>\n",
+        "{% endfragment %}\n",
+        "</div>\n",
+        "{% endfragment %}\n",
+    );
+    let config = Config::default().debug_annotations(true);
+    let templates = config.split_templates(source).unwrap();
+
+    assert_eq!(
+        templates["outer"],
+        "{# fragment \"outer\" lines 1-7 #}\n<div>\n<p></p>\n</div>\n"
+    );
+    assert_eq!(
+        templates["inner"],
+        "{# fragment \"inner\" lines 3-5 #}\n<p></p>\n"
+    );
+    assert_eq!(
+        templates[""],
+        concat!(
+            "{# begin fragment \"outer\" #}\n",
+            "<div>\n",
+            "{# begin fragment \"inner\" #}\n",
+            "<p></p>\n",
+            "{# end fragment \"inner\" #}\n",
+            "</div>\n",
+            "{# end fragment \"outer\" #}\n",
+        )
+    );
+}
+
+#[test]
+fn path_and_syntax() {
+    let source = "((* fragment title *))\nReport\n((* endfragment *))\n";
+    let templates = Config::for_path("report.tex")
+        .debug_annotations(true)
+        .split_templates_prefixed("report.tex", source)
+        .unwrap();
+
+    assert_eq!(
+        templates["report.tex#title"],
+        "((= fragment \"title\" from \"report.tex\" lines 1-3 =))\nReport\n"
+    );
+    assert_eq!(
+        templates["report.tex"],
+        concat!(
+            "((= begin fragment \"title\" =))\n",
+            "Report\n",
+            "((= end fragment \"title\" =))\n",
+        )
+    );
+}
+
+#[test]
+fn lines_with_defines() {
+    let source = concat!(
+        "{% ifdef DEBUG %}\n",
+        "debug\n",
+        "{% endifdef %}\n",
+        "{% fragment item %}\n",
+        "item\n",
+        "{% endfragment %}\n",
+    );
+    let config = Config::default()
+        .defines([("OTHER", "")])
+        .debug_annotations(true);
+    assert_eq!(
+        config.filter_template(source, "item").unwrap(),
+        "{# fragment \"item\" lines 4-6 #}\nitem\n"
+    );
+}
+
+#[test]
+fn disabled() {
+    let templates = Config::default().split_templates(SOURCE).unwrap();
+    assert_eq!(templates["footer"], "  <li>{{ footer }}</li>\n");
+    assert!(!templates[""].contains("{#"));
+}
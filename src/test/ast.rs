@@ -0,0 +1,160 @@
+use crate::{test::assert_matches, AstNode, Config, Error, ErrorWithLine, TemplateAst};
+
+const SOURCE: &str = concat!(
+    "<body>\n",
+    "{% fragment items role=list %}\n",
+    "<ul>\n",
+    "  {% fragment-block item %}\n",
+    "  <li>{{ item }}</li>\n",
+    "  {% endfragment-block %}\n",
+    "</ul>\n",
+    "{% endfragment %}\n",
+    "</body>",
+);
+
+#[test]
+fn structure() {
+    let ast = TemplateAst::parse(SOURCE).unwrap();
+
+    assert_eq!(ast.nodes.len(), 3);
+    assert!(matches!(&ast.nodes[0], AstNode::Text(text) if text.content == "<body>\n"));
+    assert!(matches!(&ast.nodes[2], AstNode::Text(text) if text.content == "</body>"));
+
+    let AstNode::Fragment(items) = &ast.nodes[1] else {
+        panic!("expected a fragment node");
+    };
+    assert!(!items.block);
+    assert_eq!(items.names, vec!["items"]);
+    assert_eq!(
+        items.attributes,
+        vec![(String::from("role"), String::from("list"))]
+    );
+    assert_eq!(items.start_tag, "{% fragment items role=list %}\n");
+    assert_eq!(items.end_tag, "{% endfragment %}\n");
+    assert_eq!(items.children.len(), 3);
+
+    let AstNode::Fragment(item) = &items.children[1] else {
+        panic!("expected a fragment node");
+    };
+    assert!(item.block);
+    assert_eq!(item.names, vec!["item"]);
+    assert_eq!(item.children.len(), 1);
+}
+
+#[test]
+fn spans() {
+    let ast = TemplateAst::parse(SOURCE).unwrap();
+
+    fn check(src: &str, nodes: &[AstNode]) {
+        for node in nodes {
+            assert_eq!(&src[node.span()], node.render());
+            if let AstNode::Fragment(fragment) = node {
+                check(src, &fragment.children);
+            }
+        }
+    }
+    check(SOURCE, &ast.nodes);
+    assert_eq!(ast.nodes.last().unwrap().span().end, SOURCE.len());
+}
+
+#[test]
+fn round_trip() {
+    for src in [
+        SOURCE,
+        "",
+        "\n",
+        "foo\r\nbar",
+        "{% fragment a b %}\n{% endfragment %}",
+    ] {
+        assert_eq!(TemplateAst::parse(src).unwrap().render(), src);
+    }
+}
+
+#[test]
+fn byte_order_mark_is_kept() {
+    for src in [
+        format!("\u{feff}{SOURCE}"),
+        String::from("\u{feff}{% fragment a %}\n{% endfragment %}\n"),
+        String::from("\u{feff}"),
+    ] {
+        let ast = TemplateAst::parse(&src).unwrap();
+        assert_eq!(ast.render(), src);
+        assert_eq!(&src[ast.nodes[0].span()], ast.nodes[0].render());
+        assert_eq!(ast.nodes.last().unwrap().span().end, src.len());
+    }
+}
+
+#[test]
+fn multiple_names_are_sorted() {
+    let ast = TemplateAst::parse("{% fragment b a %}\n{% endfragment %}\n").unwrap();
+    assert!(matches!(&ast.nodes[0], AstNode::Fragment(node) if node.names == ["a", "b"]));
+}
+
+#[test]
+fn errors() {
+    assert_matches!(
+        TemplateAst::parse("{% fragment a %}\n"),
+        Err(ErrorWithLine(0, Error::UnclosedTag(_))),
+    );
+    assert_matches!(
+        TemplateAst::parse("{% endfragment %}\n"),
+        Err(ErrorWithLine(0, Error::UnbalancedEndTag)),
+    );
+    assert_matches!(
+        Config::default().max_depth(1).parse_ast(SOURCE),
+        Err(ErrorWithLine(3, Error::NestingTooDeep(1))),
+    );
+}
+
+mod visitor {
+    use super::*;
+    use crate::{FragmentNode, Visitor};
+
+    #[derive(Default)]
+    struct Recorder(Vec<String>);
+
+    impl Visitor for Recorder {
+        fn enter_fragment(&mut self, line_idx: usize, node: &FragmentNode) {
+            self.0
+                .push(format!("{line_idx} enter {}", node.names.join(" ")));
+        }
+
+        fn text_line(&mut self, line_idx: usize, line: &str) {
+            self.0.push(format!("{line_idx} text {}", line.trim()));
+        }
+
+        fn leave_fragment(&mut self, line_idx: usize, node: &FragmentNode) {
+            self.0
+                .push(format!("{line_idx} leave {}", node.names.join(" ")));
+        }
+    }
+
+    #[test]
+    fn events_in_source_order() {
+        let mut recorder = Recorder::default();
+        TemplateAst::parse(SOURCE).unwrap().walk(&mut recorder);
+
+        assert_eq!(
+            recorder.0,
+            vec![
+                "0 text <body>",
+                "1 enter items",
+                "2 text <ul>",
+                "3 enter item",
+                "4 text <li>{{ item }}</li>",
+                "5 leave item",
+                "6 text </ul>",
+                "7 leave items",
+                "8 text </body>",
+            ]
+        );
+    }
+
+    #[test]
+    fn default_methods_do_nothing() {
+        struct Nothing;
+        impl Visitor for Nothing {}
+
+        TemplateAst::parse(SOURCE).unwrap().walk(&mut Nothing);
+    }
+}
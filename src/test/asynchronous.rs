@@ -0,0 +1,52 @@
+use crate::{
+    split_directory, split_directory_async, split_templates_async,
+    test::{block_on, TempDir},
+    Config, DirectoryError, ErrorWithLine,
+};
+
+#[test]
+fn reader() {
+    let source = "<ul>\n{% fragment item %}\n<li></li>\n{% endfragment %}\n</ul>\n";
+    let templates = block_on(split_templates_async(source.as_bytes())).unwrap();
+    assert_eq!(templates["item"], "<li></li>\n");
+    assert_eq!(templates[""], "<ul>\n<li></li>\n</ul>\n");
+
+    let config = Config::default().extends("base.html", "content");
+    let templates = block_on(config.split_templates_async(source.as_bytes())).unwrap();
+    assert!(templates["item"].starts_with("{% extends"));
+}
+
+#[test]
+fn reader_errors() {
+    let err = block_on(split_templates_async("\n{% endfragment %}\n".as_bytes())).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    let err = err
+        .get_ref()
+        .and_then(|err| err.downcast_ref::<ErrorWithLine>())
+        .unwrap();
+    assert_eq!(err.0, 1);
+
+    let err = block_on(split_templates_async(&[0xff_u8, 0xfe][..])).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn directory() {
+    let dir = TempDir::new("async-tree");
+    dir.write("index.html", "{% fragment a %}\na\n{% endfragment %}\n");
+    dir.write("nested/page.html", "b\n");
+
+    let templates = block_on(split_directory_async(&dir.0)).unwrap();
+    assert_eq!(templates, split_directory(&dir.0).unwrap());
+    assert_eq!(templates["nested/page.html"], "b\n");
+
+    dir.write("broken.html", "{% fragment a %}\n");
+    assert!(matches!(
+        block_on(split_directory_async(&dir.0)),
+        Err(DirectoryError::Template(_))
+    ));
+    assert!(matches!(
+        block_on(split_directory_async(dir.0.join("missing"))),
+        Err(DirectoryError::Io(_, _))
+    ));
+}
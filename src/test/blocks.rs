@@ -0,0 +1,285 @@
+use crate::{filter_template, Config, Error, ErrorWithLine, Template};
+
+const SOURCE: &str = concat!(
+    "{% extends \"layout.html\" %}\n",
+    "{% block title %}Items{% endblock %}\n",
+    "{% block content %}\n",
+    "<ul>\n",
+    "  {% for item in items %}\n",
+    "  {% block item %}\n",
+    "  <li>{{ item }}</li>\n",
+    "  {% endblock item %}\n",
+    "  {% endfor %}\n",
+    "</ul>\n",
+    "{% endblock %}\n",
+);
+
+fn config() -> Config {
+    Config::default().blocks_as_fragments(true)
+}
+
+#[test]
+fn blocks_are_fragments() {
+    let templates = config().split_templates(SOURCE).unwrap();
+
+    assert_eq!(templates.len(), 3);
+    assert_eq!(
+        templates[""],
+        SOURCE.replace("{% endblock item %}", "{% endblock %}")
+    );
+    assert_eq!(
+        templates["content"],
+        concat!(
+            "{% block content %}\n",
+            "<ul>\n",
+            "  {% for item in items %}\n",
+            "  {% block item %}\n",
+            "  <li>{{ item }}</li>\n",
+            "  {% endblock %}\n",
+            "  {% endfor %}\n",
+            "</ul>\n",
+            "{% endblock %}\n",
+        )
+    );
+    assert_eq!(
+        templates["item"],
+        "  {% block item %}\n  <li>{{ item }}</li>\n  {% endblock %}\n"
+    );
+
+    for (name, template) in &templates {
+        assert_eq!(&config().filter_template(SOURCE, name).unwrap(), template);
+    }
+    let parsed = config().parse_template(SOURCE).unwrap();
+    assert_eq!(parsed.filter("item"), templates["item"]);
+}
+
+#[test]
+fn inline_blocks_and_modifiers_are_kept() {
+    let source = concat!(
+        "{% block title %}Items{% endblock %}\n",
+        "{% block items scoped %}\n",
+        "{% block item %}\n",
+        "<li>{{ item }}</li>\n",
+        "{% endblock %}\n",
+        "{% endblock %}\n",
+        "{%- block footer %}\n",
+        "{% endblock %}\n",
+    );
+    let templates = config().split_templates(source).unwrap();
+
+    assert_eq!(templates.len(), 2);
+    assert_eq!(templates[""], source);
+    assert_eq!(
+        templates["item"],
+        "{% block item %}\n<li>{{ item }}</li>\n{% endblock %}\n"
+    );
+}
+
+#[test]
+fn blocks_are_checked_like_fragments() {
+    assert!(config().has_fragments(SOURCE));
+    assert!(config().fragment_exists(SOURCE, "item"));
+    assert!(!config().fragment_exists(SOURCE, "title"));
+
+    let source = "{% block item %}\n{% block item %}\n{% endblock %}\n{% endblock %}\n";
+    assert_eq!(
+        config().split_templates(source).unwrap_err(),
+        ErrorWithLine(1, Error::ReentrantFragment(vec![String::from("item")])),
+    );
+}
+
+#[test]
+fn blocks_are_not_fragments_by_default() {
+    assert!(!Config::default().has_fragments(SOURCE));
+    assert_eq!(filter_template(SOURCE, "item").unwrap(), "");
+    assert_eq!(Template::parse(SOURCE).unwrap().filter(""), SOURCE);
+}
+
+mod macros {
+    use crate::{Config, Error, ErrorWithLine};
+
+    const SOURCE: &str = concat!(
+        "{% macro item(item) -%}\r\n",
+        "  <li>{{ item }}</li>\r\n",
+        "{%- endmacro %}\r\n",
+        "<ul>\r\n",
+        "  {% macro items(items) %}\r\n",
+        "  {% for item in items %}{{ item(item) }}{% endfor %}\r\n",
+        "  {% endmacro %}\r\n",
+        "</ul>\r\n",
+        "{% macro title() %}Items{% endmacro %}\r\n",
+    );
+
+    fn config() -> Config {
+        Config::default().macros_as_fragments(true)
+    }
+
+    #[test]
+    fn macro_bodies_are_fragments() {
+        let templates = config().split_templates(SOURCE).unwrap();
+
+        assert_eq!(templates.len(), 3);
+        assert_eq!(templates[""], SOURCE);
+        assert_eq!(templates["item"], "  <li>{{ item }}</li>\r\n");
+        assert_eq!(
+            templates["items"],
+            "  {% for item in items %}{{ item(item) }}{% endfor %}\r\n"
+        );
+        assert_eq!(
+            config().filter_template(SOURCE, "items").unwrap(),
+            templates["items"]
+        );
+        assert!(config().fragment_exists(SOURCE, "item"));
+        assert!(!config().fragment_exists(SOURCE, "title"));
+        assert!(!Config::default().has_fragments(SOURCE));
+    }
+
+    #[test]
+    fn errors_refer_to_original_lines() {
+        let source = concat!(
+            "{% macro item(item) %}\n",
+            "<li>{{ item }}</li>\n",
+            "{% endmacro %}\n",
+            "{% fragment footer %}\n",
+            "{% endfragment %}\n",
+            "{% endfragment %}\n",
+        );
+        assert_eq!(
+            config().split_templates(source).unwrap_err(),
+            ErrorWithLine(5, Error::UnbalancedEndTag),
+        );
+    }
+
+    #[test]
+    fn fragments_inside_macros() {
+        let source = concat!(
+            "{% macro items(items) %}\n",
+            "<ul>\n",
+            "{% fragment-block list %}\n",
+            "<li>{{ items }}</li>\n",
+            "{% endfragment-block %}\n",
+            "</ul>\n",
+            "{% endmacro %}\n",
+        );
+        let templates = config().split_templates(source).unwrap();
+
+        assert_eq!(
+            templates["items"],
+            "<ul>\n{% block list %}\n<li>{{ items }}</li>\n{% endblock %}\n</ul>\n"
+        );
+        assert_eq!(
+            templates["list"],
+            "{% block list %}\n<li>{{ items }}</li>\n{% endblock %}\n"
+        );
+    }
+}
+
+mod django_render_block {
+    use crate::{Config, Error, ErrorWithLine};
+
+    const SOURCE: &str = concat!(
+        "{% extends \"layout.html\" %}\n",
+        "{% block content %}\n",
+        "<ul>\n",
+        "  {% fragment items %}\n",
+        "  {% for item in items %}\n",
+        "    {% fragment-block item %}\n",
+        "    <li>{{ item }}</li>\n",
+        "    {% endfragment-block %}\n",
+        "  {% endfor %}\n",
+        "  {% endfragment %}\n",
+        "</ul>\n",
+        "{% endblock %}\n",
+    );
+
+    #[test]
+    fn fragments_are_blocks() {
+        let templates = Config::django_render_block()
+            .split_templates(SOURCE)
+            .unwrap();
+
+        let mut names: Vec<&str> = templates.keys().map(String::as_str).collect();
+        names.sort();
+        assert_eq!(names, ["", "content", "item", "items"]);
+
+        assert_eq!(
+            templates[""],
+            concat!(
+                "{% extends \"layout.html\" %}\n",
+                "{% block content %}\n",
+                "<ul>\n",
+                "  {% block items %}\n",
+                "  {% for item in items %}\n",
+                "    {% block item %}\n",
+                "    <li>{{ item }}</li>\n",
+                "    {% endblock item %}\n",
+                "  {% endfor %}\n",
+                "  {% endblock items %}\n",
+                "</ul>\n",
+                "{% endblock content %}\n",
+            )
+        );
+        assert_eq!(
+            templates["items"],
+            concat!(
+                "  {% block items %}\n",
+                "  {% for item in items %}\n",
+                "    {% block item %}\n",
+                "    <li>{{ item }}</li>\n",
+                "    {% endblock item %}\n",
+                "  {% endfor %}\n",
+                "  {% endblock items %}\n",
+            )
+        );
+    }
+
+    #[test]
+    fn fragments_are_not_blocks_by_default() {
+        let templates = Config::default().split_templates(SOURCE).unwrap();
+        assert_eq!(
+            templates["items"].lines().next(),
+            Some("  {% for item in items %}")
+        );
+        assert!(!templates.contains_key("content"));
+    }
+
+    #[test]
+    fn attributes_are_kept() {
+        let source = "{% fragment item with=item %}\n<li>\n{% endfragment %}\n";
+        let config = Config::default().fragments_as_blocks(true);
+        assert_eq!(
+            config.expand_fragment_blocks(source),
+            "{% fragment-block item with=item %}\n<li>\n{% endfragment-block %}\n"
+        );
+    }
+
+    #[test]
+    fn multiple_names_are_rejected() {
+        let source = "<ul>\n{% fragment a b %}\n{% endfragment %}\n";
+        assert_eq!(
+            Config::django_render_block().split_templates(source),
+            Err(ErrorWithLine(
+                1,
+                Error::MultipleNamesBlock(vec![String::from("a"), String::from("b")])
+            ))
+        );
+    }
+
+    #[test]
+    fn block_names_must_be_unique() {
+        let source = "{% fragment a %}\n{% endfragment %}\n{% block a %}\n{% endblock %}\n";
+        assert!(matches!(
+            Config::django_render_block().split_templates(source),
+            Err(ErrorWithLine(2, Error::RepeatedFragment(_, _)))
+        ));
+    }
+
+    #[test]
+    fn verbatim_sections_are_kept() {
+        let source = "{% raw %}\n{% fragment a %}\n{% endfragment %}\n{% endraw %}\n";
+        let templates = Config::django_render_block()
+            .split_templates(source)
+            .unwrap();
+        assert_eq!(templates[""], source);
+    }
+}
@@ -0,0 +1,116 @@
+use crate::{test::assert_matches, Error, ErrorWithLine, FragmentCache};
+
+const SOURCE: &str = concat!(
+    "<body>\n",
+    "{% fragment foo %}\n",
+    "<foo>\n",
+    "{% endfragment %}\n",
+    "{% fragment bar %}\n",
+    "<bar>\n",
+    "{% endfragment %}\n",
+    "</body>\n",
+);
+
+#[test]
+fn reuses_results() {
+    let mut cache = FragmentCache::new(8);
+
+    assert_eq!(
+        cache.filter_template("index.html", SOURCE, "foo"),
+        Ok("<foo>\n")
+    );
+    assert_eq!(
+        cache.filter_template("index.html", SOURCE, "foo"),
+        Ok("<foo>\n")
+    );
+    assert_eq!(cache.len(), 1);
+
+    assert_eq!(
+        cache.filter_template("index.html", SOURCE, "bar"),
+        Ok("<bar>\n")
+    );
+    assert_eq!(cache.len(), 2);
+}
+
+#[test]
+fn changed_source() {
+    let mut cache = FragmentCache::new(8);
+
+    assert_eq!(
+        cache.filter_template("index.html", SOURCE, "foo"),
+        Ok("<foo>\n")
+    );
+
+    let changed = SOURCE.replace("<foo>", "<changed>");
+    assert_eq!(
+        cache.filter_template("index.html", &changed, "foo"),
+        Ok("<changed>\n")
+    );
+    assert_eq!(cache.len(), 2);
+}
+
+#[test]
+fn evicts_least_recently_used() {
+    let mut cache = FragmentCache::new(2);
+
+    cache.filter_template("index.html", SOURCE, "foo").unwrap();
+    cache.filter_template("index.html", SOURCE, "bar").unwrap();
+    cache.filter_template("index.html", SOURCE, "foo").unwrap();
+    cache.filter_template("index.html", SOURCE, "").unwrap();
+    assert_eq!(cache.len(), 2);
+
+    // bar was evicted, foo is still cached
+    assert!(!cache.contains("index.html", SOURCE, "bar"));
+    assert!(cache.contains("index.html", SOURCE, "foo"));
+    assert!(cache.contains("index.html", SOURCE, ""));
+
+    let changed = SOURCE.replace("<bar>", "<changed>");
+    cache.filter_template("other.html", &changed, "").unwrap();
+    assert_eq!(cache.len(), 2);
+    assert!(!cache.contains("index.html", SOURCE, "foo"));
+    assert!(cache.contains("other.html", &changed, ""));
+}
+
+#[test]
+fn invalidate_by_path() {
+    let mut cache = FragmentCache::new(8);
+    let other = SOURCE.replace("<foo>", "<other>");
+
+    cache.filter_template("index.html", SOURCE, "foo").unwrap();
+    cache.filter_template("index.html", SOURCE, "bar").unwrap();
+    cache.filter_template("other.html", &other, "foo").unwrap();
+
+    cache.invalidate("index.html");
+    assert_eq!(cache.len(), 1);
+
+    cache.invalidate("other.html");
+    assert!(cache.is_empty());
+}
+
+#[test]
+fn invalidate_paths_with_the_same_source() {
+    let mut cache = FragmentCache::new(8);
+
+    cache.filter_template("index.html", SOURCE, "foo").unwrap();
+    cache.filter_template("copy.html", SOURCE, "foo").unwrap();
+    assert_eq!(cache.len(), 2);
+
+    // only the entries of the invalidated path are removed
+    cache.invalidate("index.html");
+    assert!(!cache.contains("index.html", SOURCE, "foo"));
+    assert!(cache.contains("copy.html", SOURCE, "foo"));
+
+    cache.invalidate("copy.html");
+    assert!(cache.is_empty());
+}
+
+#[test]
+fn errors_are_not_cached() {
+    let mut cache = FragmentCache::new(8);
+
+    assert_matches!(
+        cache.filter_template("index.html", "{% fragment foo %}\n", "foo"),
+        Err(ErrorWithLine(_, Error::UnclosedTag(_))),
+    );
+    assert!(cache.is_empty());
+}
@@ -0,0 +1,95 @@
+use crate::{split_templates, Config, Error, ErrorWithLine};
+
+const SOURCE: &str = concat!(
+    "with\n",
+    "-- {% fragment orders %}\n",
+    "orders as (\n",
+    "    select * from {{ ref('orders') }}\n",
+    "    where\n",
+    "      -- {% fragment-block filter %}\n",
+    "      status = 'open'\n",
+    "      -- {% endfragment-block %}\n",
+    "),\n",
+    "-- {% endfragment %}\n",
+    "-- the final select\n",
+    "select * from orders\n",
+);
+
+#[test]
+fn for_path() {
+    let templates = Config::for_path("models/orders.sql")
+        .split_templates(SOURCE)
+        .unwrap();
+    assert_eq!(templates, Config::sql().split_templates(SOURCE).unwrap());
+    assert!(templates.contains_key("orders"));
+}
+
+#[test]
+fn tags_in_line_comments() {
+    let templates = Config::sql().split_templates(SOURCE).unwrap();
+
+    assert_eq!(
+        templates["orders"],
+        concat!(
+            "orders as (\n",
+            "    select * from {{ ref('orders') }}\n",
+            "    where\n",
+            "         {% block filter %}\n",
+            "      status = 'open'\n",
+            "         {% endblock %}\n",
+            "),\n",
+        )
+    );
+    assert_eq!(
+        templates["filter"],
+        "         {% block filter %}\n      status = 'open'\n         {% endblock %}\n"
+    );
+    assert!(templates[""].starts_with("with\norders as (\n"));
+    assert!(templates[""].ends_with("),\n-- the final select\nselect * from orders\n"));
+}
+
+#[test]
+fn prefixed_tags_are_rejected_by_default() {
+    assert_eq!(
+        split_templates(SOURCE),
+        Err(ErrorWithLine(1, Error::LeadingContent(String::from("-- "))))
+    );
+}
+
+#[test]
+fn errors_keep_their_lines() {
+    let source = "select 1\n-- {% fragment one %} -- note\n-- {% endfragment %}\n";
+    assert_eq!(
+        Config::sql().split_templates(source),
+        Err(ErrorWithLine(
+            1,
+            Error::TrailingContent(String::from(" -- note\n"))
+        ))
+    );
+
+    let source = "select 1\n-- note {% fragment one %}\n-- {% endfragment %}\n";
+    assert_eq!(
+        Config::sql().split_templates(source),
+        Err(ErrorWithLine(
+            1,
+            Error::LeadingContent(String::from("-- note "))
+        ))
+    );
+}
+
+#[test]
+fn other_prefixes() {
+    let source = "# {% fragment host %}\nhost: {{ host }}\n# {% endfragment %}\n";
+    let templates = Config::default()
+        .line_comment_prefix("#")
+        .split_templates(source)
+        .unwrap();
+    assert_eq!(templates["host"], "host: {{ host }}\n");
+}
+
+#[test]
+fn verbatim_sections_are_kept() {
+    let source = "{% raw %}\n-- {% fragment one %}\n{% endraw %}\n";
+    let templates = Config::sql().split_templates(source).unwrap();
+    assert_eq!(templates[""], source);
+}
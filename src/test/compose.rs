@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+
+use crate::{merge_fragments, split_templates, Error, ErrorWithLine};
+
+fn fragments<const N: usize>(items: [(&str, &str); N]) -> HashMap<String, String> {
+    items
+        .into_iter()
+        .map(|(k, v)| (k.to_owned(), v.to_owned()))
+        .collect()
+}
+
+#[test]
+fn nested_slots() {
+    let skeleton = "<ul>\r\n\t{% fragment-slot items %}\r\n</ul>";
+    let fragments = fragments([("items", "{% fragment-slot item %}\n"), ("item", "<li>")]);
+
+    // slots inside fragments are kept, only the skeleton is expanded
+    assert_eq!(
+        merge_fragments(skeleton, &fragments).unwrap(),
+        concat!(
+            "<ul>\r\n",
+            "\t{% fragment items %}\r\n",
+            "{% fragment-slot item %}\n",
+            "\t{% endfragment %}\r\n",
+            "</ul>",
+        )
+    );
+}
+
+#[test]
+fn round_trip() {
+    let skeleton = "<body>\n{% fragment-slot a %}\n<hr>\n{% fragment-slot b %}";
+    let fragments = fragments([("a", "<a>\n"), ("b", "<b>")]);
+
+    let source = merge_fragments(skeleton, &fragments).unwrap();
+    assert_eq!(
+        source,
+        concat!(
+            "<body>\n",
+            "{% fragment a %}\n",
+            "<a>\n",
+            "{% endfragment %}\n",
+            "<hr>\n",
+            "{% fragment b %}\n",
+            "<b>\n",
+            "{% endfragment %}",
+        )
+    );
+
+    let templates = split_templates(&source).unwrap();
+    assert_eq!(templates["a"], "<a>\n");
+    assert_eq!(templates["b"], "<b>\n");
+}
+
+#[test]
+fn empty_fragments() {
+    let source = merge_fragments("{% fragment-slot a %}\n", &fragments([("a", "")])).unwrap();
+    assert_eq!(source, "{% fragment a %}\n{% endfragment %}\n");
+}
+
+#[test]
+fn missing_fragments() {
+    assert_eq!(
+        merge_fragments("<body>\n{% fragment-slot a %}\n", &HashMap::new()),
+        Err(ErrorWithLine(
+            1,
+            Error::UnresolvedInclude(String::from("a"))
+        )),
+    );
+}
+
+#[test]
+fn invalid_content() {
+    let fragments = fragments([("a", "{% endfragment %}\n")]);
+    assert_eq!(
+        merge_fragments("<body>\n{% fragment-slot a %}\n", &fragments),
+        Err(ErrorWithLine(3, Error::UnbalancedEndTag)),
+    );
+}
+
+#[test]
+fn invalid_slots_are_kept() {
+    let skeleton = "{% fragment-slot %}\n{% fragment-slot a.b %}\n<p>{% fragment-slot a %}</p>\n";
+    assert_eq!(
+        merge_fragments(skeleton, &HashMap::new()).unwrap(),
+        skeleton
+    );
+}
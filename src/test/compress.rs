@@ -0,0 +1,80 @@
+use std::io::Read;
+
+use crate::{split_templates, split_templates_compressed, CompressedFragment, Config};
+
+const SOURCE: &str = concat!(
+    "<ul>\n",
+    "{% fragment item %}\n",
+    "  <li>{{ item }}</li>\n",
+    "{% endfragment %}\n",
+    "</ul>\n",
+);
+
+fn decompress_gzip(data: &[u8]) -> String {
+    let mut res = String::new();
+    flate2::read::GzDecoder::new(data)
+        .read_to_string(&mut res)
+        .unwrap();
+    res
+}
+
+fn decompress_brotli(data: &[u8]) -> String {
+    let mut res = String::new();
+    brotli::Decompressor::new(data, 4096)
+        .read_to_string(&mut res)
+        .unwrap();
+    res
+}
+
+#[test]
+fn roundtrip() {
+    let fragments = split_templates_compressed(SOURCE).unwrap();
+    let templates = split_templates(SOURCE).unwrap();
+
+    assert_eq!(fragments.len(), templates.len());
+    for (fragment, content) in templates {
+        let compressed = &fragments[&fragment];
+        assert_eq!(compressed.content, content);
+        assert_eq!(decompress_gzip(&compressed.gzip), content);
+        assert_eq!(decompress_brotli(&compressed.brotli), content);
+    }
+}
+
+#[test]
+fn empty_content() {
+    let fragment = CompressedFragment::new(String::new());
+    assert_eq!(decompress_gzip(&fragment.gzip), "");
+    assert_eq!(decompress_brotli(&fragment.brotli), "");
+}
+
+#[test]
+fn compression_includes_output_options() {
+    let config = Config::default().minify_html(true);
+    let fragments = config.split_templates_compressed(SOURCE).unwrap();
+    assert_eq!(
+        decompress_gzip(&fragments["item"].gzip),
+        "<li>{{ item }}</li>\n"
+    );
+}
+
+#[test]
+fn accept_encoding() {
+    let fragment = CompressedFragment::new(String::from("content\n"));
+
+    assert_eq!(
+        fragment.encode("br"),
+        (Some("br"), fragment.brotli.as_slice())
+    );
+    assert_eq!(
+        fragment.encode("gzip"),
+        (Some("gzip"), fragment.gzip.as_slice())
+    );
+    assert_eq!(fragment.encode("deflate, gzip;q=0.5, br").0, Some("br"));
+    assert_eq!(fragment.encode("GZIP, br;q=0").0, Some("gzip"));
+    assert_eq!(fragment.encode(" x-gzip ").0, Some("gzip"));
+    assert_eq!(fragment.encode("*").0, Some("br"));
+    assert_eq!(fragment.encode("*, br; q=0.0").0, Some("gzip"));
+    assert_eq!(fragment.encode("identity"), (None, "content\n".as_bytes()));
+    assert_eq!(fragment.encode("gzip;q=0, br;q=0").0, None);
+    assert_eq!(fragment.encode("").0, None);
+}
@@ -0,0 +1,681 @@
+use crate::{test::assert_matches, Config, Error, ErrorWithLine};
+
+mod unique_fragments {
+    use super::*;
+
+    const SOURCE: &str = concat!(
+        "<body>\n",
+        "{% fragment foo bar %}\n",
+        "<common>\n",
+        "{% endfragment %}\n",
+        "{% fragment-block baz %}\n",
+        "<baz>\n",
+        "{% endfragment-block %}\n",
+        "{% fragment bar %}\n",
+        "<bar>\n",
+        "{% endfragment %}\n",
+        "</body>\n",
+    );
+
+    #[test]
+    fn repeated_fragments_are_allowed_by_default() {
+        assert!(Config::default().split_templates(SOURCE).is_ok());
+    }
+
+    #[test]
+    fn repeated_fragments() {
+        let config = Config::default().unique_fragments(true);
+
+        assert_eq!(
+            config.split_templates(SOURCE),
+            Err(ErrorWithLine(
+                7,
+                Error::RepeatedFragment(String::from("bar"), 1)
+            )),
+        );
+        assert_matches!(
+            config.filter_template(SOURCE, "foo"),
+            Err(ErrorWithLine(7, Error::RepeatedFragment(_, 1))),
+        );
+        assert_matches!(
+            config.split_templates_detailed(SOURCE),
+            Err(ErrorWithLine(7, Error::RepeatedFragment(_, 1))),
+        );
+    }
+
+    #[test]
+    fn repeated_blocks() {
+        let config = Config::default().unique_fragments(true);
+        let source = SOURCE.replace("{% fragment bar %}", "{% fragment baz %}");
+
+        assert_eq!(
+            config.split_templates(&source),
+            Err(ErrorWithLine(
+                7,
+                Error::RepeatedFragment(String::from("baz"), 4)
+            )),
+        );
+    }
+
+    #[test]
+    fn unique_fragments() {
+        let config = Config::default().unique_fragments(true);
+        let source = SOURCE.replace("{% fragment bar %}", "{% fragment other %}");
+
+        assert!(config.split_templates(&source).is_ok());
+    }
+}
+
+mod limits {
+    use super::*;
+
+    const SOURCE: &str = concat!(
+        "{% fragment a %}\n",
+        "  {% fragment b c %}\n",
+        "    {% fragment-block d %}\n",
+        "    {% endfragment-block %}\n",
+        "  {% endfragment %}\n",
+        "{% endfragment %}\n",
+        "{% fragment a %}\n",
+        "{% endfragment %}\n",
+    );
+
+    #[test]
+    fn max_depth() {
+        assert!(Config::default()
+            .max_depth(3)
+            .split_templates(SOURCE)
+            .is_ok());
+        assert_eq!(
+            Config::default().max_depth(2).split_templates(SOURCE),
+            Err(ErrorWithLine(2, Error::NestingTooDeep(2))),
+        );
+        assert_eq!(
+            Config::default().max_depth(0).filter_template(SOURCE, ""),
+            Err(ErrorWithLine(0, Error::NestingTooDeep(0))),
+        );
+    }
+
+    #[test]
+    fn max_fragments() {
+        assert!(Config::default()
+            .max_fragments(4)
+            .split_templates(SOURCE)
+            .is_ok());
+        assert_eq!(
+            Config::default().max_fragments(2).split_templates(SOURCE),
+            Err(ErrorWithLine(1, Error::TooManyFragments(2))),
+        );
+    }
+}
+
+mod macros {
+    use super::*;
+
+    #[test]
+    fn macros_are_not_emitted_by_default() {
+        let templates = Config::default()
+            .split_templates("{% fragment foo %}\nfoo\n{% endfragment %}\n")
+            .unwrap();
+        assert!(!templates.contains_key("_macros"));
+    }
+
+    #[test]
+    fn macros_are_sorted_and_keep_fragments() {
+        let source = concat!(
+            "<body>\n",
+            "{% fragment-block foo %}\n",
+            "<foo>\n",
+            "{% endfragment-block %}\n",
+            "{% fragment bar %}\n",
+            "<bar>\n",
+            "{% endfragment %}\n",
+            "</body>\n",
+        );
+
+        let templates = Config::default()
+            .macros(true)
+            .split_templates(source)
+            .unwrap();
+
+        assert_eq!(templates["bar"], "<bar>\n");
+        assert_eq!(
+            templates["_macros"],
+            concat!(
+                "{% macro bar() %}\n",
+                "<bar>\n",
+                "{% endmacro %}\n",
+                "{% macro foo() %}\n",
+                "{% block foo %}\n",
+                "<foo>\n",
+                "{% endblock %}\n",
+                "{% endmacro %}\n",
+            )
+        );
+    }
+
+    #[test]
+    fn macros_use_the_tag_markers() {
+        let templates = Config::default()
+            .tag_markers("<%", "%>")
+            .macros(true)
+            .split_templates("<% fragment foo %>\nfoo\n<% endfragment %>")
+            .unwrap();
+        assert_eq!(
+            templates["_macros"],
+            "<% macro foo() %>\nfoo\n<% endmacro %>\n"
+        );
+    }
+
+    #[test]
+    fn colliding_macro_names() {
+        let source = concat!(
+            "<body>\n",
+            "{% fragment a_b %}\n",
+            "<a_b>\n",
+            "{% endfragment %}\n",
+            "{% fragment a-b %}\n",
+            "<a-b>\n",
+            "{% endfragment %}\n",
+            "</body>\n",
+        );
+        let err = Config::default()
+            .macros(true)
+            .split_templates(source)
+            .unwrap_err();
+        assert_eq!(
+            err,
+            ErrorWithLine(
+                4,
+                Error::MacroNameCollision(vec![String::from("a-b"), String::from("a_b")])
+            )
+        );
+        assert_eq!(err.1.code(), "macro-name-collision");
+
+        // numbered occurrences of repeated fragments
+        let source = concat!(
+            "{% fragment card %}\n",
+            "<card>\n",
+            "{% endfragment %}\n",
+            "{% fragment card_1 %}\n",
+            "<card_1>\n",
+            "{% endfragment %}\n",
+            "{% fragment card %}\n",
+            "<card>\n",
+            "{% endfragment %}\n",
+        );
+        let config = Config::default()
+            .macros(true)
+            .number_repeated_fragments(true);
+        assert_matches!(
+            config.split_templates(source),
+            Err(ErrorWithLine(3, Error::MacroNameCollision(_))),
+        );
+        assert_matches!(
+            config.split_templates_with(source, |_, _| {}),
+            Err(ErrorWithLine(3, Error::MacroNameCollision(_))),
+        );
+    }
+}
+
+mod name_case {
+    use std::borrow::Cow;
+
+    use super::*;
+    use crate::{NameCase, TemplateAst};
+
+    const SOURCE: &str = concat!(
+        "{% fragment Items role=List %}\n",
+        "{% fragment-block Item %}\n",
+        "<li>\n",
+        "{% endfragment-block %}\n",
+        "{% endfragment %}\n",
+    );
+
+    #[test]
+    fn names_are_case_sensitive_by_default() {
+        let templates = Config::default().split_templates(SOURCE).unwrap();
+        assert!(templates.contains_key("Items"));
+        assert!(!templates.contains_key("items"));
+        assert_eq!(
+            Config::default().filter_template(SOURCE, "items").unwrap(),
+            ""
+        );
+        assert_eq!(
+            Config::default().join_path("index.html", "Item"),
+            "index.html#Item"
+        );
+    }
+
+    #[test]
+    fn canonical_forms() {
+        let config = Config::default().name_case(NameCase::Upper);
+        let templates = config.split_templates(SOURCE).unwrap();
+        assert_eq!(
+            templates["ITEM"],
+            "{% block ITEM %}\n<li>\n{% endblock %}\n"
+        );
+        assert_eq!(templates["ITEMS"], templates["ITEM"]);
+        assert_eq!(
+            config.filter_template(SOURCE, "item").unwrap(),
+            templates["ITEM"]
+        );
+    }
+
+    #[test]
+    fn attributes_are_kept() {
+        let config = Config::default().name_case(NameCase::Lower);
+        let fragments = config.split_templates_detailed(SOURCE).unwrap();
+        assert_eq!(fragments["items"].attributes["role"], "List");
+
+        let ast = config.parse_ast(SOURCE).unwrap();
+        assert_eq!(
+            ast.render(),
+            SOURCE.replace("Items", "items").replace("Item", "item")
+        );
+        assert_ne!(ast, TemplateAst::parse(SOURCE).unwrap());
+    }
+
+    #[test]
+    fn repeated_fragments_differing_in_case() {
+        let source = "{% fragment a %}\n{% endfragment %}\n{% fragment A %}\n{% endfragment %}\n";
+        let config = Config::default().unique_fragments(true);
+
+        assert!(config.split_templates(source).is_ok());
+        assert_eq!(
+            config.name_case(NameCase::Lower).split_templates(source),
+            Err(ErrorWithLine(
+                2,
+                Error::RepeatedFragment(String::from("a"), 0)
+            )),
+        );
+    }
+
+    #[test]
+    fn errors_are_reported_for_the_normalized_source() {
+        let config = Config::default().name_case(NameCase::Lower);
+        assert_matches!(
+            config.split_templates("{% fragment A %}\n{% fragment a %}\n"),
+            Err(ErrorWithLine(1, Error::ReentrantFragment(_))),
+        );
+        assert_matches!(
+            config.split_templates("<p>{% fragment A %}\n"),
+            Err(ErrorWithLine(0, Error::LeadingContent(_))),
+        );
+    }
+
+    #[test]
+    fn normalize() {
+        assert!(matches!(
+            NameCase::Lower.normalize("foo"),
+            Cow::Borrowed("foo")
+        ));
+        assert_eq!(NameCase::Lower.normalize("Foo-BAR"), "foo-bar");
+        assert_eq!(NameCase::Upper.normalize("Foo-BAR"), "FOO-BAR");
+        assert_eq!(NameCase::Preserve.normalize("Foo-BAR"), "Foo-BAR");
+    }
+}
+
+mod named_end_tags {
+    use super::*;
+
+    const SOURCE: &str = concat!(
+        "{% fragment a b %}\n",
+        "{% fragment-block c %}\n",
+        "<c>\n",
+        "{% endfragment-block c %}\n",
+        "{% endfragment b a %}\n",
+    );
+
+    #[test]
+    fn names_are_rejected_by_default() {
+        assert_matches!(
+            Config::default().split_templates(SOURCE),
+            Err(ErrorWithLine(3, Error::EndTagWithData(_))),
+        );
+    }
+
+    #[test]
+    fn matching_names() {
+        let config = Config::default().named_end_tags(true);
+        let templates = config.split_templates(SOURCE).unwrap();
+        assert_eq!(templates["c"], "{% block c %}\n<c>\n{% endblock %}\n");
+        assert_eq!(templates["a"], templates["c"]);
+
+        // a subset of the names is sufficient, unnamed end tags are still allowed
+        let source = SOURCE
+            .replace("endfragment b a", "endfragment a")
+            .replace("endfragment-block c", "endfragment-block");
+        assert_eq!(config.split_templates(&source).unwrap(), templates);
+    }
+
+    #[test]
+    fn mismatched_names() {
+        let config = Config::default().named_end_tags(true);
+        assert_eq!(
+            config.split_templates(&SOURCE.replace("endfragment b a", "endfragment a d")),
+            Err(ErrorWithLine(
+                4,
+                Error::MismatchedEndTag(
+                    vec![String::from("a"), String::from("d")],
+                    vec![String::from("a"), String::from("b")]
+                )
+            )),
+        );
+        assert_matches!(
+            config.split_templates(&SOURCE.replace("endfragment-block c", "endfragment-block a")),
+            Err(ErrorWithLine(3, Error::MismatchedEndTag(_, _))),
+        );
+    }
+
+    #[test]
+    fn invalid_names() {
+        let config = Config::default().named_end_tags(true);
+        assert_matches!(
+            config.split_templates("{% fragment a %}\n{% endfragment a.b %}\n"),
+            Err(ErrorWithLine(1, Error::InvalidFragmentName(_))),
+        );
+        assert_matches!(
+            config.split_templates("{% endfragment a %}\n"),
+            Err(ErrorWithLine(0, Error::UnbalancedEndTag)),
+        );
+    }
+
+    #[test]
+    fn names_are_normalized() {
+        let config = Config::default()
+            .named_end_tags(true)
+            .name_case(crate::NameCase::Lower);
+        let source = "{% fragment Item %}\n<item>\n{% endfragment ITEM %}\n";
+        assert_eq!(config.split_templates(source).unwrap()["item"], "<item>\n");
+    }
+}
+
+mod strict_fragments {
+    use super::*;
+
+    const SOURCE: &str = concat!(
+        "<ul>\n",
+        "{% fragment-block list %}\n",
+        "{% fragment item %}\n",
+        "{% endfragment %}\n",
+        "{% endfragment-block %}\n",
+        "</ul>\n",
+    );
+
+    #[test]
+    fn unknown_fragment() {
+        let config = Config::default().strict_fragments(true);
+        assert_eq!(
+            config.filter_template(SOURCE, "itme"),
+            Err(ErrorWithLine(
+                0,
+                Error::UnknownFragment(
+                    String::from("itme"),
+                    vec![String::from("item"), String::from("list")],
+                ),
+            )),
+        );
+        assert_eq!(
+            config
+                .filter_template(SOURCE, "itme")
+                .unwrap_err()
+                .to_string(),
+            "Error::UnknownFragment(itme; available item, list) at line 1",
+        );
+    }
+
+    #[test]
+    fn defined_fragments() {
+        let config = Config::default().strict_fragments(true);
+        // empty fragments and the base template are defined
+        assert_eq!(config.filter_template(SOURCE, "item").unwrap(), "");
+        assert!(config.filter_template(SOURCE, "list").is_ok());
+        assert!(config.filter_template(SOURCE, "").is_ok());
+        assert_eq!(
+            Config::default().filter_template(SOURCE, "itme").unwrap(),
+            ""
+        );
+    }
+
+    #[test]
+    fn syntax_errors_take_precedence() {
+        let config = Config::default().strict_fragments(true);
+        assert_matches!(
+            config.filter_template("{% fragment a %}\n", "b"),
+            Err(ErrorWithLine(0, Error::UnclosedTag(_))),
+        );
+    }
+
+    #[test]
+    fn names_are_normalized() {
+        let config = Config::default()
+            .strict_fragments(true)
+            .name_case(crate::NameCase::Lower);
+        assert!(config.filter_template(SOURCE, "ITEM").is_ok());
+    }
+}
+
+mod keep_fragment_tags {
+    use super::*;
+
+    const SOURCE: &str = concat!(
+        "<ul>\n",
+        "  {% fragment items item %}\n",
+        "  {% fragment-block list %}\n",
+        "  <li>{{ item }}</li>\n",
+        "  {% endfragment-block %}\n",
+        "  {% endfragment %}\r\n",
+        "</ul>",
+    );
+
+    #[test]
+    fn base_keeps_tags() {
+        let config = Config::default().keep_fragment_tags(true);
+        let templates = config.split_templates(SOURCE).unwrap();
+        assert_eq!(
+            templates[""],
+            concat!(
+                "<ul>\n",
+                "  {% fragment items item %}\n",
+                "  {% block list %}\n",
+                "  <li>{{ item }}</li>\n",
+                "  {% endblock %}\n",
+                "  {% endfragment %}\r\n",
+                "</ul>",
+            ),
+        );
+        assert_eq!(templates["item"], templates["items"]);
+        assert_eq!(
+            templates["item"],
+            concat!(
+                "  {% block list %}\n",
+                "  <li>{{ item }}</li>\n",
+                "  {% endblock %}\n",
+            ),
+        );
+        assert_eq!(templates["list"], templates["item"]);
+        assert_eq!(config.filter_template(SOURCE, "").unwrap(), templates[""]);
+    }
+
+    #[test]
+    fn disabled_by_default() {
+        let templates = Config::default().split_templates(SOURCE).unwrap();
+        assert!(!templates[""].contains("fragment"));
+    }
+
+    #[test]
+    fn errors_are_reported() {
+        let config = Config::default().keep_fragment_tags(true);
+        assert_matches!(
+            config.split_templates("{% endfragment %}\n"),
+            Err(ErrorWithLine(0, Error::UnbalancedEndTag)),
+        );
+    }
+}
+
+mod skip_base {
+    use std::{borrow::Cow, collections::HashMap};
+
+    use super::*;
+
+    const SOURCE: &str = concat!(
+        "<ul>\n",
+        "{% fragment-block list %}\n",
+        "{% fragment item %}\n",
+        "<li></li>\n",
+        "{% endfragment %}\n",
+        "{% endfragment-block %}\n",
+        "</ul>\n",
+    );
+
+    #[test]
+    fn base_is_omitted() {
+        let config = Config::default().skip_base(true);
+        let mut expected = Config::default().split_templates(SOURCE).unwrap();
+        expected.remove("");
+
+        assert_eq!(config.split_templates(SOURCE).unwrap(), expected);
+
+        let mut templates = HashMap::from([(String::new(), String::from("stale"))]);
+        config.split_templates_into(SOURCE, &mut templates).unwrap();
+        assert_eq!(templates, expected);
+    }
+
+    #[test]
+    fn transform_skips_base_lines() {
+        let mut lines = Vec::new();
+        Config::default()
+            .skip_base(true)
+            .split_templates_transformed(SOURCE, |_, line| {
+                lines.push(line.to_owned());
+                Some(Cow::Borrowed(line))
+            })
+            .unwrap();
+        assert!(!lines.contains(&String::from("<ul>\n")));
+        assert!(lines.contains(&String::from("<li></li>\n")));
+    }
+
+    #[test]
+    fn macros_and_filter() {
+        let config = Config::default().skip_base(true).macros(true);
+        let templates = config.split_templates(SOURCE).unwrap();
+        assert!(!templates.contains_key(""));
+        assert!(templates.contains_key("_macros"));
+        assert_eq!(
+            config.filter_template(SOURCE, "").unwrap(),
+            Config::default().filter_template(SOURCE, "").unwrap(),
+        );
+    }
+}
+
+mod fragment_separator {
+    use super::*;
+
+    const SOURCE: &str = concat!(
+        "<ul>\n",
+        "{% fragment items %}\n",
+        "{% fragment item %}\n",
+        "<li>{{ first }}</li>\n",
+        "{% endfragment %}\n",
+        "{% fragment item %}\n",
+        "{% endfragment %}\n",
+        "{% fragment-block item %}\n",
+        "<li>{{ second }}</li>\n",
+        "{% endfragment-block %}\n",
+        "{% endfragment %}\n",
+        "</ul>\n",
+    );
+
+    fn config() -> Config {
+        Config::default().fragment_separator("\n")
+    }
+
+    #[test]
+    fn separators_between_occurrences() {
+        let templates = config().split_templates(SOURCE).unwrap();
+        let expected = Config::default().split_templates(SOURCE).unwrap();
+
+        assert_eq!(
+            templates["item"],
+            "<li>{{ first }}</li>\n\n{% block item %}\n<li>{{ second }}</li>\n{% endblock %}\n"
+        );
+        assert_eq!(templates["items"], expected["items"]);
+        assert_eq!(templates[""], expected[""]);
+
+        for (name, template) in &templates {
+            assert_eq!(&config().filter_template(SOURCE, name).unwrap(), template);
+            assert_eq!(
+                config().parse_template(SOURCE).unwrap().filter(name),
+                template
+            );
+        }
+    }
+
+    #[test]
+    fn separators_with_skip_base() {
+        let templates = config().skip_base(true).split_templates(SOURCE).unwrap();
+        let expected = config().split_templates(SOURCE).unwrap();
+
+        assert!(!templates.contains_key(""));
+        assert_eq!(templates["item"], expected["item"]);
+        assert_eq!(templates["items"], expected["items"]);
+    }
+
+    #[test]
+    fn no_separators_for_numbered_fragments() {
+        let templates = config()
+            .number_repeated_fragments(true)
+            .split_templates(SOURCE)
+            .unwrap();
+        assert_eq!(templates["item"], "<li>{{ first }}</li>\n");
+    }
+}
+
+mod split_templates_prefixed {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{join_path, split_templates, split_templates_prefixed, NameCase};
+
+    const SOURCE: &str = concat!(
+        "<ul>\n",
+        "{% fragment Item %}\n",
+        "<li></li>\n",
+        "{% endfragment %}\n",
+        "</ul>\n",
+    );
+
+    #[test]
+    fn keys_are_joined() {
+        let templates = split_templates_prefixed("pages/index.html", SOURCE).unwrap();
+        let expected: HashMap<String, String> = split_templates(SOURCE)
+            .unwrap()
+            .into_iter()
+            .map(|(fragment, content)| (join_path("pages/index.html", &fragment), content))
+            .collect();
+        assert_eq!(templates, expected);
+        assert!(templates.contains_key("pages/index.html#Item"));
+    }
+
+    #[test]
+    fn markers_and_names() {
+        let source = SOURCE.replace("{%", "((*").replace("%}", "*))");
+        let templates = split_templates_prefixed("report.tex", &source).unwrap();
+        assert_eq!(templates["report.tex#Item"], "<li></li>\n");
+
+        let config = Config::default().name_case(NameCase::Lower);
+        let templates = config
+            .split_templates_prefixed("index.html", SOURCE)
+            .unwrap();
+        assert_eq!(templates["index.html#item"], "<li></li>\n");
+    }
+
+    #[test]
+    fn errors() {
+        assert_matches!(
+            split_templates_prefixed("index.html", "{% fragment a %}\n"),
+            Err(ErrorWithLine(0, Error::UnclosedTag(_))),
+        );
+    }
+}
@@ -0,0 +1,224 @@
+use crate::{split_templates, split_templates_detailed, Config};
+
+const SOURCE: &str = concat!(
+    "<ul>\n",
+    "{% for item in items %}\n",
+    "  {% fragment item with=item=none,loop=none %}\n",
+    "  <li>{{ item }}</li>\n",
+    "  {% endfragment %}\n",
+    "  {% fragment-block other with=other=1 %}\n",
+    "  {% endfragment-block %}\n",
+    "{% endfor %}\n",
+    "</ul>\n",
+);
+
+#[test]
+fn with_attribute() {
+    let templates = split_templates(SOURCE).unwrap();
+    assert_eq!(
+        templates["item"],
+        concat!(
+            "{% with item=none,loop=none %}\n",
+            "  <li>{{ item }}</li>\n",
+            "{% endwith %}\n",
+        ),
+    );
+    assert_eq!(
+        templates["other"],
+        concat!(
+            "{% with other=1 %}\n",
+            "  {% block other %}\n",
+            "  {% endblock %}\n",
+            "{% endwith %}\n",
+        ),
+    );
+    assert!(!templates[""].contains("with"));
+
+    let config = Config::default();
+    assert_eq!(
+        config.filter_template(SOURCE, "item").unwrap(),
+        templates["item"]
+    );
+    assert_eq!(
+        config.parse_template(SOURCE).unwrap().filter("item"),
+        templates["item"]
+    );
+
+    // the attribute is still available as metadata
+    assert_eq!(
+        split_templates_detailed(SOURCE).unwrap()["item"].attributes["with"],
+        "item=none,loop=none"
+    );
+}
+
+#[test]
+fn first_attribute_is_used() {
+    let source = concat!(
+        "{% fragment a with=a=1 %}\n",
+        "1\n",
+        "{% endfragment %}\n",
+        "{% fragment a b with=a=2 %}\n",
+        "2\n",
+        "{% endfragment %}\n",
+    );
+    let templates = split_templates(source).unwrap();
+    assert_eq!(templates["a"], "{% with a=1 %}\n1\n2\n{% endwith %}\n");
+    assert_eq!(templates["b"], "{% with a=2 %}\n2\n{% endwith %}\n");
+}
+
+#[test]
+fn with_extends() {
+    let config = Config::default().extends("base.html", "content");
+    assert_eq!(
+        config.filter_template(SOURCE, "item").unwrap(),
+        concat!(
+            "{% extends \"base.html\" %}\n",
+            "{% block content %}\n",
+            "{% with item=none,loop=none %}\n",
+            "  <li>{{ item }}</li>\n",
+            "{% endwith %}\n",
+            "{% endblock %}\n",
+        ),
+    );
+}
+
+mod enclosing_control_tags {
+    use crate::{lint_template, Config};
+
+    const SOURCE: &str = concat!(
+        "<ul>\n",
+        "{% if items %}\n",
+        "  {% for item in items %}\n",
+        "  {% fragment item with=loop=none %}\n",
+        "  <li>{{ item }}</li>\n",
+        "  {% endfragment %}\n",
+        "  {% endfor %}\n",
+        "{% else %}\n",
+        "  {% fragment empty %}\n",
+        "  <li>No items</li>\n",
+        "  {% endfragment %}\n",
+        "{% endif %}\n",
+        "</ul>\n",
+        "{%- for row in rows -%}{% if row.visible %}\n",
+        "{% fragment row %}\n",
+        "{{ row }}\n",
+        "{% endfragment %}\n",
+        "{% endif %}{% endfor %}\n",
+    );
+
+    fn config() -> Config {
+        Config::default().enclosing_control_tags(true)
+    }
+
+    #[test]
+    fn tags_are_repeated() {
+        let templates = config().split_templates(SOURCE).unwrap();
+
+        assert_eq!(
+            templates["item"],
+            concat!(
+                "{% with loop=none %}\n",
+                "{% if items %}\n",
+                "{% for item in items %}\n",
+                "  <li>{{ item }}</li>\n",
+                "{% endfor %}\n",
+                "{% endif %}\n",
+                "{% endwith %}\n",
+            )
+        );
+        assert_eq!(templates["empty"], "  <li>No items</li>\n");
+        assert_eq!(
+            templates["row"],
+            concat!(
+                "{% for row in rows %}\n",
+                "{% if row.visible %}\n",
+                "{{ row }}\n",
+                "{% endif %}\n",
+                "{% endfor %}\n",
+            )
+        );
+        assert_eq!(
+            templates[""],
+            Config::default().split_templates(SOURCE).unwrap()[""]
+        );
+
+        for (name, template) in &templates {
+            assert_eq!(&config().filter_template(SOURCE, name).unwrap(), template);
+            assert_eq!(
+                config().parse_template(SOURCE).unwrap().filter(name),
+                template
+            );
+        }
+    }
+
+    #[test]
+    fn disabled_by_default() {
+        let templates = Config::default().split_templates(SOURCE).unwrap();
+        assert_eq!(templates["row"], "{{ row }}\n");
+    }
+
+    #[test]
+    fn loop_variables_are_not_linted() {
+        let source = concat!(
+            "{% for item in items %}\n",
+            "{% fragment item %}\n",
+            "{{ item }}\n",
+            "{% endfragment %}\n",
+            "{% endfor %}\n",
+        );
+        assert_eq!(lint_template(source).unwrap().len(), 1);
+        assert_eq!(config().lint_template(source).unwrap(), []);
+    }
+}
+
+mod enclosing_with_tags {
+    use crate::{lint_template, Config};
+
+    const SOURCE: &str = concat!(
+        "{% with user = request.user %}\n",
+        "{% for item in user.items %}\n",
+        "{% with label = item.name | title, price=item.price %}\n",
+        "{% fragment item %}\n",
+        "{{ label }}: {{ price }}\n",
+        "{% endfragment %}\n",
+        "{% endwith %}\n",
+        "{% endfor %}\n",
+        "{% endwith %}\n",
+    );
+
+    #[test]
+    fn with_tags_are_repeated() {
+        let config = Config::default().enclosing_with_tags(true);
+        assert_eq!(
+            config.filter_template(SOURCE, "item").unwrap(),
+            concat!(
+                "{% with user = request.user %}\n",
+                "{% with label = item.name | title, price=item.price %}\n",
+                "{{ label }}: {{ price }}\n",
+                "{% endwith %}\n",
+                "{% endwith %}\n",
+            )
+        );
+        assert_eq!(config.lint_template(SOURCE).unwrap(), []);
+        assert_eq!(lint_template(SOURCE).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn combined_with_control_tags() {
+        let config = Config::default()
+            .enclosing_with_tags(true)
+            .enclosing_control_tags(true);
+        assert_eq!(
+            config.split_templates(SOURCE).unwrap()["item"],
+            concat!(
+                "{% with user = request.user %}\n",
+                "{% for item in user.items %}\n",
+                "{% with label = item.name | title, price=item.price %}\n",
+                "{{ label }}: {{ price }}\n",
+                "{% endwith %}\n",
+                "{% endfor %}\n",
+                "{% endwith %}\n",
+            )
+        );
+    }
+}
@@ -0,0 +1,132 @@
+use crate::{
+    convert_template, split_templates, test::assert_matches, Config, Error, ErrorWithLine, Syntax,
+};
+
+#[test]
+fn html_comments() {
+    let source = concat!(
+        "<body>\r\n",
+        "<!-- doc: the list of items -->\r\n",
+        "<!-- fragment items cache=60 -->\r\n",
+        "  <!-- fragment-block: item -->\r\n",
+        "  <li>{{ item }}</li>\r\n",
+        "  <!-- endfragment-block -->\r\n",
+        "<!-- endfragment -->  \r\n",
+        "<!-- a regular comment -->\r\n",
+        "</body>",
+    );
+
+    let converted = convert_template(source, &Syntax::html_comments()).unwrap();
+    assert_eq!(
+        converted,
+        concat!(
+            "<body>\r\n",
+            "{# doc: the list of items #}\r\n",
+            "{% fragment items cache=60 %}\r\n",
+            "  {% fragment-block item %}\r\n",
+            "  <li>{{ item }}</li>\r\n",
+            "  {% endfragment-block %}\r\n",
+            "{% endfragment %}  \r\n",
+            "<!-- a regular comment -->\r\n",
+            "</body>",
+        )
+    );
+    assert_eq!(
+        split_templates(&converted).unwrap()["item"],
+        "  {% block item %}\r\n  <li>{{ item }}</li>\r\n  {% endblock %}\r\n"
+    );
+}
+
+#[test]
+fn target_syntax() {
+    let source = "/* fragment: a */\na\n/* endfragment */\n";
+    assert_eq!(
+        Config::default()
+            .syntax(Syntax::erb())
+            .convert_template(source, &Syntax::c_comments())
+            .unwrap(),
+        "<% fragment a %>\na\n<% endfragment %>\n"
+    );
+}
+
+#[test]
+fn canonical_tags_are_kept() {
+    let source = "{% fragment a %}\n{# doc: foo #}\n{% endfragment %}\n";
+    assert_eq!(convert_template(source, &Syntax::jinja()).unwrap(), source);
+}
+
+#[test]
+fn errors() {
+    assert_matches!(
+        convert_template("<!-- fragment: a -->\n", &Syntax::html_comments()),
+        Err(ErrorWithLine(0, Error::UnclosedTag(_))),
+    );
+    assert_matches!(
+        convert_template("<p><!-- fragment a --></p>\n", &Syntax::html_comments()),
+        Err(ErrorWithLine(0, Error::LeadingContent(_))),
+    );
+}
+
+mod markdown {
+    use crate::{Config, Error, ErrorWithLine};
+
+    const SOURCE: &str = concat!(
+        "# Guide\n",
+        "\n",
+        "<!-- fragment: intro -->\n",
+        "Welcome to {{ project }}.\n",
+        "<!-- endfragment -->\n",
+        "\n",
+        "<!-- fragment install -->\n",
+        "```bash\n",
+        "pip install {{ project }}\n",
+        "```\n",
+        "<!-- endfragment: -->\n",
+    );
+
+    #[test]
+    fn sections_are_fragments() {
+        let templates = Config::markdown().split_templates(SOURCE).unwrap();
+
+        assert_eq!(templates["intro"], "Welcome to {{ project }}.\n");
+        assert_eq!(
+            templates["install"],
+            "```bash\npip install {{ project }}\n```\n"
+        );
+        assert!(!templates[""].contains("<!--"));
+    }
+
+    #[test]
+    fn for_path() {
+        assert_eq!(Config::for_path("docs/index.md"), Config::markdown());
+        assert_eq!(Config::for_path("guide.markdown.j2"), Config::markdown());
+    }
+
+    #[test]
+    fn other_comments_are_kept() {
+        let source = "<!-- note: keep me -->\n<!-- fragment: a -->\nA\n<!-- endfragment -->\n";
+        let templates = Config::markdown().split_templates(source).unwrap();
+        assert_eq!(templates[""], "<!-- note: keep me -->\nA\n");
+    }
+
+    #[test]
+    fn errors_keep_their_lines() {
+        let source = "Text\n<!-- fragment: intro --> Hello\n<!-- endfragment -->\n";
+        assert_eq!(
+            Config::markdown().split_templates(source),
+            Err(ErrorWithLine(
+                1,
+                Error::TrailingContent(String::from(" Hello\n"))
+            ))
+        );
+    }
+
+    #[test]
+    fn colons_are_not_accepted_by_default() {
+        let config = Config::markdown().tag_colons(false);
+        assert_eq!(
+            config.split_templates(SOURCE),
+            Err(ErrorWithLine(4, Error::UnbalancedEndTag))
+        );
+    }
+}
@@ -0,0 +1,92 @@
+use crate::{test::assert_matches, Config, Error, ErrorWithLine};
+
+fn config() -> Config {
+    Config::default()
+        .custom_tag("cachefragment", |tag| {
+            Ok(format!("{{% fragment {} cache=60 %}}", tag.data))
+        })
+        .custom_tag("endcachefragment", |_| {
+            Ok(String::from("{% endfragment %}"))
+        })
+        .custom_tag("nonce", |_| Ok(String::from("nonce=\"{{ csp_nonce }}\"")))
+        .custom_tag("fail", |tag| Err(format!("cannot handle {:?}", tag.data)))
+        .custom_tag("multiline", |_| Ok(String::from("a\nb")))
+}
+
+#[test]
+fn expansion() {
+    let source = concat!(
+        "<ul>\r\n",
+        "  {% cachefragment items %}\r\n",
+        "    {%  nonce  %}\r\n",
+        "    <li>{{ item }}</li>\r\n",
+        "  {% endcachefragment %}\r\n",
+        "  {% unknown %}\r\n",
+        "</ul>\r\n",
+    );
+
+    let templates = config().split_templates(source).unwrap();
+    assert_eq!(
+        templates["items"],
+        "    nonce=\"{{ csp_nonce }}\"\r\n    <li>{{ item }}</li>\r\n"
+    );
+    assert_eq!(
+        templates[""],
+        concat!(
+            "<ul>\r\n",
+            "    nonce=\"{{ csp_nonce }}\"\r\n",
+            "    <li>{{ item }}</li>\r\n",
+            "  {% unknown %}\r\n",
+            "</ul>\r\n",
+        )
+    );
+    assert_eq!(
+        config().parse_ast(source).unwrap().nodes.len(),
+        3,
+        "custom fragment tags are part of the structure"
+    );
+}
+
+#[test]
+fn fragment_keywords_are_not_overwritten() {
+    let config = Config::default().custom_tag("fragment", |_| Ok(String::new()));
+    assert_eq!(
+        config
+            .filter_template("{% fragment a %}\na\n{% endfragment %}\n", "a")
+            .unwrap(),
+        "a\n"
+    );
+}
+
+#[test]
+fn errors() {
+    assert_eq!(
+        config().split_templates("\n  {% fail foo %}\n"),
+        Err(ErrorWithLine(
+            1,
+            Error::CustomTag(String::from("fail"), String::from("cannot handle \"foo\""))
+        )),
+    );
+    assert_matches!(
+        config().split_templates("{% multiline %}\n"),
+        Err(ErrorWithLine(0, Error::CustomTag(_, _))),
+    );
+    assert_matches!(
+        config().split_templates("<p>{% nonce %}</p>\n"),
+        Err(ErrorWithLine(0, Error::LeadingContent(_))),
+    );
+    assert_matches!(
+        config().split_templates("{% cachefragment a %}\n"),
+        Err(ErrorWithLine(0, Error::UnclosedTag(_))),
+    );
+}
+
+#[test]
+fn config_equality() {
+    let config = config();
+    assert_eq!(config.clone(), config);
+    assert_ne!(
+        Config::default().custom_tag("a", |_| Ok(String::new())),
+        Config::default().custom_tag("a", |_| Ok(String::new())),
+    );
+}
@@ -0,0 +1,59 @@
+use crate::{
+    decode_template, filter_template, format_template, fragment_exists, split_templates,
+    split_templates_lossy,
+};
+
+const SOURCE: &str = concat!(
+    "{% fragment item %}\r\n",
+    "<li>ä</li>\r\n",
+    "{% endfragment %}\r\n",
+);
+
+fn utf16(src: &str, to_bytes: fn(u16) -> [u8; 2]) -> Vec<u8> {
+    src.encode_utf16().flat_map(to_bytes).collect()
+}
+
+#[test]
+fn bom_is_stripped() {
+    let source = format!("\u{feff}{SOURCE}");
+
+    let templates = split_templates(&source).unwrap();
+    assert_eq!(templates[""], "<li>ä</li>\r\n");
+    assert_eq!(templates["item"], "<li>ä</li>\r\n");
+    assert_eq!(filter_template(&source, "item").unwrap(), "<li>ä</li>\r\n");
+    assert_eq!(format_template(&source).unwrap(), SOURCE);
+    assert!(fragment_exists(&source, "item"));
+}
+
+#[test]
+fn decode_utf8() {
+    let mut bytes = vec![0xef, 0xbb, 0xbf];
+    bytes.extend(SOURCE.as_bytes());
+
+    assert_eq!(decode_template(&bytes), SOURCE);
+    assert_eq!(decode_template(SOURCE.as_bytes()), SOURCE);
+    assert_eq!(decode_template(b""), "");
+}
+
+#[test]
+fn decode_utf16() {
+    let with_bom = format!("\u{feff}{SOURCE}");
+
+    assert_eq!(decode_template(&utf16(&with_bom, u16::to_le_bytes)), SOURCE);
+    assert_eq!(decode_template(&utf16(&with_bom, u16::to_be_bytes)), SOURCE);
+    assert_eq!(decode_template(&utf16(SOURCE, u16::to_le_bytes)), SOURCE);
+    assert_eq!(decode_template(&utf16(SOURCE, u16::to_be_bytes)), SOURCE);
+}
+
+#[test]
+fn decode_invalid() {
+    // unpaired surrogates and trailing odd bytes are replaced
+    assert_eq!(decode_template(b"\xff\xfea\0\x00\xd8b\0"), "a\u{fffd}b");
+    assert_eq!(decode_template(b"\xff\xfea\0b"), "a\u{fffd}");
+}
+
+#[test]
+fn split_lossy() {
+    let bytes = utf16(&format!("\u{feff}{SOURCE}"), u16::to_le_bytes);
+    assert_eq!(split_templates_lossy(&bytes), split_templates(SOURCE));
+}
@@ -0,0 +1,172 @@
+use crate::{Config, Error, ErrorWithLine};
+
+const SOURCE: &str = concat!(
+    "<body>\n",
+    "{% ifdef DEBUG %}\n",
+    "<pre>{{ context }}</pre>\n",
+    "{% endifdef %}\n",
+    "{% ifndef DEBUG %}\n",
+    "{% fragment item %}\n",
+    "<div>{{ item }}</div>\n",
+    "{% endfragment %}\n",
+    "{% endifdef %}\n",
+    "</body>\n",
+);
+
+#[test]
+fn ifdef() {
+    let config = Config::default().defines([("DEBUG", "")]);
+    let templates = config.split_templates(SOURCE).unwrap();
+    assert_eq!(templates[""], "<body>\n<pre>{{ context }}</pre>\n</body>\n");
+    assert!(!templates.contains_key("item"));
+
+    let config = Config::default().defines([("OTHER", "")]);
+    let templates = config.split_templates(SOURCE).unwrap();
+    assert_eq!(templates[""], "<body>\n<div>{{ item }}</div>\n</body>\n");
+    assert_eq!(templates["item"], "<div>{{ item }}</div>\n");
+}
+
+#[test]
+fn values() {
+    let source = concat!(
+        "{% ifdef MODE = dev %}\n",
+        "dev\n",
+        "{% endifdef %}\n",
+        "{% ifdef MODE=prod %}\n",
+        "prod\n",
+        "{% endifdef %}\n",
+        "{% ifndef MODE=prod %}\n",
+        "not prod\n",
+        "{% endifdef %}\n",
+    );
+
+    let config = Config::default().defines([("MODE", "dev")]);
+    assert_eq!(
+        config.filter_template(source, "").unwrap(),
+        "dev\nnot prod\n"
+    );
+
+    let config = Config::default().defines([(String::from("MODE"), String::from("prod"))]);
+    assert_eq!(config.filter_template(source, "").unwrap(), "prod\n");
+
+    let config = Config::default().defines(Vec::<(&str, &str)>::new());
+    assert_eq!(config.filter_template(source, "").unwrap(), "not prod\n");
+}
+
+#[test]
+fn nested() {
+    let source = concat!(
+        "a\n",
+        "  {% ifdef A %}\n",
+        "b\n",
+        "  {% ifdef B %}\n",
+        "c\n",
+        "  {% endifdef %}\n",
+        "d\n",
+        "  {% endifdef %}\n",
+        "e\n",
+    );
+
+    let config = Config::default().defines([("A", "1")]);
+    assert_eq!(config.filter_template(source, "").unwrap(), "a\nb\nd\ne\n");
+
+    let config = config.defines([("B", "1")]);
+    assert_eq!(
+        config.filter_template(source, "").unwrap(),
+        "a\nb\nc\nd\ne\n"
+    );
+
+    let config = Config::default().defines([("B", "1")]);
+    assert_eq!(config.filter_template(source, "").unwrap(), "a\ne\n");
+}
+
+#[test]
+fn without_defines() {
+    assert_eq!(
+        Config::default().filter_template(SOURCE, "").unwrap(),
+        concat!(
+            "<body>\n",
+            "{% ifdef DEBUG %}\n",
+            "<pre>{{ context }}</pre>\n",
+            "{% endifdef %}\n",
+            "{% ifndef DEBUG %}\n",
+            "<div>{{ item }}</div>\n",
+            "{% endifdef %}\n",
+            "</body>\n",
+        )
+    );
+}
+
+#[test]
+fn invalid_directives() {
+    let config = Config::default().defines([("DEBUG", "")]);
+
+    assert_eq!(
+        config.split_templates("a\n{% endifdef %}\n"),
+        Err(ErrorWithLine(1, Error::UnbalancedDirective)),
+    );
+    assert_eq!(
+        config.split_templates("{% ifdef A %}\n{% ifdef B %}\n{% endifdef %}\n"),
+        Err(ErrorWithLine(
+            0,
+            Error::UnclosedDirective(String::from("ifdef A"))
+        )),
+    );
+    assert_eq!(
+        config.split_templates("{% ifdef %}\n{% endifdef %}\n"),
+        Err(ErrorWithLine(
+            0,
+            Error::InvalidDirective(String::from("ifdef"))
+        )),
+    );
+    assert_eq!(
+        config.split_templates("{% ifdef A B %}\n{% endifdef %}\n"),
+        Err(ErrorWithLine(
+            0,
+            Error::InvalidDirective(String::from("ifdef A B"))
+        )),
+    );
+    assert_eq!(
+        config.split_templates("{% ifdef A %}\n{% endifdef A %}\n"),
+        Err(ErrorWithLine(
+            1,
+            Error::InvalidDirective(String::from("endifdef A"))
+        )),
+    );
+    assert_eq!(
+        config.split_templates("<div>{% ifdef A %}\n{% endifdef %}\n"),
+        Err(ErrorWithLine(
+            0,
+            Error::LeadingContent(String::from("<div>"))
+        )),
+    );
+}
+
+#[test]
+fn errors_refer_to_original_lines() {
+    let source = concat!(
+        "{% ifdef DEBUG %}\n",
+        "debug\n",
+        "{% endifdef %}\n",
+        "{% fragment item %}\n",
+    );
+    let config = Config::default().defines([("OTHER", "")]);
+    assert_eq!(
+        config.split_templates(source),
+        Err(ErrorWithLine(
+            3,
+            Error::UnclosedTag(vec![String::from("item")])
+        )),
+    );
+    assert_eq!(config.parse_ast(source).unwrap_err().0, 3);
+    assert_eq!(config.fragment_stats(source).unwrap_err().0, 3);
+
+    let config = config.custom_tag("fail", |_| Err(String::from("failed")));
+    assert_eq!(
+        config.split_templates("{% ifdef DEBUG %}\n{% endifdef %}\n{% fail %}\n"),
+        Err(ErrorWithLine(
+            2,
+            Error::CustomTag(String::from("fail"), String::from("failed"))
+        )),
+    );
+}
@@ -0,0 +1,125 @@
+use crate::{split_templates, split_templates_detailed};
+
+const SOURCE: &str = concat!(
+    "<body>\n",
+    "{% fragment foo bar cache=60 %}\n",
+    "<common>\n",
+    "{% endfragment %}\n",
+    "{% fragment foo role=admin cache=30 %}\n",
+    "<foo>\n",
+    "{% endfragment %}\n",
+    "  {% fragment-block baz cache=10 %}\n",
+    "<baz>\n",
+    "  {% endfragment-block %}\n",
+    "</body>\n",
+);
+
+#[test]
+fn content_matches_split_templates() {
+    let templates = split_templates(SOURCE).unwrap();
+    let fragments = split_templates_detailed(SOURCE).unwrap();
+
+    assert_eq!(fragments.len(), templates.len());
+    for (name, fragment) in &fragments {
+        assert_eq!(fragment.content, templates[name]);
+    }
+}
+
+#[test]
+fn attributes() {
+    let fragments = split_templates_detailed(SOURCE).unwrap();
+
+    assert!(fragments[""].attributes.is_empty());
+    assert_eq!(fragments["foo"].attributes.len(), 2);
+    assert_eq!(fragments["foo"].attributes["cache"], "30");
+    assert_eq!(fragments["foo"].attributes["role"], "admin");
+    assert_eq!(fragments["bar"].attributes.len(), 1);
+    assert_eq!(fragments["bar"].attributes["cache"], "60");
+    assert_eq!(fragments["baz"].attributes["cache"], "10");
+}
+
+#[test]
+fn attributes_are_ignored_by_split_templates() {
+    assert_eq!(split_templates(SOURCE).unwrap()["foo"], "<common>\n<foo>\n");
+}
+
+#[test]
+fn descriptions() {
+    let source = concat!(
+        "{# doc: The list #}\n",
+        "{# doc: of all items #}\n",
+        "{% fragment list %}\n",
+        "  {# doc: not directly before the tag #}\n",
+        "  <ul>\n",
+        "  {% fragment item %}\n",
+        "    <li>\n",
+        "  {% endfragment %}\n",
+        "  </ul>\n",
+        "{% endfragment %}\n",
+        "{# a plain comment #}\n",
+        "{% fragment other %}\n",
+        "{% endfragment %}\n",
+    );
+    let fragments = split_templates_detailed(source).unwrap();
+
+    assert_eq!(
+        fragments["list"].description.as_deref(),
+        Some("The list\nof all items")
+    );
+    assert_eq!(fragments["item"].description, None);
+    assert_eq!(fragments[""].description, None);
+    assert_eq!(fragments["other"].content, "");
+
+    // doc comments are kept in the output
+    assert_eq!(fragments[""].content, split_templates(source).unwrap()[""]);
+}
+
+#[test]
+fn parts() {
+    let fragments = split_templates_detailed(SOURCE).unwrap();
+
+    for fragment in fragments.values() {
+        let content: String = fragment
+            .parts
+            .iter()
+            .map(|part| part.content.as_str())
+            .collect();
+        assert_eq!(content, fragment.content);
+    }
+
+    let parts = &fragments["foo"].parts;
+    assert_eq!(parts.len(), 2);
+    assert_eq!(
+        (parts[0].content.as_str(), parts[0].lines.clone()),
+        ("<common>\n", 1..4)
+    );
+    assert_eq!(
+        (parts[1].content.as_str(), parts[1].lines.clone()),
+        ("<foo>\n", 4..7)
+    );
+
+    assert_eq!(fragments["bar"].parts.len(), 1);
+    assert_eq!(fragments["baz"].parts[0].lines, 7..10);
+    assert_eq!(fragments[""].parts.len(), 1);
+    assert_eq!(fragments[""].parts[0].lines, 0..11);
+}
+
+#[test]
+fn parts_of_nested_fragments() {
+    let source = concat!(
+        "{% fragment items %}\n",
+        "{% fragment item %}\n",
+        "<li>{{ first }}</li>\n",
+        "{% endfragment %}\n",
+        "{% fragment item %}\n",
+        "{% endfragment %}\n",
+        "{% endfragment %}\n",
+    );
+    let fragments = split_templates_detailed(source).unwrap();
+
+    assert_eq!(fragments["items"].parts.len(), 1);
+    assert_eq!(fragments["items"].parts[0].lines, 0..7);
+    assert_eq!(fragments["item"].parts.len(), 2);
+    assert_eq!(fragments["item"].parts[1].content, "");
+    assert_eq!(fragments["item"].parts[1].lines, 4..6);
+}
@@ -0,0 +1,64 @@
+use std::collections::HashMap;
+
+use crate::{diff_templates, test::assert_matches, Error, ErrorWithLine, TemplateDiff};
+
+#[test]
+fn identical_templates() {
+    let src = "<body>\n{% fragment a %}\n<a>\n{% endfragment %}\n";
+    let diff = diff_templates(src, src).unwrap();
+
+    assert!(diff.is_empty());
+    assert_eq!(diff, TemplateDiff::default());
+}
+
+#[test]
+fn moved_fragments_are_changed_in_their_parents_only() {
+    let old = concat!(
+        "{% fragment outer %}\n",
+        "{% fragment a %}\n<a>\n{% endfragment %}\n",
+        "{% endfragment %}\n",
+        "{% fragment b %}\n<b>\n{% endfragment %}\n",
+    );
+    let new = concat!(
+        "{% fragment b %}\n<b>\n{% endfragment %}\n",
+        "{% fragment outer %}\n",
+        "{% fragment a %}\n<a>\n{% endfragment %}\n",
+        "{% endfragment %}\n",
+    );
+
+    let diff = diff_templates(old, new).unwrap();
+    assert_eq!(diff.changed, vec![""]);
+    assert_eq!(diff.affected(), vec![""]);
+}
+
+#[test]
+fn affected_fragments() {
+    let diff = TemplateDiff {
+        added: vec![String::from("c")],
+        removed: vec![String::from("a")],
+        changed: vec![String::from("b")],
+    };
+    assert_eq!(diff.affected(), vec!["a", "b", "c"]);
+}
+
+#[test]
+fn between_split_templates() {
+    let old = HashMap::from([(String::from(""), String::from("foo"))]);
+    let new = HashMap::new();
+
+    let diff = TemplateDiff::between(&old, &new);
+    assert_eq!(diff.removed, vec![""]);
+    assert!(diff.added.is_empty());
+}
+
+#[test]
+fn invalid_templates() {
+    assert_matches!(
+        diff_templates("{% endfragment %}\n", ""),
+        Err(ErrorWithLine(0, Error::UnbalancedEndTag)),
+    );
+    assert_matches!(
+        diff_templates("", "\n{% endfragment %}\n"),
+        Err(ErrorWithLine(1, Error::UnbalancedEndTag)),
+    );
+}
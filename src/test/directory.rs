@@ -0,0 +1,77 @@
+use crate::{
+    split_directory, test::TempDir, Config, DirectoryError, Error, ErrorWithLine, ErrorWithPath,
+};
+
+#[test]
+fn for_path() {
+    let latex = Config::default()
+        .tag_markers("((*", "*))")
+        .comment_markers("((=", "=))");
+
+    assert_eq!(Config::for_path("index.html"), Config::default());
+    assert_eq!(Config::for_path("index.html.j2"), Config::default());
+    assert_eq!(Config::for_path("model.sql"), Config::sql());
+    assert_eq!(Config::for_path("model.sql.j2"), Config::sql());
+    assert_eq!(Config::for_path("report.tex"), latex);
+    assert_eq!(Config::for_path("dir/report.tex.jinja2"), latex);
+    assert_eq!(Config::for_path("README"), Config::default());
+}
+
+#[test]
+fn custom_markers_for_blocks() {
+    let source = concat!(
+        "<% fragment-block item %>\n",
+        "<item>\n",
+        "<% endfragment-block %>\n",
+    );
+    let templates = Config::default()
+        .tag_markers("<%", "%>")
+        .split_templates(source)
+        .unwrap();
+
+    assert_eq!(
+        templates["item"],
+        "<% block item %>\n<item>\n<% endblock %>\n"
+    );
+}
+
+#[test]
+fn mixed_tree() {
+    let dir = TempDir::new("mixed-tree");
+    dir.write(
+        "index.html",
+        "<body>\n{% fragment item %}\n<item>\n{% endfragment %}\n</body>\n",
+    );
+    dir.write(
+        "reports/report.tex.j2",
+        "((* fragment summary *))\n\\summary\n((* endfragment *))\n",
+    );
+
+    let templates = split_directory(&dir.0).unwrap();
+    assert_eq!(templates.len(), 4);
+    assert_eq!(templates["index.html"], "<body>\n<item>\n</body>\n");
+    assert_eq!(templates["index.html#item"], "<item>\n");
+    assert_eq!(templates["reports/report.tex.j2"], "\\summary\n");
+    assert_eq!(templates["reports/report.tex.j2#summary"], "\\summary\n");
+}
+
+#[test]
+fn errors() {
+    let dir = TempDir::new("errors");
+    dir.write("broken/index.html", "{% fragment item %}\n");
+
+    match split_directory(&dir.0) {
+        Err(DirectoryError::Template(ErrorWithPath(
+            path,
+            ErrorWithLine(0, Error::UnclosedTag(_)),
+        ))) => {
+            assert_eq!(path, "broken/index.html")
+        }
+        res => panic!("unexpected result {res:?}"),
+    }
+
+    assert!(matches!(
+        split_directory(dir.0.join("missing")),
+        Err(DirectoryError::Io(_, _))
+    ));
+}
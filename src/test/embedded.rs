@@ -0,0 +1,35 @@
+use crate::EmbeddedTemplates;
+
+static TEMPLATES: EmbeddedTemplates = EmbeddedTemplates::from_sorted(&[
+    ("index.html", "<ul>\n</ul>\n"),
+    ("index.html#item", "<li></li>\n"),
+    ("pages/page.html", "page\n"),
+]);
+
+#[test]
+fn lookup() {
+    assert_eq!(TEMPLATES.get("index.html"), Some("<ul>\n</ul>\n"));
+    assert_eq!(TEMPLATES.get("index.html#item"), Some("<li></li>\n"));
+    assert_eq!(TEMPLATES.get(" index.html # item "), Some("<li></li>\n"));
+    assert_eq!(TEMPLATES.get("index.html#"), Some("<ul>\n</ul>\n"));
+    assert_eq!(
+        TEMPLATES.get_fragment("pages/page.html", ""),
+        Some("page\n")
+    );
+    assert_eq!(TEMPLATES.get("index.html#missing"), None);
+    assert_eq!(TEMPLATES.get("missing.html"), None);
+
+    assert!(TEMPLATES.contains("pages/page.html"));
+    assert!(!TEMPLATES.contains("pages"));
+}
+
+#[test]
+fn iter() {
+    assert_eq!(
+        TEMPLATES.iter().map(|(name, _)| name).collect::<Vec<_>>(),
+        ["index.html", "index.html#item", "pages/page.html"]
+    );
+    assert_eq!(TEMPLATES.len(), 3);
+    assert!(!TEMPLATES.is_empty());
+    assert!(EmbeddedTemplates::from_sorted(&[]).is_empty());
+}
@@ -118,3 +118,86 @@ fn invalid_tag_name() {
         Err(ErrorWithLine(_, Error::InvalidFragmentName(_))),
     );
 }
+
+#[test]
+fn structured_payloads() {
+    const SOURCE: &str = r#"
+        {% fragment foo bar %}
+        {% fragment bar baz foo %}
+        {% endfragment %}
+        {% endfragment %}
+    "#;
+
+    assert_eq!(
+        split_templates(SOURCE),
+        Err(ErrorWithLine(
+            2,
+            Error::ReentrantFragment(vec![String::from("bar"), String::from("foo")])
+        )),
+    );
+    assert_eq!(
+        split_templates("{% fragment foo bar %}\n").map_err(|err| err.1.fragments().to_vec()),
+        Err(vec![String::from("bar"), String::from("foo")]),
+    );
+    assert_eq!(
+        Error::UnclosedTag(vec![String::from("bar"), String::from("foo")]).to_string(),
+        "Error::UnclosedTag(bar, foo)",
+    );
+}
+
+#[test]
+fn with_source() {
+    const SOURCE: &str = "<body>\r\n  {% endfragment %}  \r\n</body>\r\n";
+
+    let err = split_templates(SOURCE).unwrap_err().with_source(SOURCE);
+    assert_eq!(err.error, ErrorWithLine(1, Error::UnbalancedEndTag));
+    assert_eq!(err.line, "  {% endfragment %}  ");
+    assert_eq!(err.tag.as_deref(), Some("{% endfragment %}"));
+    assert_eq!(
+        err.to_string(),
+        "Error::UnbalancedTags at line 2: \"  {% endfragment %}  \"",
+    );
+
+    // errors at the end of the template do not necessarily point to a tag
+    const UNCLOSED: &str = "{% fragment foo %}\n<foo>\n";
+
+    let err = split_templates(UNCLOSED).unwrap_err().with_source(UNCLOSED);
+    assert_eq!(err.line, "<foo>");
+    assert_eq!(err.tag, None);
+}
+
+#[test]
+fn with_span() {
+    const SOURCE: &str = "<body>\r\n  {% endfragment %}  \r\n</body>\r\n";
+
+    let err = split_templates(SOURCE).unwrap_err().with_span(SOURCE);
+    assert_eq!(err.error, ErrorWithLine(1, Error::UnbalancedEndTag));
+    assert_eq!(&SOURCE[err.line.clone()], "  {% endfragment %}  ");
+    assert_eq!(err.tag, Some(10..27));
+    assert_eq!(
+        err.to_string(),
+        "Error::UnbalancedTags at line 2 (bytes 10..27)"
+    );
+
+    const UNCLOSED: &str = "{% fragment foo %}\n<foo>\n";
+
+    let err = split_templates(UNCLOSED).unwrap_err().with_span(UNCLOSED);
+    assert_eq!(err.line, 19..24);
+    assert_eq!(err.tag, None);
+    assert_eq!(
+        err.to_string(),
+        "Error::UnclosedTag(foo) at line 2 (bytes 19..24)"
+    );
+
+    // multi-byte characters are counted in bytes
+    const UNICODE: &str = "äöü\n{% endfragment %}\n";
+
+    let err = split_templates(UNICODE).unwrap_err().with_span(UNICODE);
+    assert_eq!(err.line, 7..24);
+    assert_eq!(err.tag, Some(7..24));
+
+    // lines past the end of the source result in an empty span at its end
+    let err = ErrorWithLine(5, Error::UnbalancedEndTag).with_span(UNICODE);
+    assert_eq!(err.line, 25..25);
+    assert_eq!(err.tag, None);
+}
@@ -0,0 +1,72 @@
+use crate::{fragment_exists, has_fragments, Config, NameCase, Syntax};
+
+const SOURCE: &str = concat!(
+    "<ul>\n",
+    "{% fragment-block list %}\n",
+    "{% fragment item other %}\n",
+    "{# fragment comment #}\n",
+    "<li>fragment missing</li>\n",
+    "{% endfragment %}\n",
+    "{% endfragment-block %}\n",
+    "</ul>\n",
+);
+
+#[test]
+fn defined_fragments() {
+    for fragment in ["", "list", "item", "other", "  item  "] {
+        assert!(fragment_exists(SOURCE, fragment), "{fragment:?}");
+    }
+}
+
+#[test]
+fn undefined_fragments() {
+    for fragment in ["missing", "comment", "ite", "fragment", "endfragment"] {
+        assert!(!fragment_exists(SOURCE, fragment), "{fragment:?}");
+    }
+}
+
+#[test]
+fn invalid_templates_are_not_validated() {
+    assert!(fragment_exists("{% fragment item %}\n", "item"));
+}
+
+#[test]
+fn name_case() {
+    let config = Config::default().name_case(NameCase::Lower);
+    assert!(config.fragment_exists("{% fragment Item %}\n", "ITEM"));
+    assert!(!Config::default().fragment_exists("{% fragment Item %}\n", "ITEM"));
+}
+
+#[test]
+fn syntax() {
+    let config = Config::default().syntax(Syntax::erb());
+    assert!(config.fragment_exists("<% fragment item %>\n", "item"));
+    assert!(!config.fragment_exists("{% fragment item %}\n", "item"));
+}
+
+#[test]
+fn has_fragment_tags() {
+    assert!(has_fragments(SOURCE));
+    assert!(has_fragments("{% fragment-block item %}"));
+    assert!(has_fragments("\u{feff}{% endfragment %}\n"));
+
+    assert!(!has_fragments(""));
+    assert!(!has_fragments(
+        "<ul>\n{# fragment item #}\n{% for item in items %}\n</ul>\n"
+    ));
+    assert!(!has_fragments("{% fragmented %}\n{% endfor %}\n"));
+}
+
+#[test]
+fn has_invalid_fragment_tags() {
+    assert!(has_fragments("<li>{% fragment item %}</li>\n"));
+    assert!(has_fragments("{% endfragment item %}\n"));
+}
+
+#[test]
+fn has_fragments_with_syntax() {
+    let source = "<!-- fragment item -->\n<li></li>\n<!-- endfragment -->\n";
+    let config = Config::default().syntax(Syntax::html_comments());
+    assert!(config.has_fragments(source));
+    assert!(!has_fragments(source));
+}
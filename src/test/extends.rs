@@ -0,0 +1,53 @@
+use crate::{Config, Syntax};
+
+const SOURCE: &str = concat!(
+    "<body>\n",
+    "{% fragment a %}\n",
+    "  <p>{{ a }}</p>\n",
+    "{% endfragment %}\n",
+    "{% fragment empty %}\n",
+    "{% endfragment %}\n",
+    "</body>",
+);
+
+const EXPECTED: &str = concat!(
+    "{% extends \"base.html\" %}\n",
+    "{% block content %}\n",
+    "  <p>{{ a }}</p>\n",
+    "{% endblock %}\n",
+);
+
+#[test]
+fn entry_points() {
+    let config = Config::default().extends("base.html", "content");
+
+    let templates = config.split_templates(SOURCE).unwrap();
+    assert_eq!(templates["a"], EXPECTED);
+    assert_eq!(templates[""], "<body>\n  <p>{{ a }}</p>\n</body>");
+    assert!(!templates.contains_key("empty"));
+
+    assert_eq!(config.filter_template(SOURCE, "a").unwrap(), EXPECTED);
+    assert_eq!(config.filter_template(SOURCE, "empty").unwrap(), "");
+    assert_eq!(config.parse_template(SOURCE).unwrap().filter("a"), EXPECTED);
+}
+
+#[test]
+fn other_options() {
+    let config = Config::default()
+        .syntax(Syntax::erb())
+        .extends("base.html", "main")
+        .minify_html(true)
+        .macros(true);
+    let templates = config
+        .split_templates("<% fragment a %>\n  <p></p>\n<% endfragment %>\n")
+        .unwrap();
+
+    assert_eq!(
+        templates["a"],
+        "<% extends \"base.html\" %>\n<% block main %>\n<p></p>\n<% endblock %>\n"
+    );
+    assert_eq!(
+        templates["_macros"],
+        "<% macro a() %>\n<p></p>\n<% endmacro %>\n"
+    );
+}
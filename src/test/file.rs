@@ -0,0 +1,55 @@
+use crate::{
+    split_template_file, split_templates, test::assert_matches, test::TempDir, Config,
+    DirectoryError, Error, ErrorWithLine, ErrorWithPath,
+};
+
+#[test]
+fn split_files() {
+    let dir = TempDir::new("split-files");
+    let source = "<ul>\n{% fragment item %}\n<li></li>\n{% endfragment %}\n</ul>\n";
+    dir.write("index.html", source);
+    dir.write("empty.html", "");
+    dir.write("page.tex", "((* fragment a *))\na\n((* endfragment *))\n");
+
+    assert_eq!(
+        split_template_file(dir.0.join("index.html")).unwrap(),
+        split_templates(source).unwrap()
+    );
+    assert!(split_template_file(dir.0.join("empty.html"))
+        .unwrap()
+        .is_empty());
+    assert_eq!(
+        split_template_file(dir.0.join("page.tex")).unwrap()["a"],
+        "a\n"
+    );
+    assert_eq!(
+        Config::default()
+            .unique_fragments(true)
+            .split_template_file(dir.0.join("index.html"))
+            .unwrap()["item"],
+        "<li></li>\n"
+    );
+}
+
+#[test]
+fn errors() {
+    let dir = TempDir::new("file-errors");
+    dir.write("unclosed.html", "{% fragment a %}\n");
+    std::fs::write(dir.0.join("binary.html"), [0xff, 0xfe, 0x00]).unwrap();
+
+    assert_matches!(
+        split_template_file(dir.0.join("missing.html")),
+        Err(DirectoryError::Io(_, _)),
+    );
+    assert!(matches!(
+        split_template_file(dir.0.join("binary.html")),
+        Err(DirectoryError::Io(_, err)) if err.kind() == std::io::ErrorKind::InvalidData
+    ));
+    assert_matches!(
+        split_template_file(dir.0.join("unclosed.html")),
+        Err(DirectoryError::Template(ErrorWithPath(
+            _,
+            ErrorWithLine(0, Error::UnclosedTag(_))
+        ))),
+    );
+}
@@ -0,0 +1,116 @@
+use crate::{format_template, split_templates, test::assert_matches, Config, Error, ErrorWithLine};
+
+#[test]
+fn nested_fragments() {
+    let source = concat!(
+        "<body>\n",
+        "{% fragment items  role=list  cache=60 %}\n",
+        "  <ul>\n",
+        "{% fragment-block   item %}\n",
+        "\n",
+        "    <li>{{ item }}</li>\n",
+        "{% endfragment-block %}\n",
+        "  </ul>\n",
+        "      {% endfragment %}\n",
+        "</body>\n",
+    );
+
+    assert_eq!(
+        format_template(source).unwrap(),
+        concat!(
+            "<body>\n",
+            "  {% fragment items role=list cache=60 %}\n",
+            "  <ul>\n",
+            "    {% fragment-block item %}\n",
+            "\n",
+            "    <li>{{ item }}</li>\n",
+            "    {% endfragment-block %}\n",
+            "  </ul>\n",
+            "  {% endfragment %}\n",
+            "</body>\n",
+        )
+    );
+}
+
+#[test]
+fn nested_tags_determine_the_indent() {
+    let source = concat!(
+        "{% fragment a %}\n",
+        "{% fragment b %}\n",
+        "\t<b>\n",
+        "{% endfragment %}\n",
+        "{% endfragment %}",
+    );
+    assert_eq!(
+        format_template(source).unwrap(),
+        concat!(
+            "\t{% fragment a %}\n",
+            "\t{% fragment b %}\n",
+            "\t<b>\n",
+            "\t{% endfragment %}\n",
+            "\t{% endfragment %}",
+        )
+    );
+}
+
+#[test]
+fn empty_fragments_keep_their_indent() {
+    let source = "  {% fragment  b a %}\r\n{% endfragment %}\r\n";
+    assert_eq!(
+        format_template(source).unwrap(),
+        "  {% fragment a b %}\r\n  {% endfragment %}\r\n"
+    );
+}
+
+#[test]
+fn formatting_is_idempotent() {
+    let source = concat!(
+        "<body>\n",
+        "{%\tfragment   c b a %}\n",
+        "  <ul>\n",
+        "{% fragment-block item %}\n",
+        "    <li>{{ item }}</li>\n",
+        "{% endfragment-block %}\n",
+        "  </ul>\n",
+        "{% endfragment %}\n",
+        "</body>",
+    );
+    let formatted = format_template(source).unwrap();
+    assert_eq!(format_template(&formatted).unwrap(), formatted);
+}
+
+#[test]
+fn mismatched_end_tags_are_kept() {
+    let sources = [
+        "<a>\n{% fragment a b  %}\n<x>\n{% endfragment-block %}\n</a>\n",
+        "<a>\n{% fragment-block a %}\n<x>\n{% endfragment  %}\n</a>\n",
+    ];
+    for source in sources {
+        let formatted = format_template(source).unwrap();
+        assert_ne!(formatted, source);
+        assert_eq!(
+            split_templates(&formatted).unwrap(),
+            split_templates(source).unwrap()
+        );
+    }
+}
+
+#[test]
+fn config_is_used() {
+    let config = Config::default()
+        .tag_markers("<%", "%>")
+        .named_end_tags(true);
+    let source = "<%\tfragment a %>\n<a>\n<%\tendfragment   a %>\n";
+    assert_eq!(
+        config.format_template(source).unwrap(),
+        "<% fragment a %>\n<a>\n<% endfragment a %>\n"
+    );
+}
+
+#[test]
+fn errors() {
+    assert_matches!(
+        format_template("{% fragment a %}\n"),
+        Err(ErrorWithLine(0, Error::UnclosedTag(_))),
+    );
+}
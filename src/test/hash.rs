@@ -0,0 +1,59 @@
+use crate::{split_templates, split_templates_hashed, Config, HashedFragment};
+
+const SOURCE: &str = concat!(
+    "<ul>\n",
+    "{% fragment item %}\n",
+    "  <li>{{ item }}</li>\n",
+    "{% endfragment %}\n",
+    "</ul>\n",
+);
+
+#[test]
+fn hashes_are_stable() {
+    // the hash must not change between platforms or versions
+    let fragment = HashedFragment::new(String::from("<li>{{ item }}</li>\n"));
+    assert_eq!(fragment.etag(), format!("\"{:032x}\"", fragment.hash));
+    assert_eq!(
+        fragment,
+        HashedFragment::new(String::from("<li>{{ item }}</li>\n"))
+    );
+    assert_eq!(
+        HashedFragment::new(String::new()).hash,
+        0x99aa06d3014798d86001c324468d497f,
+    );
+}
+
+#[test]
+fn split_hashed() {
+    let fragments = split_templates_hashed(SOURCE).unwrap();
+    let templates = split_templates(SOURCE).unwrap();
+
+    assert_eq!(fragments.len(), templates.len());
+    for (fragment, content) in templates {
+        assert_eq!(fragments[&fragment], HashedFragment::new(content));
+    }
+}
+
+#[test]
+fn hashes_include_output_options() {
+    let config = Config::default().minify_html(true);
+    let fragments = config.split_templates_hashed(SOURCE).unwrap();
+    assert_eq!(fragments["item"].content, "<li>{{ item }}</li>\n");
+    assert_ne!(
+        fragments["item"],
+        split_templates_hashed(SOURCE).unwrap()["item"]
+    );
+}
+
+#[test]
+fn if_none_match() {
+    let fragment = HashedFragment::new(String::from("content"));
+    let etag = fragment.etag();
+
+    assert!(fragment.matches(&etag));
+    assert!(fragment.matches("*"));
+    assert!(fragment.matches(&format!("\"a\",  W/{etag} ")));
+    assert!(!fragment.matches(""));
+    assert!(!fragment.matches(etag.trim_matches('"')));
+    assert!(!fragment.matches(&HashedFragment::new(String::from("other")).etag()));
+}
@@ -0,0 +1,189 @@
+use crate::{split_templates, Config, Error};
+
+#[test]
+fn unclosed_tag() {
+    let source = concat!(
+        "<ul>\n",
+        "{% fragment items %}\n",
+        "  {% fragment item %}\n",
+        "  <li></li>\n",
+        "  {% endfragment %}\n",
+        "</ul>\n",
+    );
+    let err = split_templates(source).unwrap_err();
+
+    assert_eq!(
+        err.1.help(),
+        "did you forget `{% endfragment %}` for `items`?"
+    );
+    assert_eq!(
+        Config::default().error_help(source, &err),
+        "did you forget `{% endfragment %}` for `items` opened on line 2?"
+    );
+}
+
+#[test]
+fn unclosed_block_with_defines() {
+    let source = concat!(
+        "{% ifdef DEBUG %}\n",
+        "debug\n",
+        "{% endifdef %}\n",
+        "{% fragment-block item %}\n",
+    );
+    let config = Config::default().defines([("OTHER", "")]);
+    let err = config.split_templates(source).unwrap_err();
+
+    assert_eq!(
+        config.error_help(source, &err),
+        "did you forget `{% endfragment-block %}` for `item` opened on line 4?"
+    );
+}
+
+#[test]
+fn other_errors() {
+    let source = "{% fragment item %}\n{% endfragment %}\n{% endfragment %}\n";
+    let err = split_templates(source).unwrap_err();
+    assert_eq!(err.1, Error::UnbalancedEndTag);
+    assert_eq!(Config::default().error_help(source, &err), err.1.help());
+
+    assert_eq!(
+        Error::RepeatedFragment(String::from("item"), 4).help(),
+        "`item` is first defined on line 5, rename one of the fragments"
+    );
+    assert_eq!(
+        Error::MismatchedEndTag(
+            vec![String::from("a")],
+            vec![String::from("b"), String::from("c")]
+        )
+        .help(),
+        "the innermost open tag is for `b`, `c`, close it first"
+    );
+    assert!(Error::UnbalancedDirective.help().contains("endifdef"));
+}
+
+#[test]
+fn suggestions() {
+    let available = |names: &[&str]| names.iter().map(|&name| name.to_owned()).collect();
+
+    let err = Error::UnknownFragment(
+        String::from("itme"),
+        available(&["footer", "item", "items"]),
+    );
+    assert_eq!(err.suggestions(), ["item"]);
+    assert_eq!(err.help(), "did you mean `item`?");
+
+    let err = Error::UnknownFragment(String::from("iten"), available(&["iter", "item", "items"]));
+    assert_eq!(err.suggestions(), ["item", "iter"]);
+    assert_eq!(err.help(), "did you mean `item`, `iter`?");
+
+    let err = Error::UnknownFragment(String::from("header"), available(&["footer", "item"]));
+    assert!(err.suggestions().is_empty());
+    assert_eq!(err.help(), "available fragments: footer, item");
+
+    let err = Error::UnknownFragment(String::from("item"), Vec::new());
+    assert_eq!(err.help(), "the template does not define any fragments");
+
+    assert!(Error::UnclosedTag(available(&["item"]))
+        .suggestions()
+        .is_empty());
+}
+
+mod render_error {
+    use crate::{Config, Error, ErrorWithLine};
+
+    fn render(config: &Config, source: &str) -> String {
+        let err = config.split_templates(source).unwrap_err();
+        config.render_error(source, &err)
+    }
+
+    #[test]
+    fn leading_content() {
+        let source = "<ul>\n<li>{% fragment item %}\n{% endfragment %}\n</ul>\n";
+        assert_eq!(
+            render(&Config::default(), source),
+            concat!(
+                "error: content before a fragment tag\n",
+                " --> line 2\n",
+                "  |\n",
+                "2 | <li>{% fragment item %}\n",
+                "  | ^^^^\n",
+                "  = help: move the content before the tag to a separate line, ",
+                "fragment tags must be on a line of their own\n",
+            )
+        );
+    }
+
+    #[test]
+    fn unclosed_tag_points_to_start_tag() {
+        let source = concat!(
+            "{% fragment page %}\n",
+            "<ul>\n",
+            "  {% fragment items %}\n",
+            "  <li></li>\n",
+            "</ul>\n",
+            "{% endfragment %}\n",
+        );
+        assert_eq!(
+            render(&Config::default(), source),
+            concat!(
+                "error: unclosed fragment `page`\n",
+                " --> line 1\n",
+                "  |\n",
+                "1 | {% fragment page %}\n",
+                "  | ^^^^^^^^^^^^^^^^^^^\n",
+                "  = help: did you forget `{% endfragment %}` for `page` opened on line 1?\n",
+            )
+        );
+    }
+
+    #[test]
+    fn fragment_stack_and_wide_gutter() {
+        let mut source = String::from("{% fragment outer %}\n");
+        source.push_str(&"<br>\n".repeat(8));
+        source.push_str("\t{% fragment-block inner %}\n");
+        source.push_str("\t{% fragment outer %}\n");
+        let rendered = render(&Config::default(), &source);
+
+        assert_eq!(
+            rendered,
+            concat!(
+                "error: `outer` already open\n",
+                "  --> line 11\n",
+                "   |\n",
+                "11 | \t{% fragment outer %}\n",
+                "   | \t^^^^^^^^^^^^^^^^^^^^\n",
+                "   = inside: `outer` (line 1) > `inner` (line 10)\n",
+                "   = help: `outer` already open, close it before reopening or use a different name\n",
+            )
+        );
+    }
+
+    #[test]
+    fn custom_syntax() {
+        let source = "((* fragment item *))\n((* endfragment item *))\n";
+        let config = Config::for_path("report.tex");
+        let rendered = render(&config, source);
+        assert!(rendered.starts_with("error: end tag with fragment names\n"));
+        assert!(rendered.contains("\n2 | ((* endfragment item *))\n  | ^^^^^^^^^^^^^^^^^^^^^^^^\n"));
+        assert!(rendered.contains("= inside: `item` (line 1)\n"));
+    }
+
+    #[test]
+    fn line_without_tag() {
+        let source = "{% ifdef DEBUG %}\ndebug\n  {% endifdef %} \n{% endifdef %}\n";
+        let config = Config::default().defines([("DEBUG", "")]);
+        let rendered = render(&config, source);
+        assert!(rendered.contains("4 | {% endifdef %}\n  | ^^^^^^^^^^^^^^\n"));
+
+        // errors of lines outside of the source do not show a snippet
+        let err = ErrorWithLine(7, Error::UnbalancedEndTag);
+        assert_eq!(
+            Config::default().render_error("", &err),
+            concat!(
+                "error: end tag without a matching start tag\n",
+                " --> line 8\n",
+                "  = help: remove the end tag or add the missing `{% fragment ... %}` before it\n",
+            )
+        );
+    }
+}
@@ -0,0 +1,123 @@
+use crate::{resolve_includes, split_templates, test::assert_matches, Error, ErrorWithLine};
+
+fn resolver(path: &str) -> Option<String> {
+    match path {
+        "item.html" => Some(String::from("<item>\n")),
+        "nested.html" => Some(String::from("<nested>\n{% include 'item.html' %}\n")),
+        "no-newline.html" => Some(String::from("<no-newline>")),
+        "recursive.html" => Some(String::from("{% include \"recursive.html\" %}\n")),
+        _ => None,
+    }
+}
+
+#[test]
+fn expands_includes() {
+    let source = concat!(
+        "<body>\n",
+        "{% fragment items %}\n",
+        "  {% include \"nested.html\" %}\n",
+        "  {% include \"no-newline.html\" %}\n",
+        "{% endfragment %}\n",
+        "</body>\n",
+    );
+
+    let source = resolve_includes(source, resolver).unwrap();
+    assert_eq!(
+        split_templates(&source).unwrap()["items"],
+        "<nested>\n<item>\n<no-newline>\n"
+    );
+}
+
+#[test]
+fn keeps_other_includes() {
+    let source = concat!(
+        "<div>{% include \"item.html\" %}</div>\n",
+        "{% include \"item.html\" with context %}\n",
+        "{% include item_path %}\n",
+    );
+    assert_eq!(resolve_includes(source, resolver).unwrap(), source);
+}
+
+#[test]
+fn unresolved_include() {
+    assert_eq!(
+        resolve_includes("\n{% include \"missing.html\" %}\n", resolver),
+        Err(ErrorWithLine(
+            1,
+            Error::UnresolvedInclude(String::from("missing.html"))
+        )),
+    );
+}
+
+#[test]
+fn recursive_include() {
+    assert_matches!(
+        resolve_includes("{% include \"recursive.html\" %}\n", resolver),
+        Err(ErrorWithLine(0, Error::RecursiveInclude(_))),
+    );
+}
+
+mod fragment_uses {
+    use crate::{resolve_fragment_uses, test::assert_matches, Error, ErrorWithLine};
+
+    fn loader(path: &str) -> Option<String> {
+        let source = match path {
+            "components.html" => concat!(
+                "{% fragment card %}\n",
+                "<card>\n",
+                "  {% fragment-use button from \"buttons.html\" %}\n",
+                "</card>\n",
+                "{% endfragment %}\n",
+                "{% fragment loop %}\n",
+                "{% fragment-use loop from 'components.html' %}\n",
+                "{% endfragment %}\n",
+            ),
+            "buttons.html" => concat!(
+                "{% fragment button %}\n",
+                "  <button>\n",
+                "{% endfragment %}\n",
+            ),
+            _ => return None,
+        };
+        Some(String::from(source))
+    }
+
+    #[test]
+    fn expands_uses_recursively() {
+        let source = concat!(
+            "<body>\n",
+            "{% fragment-use card from \"components.html\" %}\n",
+            "</body>\n",
+        );
+        assert_eq!(
+            resolve_fragment_uses(source, loader).unwrap(),
+            "<body>\n<card>\n  <button>\n</card>\n</body>\n",
+        );
+    }
+
+    #[test]
+    fn unresolved_uses() {
+        assert_eq!(
+            resolve_fragment_uses("{% fragment-use card from \"missing.html\" %}\n", loader),
+            Err(ErrorWithLine(
+                0,
+                Error::UnresolvedInclude(String::from("missing.html"))
+            )),
+        );
+        assert_eq!(
+            resolve_fragment_uses("{% fragment-use missing from \"buttons.html\" %}\n", loader),
+            Err(ErrorWithLine(
+                0,
+                Error::UnresolvedInclude(String::from("buttons.html#missing"))
+            )),
+        );
+    }
+
+    #[test]
+    fn recursive_uses() {
+        assert_matches!(
+            resolve_fragment_uses("{% fragment-use loop from \"components.html\" %}\n", loader),
+            Err(ErrorWithLine(0, Error::RecursiveInclude(_))),
+        );
+    }
+}
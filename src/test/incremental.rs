@@ -0,0 +1,188 @@
+use crate::{
+    split_templates, test::assert_matches, BlockStyle, Config, Error, ErrorWithLine,
+    IncrementalSplit,
+};
+
+const SOURCE: &str = concat!(
+    "<body>\n",
+    "{% fragment list %}\n",
+    "  {% fragment item %}\n",
+    "  <item>\n",
+    "  {% endfragment %}\n",
+    "{% endfragment %}\n",
+    "{% fragment footer %}\n",
+    "<footer>\n",
+    "{% endfragment %}\n",
+    "</body>\n",
+);
+
+fn check(split: &IncrementalSplit) {
+    assert_eq!(split.templates(), &split_templates(split.source()).unwrap());
+}
+
+#[test]
+fn unchanged_source() {
+    let mut split = IncrementalSplit::new(SOURCE).unwrap();
+    assert_eq!(split.update(SOURCE).unwrap(), Vec::<String>::new());
+    check(&split);
+}
+
+#[test]
+fn content_change() {
+    let mut split = IncrementalSplit::new(SOURCE).unwrap();
+
+    let changed = split.update(SOURCE.replace("<item>", "<changed>")).unwrap();
+    assert_eq!(changed, vec!["", "item", "list"]);
+    check(&split);
+
+    let changed = split
+        .update(SOURCE.replace("<footer>", "<changed>"))
+        .unwrap();
+    assert_eq!(changed, vec!["", "footer", "item", "list"]);
+    check(&split);
+}
+
+#[test]
+fn structural_change() {
+    let mut split = IncrementalSplit::new(SOURCE).unwrap();
+
+    let changed = split
+        .update(SOURCE.replace("{% fragment footer %}", "{% fragment other %}"))
+        .unwrap();
+    assert_eq!(changed, vec!["footer", "other"]);
+    check(&split);
+
+    // removing the end tag of list moves the footer into it
+    let source = SOURCE
+        .replace(
+            "{% endfragment %}\n{% fragment footer %}",
+            "{% fragment footer %}",
+        )
+        .replace("</body>\n", "{% endfragment %}\n</body>\n");
+    let changed = split.update(source).unwrap();
+    assert_eq!(changed, vec!["footer", "list", "other"]);
+    check(&split);
+}
+
+#[test]
+fn edit() {
+    let mut split = IncrementalSplit::new(SOURCE).unwrap();
+
+    let start = SOURCE.find("<footer>").unwrap();
+    let changed = split
+        .edit(start..start + "<footer>".len(), "<nav>\n<footer>")
+        .unwrap();
+    assert_eq!(changed, vec!["", "footer"]);
+    assert_eq!(split.templates()["footer"], "<nav>\n<footer>\n");
+    check(&split);
+}
+
+#[test]
+fn errors_keep_the_previous_state() {
+    let mut split = IncrementalSplit::new(SOURCE).unwrap();
+
+    assert_matches!(
+        split.update(SOURCE.replace("{% endfragment %}\n</body>", "</body>")),
+        Err(ErrorWithLine(_, Error::UnclosedTag(_))),
+    );
+    assert_eq!(split.source(), SOURCE);
+    check(&split);
+}
+
+#[test]
+fn config_is_used() {
+    let config = Config::default()
+        .skip_base(true)
+        .block_style(BlockStyle::Go);
+    let source = SOURCE.replace("{% fragment footer %}", "{% fragment-block footer %}");
+    let source = source.replace(
+        "<footer>\n{% endfragment %}",
+        "<footer>\n{% endfragment-block %}",
+    );
+    let mut split = config.incremental_split(source.clone()).unwrap();
+    assert_eq!(split.templates(), &config.split_templates(&source).unwrap());
+
+    let source = source.replace("<footer>", "<changed>");
+    assert_eq!(split.update(source.clone()).unwrap(), vec!["footer"]);
+    assert_eq!(split.templates(), &config.split_templates(&source).unwrap());
+}
+
+#[test]
+fn changes_across_regions() {
+    let mut split = IncrementalSplit::new(SOURCE).unwrap();
+
+    // the new start tag opens a fragment that ends with the footer
+    let source = SOURCE
+        .replace("<body>\n", "<body>\n{% fragment page %}\n")
+        .replace("</body>\n", "{% endfragment %}\n</body>\n");
+    let changed = split.update(source.clone()).unwrap();
+    assert_eq!(changed, vec!["page"]);
+    check(&split);
+
+    let changed = split
+        .update(source.replace("<item>", "{% raw %}\n<item>\n{% endraw %}"))
+        .unwrap();
+    assert_eq!(changed, vec!["", "item", "list", "page"]);
+    check(&split);
+
+    let changed = split.update(SOURCE).unwrap();
+    assert_eq!(changed, vec!["", "item", "list", "page"]);
+    check(&split);
+}
+
+#[test]
+fn whole_template_options() {
+    let config = Config::default().unique_fragments(true);
+    let mut split = config.incremental_split(SOURCE).unwrap();
+
+    assert_matches!(
+        split.update(format!(
+            "{SOURCE}{{% fragment item %}}\n{{% endfragment %}}\n"
+        )),
+        Err(ErrorWithLine(_, Error::RepeatedFragment(..))),
+    );
+
+    let source = format!("\u{feff}{SOURCE}");
+    let mut split = IncrementalSplit::new(source.clone()).unwrap();
+    assert_eq!(split.templates(), &split_templates(&source).unwrap());
+    assert_eq!(split.update(SOURCE).unwrap(), Vec::<String>::new());
+    check(&split);
+}
+
+#[test]
+fn enclosing_tags() {
+    let cases = [
+        (
+            Config::default().enclosing_control_tags(true),
+            "for x in",
+            "endfor",
+        ),
+        (
+            Config::default().enclosing_with_tags(true),
+            "with x =",
+            "endwith",
+        ),
+    ];
+    for (config, start, end) in cases {
+        let source = format!(
+            "{{% {start} xs %}}\n{}{{% {end} %}}\n",
+            concat!(
+                "{% fragment head %}\n",
+                "<h1>{{ x }}</h1>\n",
+                "{% endfragment %}\n",
+                "{% fragment item %}\n",
+                "<li>{{ x }}</li>\n",
+                "{% endfragment %}\n",
+            ),
+        );
+        let mut split = config.incremental_split(source.clone()).unwrap();
+
+        // the enclosing tag is not part of the regions of the fragments
+        let source = source.replace("xs", "ys");
+        assert_eq!(
+            split.update(source.clone()).unwrap(),
+            vec!["", "head", "item"]
+        );
+        assert_eq!(split.templates(), &config.split_templates(&source).unwrap());
+    }
+}
@@ -0,0 +1,72 @@
+use crate::Config;
+
+fn instrument(content: &str) -> String {
+    let source = format!("{{% fragment item %}}\n{content}{{% endfragment %}}\n");
+    Config::default()
+        .data_fragment_attribute(true)
+        .filter_template(&source, "item")
+        .unwrap()
+}
+
+#[test]
+fn first_element() {
+    assert_eq!(instrument("<li>\n"), "<li data-fragment=\"item\">\n");
+    assert_eq!(
+        instrument("<li class=\"a\"><span></span></li>\n"),
+        "<li data-fragment=\"item\" class=\"a\"><span></span></li>\n"
+    );
+    assert_eq!(instrument("<br/>\n"), "<br data-fragment=\"item\"/>\n");
+    assert_eq!(
+        instrument("<my-element>\n"),
+        "<my-element data-fragment=\"item\">\n"
+    );
+    assert_eq!(
+        instrument("{{ a < b }}\n<!-- <div> -->\n<!doctype html>\n</p><p>\n"),
+        "{{ a < b }}\n<!-- <div> -->\n<!doctype html>\n</p><p data-fragment=\"item\">\n"
+    );
+}
+
+#[test]
+fn unchanged() {
+    assert_eq!(instrument("text\n"), "text\n");
+    assert_eq!(instrument("<!-- <div> \n"), "<!-- <div> \n");
+    assert_eq!(
+        instrument("<li data-fragment=\"other\">\n"),
+        "<li data-fragment=\"other\">\n"
+    );
+}
+
+#[test]
+fn all_fragments() {
+    let source = concat!(
+        "<ul>\n",
+        "{% fragment list %}\n",
+        "<li>{{ a }}</li>\n",
+        "{% fragment item %}\n",
+        "<li>{{ b }}</li>\n",
+        "{% endfragment %}\n",
+        "{% endfragment %}\n",
+        "</ul>\n",
+    );
+    let config = Config::default()
+        .data_fragment_attribute(true)
+        .minify_html(true);
+    let templates = config.split_templates(source).unwrap();
+
+    assert_eq!(
+        templates[""],
+        "<ul>\n<li>{{ a }}</li>\n<li>{{ b }}</li>\n</ul>\n"
+    );
+    assert_eq!(
+        templates["list"],
+        "<li data-fragment=\"list\">{{ a }}</li>\n<li>{{ b }}</li>\n"
+    );
+    assert_eq!(
+        templates["item"],
+        "<li data-fragment=\"item\">{{ b }}</li>\n"
+    );
+    assert_eq!(
+        config.parse_template(source).unwrap().filter("item"),
+        templates["item"]
+    );
+}
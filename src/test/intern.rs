@@ -0,0 +1,63 @@
+use std::sync::Arc;
+
+use crate::{split_templates, split_templates_interned, Config, NameCase, NameInterner};
+
+const SOURCE: &str = concat!(
+    "<ul>\n",
+    "{% fragment items item %}\n",
+    "<li>{{ item }}</li>\n",
+    "{% endfragment %}\n",
+    "</ul>\n",
+);
+
+#[test]
+fn same_content_as_split() {
+    let mut names = NameInterner::new();
+    let templates = split_templates_interned(SOURCE, &mut names).unwrap();
+    let expected = split_templates(SOURCE).unwrap();
+
+    assert_eq!(templates.len(), expected.len());
+    for (fragment, content) in &templates {
+        assert_eq!(&expected[fragment.as_ref()], content);
+    }
+    assert_eq!(names.len(), 3);
+}
+
+#[test]
+fn names_are_shared() {
+    let mut names = NameInterner::new();
+    let first = split_templates_interned(SOURCE, &mut names).unwrap();
+    let second = split_templates_interned(SOURCE, &mut names).unwrap();
+
+    for fragment in first.keys() {
+        let (other, _) = second.get_key_value(fragment).unwrap();
+        assert!(Arc::ptr_eq(fragment, other));
+    }
+    assert!(Arc::ptr_eq(&names.intern("item"), &names.intern("item")));
+    assert_eq!(names.len(), 3);
+}
+
+#[test]
+fn shrink_removes_unused_names() {
+    let mut names = NameInterner::new();
+    let templates = split_templates_interned(SOURCE, &mut names).unwrap();
+    names.intern("unused");
+    assert_eq!(names.len(), 4);
+
+    names.shrink();
+    assert_eq!(names.len(), 3);
+
+    drop(templates);
+    names.shrink();
+    assert!(names.is_empty());
+}
+
+#[test]
+fn canonical_names() {
+    let mut names = NameInterner::new();
+    let config = Config::default().name_case(NameCase::Lower);
+    let templates = config
+        .split_templates_interned(&SOURCE.replace("item", "Item"), &mut names)
+        .unwrap();
+    assert!(templates.contains_key("items"));
+}
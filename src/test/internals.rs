@@ -37,22 +37,43 @@ mod parse_fragment_tag {
         assert_eq!(
             parse_fragment_tag("  {% fragment foo %}", DEFAULT_TAG_MARKERS),
             Ok(Some(Tag::Start(StartTag {
-                fragments: hashset!["foo"]
+                fragments: hashset!["foo"],
+                attributes: vec![],
             })))
         );
         assert_eq!(
             parse_fragment_tag("  {% fragment foo bar %}", DEFAULT_TAG_MARKERS),
             Ok(Some(Tag::Start(StartTag {
-                fragments: hashset!["foo", "bar"]
+                fragments: hashset!["foo", "bar"],
+                attributes: vec![],
             })))
         );
         assert_eq!(
             parse_fragment_tag("  {% fragment-block foo %}", DEFAULT_TAG_MARKERS),
             Ok(Some(Tag::StartBlock(StartBlockTag {
                 prefix: "  ",
-                fragment: "foo"
+                fragment: "foo",
+                attributes: vec![],
             })))
         );
+        assert_eq!(
+            parse_fragment_tag(
+                "  {% fragment foo cache=60 role=admin %}",
+                DEFAULT_TAG_MARKERS
+            ),
+            Ok(Some(Tag::Start(StartTag {
+                fragments: hashset!["foo"],
+                attributes: vec![("cache", "60"), ("role", "admin")],
+            })))
+        );
+        assert_eq!(
+            parse_fragment_tag("  {% fragment foo cache= %}", DEFAULT_TAG_MARKERS),
+            Err(Error::InvalidAttribute(String::from("cache=")))
+        );
+        assert_eq!(
+            parse_fragment_tag("  {% fragment cache=60 %}", DEFAULT_TAG_MARKERS),
+            Err(Error::StartTagWithoutData)
+        );
         assert_matches!(
             parse_fragment_tag("  {% endfragment %}", DEFAULT_TAG_MARKERS),
             Ok(Some(Tag::End(_))),
@@ -170,3 +191,118 @@ mod is_valid_fragment_name {
         assert_eq!(false, is_valid_fragment_name("block"));
     }
 }
+
+mod filter_template_cow {
+    use std::borrow::Cow;
+
+    use crate::{filter_template, filter_template_cow, Config, Error, ErrorWithLine};
+
+    const PLAIN: &str =
+        "<ul>\n  {% for item in items %}\n  <li>{{ item }}</li>\n  {% endfor %}\n</ul>\n";
+    const FRAGMENTS: &str = "<ul>\n{% fragment item %}\n<li></li>\n{% endfragment %}\n</ul>\n";
+
+    #[test]
+    fn borrowed_without_tags() {
+        assert!(matches!(filter_template_cow(PLAIN, ""), Ok(Cow::Borrowed(res)) if res == PLAIN));
+        assert!(matches!(
+            filter_template_cow(PLAIN, "item"),
+            Ok(Cow::Borrowed(""))
+        ));
+        assert!(matches!(filter_template_cow("", ""), Ok(Cow::Borrowed(""))));
+    }
+
+    #[test]
+    fn owned_with_tags() {
+        for fragment in ["", "item", "other"] {
+            let res = filter_template_cow(FRAGMENTS, fragment).unwrap();
+            assert!(matches!(res, Cow::Owned(_)));
+            assert_eq!(res, filter_template(FRAGMENTS, fragment).unwrap());
+        }
+    }
+
+    #[test]
+    fn invalid_tags_are_reported() {
+        assert!(matches!(
+            filter_template_cow("{% fragment %}\n", ""),
+            Err(ErrorWithLine(0, Error::StartTagWithoutData)),
+        ));
+        assert!(matches!(
+            filter_template_cow("{% endfragment %}\n", ""),
+            Err(ErrorWithLine(0, Error::UnbalancedEndTag)),
+        ));
+    }
+
+    #[test]
+    fn options_are_applied() {
+        let config = Config::default().minify_html(true);
+        assert_eq!(
+            config.filter_template_cow(PLAIN, "").unwrap(),
+            config.filter_template(PLAIN, "").unwrap(),
+        );
+
+        let config = Config::default().strict_fragments(true);
+        assert!(matches!(
+            config.filter_template_cow(PLAIN, "item"),
+            Err(ErrorWithLine(0, Error::UnknownFragment(_, _))),
+        ));
+
+        // the byte order mark is removed without copying
+        let source = format!("\u{feff}{PLAIN}");
+        assert!(matches!(filter_template_cow(&source, ""), Ok(Cow::Borrowed(res)) if res == PLAIN));
+    }
+}
+
+mod process_template {
+    use std::borrow::Cow;
+
+    use crate::{split_templates, split_templates_transformed, Error, ErrorWithLine};
+
+    #[test]
+    fn error_lines_after_plain_lines() {
+        let source = "a\nb\r\nc {% if %}\n{% fragment foo %}\nd\n{% endfragment bar %}\ne\n";
+        assert_eq!(
+            split_templates(source),
+            Err(ErrorWithLine(
+                5,
+                Error::EndTagWithData(String::from("bar "))
+            )),
+        );
+    }
+
+    #[test]
+    fn unclosed_tags_at_last_line() {
+        for (source, line) in [
+            ("{% fragment foo %}\na\nb\n", 2),
+            ("{% fragment foo %}\na\nb", 2),
+            ("a\n{% fragment foo %}\n", 1),
+            ("a\n{% fragment foo %}", 1),
+        ] {
+            assert!(
+                matches!(
+                    split_templates(source),
+                    Err(ErrorWithLine(idx, Error::UnclosedTag(_))) if idx == line,
+                ),
+                "{source:?}",
+            );
+        }
+    }
+
+    #[test]
+    fn markers_inside_lines() {
+        let source = "a {% if x %}\n{% fragment foo %}\nb {% endif %}\n{% endfragment %}\nc {%\n";
+        let templates = split_templates(source).unwrap();
+        assert_eq!(templates[""], "a {% if x %}\nb {% endif %}\nc {%\n");
+        assert_eq!(templates["foo"], "b {% endif %}\n");
+    }
+
+    #[test]
+    fn transform_is_called_per_line() {
+        let mut lines = Vec::new();
+        split_templates_transformed("a\nb\n{% fragment foo %}\nc\r\nd", |_, line| {
+            lines.push(line.to_owned());
+            Some(Cow::Borrowed(line))
+        })
+        .unwrap_err();
+        assert_eq!(lines, ["a\n", "b\n", "c\r\n", "d"]);
+    }
+}
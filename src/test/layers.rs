@@ -0,0 +1,99 @@
+use crate::{merge_layers, test::assert_matches, Error, ErrorWithLine, ErrorWithPath};
+
+const BASE: &str = concat!(
+    "<body>\n",
+    "{% fragment header %}\n",
+    "<header>\n",
+    "{% endfragment %}\n",
+    "{% fragment item %}\n",
+    "<item>\n",
+    "{% endfragment %}\n",
+    "</body>\n",
+);
+
+const OVERRIDE: &str = concat!(
+    "{% fragment item %}\n",
+    "<override>\n",
+    "{% endfragment %}\n",
+);
+
+#[test]
+fn later_layers_override_fragments() {
+    let templates = merge_layers([
+        vec![("index.html", BASE), ("other.html", BASE)],
+        vec![("index.html", OVERRIDE), ("new.html", OVERRIDE)],
+    ])
+    .unwrap();
+
+    assert_eq!(templates["index.html#header"], "<header>\n");
+    assert_eq!(templates["index.html#item"], "<override>\n");
+    assert_eq!(
+        templates["index.html"],
+        "<body>\n<header>\n<item>\n</body>\n"
+    );
+    assert_eq!(templates["other.html#item"], "<item>\n");
+
+    // templates only defined in later layers keep their base template
+    assert_eq!(templates["new.html"], "<override>\n");
+    assert_eq!(templates.len(), 8);
+}
+
+#[test]
+fn errors_include_the_path() {
+    assert_matches!(
+        merge_layers([
+            [("index.html", BASE)],
+            [("broken.html", "{% fragment foo %}\n")]
+        ]),
+        Err(ErrorWithPath(_, ErrorWithLine(0, Error::UnclosedTag(_)))),
+    );
+}
+
+mod split_many {
+    use crate::{split_many, Config, NameCase};
+
+    use super::*;
+
+    #[test]
+    fn fragments_are_keyed_by_path() {
+        let templates = split_many(vec![
+            (String::from("index.html"), BASE),
+            (String::from("item.html"), OVERRIDE),
+        ])
+        .unwrap();
+
+        let mut keys: Vec<&str> = templates.keys().map(String::as_str).collect();
+        keys.sort();
+        assert_eq!(
+            keys,
+            vec![
+                "index.html",
+                "index.html#header",
+                "index.html#item",
+                "item.html",
+                "item.html#item"
+            ]
+        );
+    }
+
+    #[test]
+    fn errors_are_tagged_with_the_path() {
+        assert_eq!(
+            split_many([
+                ("index.html", BASE),
+                ("broken.html", "\n{% endfragment %}\n")
+            ]),
+            Err(ErrorWithPath(
+                String::from("broken.html"),
+                ErrorWithLine(1, Error::UnbalancedEndTag)
+            )),
+        );
+    }
+
+    #[test]
+    fn config_is_used() {
+        let config = Config::default().name_case(NameCase::Upper);
+        let templates = config.split_many([("index.html", BASE)]).unwrap();
+        assert_eq!(templates["index.html#ITEM"], "<item>\n");
+    }
+}
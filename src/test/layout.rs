@@ -0,0 +1,69 @@
+use crate::{split_templates, split_templates_with_layout, Config, LayoutPart};
+
+const SOURCE: &str = concat!(
+    "<body>\r\n",
+    "{% fragment items %}\r\n",
+    "<ul>\r\n",
+    "  {% fragment-block item %}\r\n",
+    "  <li>{{ item }}</li>\r\n",
+    "  {% endfragment-block %}\r\n",
+    "</ul>\r\n",
+    "{% endfragment %}\r\n",
+    "{% fragment a b %}\r\n",
+    "<ab>\r\n",
+    "{% endfragment %}\r\n",
+    "</body>",
+);
+
+#[test]
+fn round_trip() {
+    for src in [
+        SOURCE,
+        "",
+        "foo",
+        "foo\nbar\n",
+        "{% fragment a %}\n{% endfragment %}\n",
+        "\u{feff}<ul>\n{% fragment-block item %}\n<li></li>\n{% endfragment-block %}\n",
+        "\u{feff}",
+    ] {
+        let (templates, layout) = split_templates_with_layout(src).unwrap();
+        assert_eq!(layout.reassemble(&templates).unwrap(), src);
+    }
+}
+
+#[test]
+fn templates_are_the_same_as_split_templates() {
+    let (templates, _) = split_templates_with_layout(SOURCE).unwrap();
+    assert_eq!(templates, split_templates(SOURCE).unwrap());
+
+    let config = Config::golang();
+    let source = SOURCE.replace("{%", "{{").replace("%}", "}}");
+    let (templates, layout) = config.split_templates_with_layout(&source).unwrap();
+    assert_eq!(templates, config.split_templates(&source).unwrap());
+    assert_eq!(layout.reassemble(&templates).unwrap(), source);
+}
+
+#[test]
+fn text_is_owned_by_the_innermost_fragment() {
+    let (_, layout) = split_templates_with_layout(SOURCE).unwrap();
+    let owners: Vec<&str> = layout
+        .parts
+        .iter()
+        .filter_map(|part| match part {
+            LayoutPart::Text { fragment, .. } => Some(fragment.as_str()),
+            LayoutPart::Tag(_) => None,
+        })
+        .collect();
+
+    assert_eq!(owners, vec!["", "items", "item", "items", "a", ""]);
+}
+
+#[test]
+fn reassembling_with_edited_fragments() {
+    let (mut templates, layout) = split_templates_with_layout(SOURCE).unwrap();
+    templates.remove("a");
+    assert_eq!(layout.reassemble(&templates), None);
+
+    templates.insert(String::from("a"), String::new());
+    assert_eq!(layout.reassemble(&templates), None);
+}
@@ -0,0 +1,188 @@
+use crate::{lint_template, test::assert_matches, Error, ErrorWithLine, LintKind, LintWarning};
+
+fn kinds(src: &str) -> Vec<LintKind> {
+    lint_template(src)
+        .unwrap()
+        .into_iter()
+        .map(|warning| warning.kind)
+        .collect()
+}
+
+#[test]
+fn clean_template() {
+    let source = concat!(
+        "<body>\n",
+        "{% fragment items %}\n",
+        "  {% fragment item %}\n",
+        "  <li>{{ item }}</li>\n",
+        "  {% endfragment %}\n",
+        "{% endfragment %}\n",
+        "</body>\n",
+    );
+    assert_eq!(lint_template(source).unwrap(), vec![]);
+}
+
+#[test]
+fn empty_fragments() {
+    let source =
+        "<body>\n{% fragment a b %}\n  \n{% fragment c %}\n{% endfragment %}\n{% endfragment %}\n";
+    assert_eq!(
+        lint_template(source).unwrap(),
+        vec![
+            LintWarning {
+                line: 1,
+                kind: LintKind::EmptyFragment(String::from("a"))
+            },
+            LintWarning {
+                line: 1,
+                kind: LintKind::EmptyFragment(String::from("b"))
+            },
+            LintWarning {
+                line: 3,
+                kind: LintKind::EmptyFragment(String::from("c"))
+            },
+        ]
+    );
+}
+
+#[test]
+fn whole_template() {
+    let source = "\n{% fragment page %}\n<body>\n{% endfragment %}\n\n";
+    assert_eq!(
+        lint_template(source).unwrap(),
+        vec![LintWarning {
+            line: 1,
+            kind: LintKind::WholeTemplate(String::from("page"))
+        }]
+    );
+
+    assert_eq!(
+        kinds("<p>\n{% fragment page %}\n<body>\n{% endfragment %}\n"),
+        vec![]
+    );
+    assert_eq!(
+        kinds(
+            "{% fragment a %}\n<a>\n{% endfragment %}\n{% fragment b %}\n<b>\n{% endfragment %}\n"
+        ),
+        vec![]
+    );
+}
+
+#[test]
+fn deeply_nested() {
+    let source = concat!(
+        "<body>\n",
+        "{% fragment a %}\n",
+        "{% fragment b %}\n",
+        "{% fragment c %}\n",
+        "{% fragment d %}\n",
+        "<d>\n",
+        "{% endfragment %}\n",
+        "{% endfragment %}\n",
+        "{% endfragment %}\n",
+        "{% endfragment %}\n",
+    );
+    assert_eq!(
+        lint_template(source).unwrap(),
+        vec![LintWarning {
+            line: 4,
+            kind: LintKind::DeeplyNested(String::from("d"), 4)
+        }]
+    );
+}
+
+#[test]
+fn similar_names() {
+    let source = concat!(
+        "<body>\n",
+        "{% fragment Item %}\n<a>\n{% endfragment %}\n",
+        "{% fragment item %}\n<a>\n{% endfragment %}\n",
+        "{% fragment item %}\n<a>\n{% endfragment %}\n",
+        "{% fragment ITEM %}\n<a>\n{% endfragment %}\n",
+        "{% fragment items %}\n<a>\n{% endfragment %}\n",
+    );
+    assert_eq!(
+        kinds(source),
+        vec![
+            LintKind::SimilarNames(String::from("item"), String::from("Item")),
+            LintKind::SimilarNames(String::from("ITEM"), String::from("Item")),
+        ]
+    );
+}
+
+#[test]
+fn display() {
+    let warning = LintWarning {
+        line: 2,
+        kind: LintKind::DeeplyNested(String::from("d"), 4),
+    };
+    assert_eq!(
+        warning.to_string(),
+        "fragment \"d\" is nested 4 levels deep at line 3"
+    );
+}
+
+#[test]
+fn errors() {
+    assert_matches!(
+        lint_template("{% fragment a %}\n"),
+        Err(ErrorWithLine(0, Error::UnclosedTag(_))),
+    );
+}
+
+#[test]
+fn enclosing_variables() {
+    let source = concat!(
+        "{% set title = 'Items' %}\n",
+        "<ul>\n",
+        "{% for item in items %}\n",
+        "{% fragment item %}\n",
+        "<li>{{ loop.index }}: {{ item.name }} {{ title }} {{ user }}</li>\n",
+        "{% endfragment %}\n",
+        "{% endfor %}\n",
+        "</ul>\n",
+        "{% fragment footer %}\n",
+        "{{ item }}\n",
+        "{% endfragment %}\n",
+    );
+    let warnings = lint_template(source).unwrap();
+
+    assert_eq!(
+        warnings,
+        [
+            LintWarning {
+                line: 3,
+                kind: LintKind::EnclosingVariable(String::from("item"), String::from("item")),
+            },
+            LintWarning {
+                line: 3,
+                kind: LintKind::EnclosingVariable(String::from("item"), String::from("loop")),
+            },
+            LintWarning {
+                line: 3,
+                kind: LintKind::EnclosingVariable(String::from("item"), String::from("title")),
+            },
+        ]
+    );
+    assert_eq!(
+        warnings[0].to_string(),
+        "fragment \"item\" uses \"item\", which is bound outside of it at line 4"
+    );
+}
+
+#[test]
+fn enclosing_variables_with_defaults_or_own_loops() {
+    let source = concat!(
+        "{% for item in items %}\n",
+        "{% fragment item with=item=none,loop=none %}\n",
+        "<li>{{ loop.index }}: {{ item }}</li>\n",
+        "{% endfragment %}\n",
+        "{% endfor %}\n",
+        "{% fragment items %}\n",
+        "{% for item in items %}\n",
+        "<li>{{ item }}</li>\n",
+        "{% endfor %}\n",
+        "{% endfragment %}\n",
+    );
+    assert_eq!(kinds(source), []);
+}
@@ -0,0 +1,226 @@
+use ::lsp_server::{Connection, Message, Notification, Request, RequestId};
+use ::lsp_types::{
+    DiagnosticSeverity, NumberOrString, Position, PublishDiagnosticsParams, Range, TextEdit,
+};
+
+use crate::{
+    lsp::{definition, diagnostics, document_symbols, rename, serve},
+    Config,
+};
+
+const SOURCE: &str = concat!(
+    "<ul>\n",
+    "{% fragment items %}\n",
+    "  {% fragment-block item %}\n",
+    "  <li>{{ item }}</li>\n",
+    "  {% endfragment-block %}\n",
+    "{% endfragment %}\n",
+    "</ul>\n",
+);
+
+fn range(start: (u32, u32), end: (u32, u32)) -> Range {
+    Range::new(Position::new(start.0, start.1), Position::new(end.0, end.1))
+}
+
+#[test]
+fn error_diagnostics() {
+    let source = "{% fragment items %}\n  {% fragment item %}\n  <li></li>\n{% endfragment %}\n";
+    let found = diagnostics(&Config::default(), source);
+
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].severity, Some(DiagnosticSeverity::ERROR));
+    assert_eq!(found[0].range, range((0, 0), (0, 20)));
+    assert_eq!(
+        found[0].message,
+        "unclosed fragment `items`\nhelp: did you forget `{% endfragment %}` for `items` opened on line 1?"
+    );
+
+    let source = "<ul>\n  {% fragment item %} <li>\n  {% endfragment %}\n";
+    let found = diagnostics(&Config::default(), source);
+    assert_eq!(found[0].range, range((1, 22), (1, 26)));
+    assert_eq!(
+        found[0].code,
+        Some(NumberOrString::String(String::from("trailing-content")))
+    );
+}
+
+#[test]
+fn lint_diagnostics() {
+    let source = "<ul>\n{% fragment item %}\n{% endfragment %}\n</ul>\n";
+    let found = diagnostics(&Config::default(), source);
+
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].severity, Some(DiagnosticSeverity::WARNING));
+    assert_eq!(found[0].range, range((1, 0), (1, 19)));
+    assert_eq!(found[0].message, "fragment \"item\" is empty");
+
+    assert!(diagnostics(&Config::default(), SOURCE).is_empty());
+}
+
+#[test]
+fn symbols() {
+    let symbols = document_symbols(&Config::default(), SOURCE);
+    assert_eq!(symbols.len(), 1);
+    assert_eq!(symbols[0].name, "items");
+    assert_eq!(symbols[0].range, range((1, 0), (5, 17)));
+    assert_eq!(symbols[0].selection_range, range((1, 12), (1, 17)));
+
+    let children = symbols[0].children.as_ref().unwrap();
+    assert_eq!(children.len(), 1);
+    assert_eq!(children[0].name, "item");
+    assert_eq!(children[0].detail.as_deref(), Some("block"));
+    assert_eq!(children[0].range, range((2, 0), (4, 25)));
+
+    // unclosed tags extend to the end of the template
+    let symbols = document_symbols(&Config::default(), "{% fragment a b %}\n<div>\n");
+    assert_eq!(symbols[0].name, "a, b");
+    assert_eq!(symbols[0].range, range((0, 0), (1, 5)));
+}
+
+#[test]
+fn symbols_custom_syntax() {
+    let source = "((* fragment intro *))\nText\n((* endfragment *))\n";
+    let symbols = document_symbols(&Config::for_path("report.tex"), source);
+    assert_eq!(symbols[0].name, "intro");
+    assert!(document_symbols(&Config::default(), source).is_empty());
+}
+
+#[test]
+fn definitions() {
+    let config = Config::default();
+    assert_eq!(
+        definition(&config, SOURCE, Position::new(1, 3)),
+        Some(range((5, 0), (5, 17)))
+    );
+    assert_eq!(
+        definition(&config, SOURCE, Position::new(5, 0)),
+        Some(range((1, 0), (1, 20)))
+    );
+    assert_eq!(
+        definition(&config, SOURCE, Position::new(4, 10)),
+        Some(range((2, 2), (2, 27)))
+    );
+    assert_eq!(definition(&config, SOURCE, Position::new(3, 3)), None);
+}
+
+#[test]
+fn renames() {
+    let source = concat!(
+        "<p>ä</p> {# unrelated #}\n",
+        "{% fragment ünit item %}\n",
+        "{% endfragment ünit item %}\n",
+        "{% fragment ünit %}\n",
+        "{% endfragment %}\n",
+    );
+    let config = Config::default().named_end_tags(true);
+
+    let edit = |line, start, end| TextEdit::new(range((line, start), (line, end)), "unit".into());
+    assert_eq!(
+        rename(&config, source, Position::new(2, 17), "unit"),
+        Some(vec![edit(1, 12, 16), edit(2, 15, 19), edit(3, 12, 16)])
+    );
+    assert_eq!(rename(&config, source, Position::new(1, 2), "unit"), None);
+    assert_eq!(rename(&config, source, Position::new(0, 2), "unit"), None);
+}
+
+#[test]
+fn server() {
+    let (server, client) = Connection::memory();
+    let handle = std::thread::spawn(move || serve(&server).unwrap());
+
+    let request = |id: i32, method: &str, params: serde_json::Value| {
+        client
+            .sender
+            .send(Message::Request(Request::new(
+                RequestId::from(id),
+                method.to_owned(),
+                params,
+            )))
+            .unwrap();
+        match client.receiver.recv().unwrap() {
+            Message::Response(response) => response,
+            message => panic!("unexpected message {message:?}"),
+        }
+    };
+    let notify = |method: &str, params: serde_json::Value| {
+        client
+            .sender
+            .send(Message::Notification(Notification::new(
+                method.to_owned(),
+                params,
+            )))
+            .unwrap();
+    };
+
+    let response = request(1, "initialize", serde_json::json!({ "capabilities": {} }));
+    assert_eq!(
+        response.result.unwrap()["capabilities"]["renameProvider"],
+        true
+    );
+    notify("initialized", serde_json::json!({}));
+
+    let uri = "file:///templates/index.html";
+    notify(
+        "textDocument/didOpen",
+        serde_json::json!({
+            "textDocument": {
+                "uri": uri, "languageId": "html", "version": 1,
+                "text": "{% fragment item %}\n<li></li>\n",
+            },
+        }),
+    );
+    let Message::Notification(notification) = client.receiver.recv().unwrap() else {
+        panic!("expected diagnostics");
+    };
+    let params: PublishDiagnosticsParams = serde_json::from_value(notification.params).unwrap();
+    assert_eq!(params.diagnostics.len(), 1);
+    assert_eq!(params.diagnostics[0].range, range((0, 0), (0, 19)));
+
+    let response = request(
+        2,
+        "textDocument/documentSymbol",
+        serde_json::json!({ "textDocument": { "uri": uri } }),
+    );
+    assert_eq!(response.result.unwrap()[0]["name"], "item");
+
+    let response = request(
+        3,
+        "textDocument/rename",
+        serde_json::json!({
+            "textDocument": { "uri": uri },
+            "position": { "line": 0, "character": 13 },
+            "newName": "invalid name",
+        }),
+    );
+    assert!(response.error.is_some());
+
+    let response = request(
+        4,
+        "textDocument/rename",
+        serde_json::json!({
+            "textDocument": { "uri": uri },
+            "position": { "line": 0, "character": 13 },
+            "newName": "",
+        }),
+    );
+    assert!(response.error.is_some());
+
+    // malformed notifications are logged and the server keeps running
+    notify("textDocument/didOpen", serde_json::json!({}));
+    let Message::Notification(notification) = client.receiver.recv().unwrap() else {
+        panic!("expected a log message");
+    };
+    assert_eq!(notification.method, "window/logMessage");
+    let response = request(
+        5,
+        "textDocument/documentSymbol",
+        serde_json::json!({ "textDocument": { "uri": uri } }),
+    );
+    assert_eq!(response.result.unwrap()[0]["name"], "item");
+
+    assert!(request(6, "shutdown", serde_json::Value::Null)
+        .error
+        .is_none());
+    notify("exit", serde_json::Value::Null);
+    handle.join().unwrap();
+}
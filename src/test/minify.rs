@@ -0,0 +1,61 @@
+use crate::{minify::minify_html, Config, Template};
+
+#[test]
+fn whitespace() {
+    assert_eq!(minify_html(""), "");
+    assert_eq!(minify_html("  \n\n  "), "");
+    assert_eq!(minify_html("  <p>a  \t b</p>  "), "<p>a b</p>");
+    assert_eq!(minify_html("<p>\n  a\n\n  b\n</p>\n"), "<p>\na\nb\n</p>\n");
+    assert_eq!(
+        minify_html("<p>\r\n  a  \r\n</p>\r\n"),
+        "<p>\r\na\r\n</p>\r\n"
+    );
+    assert_eq!(minify_html("<p>ä  ö</p>"), "<p>ä ö</p>");
+}
+
+#[test]
+fn preserved_elements() {
+    assert_eq!(
+        minify_html("  <PRE class=\"x\">a\n  b</pre>  \n  <p>  c</p>\n"),
+        "<PRE class=\"x\">a\n  b</pre>\n<p> c</p>\n",
+    );
+    assert_eq!(
+        minify_html("<textarea>\n  a\n</TEXTAREA>\n"),
+        "<textarea>\n  a\n</TEXTAREA>\n",
+    );
+    assert_eq!(
+        minify_html("<script>\n  // a\n  b()\n</script>\n"),
+        "<script>\n  // a\n  b()\n</script>\n",
+    );
+    assert_eq!(minify_html("<pre>\n  unclosed  "), "<pre>\n  unclosed  ");
+
+    // only exact element names are preserved
+    assert_eq!(
+        minify_html("<prefix>\n  a</prefix>"),
+        "<prefix>\na</prefix>"
+    );
+}
+
+#[test]
+fn config() {
+    let source = concat!(
+        "<div>\n",
+        "  {% fragment a %}\n",
+        "  <p>\n",
+        "    {{ a }}\n",
+        "  </p>\n",
+        "  {% endfragment %}\n",
+        "</div>\n",
+    );
+    let config = Config::default().minify_html(true);
+
+    let templates = config.split_templates(source).unwrap();
+    assert_eq!(templates[""], "<div>\n<p>\n{{ a }}\n</p>\n</div>\n");
+    assert_eq!(templates["a"], "<p>\n{{ a }}\n</p>\n");
+    assert_eq!(config.filter_template(source, "a").unwrap(), templates["a"]);
+    assert_eq!(
+        config.parse_template(source).unwrap().filter("a"),
+        templates["a"]
+    );
+    assert_ne!(Template::parse(source).unwrap().filter("a"), templates["a"]);
+}
@@ -0,0 +1,303 @@
+use std::collections::HashMap;
+
+use ::minijinja::{context, syntax::SyntaxConfig, Environment, ErrorKind};
+
+use crate::{
+    minijinja::{add_template_fragments, fragment_loader},
+    Config, ErrorWithLine, NameCase, Syntax,
+};
+
+const SOURCE: &str = concat!(
+    "<ul>\n",
+    "{% for item in items %}\n",
+    "  {% fragment item %}\n",
+    "  <li>{{ item }}</li>\n",
+    "  {% endfragment %}\n",
+    "{% endfor %}\n",
+    "</ul>\n",
+);
+
+fn env(config: Config) -> Environment<'static> {
+    let sources = HashMap::from([
+        ("index.html", SOURCE),
+        ("broken.html", "{% fragment item %}\n"),
+    ]);
+
+    let mut env = Environment::new();
+    env.set_trim_blocks(true);
+    env.set_keep_trailing_newline(true);
+    env.set_loader(fragment_loader(config, move |path| {
+        Ok(sources.get(path).map(|&src| src.to_owned()))
+    }));
+    env
+}
+
+#[test]
+fn lazy_loading() {
+    let env = env(Config::default());
+
+    let template = env.get_template("index.html").unwrap();
+    assert_eq!(
+        template.render(context!(items => ["a", "b"])).unwrap(),
+        "<ul>\n  <li>a</li>\n  <li>b</li>\n</ul>\n"
+    );
+
+    let template = env.get_template("index.html#item").unwrap();
+    assert_eq!(
+        template.render(context!(item => "a")).unwrap(),
+        "  <li>a</li>\n"
+    );
+}
+
+#[test]
+fn missing_templates() {
+    let env = env(Config::default());
+    for name in ["missing.html", "missing.html#item", "index.html#footer"] {
+        assert_eq!(
+            env.get_template(name).unwrap_err().kind(),
+            ErrorKind::TemplateNotFound,
+            "{name}"
+        );
+    }
+}
+
+#[test]
+fn invalid_templates() {
+    let env = env(Config::default());
+    let err = env.get_template("broken.html#item").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::SyntaxError);
+    assert!(err.to_string().contains("broken.html"));
+}
+
+#[test]
+fn config() {
+    let env = env(Config::default().name_case(NameCase::Upper));
+    assert!(env.get_template("index.html#Item").is_ok());
+}
+
+#[test]
+fn add_fragments() {
+    let mut env = Environment::new();
+    env.set_keep_trailing_newline(true);
+    add_template_fragments(&mut env, "index.html", SOURCE).unwrap();
+    add_template_fragments(
+        &mut env,
+        "page.tex",
+        "((* fragment title *))\n{{ title }}\n((* endfragment *))\n",
+    )
+    .unwrap();
+
+    let mut names: Vec<&str> = env.templates().map(|(name, _)| name).collect();
+    names.sort();
+    assert_eq!(
+        names,
+        [
+            "index.html",
+            "index.html#item",
+            "page.tex",
+            "page.tex#title"
+        ]
+    );
+    assert_eq!(
+        env.get_template("index.html#item")
+            .unwrap()
+            .render(context!(item => "a"))
+            .unwrap(),
+        "  <li>a</li>\n"
+    );
+}
+
+#[test]
+fn add_fragments_errors() {
+    let mut env = Environment::new();
+    let err = add_template_fragments(&mut env, "broken.html", "\n{% endfragment %}\n").unwrap_err();
+
+    assert_eq!(err.kind(), ErrorKind::SyntaxError);
+    assert_eq!(
+        err.to_string(),
+        "syntax error: Error::UnbalancedTags at line 2 in broken.html"
+    );
+    let source = std::error::Error::source(&err)
+        .and_then(|err| err.downcast_ref::<ErrorWithLine>())
+        .unwrap();
+    assert_eq!(source.0, 1);
+    assert_eq!(env.templates().count(), 0);
+}
+
+fn custom_syntax_env() -> Environment<'static> {
+    let mut env = Environment::new();
+    env.set_keep_trailing_newline(true);
+    env.set_syntax(
+        SyntaxConfig::builder()
+            .block_delimiters("<%", "%>")
+            .variable_delimiters("<<", ">>")
+            .comment_delimiters("<#", "#>")
+            .build()
+            .unwrap(),
+    );
+    env
+}
+
+const CUSTOM_SOURCE: &str = concat!(
+    "<ul>\n",
+    "<% for item in items %>\n",
+    "<# doc: a single item #>\n",
+    "<% fragment item %>\n",
+    "<li><< item >></li>\n",
+    "<% endfragment %>\n",
+    "<% endfor %>\n",
+    "</ul>\n",
+);
+
+#[test]
+fn custom_syntax_conversion() {
+    let env = custom_syntax_env();
+    let syntax = Syntax::from(env.syntax());
+    assert_eq!(syntax.tag_markers(), ("<%", "%>"));
+    assert_eq!(syntax.comment_markers(), ("<#", "#>"));
+
+    let syntax = Syntax::from(Environment::new().syntax());
+    assert_eq!(syntax, Syntax::jinja());
+}
+
+#[test]
+fn add_fragments_custom_syntax() {
+    let mut env = custom_syntax_env();
+    add_template_fragments(&mut env, "index.html", CUSTOM_SOURCE).unwrap();
+
+    assert_eq!(
+        env.get_template("index.html#item")
+            .unwrap()
+            .render(context!(item => "a"))
+            .unwrap(),
+        "<li>a</li>\n"
+    );
+    assert_eq!(
+        env.get_template("index.html")
+            .unwrap()
+            .render(context!(items => ["a", "b"]))
+            .unwrap(),
+        "<ul>\n\n\n<li>a</li>\n\n\n<li>b</li>\n\n</ul>\n"
+    );
+}
+
+#[test]
+fn custom_comment_delimiters() {
+    let mut env = Environment::new();
+    env.set_syntax(
+        SyntaxConfig::builder()
+            .comment_delimiters("<#", "#>")
+            .build()
+            .unwrap(),
+    );
+    let source = "{% fragment item %}\n<# an item #>\n<li></li>\n{% endfragment %}\n";
+    add_template_fragments(&mut env, "page.tex", source).unwrap();
+
+    assert_eq!(
+        env.get_template("page.tex#item")
+            .unwrap()
+            .render(())
+            .unwrap(),
+        "\n<li></li>"
+    );
+}
+
+#[test]
+fn emitted_tags_use_custom_syntax() {
+    let mut env = custom_syntax_env();
+    let config = Config::default()
+        .syntax(env.syntax().into())
+        .extends("base.html", "content");
+    env.add_template("base.html", "[<% block content %><% endblock %>]")
+        .unwrap();
+    config
+        .add_template_fragments(&mut env, "index.html", CUSTOM_SOURCE)
+        .unwrap();
+
+    assert_eq!(
+        env.get_template("index.html#item")
+            .unwrap()
+            .render(context!(item => "a"))
+            .unwrap(),
+        "[\n<li>a</li>\n]"
+    );
+}
+
+mod verify {
+    use super::{custom_syntax_env, CUSTOM_SOURCE, SOURCE};
+    use crate::{Config, Error, ErrorWithLine};
+
+    #[test]
+    fn valid_fragments() {
+        let config = Config::default().verify(true);
+        assert!(config.split_templates(SOURCE).is_ok());
+
+        let env = custom_syntax_env();
+        let config = Config::default().syntax(env.syntax().into()).verify(true);
+        assert!(config.split_templates(CUSTOM_SOURCE).is_ok());
+    }
+
+    #[test]
+    fn unclosed_tags_inside_fragments() {
+        let source = concat!(
+            "<ul>\n",
+            "{% fragment items %}\n",
+            "{% for item in items %}\n",
+            "  <li>{{ item }}</li>\n",
+            "{% endfragment %}\n",
+            "{% endfor %}\n",
+            "</ul>\n",
+        );
+        let err = Config::default()
+            .verify(true)
+            .split_templates(source)
+            .unwrap_err();
+
+        assert_eq!(err.0, 1);
+        assert!(
+            matches!(&err.1, Error::InvalidFragmentSyntax(fragment, _) if fragment == "items"),
+            "{err:?}"
+        );
+        assert_eq!(err.1.code(), "invalid-fragment-syntax");
+    }
+
+    #[test]
+    fn custom_syntax() {
+        let source = CUSTOM_SOURCE.replace(
+            "<% endfragment %>\n<% endfor %>\n",
+            "<% endfor %>\n<% endfragment %>\n",
+        );
+        let env = custom_syntax_env();
+        let config = Config::default().syntax(env.syntax().into()).verify(true);
+
+        assert_eq!(
+            config.split_templates(&source),
+            Err(ErrorWithLine(
+                3,
+                Error::InvalidFragmentSyntax(
+                    String::from("item"),
+                    String::from("unknown statement endfor at line 2")
+                )
+            ))
+        );
+    }
+
+    #[test]
+    fn errors_refer_to_original_lines() {
+        let source = concat!(
+            "{% ifdef debug %}\n",
+            "<pre>{{ state }}</pre>\n",
+            "{% endifdef %}\n",
+            "{% fragment item %}\n",
+            "{% if item %}\n",
+            "{% endfragment %}\n",
+            "{% endif %}\n",
+        );
+        let err = Config::default()
+            .defines([("debug", "")])
+            .verify(true)
+            .split_templates(source)
+            .unwrap_err();
+        assert_eq!(err.0, 3);
+    }
+}
@@ -1,8 +1,72 @@
+mod annotate;
+mod ast;
+#[cfg(feature = "tokio")]
+mod asynchronous;
+mod blocks;
+mod cache;
+mod comments;
+mod compose;
+#[cfg(feature = "compress")]
+mod compress;
+mod config;
+mod context;
+mod convert;
+mod custom;
+mod decode;
+mod defines;
+mod details;
+mod diff;
+mod directory;
+mod embedded;
+#[allow(clippy::redundant_static_lifetimes)]
 mod errors;
 mod examples;
+mod exists;
+mod extends;
+#[cfg(feature = "mmap")]
+mod file;
+mod format;
 #[rustfmt::skip]
+#[allow(clippy::useless_concat)]
 mod generated;
+#[cfg(feature = "hash")]
+mod hash;
+mod help;
+mod include;
+mod incremental;
+mod instrument;
+mod intern;
+#[allow(clippy::bool_assert_comparison)]
 mod internals;
+mod layers;
+mod layout;
+mod lint;
+#[cfg(feature = "lsp")]
+mod lsp;
+mod minify;
+#[cfg(feature = "minijinja")]
+mod minijinja;
+mod name;
+mod numbered;
+#[cfg(feature = "poem")]
+mod poem;
+mod routes;
+mod set;
+mod sink;
+mod stats;
+mod syntax;
+mod tag;
+mod template;
+#[cfg(feature = "test-util")]
+mod testing;
+#[cfg(feature = "tracing")]
+mod trace;
+mod transform;
+mod validate;
+mod variables;
+mod verbatim;
+#[cfg(feature = "notify")]
+mod watch;
 
 macro_rules! hashset {
     ($($part:expr),*) => {
@@ -26,3 +90,37 @@ macro_rules! assert_matches {
 }
 
 pub(crate) use assert_matches;
+
+/// Run the future to completion on a single-threaded runtime
+#[cfg(any(feature = "tokio", feature = "poem"))]
+pub(crate) fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap()
+        .block_on(future)
+}
+
+/// A temporary directory that is removed on drop
+pub(crate) struct TempDir(pub(crate) std::path::PathBuf);
+
+impl TempDir {
+    pub(crate) fn new(name: &str) -> Self {
+        let path =
+            std::env::temp_dir().join(format!("template-fragments-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+
+    pub(crate) fn write(&self, name: &str, content: &str) {
+        let path = self.0.join(name);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
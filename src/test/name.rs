@@ -0,0 +1,43 @@
+use crate::{Error, FragmentName};
+
+#[test]
+fn valid_names() {
+    for name in ["item", "list-item", "list_item", "Item2", "äöü", "card.1"] {
+        let parsed: FragmentName = name.parse().unwrap();
+        assert_eq!(parsed.as_str(), name);
+        assert_eq!(parsed.to_string(), name);
+        assert!(!parsed.is_base());
+    }
+}
+
+#[test]
+fn base_name() {
+    let name: FragmentName = "".parse().unwrap();
+    assert!(name.is_base());
+    assert_eq!(name, FragmentName::default());
+}
+
+#[test]
+fn invalid_names() {
+    for name in [
+        "block", "foo bar", " foo", "foo.bar", "foo/bar", "foo#bar", "a=b", "card.0", ".1",
+        "block.1", "card.1.2", "card. 1", "card.+1", "card.01",
+    ] {
+        assert_eq!(
+            name.parse::<FragmentName>(),
+            Err(Error::InvalidFragmentName(vec![name.to_owned()])),
+        );
+    }
+}
+
+#[test]
+fn names_match_the_parser() {
+    for name in ["item", "block", "foo.bar", "list-item"] {
+        let source = format!("{{% fragment {name} %}}\n{{% endfragment %}}\n");
+        assert_eq!(
+            name.parse::<FragmentName>().is_ok(),
+            crate::split_templates(&source).is_ok(),
+            "{name}"
+        );
+    }
+}
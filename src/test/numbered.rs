@@ -0,0 +1,118 @@
+use std::borrow::Cow;
+
+use crate::Config;
+
+const SOURCE: &str = concat!(
+    "<ul>\n",
+    "{% fragment items %}\n",
+    "{% fragment item %}\n",
+    "<li>{{ first }}</li>\n",
+    "{% endfragment %}\n",
+    "{% fragment item %}\n",
+    "<li>{{ second }}</li>\n",
+    "{% endfragment %}\n",
+    "{% endfragment %}\n",
+    "{% fragment-block item %}\n",
+    "<li>{{ third }}</li>\n",
+    "{% endfragment-block %}\n",
+    "</ul>\n",
+);
+
+fn config() -> Config {
+    Config::default().number_repeated_fragments(true)
+}
+
+#[test]
+fn repeated_fragments_are_numbered() {
+    let templates = config().split_templates(SOURCE).unwrap();
+
+    assert_eq!(templates.len(), 5);
+    assert_eq!(templates["item"], "<li>{{ first }}</li>\n");
+    assert_eq!(templates["item.1"], "<li>{{ second }}</li>\n");
+    assert_eq!(
+        templates["item.2"],
+        "{% block item %}\n<li>{{ third }}</li>\n{% endblock %}\n"
+    );
+    assert_eq!(
+        templates["items"],
+        "<li>{{ first }}</li>\n<li>{{ second }}</li>\n"
+    );
+    assert_eq!(
+        templates[""],
+        Config::default().split_templates(SOURCE).unwrap()[""]
+    );
+}
+
+#[test]
+fn occurrences_share_contexts() {
+    let source = concat!(
+        "{% fragment item with=item=none %}\n",
+        "<li>{{ item }}</li>\n",
+        "{% endfragment %}\n",
+        "{% fragment item %}\n",
+        "<li>{{ item }}!</li>\n",
+        "{% endfragment %}\n",
+    );
+    let templates = config().skip_base(true).split_templates(source).unwrap();
+
+    assert_eq!(templates.len(), 2);
+    assert_eq!(
+        templates["item.1"],
+        "{% with item=none %}\n<li>{{ item }}!</li>\n{% endwith %}\n"
+    );
+}
+
+#[test]
+fn transform_receives_fragment_names() {
+    let templates = config()
+        .split_templates_transformed(SOURCE, |fragments, line| {
+            if fragments.contains("item") {
+                Some(Cow::Owned(line.to_uppercase()))
+            } else {
+                Some(Cow::Borrowed(line))
+            }
+        })
+        .unwrap();
+
+    assert_eq!(templates["item.1"], "<LI>{{ SECOND }}</LI>\n");
+}
+
+#[test]
+fn numbered_macros() {
+    let templates = config().macros(true).split_templates(SOURCE).unwrap();
+    assert!(templates["_macros"].contains("{% macro item_1() %}\n"));
+}
+
+#[test]
+fn filter_matches_split() {
+    let config = config();
+    let templates = config.split_templates(SOURCE).unwrap();
+    let template = config.parse_template(SOURCE).unwrap();
+
+    for (key, content) in &templates {
+        assert_eq!(&config.filter_template(SOURCE, key).unwrap(), content);
+        assert_eq!(template.filter(key), content);
+        assert!(template.has(key));
+    }
+    assert_eq!(template.names(), ["item", "item.1", "item.2", "items"]);
+    assert_eq!(config.filter_template(SOURCE, "item.3").unwrap(), "");
+
+    let config = config.strict_fragments(true);
+    assert_eq!(
+        config.filter_template(SOURCE, "item.1").unwrap(),
+        templates["item.1"]
+    );
+    assert!(config.filter_template(SOURCE, "item.3").is_err());
+}
+
+#[test]
+fn numbered_fragments_exist() {
+    let config = config();
+    for key in config.split_templates(SOURCE).unwrap().keys() {
+        assert!(config.fragment_exists(SOURCE, key), "{key}");
+    }
+    assert!(!config.fragment_exists(SOURCE, "item.3"));
+    assert!(!config.fragment_exists(SOURCE, "item.0"));
+    assert!(!config.fragment_exists(SOURCE, "items.1"));
+    assert!(!Config::default().fragment_exists(SOURCE, "item.1"));
+}
@@ -0,0 +1,101 @@
+use std::collections::HashMap;
+
+use ::poem::{http::StatusCode, test::TestClient, Request};
+
+use crate::{
+    poem::{FragmentRequest, TemplateStore},
+    test::block_on,
+    Config, NameCase,
+};
+
+const SOURCE: &str = concat!(
+    "<ul>\n",
+    "{% fragment items %}\n",
+    "  <li>{{ item }}</li>\n",
+    "{% endfragment %}\n",
+    "</ul>\n",
+);
+
+fn store() -> TemplateStore {
+    let mut store = TemplateStore::default();
+    store.add("index.html", SOURCE).unwrap();
+    store
+}
+
+#[test]
+fn fragment_request() {
+    let is_fragment = |headers: &[(&str, &str)]| {
+        let mut req = Request::builder();
+        for &(name, value) in headers {
+            req = req.header(name, value);
+        }
+        FragmentRequest::from_headers(&req.finish()).is_fragment
+    };
+
+    assert!(!is_fragment(&[]));
+    assert!(is_fragment(&[("HX-Request", "true")]));
+    assert!(!is_fragment(&[("HX-Request", "false")]));
+    assert!(!is_fragment(&[
+        ("HX-Request", "true"),
+        ("HX-Boosted", "true")
+    ]));
+    assert!(!is_fragment(&[
+        ("HX-Request", "true"),
+        ("HX-History-Restore-Request", "true")
+    ]));
+}
+
+#[test]
+fn resolve() {
+    let store = store();
+    let page = FragmentRequest { is_fragment: false };
+    let fragment = FragmentRequest { is_fragment: true };
+
+    assert_eq!(
+        store.get("index.html"),
+        Some("<ul>\n  <li>{{ item }}</li>\n</ul>\n")
+    );
+    assert_eq!(
+        store.get(" index.html # items "),
+        Some("  <li>{{ item }}</li>\n")
+    );
+    assert_eq!(
+        store.resolve("index.html#items", page),
+        store.get("index.html")
+    );
+    assert_eq!(
+        store.resolve("index.html#items", fragment),
+        store.get("index.html#items")
+    );
+    assert_eq!(store.resolve("index.html#footer", fragment), None);
+
+    let store = TemplateStore::new(
+        Config::default().name_case(NameCase::Lower),
+        HashMap::from([(String::from("index.html#items"), String::from("items"))]),
+    );
+    assert_eq!(store.get("index.html#ITEMS"), Some("items"));
+}
+
+#[test]
+fn endpoint() {
+    let store = store();
+    let client =
+        TestClient::new(store.endpoint("index.html#items", |src, _| Ok(src.to_uppercase())));
+
+    block_on(async {
+        let resp = client.get("/").send().await;
+        resp.assert_status_is_ok();
+        resp.assert_header("Vary", "HX-Request");
+        resp.assert_text("<UL>\n  <LI>{{ ITEM }}</LI>\n</UL>\n")
+            .await;
+
+        let resp = client.get("/").header("HX-Request", "true").send().await;
+        resp.assert_text("  <LI>{{ ITEM }}</LI>\n").await;
+    });
+
+    let client = TestClient::new(store.endpoint("missing.html", |src, _| Ok(src.to_owned())));
+    block_on(async {
+        let resp = client.get("/").send().await;
+        resp.assert_status(StatusCode::NOT_FOUND);
+    });
+}
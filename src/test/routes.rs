@@ -0,0 +1,62 @@
+use crate::FragmentRoutes;
+
+#[test]
+fn nested_templates() {
+    let mut routes = FragmentRoutes::new("/fragments/");
+    routes.add("users/list.html.j2", ["", "row", "pagination"]);
+
+    assert_eq!(
+        routes.iter().collect::<Vec<_>>(),
+        vec![
+            (
+                "/fragments/users/list/pagination",
+                "users/list.html.j2",
+                "pagination"
+            ),
+            ("/fragments/users/list/row", "users/list.html.j2", "row"),
+        ]
+    );
+}
+
+#[test]
+fn base_fragments_are_not_routed() {
+    let mut routes = FragmentRoutes::default();
+    routes.add("index.html", [""]);
+
+    assert_eq!(routes.iter().count(), 0);
+    assert_eq!(routes.url("index.html", ""), None);
+}
+
+#[test]
+fn unknown_fragments() {
+    let mut routes = FragmentRoutes::default();
+    routes.add("index.html", ["item"]);
+
+    assert_eq!(routes.url("index.html", "footer"), None);
+    assert_eq!(routes.url("other.html", "item"), None);
+    assert_eq!(routes.lookup("/partials/index/footer"), None);
+    assert_eq!(routes.name("/partials/other/item"), None);
+}
+
+#[test]
+fn conflicting_templates() {
+    let mut routes = FragmentRoutes::default();
+    routes.add("index.html", ["item"]);
+    routes.add("index.txt", ["item"]);
+
+    assert_eq!(
+        routes.lookup("/partials/index/item"),
+        Some(("index.txt", "item"))
+    );
+    assert_eq!(routes.url("index.html", "item"), None);
+    assert_eq!(
+        routes.url("index.txt", "item"),
+        Some("/partials/index/item")
+    );
+}
+
+#[test]
+fn hidden_files_keep_their_name() {
+    let routes = FragmentRoutes::default();
+    assert_eq!(routes.route(".base.html", "item"), "/partials/.base/item");
+}
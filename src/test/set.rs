@@ -0,0 +1,135 @@
+use std::sync::Arc;
+
+use crate::{Config, Error, ErrorWithLine, ErrorWithPath, NameCase, TemplateSet, TemplateSetError};
+
+const INDEX: &str = concat!(
+    "<ul>\n",
+    "{% fragment item %}\n",
+    "<li>{{ item }}</li>\n",
+    "{% endfragment %}\n",
+    "</ul>\n",
+);
+
+#[test]
+fn insert_and_lookup() {
+    let mut templates = TemplateSet::new();
+    templates.insert("index.html", INDEX).unwrap();
+    templates
+        .insert(
+            "report.tex",
+            "((* fragment summary *))\nsummary\n((* endfragment *))\n",
+        )
+        .unwrap();
+
+    assert_eq!(
+        templates.get("index.html#item"),
+        Some("<li>{{ item }}</li>\n")
+    );
+    assert_eq!(
+        templates.get(" index.html # item "),
+        Some("<li>{{ item }}</li>\n")
+    );
+    assert_eq!(
+        templates.get_fragment("index.html", "item"),
+        Some("<li>{{ item }}</li>\n")
+    );
+    assert_eq!(templates.get("report.tex#summary"), Some("summary\n"));
+    assert!(templates.contains("index.html"));
+    assert!(!templates.contains("index.html#missing"));
+    assert!(!templates.contains("missing.html"));
+
+    assert_eq!(
+        templates.paths().collect::<Vec<_>>(),
+        ["index.html", "report.tex"]
+    );
+    assert_eq!(
+        templates.fragments("index.html").unwrap(),
+        ["index.html", "index.html#item"]
+    );
+    assert_eq!(templates.len(), 4);
+    assert_eq!(templates.iter().count(), 4);
+}
+
+#[test]
+fn reinsert_replaces_fragments() {
+    let mut templates = TemplateSet::new();
+    templates.insert("index.html", INDEX).unwrap();
+    templates
+        .insert(
+            "index.html",
+            "{% fragment other %}\nother\n{% endfragment %}\n",
+        )
+        .unwrap();
+
+    assert!(!templates.contains("index.html#item"));
+    assert_eq!(templates.get("index.html#other"), Some("other\n"));
+    assert_eq!(templates.len(), 2);
+
+    assert!(templates.remove("index.html"));
+    assert!(!templates.remove("index.html"));
+    assert!(templates.is_empty());
+}
+
+#[test]
+fn invalid_templates() {
+    let mut templates = TemplateSet::new();
+    templates.insert("index.html", INDEX).unwrap();
+
+    assert_eq!(
+        templates.insert("index.html", "{% fragment item %}\n"),
+        Err(TemplateSetError::Template(ErrorWithPath(
+            String::from("index.html"),
+            ErrorWithLine(0, Error::UnclosedTag(vec![String::from("item")])),
+        ))),
+    );
+    // the previous version is kept
+    assert!(templates.contains("index.html#item"));
+}
+
+#[test]
+fn conflicts() {
+    let mut templates = TemplateSet::new();
+    templates.insert("index.html", INDEX).unwrap();
+
+    let err = templates.insert("index.html#item", "other\n").unwrap_err();
+    assert_eq!(
+        err,
+        TemplateSetError::Conflict(String::from("index.html#item"), String::from("index.html")),
+    );
+    assert_eq!(
+        err.to_string(),
+        "\"index.html#item\" is already defined by index.html"
+    );
+    assert_eq!(
+        templates.get("index.html#item"),
+        Some("<li>{{ item }}</li>\n")
+    );
+    assert_eq!(templates.paths().count(), 1);
+}
+
+#[test]
+fn config() {
+    let mut templates = TemplateSet::with_config(Config::default().name_case(NameCase::Lower));
+    templates
+        .insert("index.html", &INDEX.replace("item", "Item"))
+        .unwrap();
+
+    assert!(templates.contains("index.html#ITEM"));
+    assert!(templates.contains("index.html#item"));
+}
+
+#[test]
+fn shared_between_threads() {
+    let mut templates = TemplateSet::new();
+    templates.insert("index.html", INDEX).unwrap();
+    let templates = Arc::new(templates);
+
+    let handle = std::thread::spawn({
+        let templates = Arc::clone(&templates);
+        move || templates.get("index.html#item").map(str::to_owned)
+    });
+    assert_eq!(
+        handle.join().unwrap().as_deref(),
+        templates.get("index.html#item")
+    );
+}
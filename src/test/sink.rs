@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+
+use crate::{Config, FragmentSink};
+
+const SOURCE: &str = concat!(
+    "<ul>\n",
+    "{% fragment items %}\n",
+    "{% fragment item %}\n",
+    "<li>{{ first }}</li>\n",
+    "{% endfragment %}\n",
+    "{% fragment item %}\n",
+    "<li>{{ second }}</li>\n",
+    "{% endfragment %}\n",
+    "{% endfragment %}\n",
+    "</ul>\n",
+);
+
+/// Record all calls of the sink
+#[derive(Default)]
+struct Recorder(Vec<(String, String)>);
+
+impl FragmentSink for Recorder {
+    fn emit(&mut self, fragment: &str, lines: &str) {
+        self.0.push((fragment.to_owned(), lines.to_owned()));
+    }
+}
+
+#[test]
+fn map_sink_matches_split_templates() {
+    for config in [
+        Config::default(),
+        Config::default().skip_base(true),
+        Config::default().number_repeated_fragments(true),
+        Config::default().fragment_separator("\n"),
+    ] {
+        let mut templates = HashMap::new();
+        config
+            .split_templates_to_sink(SOURCE, &mut templates)
+            .unwrap();
+        assert_eq!(templates, config.split_templates(SOURCE).unwrap());
+    }
+}
+
+#[test]
+fn output_is_streamed_in_order() {
+    let mut recorder = Recorder::default();
+    Config::default()
+        .split_templates_to_sink(SOURCE, &mut recorder)
+        .unwrap();
+
+    let item: Vec<&str> = recorder
+        .0
+        .iter()
+        .filter(|(fragment, _)| fragment == "item")
+        .map(|(_, lines)| lines.as_str())
+        .collect();
+    assert_eq!(item, ["<li>{{ first }}</li>\n", "<li>{{ second }}</li>\n"]);
+    assert_eq!(recorder.0[0], (String::new(), String::from("<ul>\n")));
+    assert_eq!(
+        recorder.0.last(),
+        Some(&(String::new(), String::from("</ul>\n")))
+    );
+}
+
+#[test]
+fn errors() {
+    let mut recorder = Recorder::default();
+    let err = Config::default()
+        .split_templates_to_sink("<ul>\n{% fragment item %}\n", &mut recorder)
+        .unwrap_err();
+    assert_eq!(err.0, 1);
+    assert_eq!(recorder.0, [(String::new(), String::from("<ul>\n"))]);
+}
+
+mod split_templates_with {
+    use super::SOURCE;
+    use crate::{split_templates, split_templates_with, Config, Error, ErrorWithLine};
+
+    #[test]
+    fn callback_receives_all_fragments_in_order() {
+        let mut calls = Vec::new();
+        split_templates_with(SOURCE, |fragment, content| {
+            calls.push((fragment.to_owned(), content))
+        })
+        .unwrap();
+
+        let mut expected: Vec<(String, String)> =
+            split_templates(SOURCE).unwrap().into_iter().collect();
+        expected.sort();
+        assert_eq!(calls, expected);
+    }
+
+    #[test]
+    fn output_options_are_applied() {
+        let config = Config::default().skip_base(true).minify_html(true);
+        let mut calls = Vec::new();
+        config
+            .split_templates_with(SOURCE, |fragment, content| {
+                calls.push((fragment.to_owned(), content))
+            })
+            .unwrap();
+
+        let mut expected: Vec<(String, String)> = config
+            .split_templates(SOURCE)
+            .unwrap()
+            .into_iter()
+            .collect();
+        expected.sort();
+        assert_eq!(calls, expected);
+        assert!(calls.iter().all(|(fragment, _)| !fragment.is_empty()));
+    }
+
+    #[test]
+    fn numbered_fragments_and_macros() {
+        let source = concat!(
+            "{% fragment item %}\n",
+            "<li>{{ first }}</li>\n",
+            "{% endfragment %}\n",
+            "{% fragment item %}\n",
+            "<li>{{ second }}</li>\n",
+            "{% endfragment %}\n",
+        );
+        let config = Config::default()
+            .number_repeated_fragments(true)
+            .macros(true);
+        let mut calls = Vec::new();
+        config
+            .split_templates_with(source, |fragment, content| {
+                calls.push((fragment.to_owned(), content))
+            })
+            .unwrap();
+
+        let mut expected: Vec<(String, String)> = config
+            .split_templates(source)
+            .unwrap()
+            .into_iter()
+            .collect();
+        expected.sort();
+        assert_eq!(calls, expected);
+        let names: Vec<&str> = calls.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["", "_macros", "item", "item.1"]);
+    }
+
+    #[test]
+    fn invalid_templates_do_not_call_the_callback() {
+        let mut called = false;
+        let res = split_templates_with("{% fragment a %}\n", |_, _| called = true);
+        assert!(matches!(res, Err(ErrorWithLine(_, Error::UnclosedTag(_)))));
+        assert!(!called);
+    }
+}
@@ -0,0 +1,65 @@
+use crate::{filter_template, fragment_stats, test::assert_matches, Error, ErrorWithLine};
+
+const SOURCE: &str = concat!(
+    "<body>\n",
+    "{% fragment a %}\n",
+    "<a>\n",
+    "  {% fragment-block b %}\n",
+    "  {% fragment c d %}\n",
+    "  <c>\n",
+    "  {% endfragment %}\n",
+    "  {% endfragment-block %}\n",
+    "{% endfragment %}\n",
+    "{% fragment c %}\n",
+    "{% endfragment %}\n",
+    "</body>",
+);
+
+#[test]
+fn sizes_match_filter_template() {
+    let stats = fragment_stats(SOURCE).unwrap();
+
+    for (fragment, stats) in &stats {
+        let content = filter_template(SOURCE, fragment).unwrap();
+        assert_eq!(stats.bytes, content.len(), "{fragment:?}");
+        assert_eq!(stats.lines, content.lines().count(), "{fragment:?}");
+    }
+}
+
+#[test]
+fn structure() {
+    let stats = fragment_stats(SOURCE).unwrap();
+
+    assert_eq!(stats[""].occurrences, 1);
+    assert_eq!(stats[""].depth, 0);
+    assert_eq!(stats[""].contains, vec!["a", "b", "c", "d"]);
+
+    assert_eq!(stats["a"].occurrences, 1);
+    assert_eq!(stats["a"].depth, 1);
+    assert_eq!(stats["a"].contains, vec!["b", "c", "d"]);
+
+    assert_eq!(stats["b"].depth, 2);
+    assert_eq!(stats["b"].contains, vec!["c", "d"]);
+
+    assert_eq!(stats["c"].occurrences, 2);
+    assert_eq!(stats["c"].depth, 3);
+    assert!(stats["c"].contains.is_empty());
+    assert_eq!(stats["d"].occurrences, 1);
+}
+
+#[test]
+fn empty_fragments() {
+    let stats = fragment_stats("{% fragment a %}\n{% endfragment %}\n").unwrap();
+    assert_eq!(stats["a"].occurrences, 1);
+    assert_eq!(stats["a"].lines, 0);
+    assert_eq!(stats["a"].bytes, 0);
+    assert_eq!(stats[""].lines, 0);
+}
+
+#[test]
+fn errors() {
+    assert_matches!(
+        fragment_stats("{% fragment a %}\n{% fragment a %}\n"),
+        Err(ErrorWithLine(1, Error::ReentrantFragment(_))),
+    );
+}
@@ -0,0 +1,469 @@
+use crate::{Config, Syntax};
+
+fn split(syntax: Syntax, source: &str) -> std::collections::HashMap<String, String> {
+    Config::default()
+        .syntax(syntax)
+        .split_templates(source)
+        .unwrap()
+}
+
+#[test]
+fn default_is_jinja() {
+    assert_eq!(Syntax::default(), Syntax::jinja());
+    assert_eq!(Syntax::jinja().tag_markers(), ("{%", "%}"));
+    assert_eq!(Syntax::jinja().comment_markers(), ("{#", "#}"));
+}
+
+#[test]
+fn golang() {
+    let source = concat!(
+        "<ul>\n",
+        "{{ range .Items }}\n",
+        "{{ fragment item }}\n",
+        "  <li>{{ .Name }}</li>\n",
+        "{{ endfragment }}\n",
+        "{{ end }}\n",
+        "</ul>\n",
+    );
+    assert_eq!(
+        split(Syntax::golang(), source)["item"],
+        "  <li>{{ .Name }}</li>\n"
+    );
+}
+
+#[test]
+fn html_comments() {
+    let source = concat!(
+        "<ul>\n",
+        "<!-- fragment item -->\n",
+        "  <li>{{ item }}</li>\n",
+        "<!-- endfragment -->\n",
+        "</ul>\n",
+    );
+    let templates = split(Syntax::html_comments(), source);
+    assert_eq!(templates["item"], "  <li>{{ item }}</li>\n");
+    assert_eq!(templates[""], "<ul>\n  <li>{{ item }}</li>\n</ul>\n");
+}
+
+#[test]
+fn c_comments() {
+    let source = concat!(
+        "/* fragment button */\n",
+        ".button { color: red; }\n",
+        "/* endfragment */\n",
+    );
+    assert_eq!(
+        split(Syntax::c_comments(), source)["button"],
+        ".button { color: red; }\n"
+    );
+}
+
+#[test]
+fn doc_comments() {
+    let source = concat!(
+        "<%# doc: A single item %>\n",
+        "<% fragment item %>\n",
+        "<% endfragment %>\n",
+    );
+    let fragments = Config::default()
+        .syntax(Syntax::erb())
+        .split_templates_detailed(source)
+        .unwrap();
+    assert_eq!(
+        fragments["item"].description.as_deref(),
+        Some("A single item")
+    );
+}
+
+mod go_blocks {
+    use crate::{BlockStyle, Config, Syntax};
+
+    const SOURCE: &str = concat!(
+        "{{ fragment-block outer }}\n",
+        "  {{ fragment-block inner }}\n",
+        "  <inner>\n",
+        "  {{ endfragment-block }}\n",
+        "{{ endfragment-block }}\n",
+    );
+
+    #[test]
+    fn nested_blocks() {
+        let templates = Config::golang().split_templates(SOURCE).unwrap();
+
+        assert_eq!(
+            templates[""],
+            concat!(
+                "{{ block \"outer\" . }}\n",
+                "  {{ block \"inner\" . }}\n",
+                "  <inner>\n",
+                "  {{ end }}\n",
+                "{{ end }}\n",
+            )
+        );
+        assert_eq!(
+            templates["outer"],
+            concat!(
+                "{{ define \"outer\" }}\n",
+                "  {{ block \"inner\" . }}\n",
+                "  <inner>\n",
+                "  {{ end }}\n",
+                "{{ end }}\n",
+            )
+        );
+        assert_eq!(
+            templates["inner"],
+            concat!("  {{ define \"inner\" }}\n", "  <inner>\n", "  {{ end }}\n")
+        );
+        assert_eq!(
+            Config::golang().filter_template(SOURCE, "inner").unwrap(),
+            templates["inner"]
+        );
+    }
+
+    #[test]
+    fn skip_base_keeps_block_tags() {
+        let templates = Config::golang()
+            .skip_base(true)
+            .split_templates(SOURCE)
+            .unwrap();
+        assert!(!templates.contains_key(""));
+        assert_eq!(
+            templates["inner"],
+            concat!("  {{ define \"inner\" }}\n", "  <inner>\n", "  {{ end }}\n")
+        );
+        assert_eq!(
+            templates["outer"],
+            Config::golang().split_templates(SOURCE).unwrap()["outer"]
+        );
+    }
+
+    #[test]
+    fn block_style_is_independent_of_the_syntax() {
+        let templates = Config::default()
+            .syntax(Syntax::golang())
+            .split_templates(SOURCE)
+            .unwrap();
+        assert_eq!(
+            templates["inner"],
+            concat!("  {{ block inner }}\n", "  <inner>\n", "  {{ endblock }}\n")
+        );
+
+        let templates = Config::default()
+            .block_style(BlockStyle::Go)
+            .split_templates(&SOURCE.replace("{{", "{%").replace("}}", "%}"))
+            .unwrap();
+        assert_eq!(
+            templates["inner"],
+            concat!("  {% define \"inner\" %}\n", "  <inner>\n", "  {% end %}\n")
+        );
+    }
+}
+
+mod handlebars_blocks {
+    use crate::{BlockStyle, Config};
+
+    const SOURCE: &str = concat!(
+        "{{!-- fragment-block outer --}}\n",
+        "  {{!-- fragment-block inner --}}\n",
+        "  <inner>\n",
+        "  {{!-- endfragment-block --}}\n",
+        "{{!-- endfragment-block --}}\n",
+    );
+
+    #[test]
+    fn nested_blocks() {
+        let templates = Config::handlebars().split_templates(SOURCE).unwrap();
+
+        assert_eq!(
+            templates[""],
+            concat!(
+                "{{#*inline \"outer\"}}\n",
+                "  {{#*inline \"inner\"}}\n",
+                "  <inner>\n",
+                "  {{/inline}}{{> inner}}\n",
+                "{{/inline}}{{> outer}}\n",
+            )
+        );
+        assert_eq!(
+            templates["outer"],
+            concat!(
+                "  {{#*inline \"inner\"}}\n",
+                "  <inner>\n",
+                "  {{/inline}}{{> inner}}\n",
+            )
+        );
+        assert_eq!(templates["inner"], "  <inner>\n");
+        assert_eq!(
+            Config::handlebars()
+                .filter_template(SOURCE, "inner")
+                .unwrap(),
+            templates["inner"]
+        );
+    }
+
+    #[test]
+    fn fragments_and_blocks() {
+        let source = concat!(
+            "{{!-- fragment list --}}\n",
+            "<ul>\n",
+            "{{#each items}}\n",
+            "{{!-- fragment-block item --}}\n",
+            "<li>{{name}}</li>\n",
+            "{{!-- endfragment-block --}}\n",
+            "{{/each}}\n",
+            "</ul>\n",
+            "{{!-- endfragment --}}\n",
+        );
+        let templates = Config::for_path("index.hbs")
+            .split_templates(source)
+            .unwrap();
+
+        assert_eq!(
+            templates["list"],
+            concat!(
+                "<ul>\n",
+                "{{#each items}}\n",
+                "{{#*inline \"item\"}}\n",
+                "<li>{{name}}</li>\n",
+                "{{/inline}}{{> item}}\n",
+                "{{/each}}\n",
+                "</ul>\n",
+            )
+        );
+        assert_eq!(templates["item"], "<li>{{name}}</li>\n");
+        assert_eq!(templates[""], templates["list"]);
+    }
+
+    #[test]
+    fn block_style_is_independent_of_the_syntax() {
+        let templates = Config::default()
+            .block_style(BlockStyle::Handlebars)
+            .split_templates("{% fragment-block item %}\n<li>\n{% endfragment-block %}\n")
+            .unwrap();
+        assert_eq!(
+            templates[""],
+            "{{#*inline \"item\"}}\n<li>\n{{/inline}}{{> item}}\n"
+        );
+        assert_eq!(templates["item"], "<li>\n");
+    }
+}
+
+mod liquid_blocks {
+    use crate::{Config, Syntax};
+
+    const SOURCE: &str = concat!(
+        "{% fragment-block outer %}\n",
+        "  {% fragment-block inner %}\n",
+        "  <inner>\n",
+        "  {% endfragment-block %}\n",
+        "{% endfragment-block %}\n",
+    );
+
+    #[test]
+    fn nested_blocks() {
+        let templates = Config::liquid().split_templates(SOURCE).unwrap();
+
+        assert_eq!(
+            templates[""],
+            concat!(
+                "{% capture outer %}\n",
+                "  {% capture inner %}\n",
+                "  <inner>\n",
+                "  {% endcapture %}{{ inner }}\n",
+                "{% endcapture %}{{ outer }}\n",
+            )
+        );
+        assert_eq!(
+            templates["outer"],
+            concat!(
+                "  {% capture inner %}\n",
+                "  <inner>\n",
+                "  {% endcapture %}{{ inner }}\n",
+            )
+        );
+        assert_eq!(templates["inner"], "  <inner>\n");
+        assert_eq!(
+            Config::for_path("product.liquid")
+                .filter_template(SOURCE, "outer")
+                .unwrap(),
+            templates["outer"]
+        );
+    }
+
+    #[test]
+    fn comments() {
+        let source = "{% fragment item %}\n<li>\n{% endfragment %}\n";
+        let config = Config::liquid().debug_annotations(true);
+
+        assert_eq!(Syntax::liquid().tag_markers(), ("{%", "%}"));
+        assert_eq!(
+            config.filter_template(source, "item").unwrap(),
+            "{% comment %} fragment \"item\" lines 1-3 {% endcomment %}\n<li>\n"
+        );
+    }
+}
+
+mod output_profiles {
+    use crate::{BlockStyle, Config, OutputProfile};
+
+    const SOURCE: &str = concat!(
+        "{% fragment-block outer %}\n",
+        "  {% fragment-block inner %}\n",
+        "  <inner>\n",
+        "  {% endfragment-block %}\n",
+        "{% endfragment-block %}\n",
+    );
+
+    #[test]
+    fn blocks_are_unnamed_and_unscoped_by_default() {
+        let templates = Config::default().split_templates(SOURCE).unwrap();
+        assert_eq!(
+            templates["inner"],
+            "  {% block inner %}\n  <inner>\n  {% endblock %}\n"
+        );
+    }
+
+    #[test]
+    fn profiles() {
+        for (profile, start_tag) in [
+            (OutputProfile::MiniJinja, "{% block inner %}"),
+            (OutputProfile::Jinja2, "{% block inner scoped %}"),
+            (OutputProfile::Tera, "{% block inner %}"),
+            (OutputProfile::Django, "{% block inner %}"),
+        ] {
+            let config = Config::default().output_profile(profile);
+            let templates = config.split_templates(SOURCE).unwrap();
+            assert_eq!(
+                templates["inner"],
+                format!("  {start_tag}\n  <inner>\n  {{% endblock inner %}}\n"),
+                "{profile:?}"
+            );
+            assert!(templates["outer"].ends_with("{% endblock outer %}\n"));
+        }
+    }
+
+    #[test]
+    fn profiles_reset_the_syntax() {
+        let config = Config::golang().output_profile(OutputProfile::Tera);
+        assert_eq!(config.block_style, BlockStyle::Jinja);
+        assert_eq!(config.syntax, Config::default().syntax);
+
+        let config = Config::default()
+            .output_profile(OutputProfile::Jinja2)
+            .tag_markers("((*", "*))");
+        let templates = config
+            .split_templates(&SOURCE.replace("{%", "((*").replace("%}", "*))"))
+            .unwrap();
+        assert_eq!(
+            templates["inner"],
+            "  ((* block inner scoped *))\n  <inner>\n  ((* endblock inner *))\n"
+        );
+    }
+
+    #[test]
+    fn named_endblocks_apply_to_extends() {
+        let source = "{% fragment item %}\n<li>\n{% endfragment %}\n";
+        let config = Config::default()
+            .output_profile(OutputProfile::Django)
+            .extends("base.html", "content");
+        assert_eq!(
+            config.filter_template(source, "item").unwrap(),
+            concat!(
+                "{% extends \"base.html\" %}\n",
+                "{% block content %}\n",
+                "<li>\n",
+                "{% endblock content %}\n",
+            ),
+        );
+    }
+
+    #[test]
+    fn other_block_styles_are_unchanged() {
+        let templates = Config::default()
+            .named_endblocks(true)
+            .scoped_blocks(true)
+            .block_style(BlockStyle::Liquid)
+            .split_templates(SOURCE)
+            .unwrap();
+        assert_eq!(
+            templates["outer"],
+            "  {% capture inner %}\n  <inner>\n  {% endcapture %}{{ inner }}\n"
+        );
+    }
+}
+
+mod latex {
+    use crate::{Config, Error, ErrorWithLine, Syntax, TagKind};
+
+    fn config() -> Config {
+        Config::default().syntax(Syntax::latex_commands())
+    }
+
+    #[test]
+    fn command_markers() {
+        let source = concat!(
+            "\\section{Items}\n",
+            "\\BLOCK{fragment items}\n",
+            "\\begin{itemize}\n",
+            "  \\BLOCK{ fragment item }\n",
+            "  \\item \\VAR{item}\n",
+            "  \\BLOCK{ endfragment }\n",
+            "\\end{itemize}\n",
+            "\\BLOCK{endfragment}\n",
+        );
+        let templates = config().split_templates(source).unwrap();
+
+        assert_eq!(templates["item"], "  \\item \\VAR{item}\n");
+        assert_eq!(
+            templates["items"],
+            "\\begin{itemize}\n  \\item \\VAR{item}\n\\end{itemize}\n"
+        );
+        assert_eq!(
+            templates[""],
+            "\\section{Items}\n\\begin{itemize}\n  \\item \\VAR{item}\n\\end{itemize}\n"
+        );
+    }
+
+    #[test]
+    fn keywords_must_be_complete() {
+        let source = "\\BLOCK{fragments}\n\\BLOCK{endfragmentx}\n";
+        let templates = config().split_templates(source).unwrap();
+        assert_eq!(templates[""], source);
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            config().split_templates("\\BLOCK{fragment item} text\n\\BLOCK{endfragment}\n"),
+            Err(ErrorWithLine(
+                0,
+                Error::TrailingContent(String::from(" text\n"))
+            ))
+        );
+        assert_eq!(
+            config().split_templates("\\BLOCK{fragment}\n"),
+            Err(ErrorWithLine(0, Error::StartTagWithoutData))
+        );
+    }
+
+    #[test]
+    fn parse_tag() {
+        let line = "  \\BLOCK{endfragment}\n";
+        let tag = config().parse_tag(line).unwrap().unwrap();
+        assert_eq!(tag.kind, TagKind::End);
+        assert_eq!(&line[tag.span], "\\BLOCK{endfragment}");
+    }
+
+    #[test]
+    fn other_markers_require_whitespace() {
+        let source = "{%fragment item %}\n<li>\n{% endfragment%}\n";
+        let templates = Config::default().split_templates(source).unwrap();
+        assert_eq!(templates[""], source);
+    }
+
+    #[test]
+    fn for_path() {
+        assert_eq!(Config::for_path("report.tex"), Config::latex());
+        assert_eq!(Config::latex().syntax, Syntax::latex());
+    }
+}
@@ -0,0 +1,94 @@
+use crate::{parse_tag, Config, Error, Syntax, TagKind, TagPart};
+
+fn texts<'a>(parts: &[TagPart<'a>]) -> Vec<&'a str> {
+    parts.iter().map(|part| part.text).collect()
+}
+
+#[test]
+fn kinds() {
+    for (line, kind) in [
+        ("{% fragment item %}\n", TagKind::Start),
+        ("{% endfragment %}\n", TagKind::End),
+        ("{% fragment-block item %}\n", TagKind::StartBlock),
+        ("{% endfragment-block %}\n", TagKind::EndBlock),
+    ] {
+        assert_eq!(parse_tag(line).unwrap().unwrap().kind, kind, "{line:?}");
+    }
+}
+
+#[test]
+fn spans() {
+    let line = "\t{% fragment  item with=ctx footer %}  \r\n";
+    let tag = parse_tag(line).unwrap().unwrap();
+
+    assert_eq!(
+        &line[tag.span.clone()],
+        "{% fragment  item with=ctx footer %}"
+    );
+    assert_eq!(texts(&tag.names), ["item", "footer"]);
+    for part in &tag.names {
+        assert_eq!(&line[part.span.clone()], part.text);
+    }
+
+    let (key, value) = &tag.attributes[0];
+    assert_eq!((key.text, key.span.clone()), ("with", 19..23));
+    assert_eq!((value.text, value.span.clone()), ("ctx", 24..27));
+}
+
+#[test]
+fn end_tags() {
+    let tag = parse_tag("  {% endfragment %}").unwrap().unwrap();
+    assert_eq!(tag.span, 2..19);
+    assert!(tag.names.is_empty());
+
+    // names of end tags are only checked when processing the template
+    let tag = parse_tag("{% endfragment item %}\n").unwrap().unwrap();
+    assert_eq!(texts(&tag.names), ["item"]);
+}
+
+#[test]
+fn no_tags() {
+    for line in [
+        "",
+        "<li>{{ item }}</li>\n",
+        "{# fragment item #}\n",
+        "{% fragmented %}\n",
+        "{% for item in items %}\n",
+    ] {
+        assert_eq!(parse_tag(line), Ok(None), "{line:?}");
+    }
+}
+
+#[test]
+fn invalid_tags() {
+    assert_eq!(
+        parse_tag("<li>{% fragment item %}\n"),
+        Err(Error::LeadingContent(String::from("<li>")))
+    );
+    assert_eq!(
+        parse_tag("{% fragment %}\n"),
+        Err(Error::StartTagWithoutData)
+    );
+    assert_eq!(
+        parse_tag("{% fragment block %}\n"),
+        Err(Error::InvalidFragmentName(vec![String::from("block")]))
+    );
+    assert_eq!(
+        parse_tag("{% fragment-block a b %}\n"),
+        Err(Error::MultipleNamesBlock(vec![
+            String::from("a"),
+            String::from("b")
+        ]))
+    );
+}
+
+#[test]
+fn syntax() {
+    let config = Config::default().syntax(Syntax::html_comments());
+    let line = "<!-- fragment item -->\n";
+
+    let tag = config.parse_tag(line).unwrap().unwrap();
+    assert_eq!(tag.span, 0..22);
+    assert_eq!(tag.names[0].span, 14..18);
+    assert_eq!(parse_tag(line), Ok(None));
+}
@@ -0,0 +1,67 @@
+use crate::{
+    filter_template, test::assert_matches, Config, Error, ErrorWithLine, NameCase, Template,
+};
+
+const SOURCE: &str = concat!(
+    "<body>\n",
+    "{% fragment items %}\n",
+    "<ul>\n",
+    "  {% fragment-block item %}\n",
+    "  <li>{{ item }}</li>\n",
+    "  {% endfragment-block %}\n",
+    "</ul>\n",
+    "{% endfragment %}\n",
+    "{% fragment empty %}\n",
+    "{% endfragment %}\n",
+    "</body>\n",
+);
+
+#[test]
+fn filter_matches_filter_template() {
+    let template = Template::parse(SOURCE).unwrap();
+    for fragment in ["", "items", "item", "empty", "unknown"] {
+        assert_eq!(
+            template.filter(fragment),
+            filter_template(SOURCE, fragment).unwrap(),
+        );
+    }
+}
+
+#[test]
+fn names() {
+    let template = Template::parse(SOURCE).unwrap();
+    assert_eq!(template.names(), ["empty", "item", "items"]);
+
+    assert!(template.has(""));
+    assert!(template.has("empty"));
+    assert!(template.has("item"));
+    assert!(!template.has("unknown"));
+}
+
+#[test]
+fn empty_template() {
+    let template = Template::parse("").unwrap();
+    assert!(template.names().is_empty());
+    assert!(template.has(""));
+    assert_eq!(template.filter(""), "");
+}
+
+#[test]
+fn config_is_used() {
+    let template = Config::default()
+        .name_case(NameCase::Upper)
+        .parse_template(SOURCE)
+        .unwrap();
+    assert_eq!(template.names(), ["EMPTY", "ITEM", "ITEMS"]);
+    assert!(template.has("item"));
+    assert_eq!(template.filter("Item"), template.filter("ITEM"));
+    assert!(!template.filter("item").is_empty());
+}
+
+#[test]
+fn errors() {
+    assert_matches!(
+        Template::parse("{% fragment a %}\n"),
+        Err(ErrorWithLine(0, Error::UnclosedTag(_))),
+    );
+}
@@ -0,0 +1,32 @@
+use crate::{assert_fragments, Config, NameCase};
+
+const SOURCE: &str = concat!(
+    "{% fragment a %}\n",
+    "{% fragment-block b %}\n",
+    "{% endfragment-block %}\n",
+    "{% endfragment %}\n",
+);
+
+#[test]
+fn matching_fragments() {
+    assert_fragments!(SOURCE, ["a", "b"]);
+    assert_fragments!(SOURCE, ["b", "a", "a"]);
+    assert_fragments!("<div></div>\n", []);
+    assert_fragments!(
+        Config::default().name_case(NameCase::Lower),
+        SOURCE,
+        ["A", "b"],
+    );
+}
+
+#[test]
+#[should_panic(expected = "missing: [\"c\"]\n  unexpected: [\"b\"]")]
+fn differing_fragments() {
+    assert_fragments!(SOURCE, ["a", "c"]);
+}
+
+#[test]
+#[should_panic(expected = "template is invalid\n  error: Error::UnclosedTag(a) at line 1")]
+fn invalid_template() {
+    assert_fragments!("{% fragment a %}\n", ["a"]);
+}
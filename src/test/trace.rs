@@ -0,0 +1,101 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Mutex,
+};
+
+use tracing::{
+    field::{Field, Visit},
+    span, Event, Metadata, Subscriber,
+};
+
+use crate::Config;
+
+/// Record the names of new spans and the fields of events
+#[derive(Default, Clone)]
+struct Recorder {
+    next_id: Arc<AtomicU64>,
+    spans: Arc<Mutex<Vec<String>>>,
+    events: Arc<Mutex<Vec<Vec<String>>>>,
+}
+
+struct FieldNames(Vec<String>);
+
+impl Visit for FieldNames {
+    fn record_debug(&mut self, field: &Field, _: &dyn std::fmt::Debug) {
+        self.0.push(field.name().to_owned());
+    }
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &span::Attributes<'_>) -> span::Id {
+        self.spans
+            .lock()
+            .unwrap()
+            .push(span.metadata().name().to_owned());
+        span::Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+    }
+
+    fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+
+    fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut fields = FieldNames(Vec::new());
+        event.record(&mut fields);
+        self.events.lock().unwrap().push(fields.0);
+    }
+
+    fn enter(&self, _: &span::Id) {}
+
+    fn exit(&self, _: &span::Id) {}
+}
+
+const SOURCE: &str = "<ul>\n{% fragment item %}\n<li></li>\n{% endfragment %}\n</ul>\n";
+
+#[test]
+fn split_templates() {
+    let recorder = Recorder::default();
+    tracing::subscriber::with_default(recorder.clone(), || {
+        Config::default().split_templates(SOURCE).unwrap();
+    });
+
+    assert_eq!(
+        *recorder.spans.lock().unwrap(),
+        ["split_templates", "preprocess"]
+    );
+    let events = recorder.events.lock().unwrap();
+    assert_eq!(events.len(), 2);
+    assert!(events[1].contains(&String::from("elapsed_us")));
+    assert!(events[1].contains(&String::from("fragments")));
+}
+
+#[test]
+fn path_and_filter_spans() {
+    let recorder = Recorder::default();
+    tracing::subscriber::with_default(recorder.clone(), || {
+        Config::default()
+            .split_templates_prefixed("index.html", SOURCE)
+            .unwrap();
+        Config::default().filter_template(SOURCE, "item").unwrap();
+    });
+
+    assert_eq!(
+        *recorder.spans.lock().unwrap(),
+        [
+            "split_template",
+            "split_templates",
+            "preprocess",
+            "filter_template",
+            "preprocess",
+        ]
+    );
+    let events = recorder.events.lock().unwrap();
+    assert!(events
+        .last()
+        .unwrap()
+        .contains(&String::from("output_bytes")));
+}
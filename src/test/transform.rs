@@ -0,0 +1,119 @@
+use std::{borrow::Cow, collections::HashMap};
+
+use crate::{split_templates, split_templates_into, split_templates_transformed, Config};
+
+const SOURCE: &str = concat!(
+    "<div>\n",
+    "{% fragment a %}\n",
+    "  <p>{{ nonce }}</p>\n",
+    "  {% fragment-block b %}\n",
+    "  <span></span>\n",
+    "  {% endfragment-block %}\n",
+    "{% endfragment %}\n",
+    "</div>\n",
+);
+
+#[test]
+fn rewrite_lines() {
+    let templates = split_templates_transformed(SOURCE, |_, line| {
+        Some(Cow::Owned(line.replace("{{ nonce }}", "abc")))
+    })
+    .unwrap();
+
+    assert_eq!(
+        templates[""],
+        "<div>\n  <p>abc</p>\n  {% block b %}\n  <span></span>\n  {% endblock %}\n</div>\n"
+    );
+    assert_eq!(
+        templates["b"],
+        "  {% block b %}\n  <span></span>\n  {% endblock %}\n"
+    );
+}
+
+#[test]
+fn drop_lines() {
+    let templates = split_templates_transformed(SOURCE, |fragments, line| {
+        (!fragments.contains("b")).then_some(Cow::Borrowed(line))
+    })
+    .unwrap();
+
+    assert_eq!(templates[""], "<div>\n  <p>{{ nonce }}</p>\n</div>\n");
+    assert_eq!(templates["a"], "  <p>{{ nonce }}</p>\n");
+    assert!(!templates.contains_key("b"));
+}
+
+#[test]
+fn active_fragments() {
+    let mut calls = Vec::new();
+    split_templates_transformed(SOURCE, |fragments, line| {
+        let mut fragments: Vec<String> = fragments.iter().map(|f| f.to_string()).collect();
+        fragments.sort();
+        calls.push((fragments, line.to_owned()));
+        Some(Cow::Borrowed(line))
+    })
+    .unwrap();
+
+    assert_eq!(calls.len(), 6);
+    assert_eq!(calls[0], (vec![String::from("")], String::from("<div>\n")));
+    assert_eq!(
+        calls[2],
+        (
+            vec![String::from(""), String::from("a"), String::from("b")],
+            String::from("  {% block b %}\n")
+        )
+    );
+}
+
+#[test]
+fn output_options() {
+    let config = Config::default().minify_html(true).macros(true);
+    let templates = config
+        .split_templates_transformed(SOURCE, |_, line| Some(Cow::Borrowed(line)))
+        .unwrap();
+    assert_eq!(templates, config.split_templates(SOURCE).unwrap());
+    assert_eq!(
+        templates["b"],
+        "{% block b %}\n<span></span>\n{% endblock %}\n"
+    );
+}
+
+#[test]
+fn split_into_reuses_buffers() {
+    let mut templates = HashMap::new();
+    split_templates_into(SOURCE, &mut templates).unwrap();
+    assert_eq!(templates, split_templates(SOURCE).unwrap());
+
+    let before: HashMap<String, (*const u8, usize)> = templates
+        .iter()
+        .map(|(fragment, content)| (fragment.clone(), (content.as_ptr(), content.capacity())))
+        .collect();
+
+    split_templates_into(SOURCE, &mut templates).unwrap();
+    assert_eq!(templates, split_templates(SOURCE).unwrap());
+    for (fragment, content) in &templates {
+        assert_eq!(
+            before[fragment],
+            (content.as_ptr(), content.capacity()),
+            "{fragment:?}"
+        );
+    }
+}
+
+#[test]
+fn split_into_removes_stale_fragments() {
+    let mut templates = HashMap::from([(String::from("stale"), String::from("old\n"))]);
+    split_templates_into(SOURCE, &mut templates).unwrap();
+    assert_eq!(templates, split_templates(SOURCE).unwrap());
+
+    split_templates_into("", &mut templates).unwrap();
+    assert!(templates.is_empty());
+}
+
+#[test]
+fn split_into_applies_options() {
+    let config = Config::default().macros(true).minify_html(true);
+    let mut templates = HashMap::new();
+    config.split_templates_into(SOURCE, &mut templates).unwrap();
+    config.split_templates_into(SOURCE, &mut templates).unwrap();
+    assert_eq!(templates, config.split_templates(SOURCE).unwrap());
+}
@@ -0,0 +1,89 @@
+use crate::{validate_template, Config, Diagnostic, Severity};
+
+#[test]
+fn valid_templates_have_no_diagnostics() {
+    let source = "<ul>\n{% fragment item %}\n<li>{{ item }}</li>\n{% endfragment %}\n</ul>\n";
+    assert_eq!(validate_template(source), []);
+}
+
+#[test]
+fn errors_are_located() {
+    let source = "<ul>\n  {% fragment item %} <li>\n  {% endfragment %}\n</ul>\n";
+    let diagnostics = validate_template(source);
+
+    assert_eq!(diagnostics.len(), 1);
+    let diagnostic = &diagnostics[0];
+    assert_eq!(diagnostic.code, "trailing-content");
+    assert_eq!(diagnostic.severity, Severity::Error);
+    assert_eq!(
+        (diagnostic.line, diagnostic.column, diagnostic.end_column),
+        (2, 23, 27)
+    );
+    assert_eq!(diagnostic.message, "content after a fragment tag");
+    assert!(diagnostic.help.is_some());
+}
+
+#[test]
+fn unclosed_tags_are_reported_at_the_innermost_start_tag() {
+    let source = "{% fragment items %}\n  {% fragment item %}\n  <li></li>\n{% endfragment %}\n";
+    let diagnostics = validate_template(source);
+
+    assert_eq!(diagnostics[0].code, "unclosed-tag");
+    assert_eq!((diagnostics[0].line, diagnostics[0].column), (1, 1));
+    assert_eq!(diagnostics[0].end_column, 21);
+}
+
+#[test]
+fn lint_warnings_are_reported() {
+    let source =
+        "{% fragment item %}\n{% endfragment %}\n{% fragment Item %}\nx\n{% endfragment %}\n";
+    let diagnostics = validate_template(source);
+
+    let codes: Vec<_> = diagnostics.iter().map(|d| (d.line, d.code)).collect();
+    assert_eq!(codes, [(1, "empty-fragment"), (3, "similar-names")]);
+    assert!(diagnostics
+        .iter()
+        .all(|d| d.severity == Severity::Warning && d.help.is_none()));
+}
+
+#[test]
+fn columns_count_characters() {
+    let source = "<h1>\n  ä {% fragment title %}\n{% endfragment %}\n";
+    let diagnostics = validate_template(source);
+
+    assert_eq!(diagnostics[0].code, "leading-content");
+    assert_eq!((diagnostics[0].column, diagnostics[0].end_column), (3, 4));
+}
+
+#[test]
+fn custom_markers_are_respected() {
+    let config = Config::for_path("report.tex");
+    let diagnostics = config.validate_template("((* fragment item *))\n");
+    assert_eq!(diagnostics[0].code, "unclosed-tag");
+    assert_eq!((diagnostics[0].column, diagnostics[0].end_column), (1, 22));
+}
+
+#[test]
+fn json_escapes_strings() {
+    let diagnostic = Diagnostic {
+        file: None,
+        line: 3,
+        column: 1,
+        end_column: 2,
+        code: "custom-tag",
+        severity: Severity::Warning,
+        message: String::from("a \"quoted\"\tname\\\n\u{1}"),
+        help: None,
+    };
+    assert_eq!(
+        diagnostic.to_json(),
+        concat!(
+            r#"{"file":null,"line":3,"column":1,"end_column":2,"code":"custom-tag","#,
+            r#""severity":"warning","message":"a \"quoted\"\tname\\\n\u0001","help":null}"#,
+        ),
+    );
+    assert_eq!(
+        diagnostic.in_path("a.html").to_string(),
+        "a.html:3:1: warning[custom-tag]: a \"quoted\"\tname\\\n\u{1}"
+    );
+}
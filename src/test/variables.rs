@@ -0,0 +1,73 @@
+use std::collections::BTreeSet;
+
+use crate::{fragment_variables, Config, Syntax};
+
+fn names<const N: usize>(names: [&str; N]) -> BTreeSet<String> {
+    names.into_iter().map(String::from).collect()
+}
+
+#[test]
+fn expressions() {
+    let source = concat!(
+        "{{ user.name }} {{- items | join(\", \") -}}\n",
+        "{{ 'title' ~ page_title if show_title else default_title }}\n",
+        "{{ count + 1 }} {{ value is not defined }} {{ not hidden and true }}\n",
+        "{{ format(value=amount, precision=2) }} {{ rows[index] }}\n",
+        "{% if condition %}{{ none }}{% endif %}\n",
+    );
+    let variables = fragment_variables(source).unwrap();
+
+    assert_eq!(
+        variables[""],
+        names([
+            "amount",
+            "count",
+            "default_title",
+            "format",
+            "hidden",
+            "index",
+            "items",
+            "page_title",
+            "rows",
+            "show_title",
+            "user",
+            "value",
+        ])
+    );
+}
+
+#[test]
+fn bound_variables_are_excluded() {
+    let source = concat!(
+        "{% fragment items %}\n",
+        "{% for key, value in pairs %}\n",
+        "{{ key }}={{ value }} {{ loop.index }}\n",
+        "{% endfor %}\n",
+        "{% set total = pairs | length %}\n",
+        "{% with label = 'Total', unit=currency %}\n",
+        "{{ label }}: {{ total }} {{ unit }}\n",
+        "{% endwith %}\n",
+        "{% endfragment %}\n",
+        "{% fragment item %}\n",
+        "{{ key }}\n",
+        "{% endfragment %}\n",
+    );
+    let variables = fragment_variables(source).unwrap();
+
+    assert_eq!(variables["items"], names([]));
+    assert_eq!(variables["item"], names(["key"]));
+}
+
+#[test]
+fn tag_markers_of_the_config() {
+    let source = concat!(
+        "<% fragment item %>\n",
+        "<% for item in items %>{{ item }}<% endfor %>\n",
+        "<% endfragment %>\n",
+    );
+    let variables = Config::default()
+        .syntax(Syntax::erb())
+        .fragment_variables(source)
+        .unwrap();
+    assert_eq!(variables["item"], names([]));
+}
@@ -0,0 +1,99 @@
+use crate::{
+    filter_template, fragment_exists, has_fragments, split_templates, Config, IncrementalSplit,
+    NameCase,
+};
+
+const SOURCE: &str = concat!(
+    "{% fragment item %}\n",
+    "<li>\n",
+    "{% verbatim %}\n",
+    "{% fragment example %}\n",
+    "{% endfragment %}\n",
+    "{% endverbatim %}\n",
+    "</li>\n",
+    "{% endfragment %}\n",
+);
+
+#[test]
+fn verbatim_content_is_not_scanned() {
+    let templates = split_templates(SOURCE).unwrap();
+    assert_eq!(templates.len(), 2);
+    assert_eq!(
+        templates["item"],
+        concat!(
+            "<li>\n",
+            "{% verbatim %}\n",
+            "{% fragment example %}\n",
+            "{% endfragment %}\n",
+            "{% endverbatim %}\n",
+            "</li>\n",
+        )
+    );
+    assert_eq!(filter_template(SOURCE, "item").unwrap(), templates["item"]);
+    assert!(!fragment_exists(SOURCE, "example"));
+}
+
+#[test]
+fn raw_and_whitespace_control() {
+    let source = concat!(
+        "{%- raw -%}\n",
+        "{% fragment item %}\n",
+        "{%- endraw %}\n",
+        "{% fragment item %}\n",
+        "{% endfragment %}\n",
+    );
+    let templates = split_templates(source).unwrap();
+    assert_eq!(
+        templates[""],
+        "{%- raw -%}\n{% fragment item %}\n{%- endraw %}\n"
+    );
+    assert!(!templates.contains_key("item"));
+
+    assert!(!has_fragments(
+        "{% raw %}\n{% fragment item %}\n{% endraw %}\n"
+    ));
+    assert!(has_fragments(source));
+}
+
+#[test]
+fn named_verbatim_regions() {
+    let source = concat!(
+        "{% verbatim outer %}\n",
+        "{% verbatim %}\n",
+        "{% endverbatim %}\n",
+        "{% endfragment %}\n",
+        "{% endverbatim outer %}\n",
+        "{% endfragment %}\n",
+    );
+    assert_eq!(split_templates(source).unwrap_err().0, 5);
+}
+
+#[test]
+fn regions_on_a_single_line() {
+    let source = "{% raw %}{{ a }}{% endraw %}\n{% fragment item %}\nitem\n{% endfragment %}\n";
+    assert_eq!(filter_template(source, "item").unwrap(), "item\n");
+}
+
+#[test]
+fn names_are_not_normalized() {
+    let source =
+        "{% fragment Item %}\n{% raw %}\n{% fragment Raw %}\n{% endraw %}\n{% endfragment %}\n";
+    let templates = Config::default()
+        .name_case(NameCase::Lower)
+        .split_templates(source)
+        .unwrap();
+    assert_eq!(
+        templates["item"],
+        "{% raw %}\n{% fragment Raw %}\n{% endraw %}\n"
+    );
+}
+
+#[test]
+fn incremental_updates() {
+    let mut split = IncrementalSplit::new(SOURCE).unwrap();
+    let changed = split
+        .update(SOURCE.replace("{% endfragment %}\n{% endverbatim", "x\n{% endverbatim"))
+        .unwrap();
+    assert_eq!(changed, ["", "item"]);
+    assert!(!split.templates().contains_key("example"));
+}
@@ -0,0 +1,89 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::{test::TempDir, TemplateSetError, TemplateWatcher, WatchError};
+
+const INDEX: &str = "<ul>\n{% fragment item %}\n<li></li>\n{% endfragment %}\n</ul>\n";
+
+/// Wait until the condition holds, changes are reported asynchronously
+fn wait_for(mut condition: impl FnMut() -> bool) {
+    let start = Instant::now();
+    while !condition() {
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "timeout while waiting for changes"
+        );
+        std::thread::sleep(Duration::from_millis(20));
+    }
+}
+
+fn get(watcher: &TemplateWatcher, name: &str) -> Option<String> {
+    watcher.templates().get(name).map(str::to_owned)
+}
+
+#[test]
+fn initial_load() {
+    let dir = TempDir::new("watch-initial");
+    dir.write("index.html", INDEX);
+    dir.write(
+        "pages/page.tex",
+        "((* fragment a *))\na\n((* endfragment *))\n",
+    );
+
+    let watcher = TemplateWatcher::new(&dir.0, |err| panic!("{err}")).unwrap();
+    assert_eq!(
+        get(&watcher, "index.html#item").as_deref(),
+        Some("<li></li>\n")
+    );
+    assert_eq!(get(&watcher, "pages/page.tex#a").as_deref(), Some("a\n"));
+    assert_eq!(watcher.shared().read().unwrap().paths().count(), 2);
+}
+
+#[test]
+fn initial_errors() {
+    let dir = TempDir::new("watch-initial-errors");
+    dir.write("index.html", "{% fragment item %}\n");
+
+    assert!(matches!(
+        TemplateWatcher::new(&dir.0, |_| {}),
+        Err(WatchError::Template(TemplateSetError::Template(_))),
+    ));
+    assert!(matches!(
+        TemplateWatcher::new(dir.0.join("missing"), |_| {}),
+        Err(WatchError::Io(_, _)),
+    ));
+}
+
+#[test]
+fn reload_changes() {
+    let dir = TempDir::new("watch-reload");
+    dir.write("index.html", INDEX);
+    dir.write("other.html", "other\n");
+
+    let errors = Arc::new(Mutex::new(Vec::new()));
+    let watcher = TemplateWatcher::new(&dir.0, {
+        let errors = Arc::clone(&errors);
+        move |err| errors.lock().unwrap().push(err.to_string())
+    })
+    .unwrap();
+
+    dir.write("index.html", &INDEX.replace("<li>", "<li class=\"new\">"));
+    wait_for(|| get(&watcher, "index.html#item").as_deref() == Some("<li class=\"new\"></li>\n"));
+
+    dir.write("sub/new.html", "new\n");
+    wait_for(|| get(&watcher, "sub/new.html").is_some());
+
+    std::fs::remove_file(dir.0.join("other.html")).unwrap();
+    wait_for(|| get(&watcher, "other.html").is_none());
+
+    // invalid templates are reported and the previous version is kept
+    dir.write("index.html", "{% fragment item %}\n");
+    wait_for(|| !errors.lock().unwrap().is_empty());
+    assert!(errors.lock().unwrap()[0].contains("Error::UnclosedTag(item)"));
+    assert_eq!(
+        get(&watcher, "index.html#item").as_deref(),
+        Some("<li class=\"new\"></li>\n")
+    );
+}
@@ -0,0 +1,87 @@
+use crate::Config;
+
+/// Assert that a template defines exactly the given fragments
+///
+/// The template must be valid, i.e., each fragment can be filtered without
+/// error, and define the given fragments, in any order. The base fragment `""`
+/// is always defined and must not be listed. On failure, the panic message
+/// lists the missing and unexpected fragments or the offending line of the
+/// template. A [Config] can be passed as the first argument. Requires the
+/// `test-util` feature.
+///
+/// ```rust
+/// # use template_fragments::{assert_fragments, Config, Syntax};
+/// let source = concat!(
+///     "<ul>\n",
+///     "{% fragment list %}\n",
+///     "  {% fragment-block item %}\n",
+///     "  <li>{{ item }}</li>\n",
+///     "  {% endfragment-block %}\n",
+///     "{% endfragment %}\n",
+///     "</ul>\n",
+/// );
+/// assert_fragments!(source, ["item", "list"]);
+///
+/// let source = "<!-- fragment item -->\n<li></li>\n<!-- endfragment -->\n";
+/// assert_fragments!(
+///     Config::default().syntax(Syntax::html_comments()),
+///     source,
+///     ["item"],
+/// );
+/// ```
+#[macro_export]
+macro_rules! assert_fragments {
+    ($src:expr, [$($fragment:expr),* $(,)?] $(,)?) => {
+        $crate::assert_fragments!(
+            $crate::Config::default(),
+            $src,
+            [$($fragment),*],
+        )
+    };
+    ($config:expr, $src:expr, [$($fragment:expr),* $(,)?] $(,)?) => {
+        $crate::check_fragments(&$config, $src, &[$($fragment),*])
+    };
+}
+
+/// The implementation of [assert_fragments]
+#[doc(hidden)]
+#[track_caller]
+pub fn check_fragments(config: &Config, src: &str, expected: &[&str]) {
+    let template = match config.parse_template(src) {
+        Ok(template) => template,
+        Err(err) => panic!(
+            "assertion failed: template is invalid\n  error: {}",
+            err.with_source(src)
+        ),
+    };
+
+    let mut expected: Vec<String> = expected
+        .iter()
+        .map(|name| config.name_case.normalize(name).into_owned())
+        .collect();
+    expected.sort();
+    expected.dedup();
+
+    let found = template.names();
+    let missing: Vec<&String> = expected
+        .iter()
+        .filter(|name| !found.contains(name))
+        .collect();
+    let unexpected: Vec<&String> = found
+        .iter()
+        .filter(|name| !expected.contains(name))
+        .collect();
+
+    if !missing.is_empty() || !unexpected.is_empty() {
+        panic!(
+            concat!(
+                "assertion failed: fragments differ\n",
+                "     missing: {:?}\n",
+                "  unexpected: {:?}\n",
+                "    expected: {:?}\n",
+                "       found: {:?}",
+            ),
+            missing, unexpected, expected, found,
+        );
+    }
+}
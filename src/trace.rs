@@ -0,0 +1,28 @@
+/// Enter a debug span for a stage of the pipeline and start its timer
+///
+/// The span is exited at the end of the enclosing scope. Without the
+/// `tracing` feature, nothing is emitted and the fields are not evaluated.
+macro_rules! start_span {
+    ($timer:ident, $name:literal $(, $($fields:tt)*)?) => {
+        #[cfg(feature = "tracing")]
+        let $timer = (
+            tracing::debug_span!($name $(, $($fields)*)?).entered(),
+            std::time::Instant::now(),
+        );
+    };
+}
+
+/// Emit a debug event with the duration of a stage started with
+/// [start_span] and additional fields
+macro_rules! finish_span {
+    ($timer:ident $(, $($fields:tt)*)?) => {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            elapsed_us = $timer.1.elapsed().as_micros() as u64,
+            $($($fields)*,)?
+            "finished"
+        );
+    };
+}
+
+pub(crate) use {finish_span, start_span};
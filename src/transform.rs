@@ -0,0 +1,157 @@
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+};
+
+use crate::{
+    annotate::annotate,
+    defines::LineMap,
+    iterate_with_endings,
+    numbered::{fragment_of_key, process_numbered},
+    process_template, push_line,
+    trace::{finish_span, start_span},
+    Config, ErrorWithLine,
+};
+
+/// Split the template into all fragments, rewriting or dropping each output
+/// line with a callback
+///
+/// See [Config::split_templates_transformed].
+///
+/// ```rust
+/// # use std::borrow::Cow;
+/// # use template_fragments::split_templates_transformed;
+/// let source = concat!(
+///     "<body>\n",
+///     "  {% fragment item %}\n",
+///     "  <img src=\"/static/item.png\">\n",
+///     "  {# todo: use the final icon #}\n",
+///     "  {% endfragment %}\n",
+///     "</body>\n",
+/// );
+///
+/// let templates = split_templates_transformed(source, |fragments, line| {
+///     if fragments.contains("item") && line.trim_start().starts_with("{#") {
+///         None
+///     } else if line.contains("\"/static/") {
+///         Some(Cow::Owned(line.replace("\"/static/", "\"https://cdn.example.com/")))
+///     } else {
+///         Some(Cow::Borrowed(line))
+///     }
+/// })
+/// .unwrap();
+///
+/// assert_eq!(
+///     templates["item"],
+///     "  <img src=\"https://cdn.example.com/item.png\">\n",
+/// );
+/// ```
+pub fn split_templates_transformed(
+    src: &str,
+    transform: impl for<'l> FnMut(&HashSet<&str>, &'l str) -> Option<Cow<'l, str>>,
+) -> Result<HashMap<String, String>, ErrorWithLine> {
+    Config::default().split_templates_transformed(src, transform)
+}
+
+impl Config {
+    /// Split the template into all fragments, rewriting or dropping each
+    /// output line with a callback
+    ///
+    /// The callback is called once for each output line, including its line
+    /// ending, with the fragments the line belongs to. The base fragment is
+    /// included as `""`. It returns the rewritten line or `None` to drop it.
+    /// Returned lines are not parsed again for fragment tags. Afterwards, the
+    /// fragments are processed as in [Config::split_templates].
+    pub fn split_templates_transformed(
+        &self,
+        src: &str,
+        mut transform: impl for<'l> FnMut(&HashSet<&str>, &'l str) -> Option<Cow<'l, str>>,
+    ) -> Result<HashMap<String, String>, ErrorWithLine> {
+        let mut res = HashMap::new();
+        self.split_transformed_into(src, &mut transform, &mut res)?;
+        Ok(res)
+    }
+
+    /// Split the template into an existing map, reusing its buffers
+    ///
+    /// Existing contents are cleared, but not deallocated. Afterwards, only the
+    /// fragments of the template are kept.
+    pub(crate) fn split_transformed_into(
+        &self,
+        src: &str,
+        mut transform: impl for<'l> FnMut(&HashSet<&str>, &'l str) -> Option<Cow<'l, str>>,
+        res: &mut HashMap<String, String>,
+    ) -> Result<(), ErrorWithLine> {
+        start_span!(timer, "split_templates", bytes = src.len());
+        for content in res.values_mut() {
+            content.clear();
+        }
+        let (src, lines) = self.preprocess(src)?;
+
+        let mut push = |active: &HashSet<&str>, keys: &HashMap<&str, String>, lines: &str| {
+            // lines only of the base template
+            if self.skip_base && active.len() == 1 && active.contains("") {
+                return;
+            }
+            for line in iterate_with_endings(lines) {
+                let Some(line) = transform(active, line) else {
+                    continue;
+                };
+                for &fragment in active {
+                    if !(self.skip_base && fragment.is_empty()) {
+                        let key = keys.get(fragment).map_or(fragment, String::as_str);
+                        push_line(res, key, &line);
+                    }
+                }
+            }
+        };
+        if self.number_repeated_fragments {
+            process_numbered(self, &src, push)
+        } else {
+            process_template(self, &src, |active, lines| {
+                push(active, &HashMap::new(), lines)
+            })
+        }
+        .map_err(|err| lines.restore(err))?;
+        // fragments of previous templates
+        res.retain(|_, content| !content.is_empty());
+
+        let macros = self
+            .macros
+            .then(|| self.render_macros(&src, res.iter().map(|(k, v)| (k.as_str(), v.as_str()))))
+            .transpose()
+            .map_err(|err| lines.restore(err))?;
+        self.finish_fragments(&src, &lines, res.iter_mut().map(|(k, v)| (k.as_str(), v)));
+        if let Some(macros) = macros {
+            res.insert(
+                String::from("_macros"),
+                self.finish_fragment("", None, macros),
+            );
+        }
+        #[cfg(feature = "minijinja")]
+        if self.verify {
+            self.verify_fragments(&src, res.iter().map(|(k, v)| (k.as_str(), v.as_str())))
+                .map_err(|err| lines.restore(err))?;
+        }
+        finish_span!(timer, fragments = res.len());
+        Ok(())
+    }
+
+    /// Apply the options that need the complete output of each fragment,
+    /// keyed as produced by [process_numbered]
+    pub(crate) fn finish_fragments<'t>(
+        &self,
+        src: &str,
+        lines: &LineMap,
+        templates: impl Iterator<Item = (&'t str, &'t mut String)>,
+    ) {
+        let contexts = self.fragment_contexts(src);
+        let annotations = self.fragment_annotations(src, lines);
+        for (key, content) in templates {
+            let fragment = fragment_of_key(key);
+            let context = contexts.get(fragment);
+            let finished = self.finish_fragment(fragment, context, std::mem::take(content));
+            *content = annotate(&annotations, fragment, finished);
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use std::ops::Range;
+
+use crate::{iterate_with_endings, Config};
+
+/// The severity of a [Diagnostic]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Severity {
+    /// The template is invalid
+    Error,
+    /// A suspicious, but valid construct, see [crate::lint_template]
+    Warning,
+}
+
+impl Severity {
+    /// The name of the severity as used in JSON, i.e., `error` or `warning`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Error => "error",
+            Self::Warning => "warning",
+        }
+    }
+}
+
+/// An error or warning of a template with its location
+///
+/// Lines and columns are one-based, columns count characters. The fields and
+/// codes are stable, so that CI systems and editor plugins can rely on them,
+/// see [Diagnostic::to_json].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// The path of the template, set with [Diagnostic::in_path]
+    pub file: Option<String>,
+    pub line: usize,
+    pub column: usize,
+    /// The column after the marked part of the line
+    pub end_column: usize,
+    /// The kind of the problem, see [crate::Error::code] and
+    /// [crate::LintKind::code]
+    pub code: &'static str,
+    pub severity: Severity,
+    pub message: String,
+    /// A hint how to fix the problem, only given for errors
+    pub help: Option<String>,
+}
+
+impl Diagnostic {
+    /// Attach the path of the template
+    pub fn in_path(mut self, path: &str) -> Self {
+        self.file = Some(path.to_owned());
+        self
+    }
+
+    /// Format the diagnostic as a single line JSON object
+    ///
+    /// The object has the keys `file`, `line`, `column`, `end_column`, `code`,
+    /// `severity`, `message`, and `help`. Missing values are `null`.
+    ///
+    /// ```rust
+    /// # use template_fragments::validate_template;
+    /// let diagnostics = validate_template("{% fragment item %} <li>\n{% endfragment %}\n");
+    /// assert_eq!(
+    ///     diagnostics[0].clone().in_path("index.html").to_json(),
+    ///     concat!(
+    ///         r#"{"file":"index.html","line":1,"column":21,"end_column":25,"#,
+    ///         r#""code":"trailing-content","severity":"error","#,
+    ///         r#""message":"content after a fragment tag","#,
+    ///         r#""help":"move the content after the tag to a separate line, "#,
+    ///         r#"fragment tags must be on a line of their own"}"#,
+    ///     ),
+    /// );
+    /// ```
+    pub fn to_json(&self) -> String {
+        let optional = |value: Option<&str>| value.map_or(String::from("null"), json_string);
+        format!(
+            "{{\"file\":{},\"line\":{},\"column\":{},\"end_column\":{},\"code\":{},\"severity\":{},\"message\":{},\"help\":{}}}",
+            optional(self.file.as_deref()),
+            self.line,
+            self.column,
+            self.end_column,
+            json_string(self.code),
+            json_string(self.severity.as_str()),
+            json_string(&self.message),
+            optional(self.help.as_deref()),
+        )
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    /// Format the diagnostic as `FILE:LINE:COLUMN: SEVERITY[CODE]: MESSAGE`
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{file}:")?;
+        }
+        write!(
+            f,
+            "{}:{}: {}[{}]: {}",
+            self.line,
+            self.column,
+            self.severity.as_str(),
+            self.code,
+            self.message
+        )
+    }
+}
+
+/// Check a template and report its errors and lint warnings as diagnostics
+///
+/// Invalid templates result in a single error, valid templates in the
+/// warnings of [crate::lint_template]. Unclosed tags are reported at the
+/// innermost unclosed start tag.
+///
+/// ```rust
+/// # use template_fragments::{validate_template, Severity};
+/// let source = "<ul>\n  {% fragment item %}\n  <li></li>\n</ul>\n";
+/// let diagnostics = validate_template(source);
+///
+/// assert_eq!(diagnostics.len(), 1);
+/// assert_eq!(diagnostics[0].severity, Severity::Error);
+/// assert_eq!(diagnostics[0].code, "unclosed-tag");
+/// assert_eq!((diagnostics[0].line, diagnostics[0].column), (2, 3));
+/// assert_eq!(diagnostics[0].to_string(), "2:3: error[unclosed-tag]: unclosed fragment `item`");
+/// ```
+pub fn validate_template(src: &str) -> Vec<Diagnostic> {
+    Config::default().validate_template(src)
+}
+
+impl Config {
+    /// Check a template and report its errors and lint warnings as
+    /// diagnostics
+    ///
+    /// See [crate::validate_template].
+    pub fn validate_template(&self, src: &str) -> Vec<Diagnostic> {
+        let err = match self.lint_template(src) {
+            Ok(warnings) => {
+                return warnings
+                    .into_iter()
+                    .map(|warning| {
+                        let line = line_text(src, warning.line);
+                        Diagnostic {
+                            code: warning.kind.code(),
+                            severity: Severity::Warning,
+                            message: warning.kind.to_string(),
+                            help: None,
+                            ..located(warning.line, line, self.tag_span(line))
+                        }
+                    })
+                    .collect();
+            }
+            Err(err) => err,
+        };
+
+        let (line_idx, span) = self.error_location(src, &err);
+        vec![Diagnostic {
+            code: err.1.code(),
+            severity: Severity::Error,
+            message: err.1.summary(),
+            help: Some(self.error_help(src, &err)),
+            ..located(line_idx, line_text(src, line_idx), span)
+        }]
+    }
+}
+
+/// A diagnostic at the given span of a line, without code and message
+fn located(line_idx: usize, line: &str, span: Range<usize>) -> Diagnostic {
+    let column = |offset: usize| line[..offset].chars().count() + 1;
+    Diagnostic {
+        file: None,
+        line: line_idx + 1,
+        column: column(span.start),
+        end_column: column(span.end),
+        code: "",
+        severity: Severity::Error,
+        message: String::new(),
+        help: None,
+    }
+}
+
+/// The line without its line ending, empty for lines after the end
+pub(crate) fn line_text(src: &str, line_idx: usize) -> &str {
+    iterate_with_endings(src)
+        .nth(line_idx)
+        .unwrap_or_default()
+        .trim_end_matches(['\r', '\n'])
+}
+
+fn json_string(value: &str) -> String {
+    let mut res = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => res.push_str("\\\""),
+            '\\' => res.push_str("\\\\"),
+            '\n' => res.push_str("\\n"),
+            '\r' => res.push_str("\\r"),
+            '\t' => res.push_str("\\t"),
+            c if (c as u32) < 0x20 => res.push_str(&format!("\\u{:04x}", c as u32)),
+            c => res.push(c),
+        }
+    }
+    res.push('"');
+    res
+}
@@ -0,0 +1,187 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::{verbatim::tags, Config, ErrorWithLine};
+
+/// Words that are part of the expression syntax, not variables
+const KEYWORDS: &[&str] = &[
+    "and", "or", "not", "in", "is", "if", "else", "true", "false", "none", "True", "False", "None",
+];
+
+/// Report the top-level variables referenced by each fragment
+///
+/// All `{{ ... }}` expressions of the fragment are scanned for variables.
+/// Attributes, e.g., `user` in `{{ page.user }}`, filters, tests, and keyword
+/// arguments are not reported. Variables bound inside the fragment by `{% for
+/// %}`, `{% set %}`, or `{% with %}` tags are excluded, as the fragment does
+/// not need them from the context. The analysis is lightweight: it does not
+/// evaluate the expressions and assumes Jinja-like syntax.
+///
+/// ```rust
+/// # use template_fragments::fragment_variables;
+/// let source = concat!(
+///     "<h1>{{ title }}</h1>\n",
+///     "{% fragment items %}\n",
+///     "{% for item in items %}\n",
+///     "<li>{{ item.name | upper }} {{ url_for(\"item\", id=item.id) }}</li>\n",
+///     "{% endfor %}\n",
+///     "{% endfragment %}\n",
+/// );
+///
+/// let variables = fragment_variables(source).unwrap();
+/// assert_eq!(variables["items"], ["url_for"].map(String::from).into());
+/// assert_eq!(variables[""], ["title", "url_for"].map(String::from).into());
+/// ```
+pub fn fragment_variables(src: &str) -> Result<HashMap<String, BTreeSet<String>>, ErrorWithLine> {
+    Config::default().fragment_variables(src)
+}
+
+impl Config {
+    /// Report the top-level variables referenced by each fragment
+    ///
+    /// See [crate::fragment_variables].
+    pub fn fragment_variables(
+        &self,
+        src: &str,
+    ) -> Result<HashMap<String, BTreeSet<String>>, ErrorWithLine> {
+        let fragments = self.split_templates_detailed(src)?;
+        let tag_markers = self.syntax.tag_markers();
+
+        Ok(fragments
+            .into_iter()
+            .map(|(name, fragment)| {
+                let bound = bound_variables(&fragment.content, tag_markers);
+                let variables = referenced_variables(&fragment.content)
+                    .into_iter()
+                    .filter(|variable| !bound.contains(variable))
+                    .map(str::to_owned)
+                    .collect();
+                (name, variables)
+            })
+            .collect())
+    }
+}
+
+/// The top-level variables of all `{{ ... }}` expressions
+pub(crate) fn referenced_variables(mut content: &str) -> BTreeSet<&str> {
+    let mut res = BTreeSet::new();
+    while let Some((_, rest)) = content.split_once("{{") {
+        let Some((expression, rest)) = rest.split_once("}}") else {
+            break;
+        };
+        content = rest;
+
+        let expression = expression.strip_prefix('-').unwrap_or(expression);
+        let expression = expression.strip_suffix('-').unwrap_or(expression);
+        res.extend(expression_variables(expression));
+    }
+    res
+}
+
+/// The variables bound by `for`, `set`, and `with` tags
+pub(crate) fn bound_variables<'c>(
+    content: &'c str,
+    tag_markers: (&'c str, &'c str),
+) -> BTreeSet<&'c str> {
+    tags(content, tag_markers)
+        .flat_map(|words| tag_bindings(&words))
+        .collect()
+}
+
+/// The variables bound by a `for`, `set`, or `with` tag given by its words
+pub(crate) fn tag_bindings<'w>(words: &[&'w str]) -> Vec<&'w str> {
+    let targets: Vec<&str> = match words {
+        // `for a, b in pairs`
+        ["for", rest @ ..] => std::iter::once("loop")
+            .chain(
+                rest.iter()
+                    .take_while(|&&word| word != "in")
+                    .flat_map(|word| word.split(',')),
+            )
+            .collect(),
+        // `set a, b = pair` or `set a %}...{% endset`
+        ["set", rest @ ..] => {
+            let mut targets = Vec::new();
+            for word in rest {
+                match word.split_once('=') {
+                    Some((target, _)) => {
+                        targets.extend(target.split(','));
+                        break;
+                    }
+                    None => targets.extend(word.split(',')),
+                }
+            }
+            targets
+        }
+        // `with a=1, b=2`
+        ["with", rest @ ..] => rest
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, word)| match word.split_once('=') {
+                Some((name, _)) => Some(name),
+                None => rest
+                    .get(idx + 1)
+                    .filter(|next| next.starts_with('='))
+                    .map(|_| *word),
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    targets
+        .into_iter()
+        .filter(|target| is_identifier(target))
+        .collect()
+}
+
+/// The top-level variables of a single expression
+fn expression_variables(expression: &str) -> Vec<&str> {
+    let mut res = Vec::new();
+    // the last non-whitespace token
+    let mut previous = "";
+    let mut rest = expression;
+
+    while let Some(c) = rest.chars().next() {
+        if c.is_whitespace() {
+            rest = &rest[c.len_utf8()..];
+        } else if c == '"' || c == '\'' {
+            let end = rest[1..].find(c).map_or(rest.len(), |pos| pos + 2);
+            previous = &rest[..end];
+            rest = &rest[end..];
+        } else if c.is_alphabetic() || c == '_' {
+            let end = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            let (word, tail) = rest.split_at(end);
+            let next = tail.trim_start();
+            let is_keyword_argument = next.starts_with('=') && !next.starts_with("==");
+            let is_variable = !matches!(previous, "." | "|" | "is")
+                && !KEYWORDS.contains(&word)
+                && !is_keyword_argument;
+            if is_variable {
+                res.push(word);
+            }
+            // `is not defined` tests
+            previous = if previous == "is" && word == "not" {
+                "is"
+            } else {
+                word
+            };
+            rest = tail;
+        } else if c.is_ascii_digit() {
+            let end = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '.' || c == '_'))
+                .unwrap_or(rest.len());
+            previous = &rest[..end];
+            rest = &rest[end..];
+        } else {
+            previous = &rest[..c.len_utf8()];
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    res
+}
+
+fn is_identifier(word: &str) -> bool {
+    let mut chars = word.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
@@ -0,0 +1,58 @@
+/// Tracks `raw` and `verbatim` regions, whose content is not scanned for
+/// fragment tags
+///
+/// Regions start with `{% raw %}` (Jinja, Liquid) or `{% verbatim %}`
+/// (Django) and end with `{% endraw %}` or `{% endverbatim %}`. Django allows
+/// named regions, e.g., `{% verbatim myblock %}`, which only end with the
+/// end tag of the same name. The tag markers of the syntax are used and
+/// whitespace control, e.g., `{%- raw -%}`, is supported.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Verbatim {
+    /// The content of the tag that ends the current region, if any
+    end: Option<String>,
+}
+
+impl Verbatim {
+    /// Whether the line is part of a region and must not be parsed as a
+    /// fragment tag
+    ///
+    /// The lines have to be passed in order. Lines that start a region are not
+    /// skipped, lines that end it are.
+    pub(crate) fn skip(&mut self, line: &str, tag_markers: (&str, &str)) -> bool {
+        let skip = self.end.is_some();
+        if !line.contains(tag_markers.0) {
+            return skip;
+        }
+
+        for words in tags(line, tag_markers) {
+            match &self.end {
+                Some(end) if words.join(" ") == *end => self.end = None,
+                Some(_) => {}
+                None => match words.as_slice() {
+                    ["raw"] => self.end = Some(String::from("endraw")),
+                    ["verbatim"] => self.end = Some(String::from("endverbatim")),
+                    ["verbatim", name] => self.end = Some(format!("endverbatim {name}")),
+                    _ => {}
+                },
+            }
+        }
+        skip
+    }
+}
+
+/// The words of all tags in the line
+pub(crate) fn tags<'l>(
+    mut line: &'l str,
+    tag_markers: (&'l str, &'l str),
+) -> impl Iterator<Item = Vec<&'l str>> {
+    let (open, close) = tag_markers;
+    std::iter::from_fn(move || {
+        let (_, rest) = line.split_once(open)?;
+        let (content, rest) = rest.split_once(close)?;
+        line = rest;
+
+        let content = content.strip_prefix('-').unwrap_or(content);
+        let content = content.strip_suffix('-').unwrap_or(content);
+        Some(content.split_whitespace().collect())
+    })
+}
@@ -0,0 +1,238 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{mpsc, Arc, PoisonError, RwLock, RwLockReadGuard},
+    time::Duration,
+};
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::{
+    directory::{list_files, relative_name},
+    DirectoryError, TemplateSet, TemplateSetError,
+};
+
+/// A [TemplateSet] of a directory tree that is refreshed when files change
+///
+/// Requires the `notify` feature. Templates are keyed by their path relative
+/// to the root, as with [crate::split_directory]. When files are created,
+/// modified, or removed, the affected templates are reloaded and replaced in
+/// a single update of the set. Errors while reloading are passed to the
+/// callback and the previous version of the template is kept.
+///
+/// Changes are applied once the files were not modified for a short time, see
+/// [TemplateWatcher::DEBOUNCE]. This way, files are not read while editors
+/// write them.
+///
+/// ```rust,no_run
+/// # use template_fragments::TemplateWatcher;
+/// let watcher = TemplateWatcher::new("templates", |err| eprintln!("{err}")).unwrap();
+///
+/// // later, e.g., in a request handler
+/// let item = watcher.templates().get("index.html#item").map(str::to_owned);
+/// ```
+#[derive(Debug)]
+pub struct TemplateWatcher {
+    templates: Arc<RwLock<TemplateSet>>,
+    _watcher: RecommendedWatcher,
+}
+
+impl TemplateWatcher {
+    /// The time without further changes after which changed files are read
+    pub const DEBOUNCE: Duration = Duration::from_millis(50);
+
+    /// Load all templates below `root` and watch them for changes
+    ///
+    /// Errors while loading the templates initially are returned.
+    pub fn new(
+        root: impl AsRef<Path>,
+        mut on_error: impl FnMut(WatchError) + Send + 'static,
+    ) -> Result<Self, WatchError> {
+        let root = root
+            .as_ref()
+            .canonicalize()
+            .map_err(|err| WatchError::Io(root.as_ref().to_owned(), err))?;
+
+        let mut templates = TemplateSet::new();
+        for path in list_files(&root)? {
+            let src = read_template(&path)?;
+            templates
+                .insert(&relative_name(&root, &path), &src)
+                .map_err(WatchError::Template)?;
+        }
+        let templates = Arc::new(RwLock::new(templates));
+
+        // changes are collected until the files are quiet, the thread stops
+        // when the watcher is dropped
+        let (events, received) = mpsc::channel::<notify::Result<Event>>();
+        std::thread::spawn({
+            let templates = Arc::clone(&templates);
+            let root = root.clone();
+            move || {
+                while let Ok(event) = received.recv() {
+                    let mut batch = vec![event];
+                    while let Ok(event) = received.recv_timeout(Self::DEBOUNCE) {
+                        batch.push(event);
+                    }
+
+                    let mut paths = Vec::new();
+                    for event in batch {
+                        match event {
+                            Ok(event) if is_change(&event.kind) => paths.extend(event.paths),
+                            Ok(_) => {}
+                            Err(err) => on_error(WatchError::Notify(err)),
+                        }
+                    }
+                    paths.sort();
+                    paths.dedup();
+                    refresh(&root, &templates, &paths, &mut on_error);
+                }
+            }
+        });
+
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+            // the receiver only stops once the watcher is dropped
+            let _ = events.send(event);
+        })
+        .map_err(WatchError::Notify)?;
+        watcher
+            .watch(&root, RecursiveMode::Recursive)
+            .map_err(WatchError::Notify)?;
+
+        Ok(Self {
+            templates,
+            _watcher: watcher,
+        })
+    }
+
+    /// The current templates
+    ///
+    /// Updates are blocked while the guard is held.
+    pub fn templates(&self) -> RwLockReadGuard<'_, TemplateSet> {
+        self.templates
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// The shared templates, e.g., to hand them to request handlers
+    pub fn shared(&self) -> Arc<RwLock<TemplateSet>> {
+        Arc::clone(&self.templates)
+    }
+}
+
+fn is_change(kind: &EventKind) -> bool {
+    matches!(
+        kind,
+        EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_) | EventKind::Any
+    )
+}
+
+/// Reload the templates of the changed paths and update the set at once
+fn refresh(
+    root: &Path,
+    templates: &RwLock<TemplateSet>,
+    paths: &[PathBuf],
+    on_error: &mut impl FnMut(WatchError),
+) {
+    // the new source of each affected template, `None` for removed templates
+    let mut updates: Vec<(String, Option<String>)> = Vec::new();
+    for path in paths {
+        if !path.starts_with(root) || path == root {
+            continue;
+        }
+        let name = relative_name(root, path);
+
+        if path.is_dir() {
+            let files = match list_files(path) {
+                Ok(files) => files,
+                Err(err) => {
+                    on_error(err.into());
+                    continue;
+                }
+            };
+            for file in files {
+                match read_template(&file) {
+                    Ok(src) => updates.push((relative_name(root, &file), Some(src))),
+                    Err(err) => on_error(err),
+                }
+            }
+        } else {
+            match std::fs::read_to_string(path) {
+                Ok(src) => updates.push((name, Some(src))),
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                    updates.push((name, None))
+                }
+                Err(err) => on_error(WatchError::Io(path.clone(), err)),
+            }
+        }
+    }
+    if updates.is_empty() {
+        return;
+    }
+
+    let mut templates = templates.write().unwrap_or_else(PoisonError::into_inner);
+    for (name, src) in updates {
+        match src {
+            Some(src) => {
+                if let Err(err) = templates.insert(&name, &src) {
+                    on_error(WatchError::Template(err));
+                }
+            }
+            None => {
+                // the path may also have been a directory
+                let prefix = format!("{name}/");
+                let removed: Vec<String> = templates
+                    .paths()
+                    .filter(|path| *path == name || path.starts_with(&prefix))
+                    .map(str::to_owned)
+                    .collect();
+                for path in removed {
+                    templates.remove(&path);
+                }
+            }
+        }
+    }
+}
+
+fn read_template(path: &Path) -> Result<String, WatchError> {
+    std::fs::read_to_string(path).map_err(|err| WatchError::Io(path.to_owned(), err))
+}
+
+/// Errors that can occur while watching templates
+#[derive(Debug)]
+pub enum WatchError {
+    /// A file or directory that could not be read
+    Io(PathBuf, std::io::Error),
+    /// A template that could not be added, e.g., because it is invalid
+    Template(TemplateSetError),
+    /// An error of the file system watcher
+    Notify(notify::Error),
+}
+
+impl From<DirectoryError> for WatchError {
+    fn from(err: DirectoryError) -> Self {
+        match err {
+            DirectoryError::Io(path, err) => Self::Io(path, err),
+            DirectoryError::Template(err) => Self::Template(TemplateSetError::Template(err)),
+        }
+    }
+}
+
+impl std::fmt::Display for WatchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(path, err) => write!(f, "{err} for {}", path.display()),
+            Self::Template(err) => write!(f, "{err}"),
+            Self::Notify(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for WatchError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(_, err) => Some(err),
+            Self::Template(err) => Some(err),
+            Self::Notify(err) => Some(err),
+        }
+    }
+}
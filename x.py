@@ -15,7 +15,7 @@ once = lambda: lambda func: ft.lru_cache(maxsize=None)(func)
 def precommit(backtrace=False):
     generate_tests()
     cargo("fmt")
-    cargo("clippy")
+    cargo("clippy", "--workspace")
     test(backtrace=backtrace)
 
 
@@ -25,6 +25,7 @@ def test(backtrace):
     generate_tests()
     cargo(
         "test",
+        "--workspace",
         env=dict(os.environ, RUST_BACKTRACE=f"{int(bool(backtrace))}"),
     )
 